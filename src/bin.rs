@@ -4,1375 +4,1561 @@ extern crate backtrace;
 
 use std::fs;
 use std::fs::File;
-use std::io::Write;
 use libsnarkrs::parser::compile;
-use libsnarkrs::parser::ast::tokens::Token;
-use libsnarkrs::parser::ast::Rule;
-use libsnarkrs::parser::expression_parser;
-use pest::pratt_parser::Op;
+use libsnarkrs::parser::symbols;
+use libsnarkrs::parser::semantic;
+use libsnarkrs::analysis::{Diagnostic, Severity};
+use libsnarkrs::analysis::{self, lints};
+use libsnarkrs::execution;
+use libsnarkrs::r1cs;
+use libsnarkrs::parser::template;
+use libsnarkrs::parser::template::*;
+use libsnarkrs::parser::errors as compile_errors;
+use libsnarkrs::parser::source::{SourceProvider, DiskSource, MemorySource, OverlaySource};
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::collections::VecDeque;
 use std::process;
-use backtrace::Backtrace;
-use expression_parser::*;
 use serde::{Serialize, Deserialize};
 use warp::{http::Response, Filter};
-use anyhow::Result;
-use warp::http::header::{HeaderValue, ACCESS_CONTROL_ALLOW_ORIGIN};
+use anyhow::{anyhow, Result};
 use std::path::Path;
 use std::env;
+use std::sync::OnceLock;
 
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-struct Template{
-    name: String,
-    params: Vec<String>,
-    private_input_signals: Vec<Signal>,
-    output_signals: Vec<Signal>,
-    intermediate_signals: Vec<Signal>,
-    components: Vec<Component>,
-    constraints: Vec<Expression>,
-    instructions: Vec<SingleCommand>,
-    path: String,
-}
-
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-enum DeclType {
-    Signal,
-    Variable,
-    Component
-}
-
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-enum SignalDirection{
-    Input,
-    Output,
-    Intermediate
-}
-
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-struct Signal {
-    name: String,
-    direction: SignalDirection,
-    size_per_dimension: Vec<String>,
-    expression: Stmt,
+#[derive(Serialize, Debug, Clone)]
+struct NodePosition{
+    x: usize,
+    y: usize,
 }
-
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-struct Component {
-    name: String,
-    size_per_dimension: Vec<String>,
-    template_to_use: String,
-    arguments: Vec<i32>,
+#[derive(Serialize, Debug, Clone)]
+struct NodeData {
+    label: String,
+    // full names of the inputs of a sub-component, one handle each
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    inputs: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+    // the values of the signal with two sets of inputs, see `annotate_diff`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    values: Option<DiffValues>
 }
 
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-struct DeclStatement {
-    decl_type: DeclType,
-    name: String,
-    direction: SignalDirection,
-    size_per_dimension: Vec<String>,
-    template_to_use: String,
-    expression: Stmt,
-    arguments: Vec<i32>,
+#[derive(Serialize, Debug, Clone)]
+struct DiffValues {
+    left: String,
+    right: String
 }
 
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-struct Expression {
-    content: String,
-}
 
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-struct BoolExpression{
-    lhs: String,
-    rhs: String,
-    operation: String,
+#[derive(Serialize, Debug, Clone)]
+struct Style{
+    backgroundColor: String,
+    width: i32,
+    height: i32
 }
 
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-struct ForLoop {
-    index: String,
-    start_value: i32,
-    condition: BoolExpression,
-    step: i32,
-    body: Vec<SingleCommand>
+#[derive(Serialize, Debug, Clone)]
+struct Node {
+    id: String,
+    r#type: String,
+    data: NodeData,
+    position: NodePosition,
+    className: String,
+    style: Option<Style>,
+    draggable: Option<bool>,
+    selectable: Option<bool>
 }
 
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-struct ExecutionVariable{
+#[derive(Serialize, Debug, Clone)]
+struct Edge{
     id: String,
-    indexing: Vec<String>,
-    sub_variable: Option<Box<ExecutionVariable>>,
-    is_constant: bool,
-    numerical_value: i32,
+    source: String,
+    target: String,
+    targetHandle: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    animated: bool
 }
 
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-struct Evaluation {
-    variables: Vec<ExecutionVariable>,
-    operation: Operation,
-    params: Vec<ExecutionVariable>
+// Label of a signal node, the value is its last word
+fn signal_label(name: &str, value: &Option<r1cs::field::FieldElement>) -> String {
+    format!("{} value - {}", name, value.as_ref().map(|value| value.to_signed().to_string()).unwrap_or_else(|| "unknown".to_string()))
 }
 
+// Nodes and edges of the graph of an instance. Signals and sub-components are identified by their full
+// name, as `execution::graph::node_of` maps them, and labelled with the name local to the instance.
+fn serialize_for_visual(graph: &execution::graph::Graph, diagnostics: &[Diagnostic]) -> (Vec<Node>, Vec<Edge>) {
+    let mut nodes : Vec<Node> = vec![];
+    let local = |name: &str| name.strip_prefix(&format!("{}.", graph.instance)).unwrap_or(name).to_string();
+    let title = graph.template.clone().unwrap_or_else(|| graph.instance.clone());
+    let node = |id: &str, r#type: &str, label: String, inputs: Vec<String>, x: usize, y: usize, style: Option<Style>| Node {
+        id: id.to_string(),
+        r#type: r#type.to_string(),
+        data: NodeData{label, inputs, warnings: vec![], values: None},
+        position: NodePosition{x, y},
+        className: "light".to_string(),
+        style,
+        draggable: Some(true),
+        selectable: Some(true)
+    };
 
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-enum SingleCommand {
-    ForLoop(ForLoop),
-    Instruction(Instruction),
-    DeclarationStatement(DeclStatement)
-}
+    nodes.push(Node{
+        id: format!("{}#template", graph.instance),
+        r#type: "default".to_string(),
+        className: "annotation".to_string(),
+        data: NodeData{label: title.clone(), inputs: vec![], warnings: vec![], values: None},
+        draggable: Some(false),
+        selectable: Some(false),
+        style: None,
+        position:  NodePosition{x:0, y:0}});
+    nodes.push(node(&format!("{}#group", graph.instance), "group", title, vec![], 0, 100, Some(Style{ backgroundColor: String::from("rgba(255, 0, 0, 0.2)"), width: 900, height: 600})));
+
+    // inputs, intermediate signals, sub-components and outputs, a row each
+    let direction = |signal: &&execution::graph::SignalNode, direction: Option<SignalDirection>| signal.direction == direction;
+    let rows = [
+        ("input", 100, 150, graph.signals.iter().filter(|signal| direction(signal, Some(SignalDirection::Input))).collect::<Vec<_>>()),
+        ("default", 150, 150, graph.signals.iter().filter(|signal| direction(signal, Some(SignalDirection::Intermediate)) || direction(signal, None)).collect()),
+        ("output", 400, 200, graph.signals.iter().filter(|signal| direction(signal, Some(SignalDirection::Output))).collect())
+    ];
+    for (r#type, y, step, signals) in &rows[..2] {
+        for (idx, signal) in signals.iter().enumerate() {
+            nodes.push(node(&signal.name, r#type, signal_label(&local(&signal.name), &signal.value), vec![], 20 + idx * step, *y, None));
+        }
+    }
+    for (idx, component) in graph.components.iter().enumerate() {
+        let style = Some(Style{ backgroundColor: String::from("rgba(255, 0, 0, 0.2)"), width: 100, height:120});
+        nodes.push(node(&component.name, "customNode", local(&component.name), component.inputs.clone(), 20 + idx * 200, 200, style));
+    }
+    for (r#type, y, step, signals) in &rows[2..] {
+        for (idx, signal) in signals.iter().enumerate() {
+            nodes.push(node(&signal.name, r#type, signal_label(&local(&signal.name), &signal.value), vec![], 20 + idx * step, *y, None));
+        }
+    }
 
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-enum Operation {
-    Multiply,
-    ComponentInstance,
-    Id
-}
-//Make this be enum
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-struct Instruction {
-    statement: expression_parser::Stmt
-}
+    let edges = graph.edges.iter().map(|edge| Edge{
+        id: format!("e{}-{}", edge.source, edge.input.as_ref().unwrap_or(&edge.target)),
+        source: edge.source.clone(),
+        target: edge.target.clone(),
+        targetHandle: edge.input.clone(),
+        animated: false
+    }).collect();
 
-fn get_value_for_evaluation(eval: Evaluation, heap: &mut Heap) -> i32 {
-    for var in eval.variables {
-        let (var_string, signal_string) = generate_string_from_variable(&var, &mut heap.variable_to_value_map);
-        match heap.variable_to_value_map.get(&var_string) {
-            Some(value) => { return value.clone();},
-            None => { return var.numerical_value; } 
-        }
+    if let Some(template) = &graph.template {
+        annotate_nodes(&mut nodes, template, &local, diagnostics);
     }
-    // if eval.operation == Operation::Id {
-    //     match mini_map.get(eval.variables[0]){
-    //         Some(value) => { return value;},
-    //         None
-    //     }
-    // }
-    0
+    (nodes, edges)
 }
 
-fn parse_expression(expression: &Token) -> Expression {
-    let mut result = "".to_string();
-
-    match expression {
-        Token::NonTerminal(ntt) => {
-            for token in &ntt.subrules {
-                result = format!("{}{}", result, parse_expression(token).content);
+// Attaches the analysis findings of a template to the nodes of its signals and sub-components
+fn annotate_nodes(nodes: &mut [Node], template_name: &String, local: &dyn Fn(&str) -> String, diagnostics: &[Diagnostic]) {
+    for node in nodes.iter_mut() {
+        let local_name = local(&node.id);
+        let base_name = local_name.split('[').next().unwrap_or("").to_string();
+        for diagnostic in diagnostics {
+            let subject = diagnostic.subject.split('.').next().unwrap_or("");
+            if diagnostic.template.eq(template_name) && subject.eq(&base_name) && !node.data.warnings.contains(&diagnostic.message) {
+                node.data.warnings.push(diagnostic.message.clone());
             }
         }
-        Token::Terminal(tt) => {
-            result = format!("{}{}", result, tt.content.clone());
+        if !node.data.warnings.is_empty() {
+            node.className = format!("{} warning", node.className);
         }
     }
-    Expression{
-        content: result
-    }
 }
 
-fn parse_array_declaration(array_decl_root: &Vec<Token>) -> String {
-    if let Token::NonTerminal(ntt) = &array_decl_root[1] {
-        if let Token::NonTerminal(subntt) = &ntt.subrules[0] {
-            if let Token::NonTerminal(subnntt) = &subntt.subrules[0] {
-                if let Token::Terminal(tt) = &subnntt.subrules[0] {
-                    return tt.content.clone();
-                }
-            } else if let Token::Terminal(subtt) = &subntt.subrules[0] {
-                return subtt.content.clone();
-            }
-        }
+ ///
+ /// Compiles a circuit and runs the semantic checker. Anything returned here is safe to execute.
+ ///
+ fn compile_program(path_str: &str, sources: &dyn SourceProvider) -> Result<(template::Program, symbols::SymbolTable)> {
+    // Sources that only live in memory have nothing to canonicalize against
+    let path = std::fs::canonicalize(path_str).unwrap_or_else(|_| std::path::PathBuf::from(path_str));
+
+    let mut ctx = compile::build_context_from_source(&path, sources);
+    let symbol_table = symbols::build_symbol_table(&mut ctx);
+
+    // Syntax, include and duplicate symbol errors, the program would silently miss what they cover
+    let load_errors: Vec<String> = ctx.errors.iter().map(|error| error.to_string()).collect();
+    if !load_errors.is_empty() {
+        return Err(anyhow!(load_errors.join("\n")));
     }
-    String::from("")
-}
 
-fn parse_list_of_vars_or_values(argument_decl_root: &Vec<Token>, index: usize) -> Vec<String> {
-    let mut temp = vec![];
-    
-    if let Token::NonTerminal(ntt) = &argument_decl_root[index] {
-        for token in &ntt.subrules {
-            if let Token::NonTerminal(subntt) = &token {
-                if let Token::Terminal(tt) = &subntt.subrules[0] {
-                    temp.push(tt.content.clone());
-                }
-            }
-        }
+    let program = template::load_program(&ctx, &symbol_table);
+
+    // Never interpret a circuit that breaks the static rules
+    let semantic_errors: Vec<String> = semantic::check_program(&program, &symbol_table).iter()
+        .map(|error| error.to_string())
+        .collect();
+    if !semantic_errors.is_empty() {
+        return Err(anyhow!(semantic_errors.join("\n")));
     }
-    temp
-}
 
-fn parse_declaration_statement(declaration_statement_root: &Vec<Token>, path: &String, path_to_content_map: &HashMap<String, String>) -> DeclStatement {
-    let mut name = "".to_string();
-    let mut decl_type = DeclType::Variable;
-    let mut direction = SignalDirection::Input;
-    let mut size_per_dimension = vec![];
-    let mut template_to_use = "".to_string();
-    let mut expression = expression_parser::Stmt::Empty;
-    let mut arguments = vec![];
-
-    if let Token::NonTerminal(ntt) = &declaration_statement_root[0] {
-        if ntt.rule == Rule::SignalDeclarationWithConstraint {
-            decl_type = DeclType::Signal;
-            if let Token::NonTerminal(wrapper) = &ntt.subrules[0] {
-                if let Token::Terminal(subtt) = &wrapper.subrules[2] {
-                    if subtt.content.contains("output") {
-                        direction = SignalDirection::Output;
-                    } else if subtt.content.contains("input") {
-                        direction = SignalDirection::Input;
-                    } else {
-                        direction = SignalDirection::Intermediate;
-                    }
-                }
-                if let Token::Terminal(subtt) = &ntt.subrules[1] {
-                    name = subtt.content.clone();
-                }
-            }
+    Ok((program, symbol_table))
+ }
 
-            if ntt.subrules.len() > 2 {
-                for st_index in 2..ntt.subrules.len() {
-                    if let Token::Terminal(subsubtt) = &ntt.subrules[st_index] {
-                        if subsubtt.rule == Rule::END_OF_LINE {
-                            break;
-                        }
-                    }
-                    if let Token::NonTerminal(subsubtt) = &ntt.subrules[st_index] {
-                        if subsubtt.rule == Rule::ArrayDeclaration {
-                            size_per_dimension.push(parse_array_declaration(&subsubtt.subrules));
-                        }
-                    } 
-                }
-                if let Token::NonTerminal(subsubtt) = &ntt.subrules[ntt.subrules.len() - 1] {
-                    if subsubtt.rule == Rule::Expression {
-                        // println!("{:?}", subsubtt.subrules);
-                        let old_expression = parse_expression(&ntt.subrules[ntt.subrules.len() - 1]);
-                        expression = extract_original_content_from_span(&path_to_content_map, subsubtt.span, path);
-                        // println!("PARPRPAPRAR  {:?}", expression);
-                    }
-                } 
-            }
-        }
-        if ntt.rule == Rule::ForStatement {
-            // println!("FOR {:?}", ntt.subrules);
-        }
-        if ntt.rule == Rule::ComponentDeclaration {
-            // println!("{:?}", ntt.subrules);
-            // println!("");
+ // The circuit the server shows, given on the command line
+ static CIRCUIT: OnceLock<String> = OnceLock::new();
 
-            decl_type = DeclType::Component;
-            if let Token::Terminal(subtt) = &ntt.subrules[1] {
-                name = subtt.content.clone();
-            }
-            if ntt.subrules.len() > 2 {
-                for st_index in 2..ntt.subrules.len() {
-                    if let Token::Terminal(subsubtt) = &ntt.subrules[st_index] {
-                        if subsubtt.rule == Rule::END_OF_LINE {
-                            break;
-                        }
-                    }
-                    if let Token::NonTerminal(subsubtt) = &ntt.subrules[st_index] {
-                        if subsubtt.rule == Rule::ArrayDeclaration {
-                            size_per_dimension.push(parse_array_declaration(&subsubtt.subrules));
-                        }
-                        // = TemplateName(A, B, C)
-                        if subsubtt.rule == Rule::Expression {
-                            if let Token::NonTerminal(exprntt) = &subsubtt.subrules[0] {
-                                if let Token::NonTerminal(exprnntt) = &exprntt.subrules[0] {
-                                    if let Token::Terminal(vartt) = &exprnntt.subrules[0] {
-                                        //Template name is always first
-                                        template_to_use = vartt.content.clone();
-                                    }
-                                }
-                            }
-                            if subsubtt.subrules.len() > 3 {
-                                if let Token::NonTerminal(exprntt) = &subsubtt.subrules[2] {
-                                    let argument_strings = parse_list_of_vars_or_values(&exprntt.subrules, 0);
-                                    for arg_string in argument_strings {
-                                        arguments.push(arg_string.parse::<i32>().unwrap());
-                                    }
-                                }
-                            }
-                        }
-                        if subsubtt.rule == Rule::PublicSignalBlock {
-                            // println!("Public signal block {:?}", subsubtt.subrules);
-                            if let Token::NonTerminal(pub_block_ntt) = &subsubtt.subrules[0] {
-                                if pub_block_ntt.rule == Rule::ArrayDeclaration {
-                                    let public_signals = parse_list_of_vars_or_values(&pub_block_ntt.subrules, 1);
-                                    // println!("These are public signals: {:?}", public_signals);
-                                }
-                            }
-                        }
-                    } 
-                }
+ fn circuit_path() -> Result<&'static str> {
+    CIRCUIT.get().map(|path| path.as_str()).ok_or_else(|| anyhow!("no circuit is served, start the server with `snarkrs <file.circom>`"))
+ }
+
+ // Unsaved editor buffers, keyed by file path. They shadow the files on disk for a single request.
+ #[derive(Deserialize, Serialize)]
+ struct UnsavedSources {
+     sources: HashMap<String, String>,
+ }
+
+ ///
+ /// Reads a `.r1cs` file, or the constraints of a circom `--json` dump, and when given the names of its
+ /// wires from the matching `.sym` file. A dump has no header, the counts only cover the wires it uses.
+ ///
+ fn load_r1cs(r1cs_path: &str, sym_path: Option<&str>) -> Result<(r1cs::R1cs, HashMap<usize, String>)> {
+    let system = match r1cs_path.ends_with(".json") {
+        true => {
+            let constraints = r1cs::json::from_json(&fs::read_to_string(r1cs_path)?).map_err(|error| anyhow!("{}: {}", r1cs_path, error))?;
+            let n_wires = constraints.iter()
+                .flat_map(|constraint| constraint.a.terms.keys().chain(constraint.b.terms.keys()).chain(constraint.c.terms.keys()))
+                .max().map(|wire| wire + 1).unwrap_or(1);
+            r1cs::R1cs {
+                prime: libsnarkrs::r1cs::field::prime().clone(),
+                n_wires,
+                n_pub_out: 0,
+                n_pub_in: 0,
+                n_prv_in: 0,
+                n_labels: n_wires,
+                wire_to_label: (0..n_wires).collect(),
+                constraints,
+                custom_gates: vec![],
+                custom_gate_applications: vec![]
             }
+        },
+        false => r1cs::binary::read(&mut File::open(r1cs_path)?).map_err(|error| anyhow!("{}: {}", r1cs_path, error))?
+    };
+    let names = match sym_path {
+        Some(sym_path) => r1cs::sym::wire_names(&r1cs::sym::parse(&fs::read_to_string(sym_path)?)?),
+        None => HashMap::new()
+    };
+    Ok((system, names))
+ }
+
+ // a system or a witness over another prime than BN254 can be shown, not evaluated nor proved
+ fn not_bn254(path: &str, prime: &num_bigint::BigUint) -> anyhow::Error {
+    let curve = libsnarkrs::r1cs::field::prime_name(prime).map(|name| name.to_string()).unwrap_or_else(|| prime.to_string());
+    anyhow!("{}: the values are over {}, only bn128 circuits can be checked or proved", path, curve)
+ }
+
+ fn r1cs_stats(system: &r1cs::R1cs) -> serde_json::Value {
+    serde_json::json!({
+        "prime": system.prime.to_string(),
+        "curve": libsnarkrs::r1cs::field::prime_name(&system.prime),
+        "fieldSize": r1cs::binary::field_size(&system.prime),
+        "wires": system.n_wires,
+        "labels": system.n_labels,
+        "constraints": system.constraints.len(),
+        "publicOutputs": system.n_pub_out,
+        "publicInputs": system.n_pub_in,
+        "privateInputs": system.n_prv_in,
+        "customGates": system.custom_gates.len(),
+        "customGateApplications": system.custom_gate_applications.len()
+    })
+ }
+
+ // `GET /r1cs-data?r1cs=<path>[&sym=<path>]`: stats and every constraint of a file compiled by circom,
+ // `.r1cs` or `--json` dump
+ async fn fetch_r1cs_data(query: HashMap<String, String>) -> Result<impl warp::Reply, warp::Rejection> {
+    let loaded = match query.get("r1cs") {
+        Some(r1cs_path) => load_r1cs(r1cs_path, query.get("sym").map(|sym| sym.as_str())),
+        None => Err(anyhow!("missing r1cs parameter"))
+    };
+    let (system, names) = match loaded {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            return Ok(Response::builder().status(400).header("Content-Type", "text/plain").body(error.to_string()));
         }
+    };
+    let constraints: Vec<String> = system.constraints.iter()
+        .map(|constraint| constraint.format_over(&system.prime, &|wire| names.get(&wire).cloned().unwrap_or_else(|| format!("w{}", wire))))
+        .collect();
+    let r1cs_data = serde_json::json!({ "stats": r1cs_stats(&system), "constraints": constraints });
+
+    Ok(Response::builder().header("Content-Type", "application/json").body(serde_json::to_string(&r1cs_data).unwrap()))
+ }
+
+ // `GET /graph-data/<instance>[?input=<input.json>]`: graph of an instance of the served circuit (`main`,
+ // `main.mul%5B0%5D`), executed with the inputs of a JSON file to compute values
+ async fn fetch_graph_data(instance: String, query: HashMap<String, String>) -> Result<impl warp::Reply, warp::Rejection> {
+    graph_data_response(&percent_decode(&instance), &query, &DiskSource)
+ }
+
+ async fn fetch_graph_data_unsaved(instance: String, query: HashMap<String, String>, unsaved: UnsavedSources) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut buffers = MemorySource::new();
+    for (path, content) in unsaved.sources.iter() {
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| std::path::PathBuf::from(path));
+        buffers.insert(path, content);
     }
-    if let Token::Terminal(ntt) = &declaration_statement_root[0] {
-        if ntt.rule == Rule::VariableDeclarationKW {
-            if let Token::Terminal(subtt) = &declaration_statement_root[1] {
-                name = subtt.content.clone();
-            }
-            // = is in play, or maybe array?
-            if declaration_statement_root.len() > 2 {
-                // println!("decl {:?}", declaration_statement_root);
-                if let Token::NonTerminal(subntt) = &declaration_statement_root[2] {
-                    let exp = extract_original_content_from_span(&path_to_content_map, subntt.span, path);
-                }
-            }
+    graph_data_response(&percent_decode(&instance), &query, &OverlaySource::new(&DiskSource, buffers))
+ }
 
-        }
+ // Symbols and values of a witness computed by circom, when the server was started with one
+ static WITNESS: OnceLock<(Vec<r1cs::sym::Symbol>, Vec<r1cs::field::FieldElement>)> = OnceLock::new();
+
+ // Limits of the executions started here, see `take_limits`
+ static LIMITS: OnceLock<execution::Limits> = OnceLock::new();
+
+ fn limits() -> &'static execution::Limits {
+    LIMITS.get_or_init(execution::Limits::default)
+ }
+
+ ///
+ /// Reads the values of a `.wtns` file and the `.sym` file of the same compilation naming them.
+ ///
+ fn load_witness(wtns_path: &str, sym_path: &str) -> Result<(Vec<r1cs::sym::Symbol>, Vec<r1cs::field::FieldElement>)> {
+    let witness = r1cs::wtns::read(&mut File::open(wtns_path)?).map_err(|error| anyhow!("{}: {}", wtns_path, error))?.values;
+    let symbols = r1cs::sym::parse(&fs::read_to_string(sym_path)?).map_err(|error| anyhow!("{}: {}", sym_path, error))?;
+    Ok((symbols, witness))
+ }
+
+ fn read_inputs(path: &str) -> Result<HashMap<String, r1cs::field::FieldElement>> {
+    fs::read_to_string(path).and_then(|text| execution::inputs::from_json(&text)).map_err(|error| anyhow!("{}: {}", path, error))
+ }
+
+ ///
+ /// Executes the served circuit, with the inputs of a JSON file to compute values, and explains how the
+ /// signal got its value.
+ ///
+ fn explain_signal(signal: &str, input_path: Option<&String>, sources: &dyn SourceProvider) -> Result<execution::provenance::Provenance> {
+    let inputs = input_path.map(|input_path| read_inputs(input_path)).transpose()?;
+    let (program, _) = compile_program(circuit_path()?, sources)?;
+    let (trace, _) = execution::trace::record(&program, inputs.as_ref(), limits());
+    execution::provenance::explain(&trace, &program.sources, signal).ok_or_else(|| anyhow!("nothing sets {}", signal))
+ }
+
+ // `GET /provenance/<signal>[?input=<input.json>]`: how a signal of the served circuit (`main.square.b`) got
+ // its value, down to the inputs of main and constants
+ async fn fetch_provenance(signal: String, query: HashMap<String, String>) -> Result<impl warp::Reply, warp::Rejection> {
+    let signal = percent_decode(&signal);
+    match explain_signal(&signal, query.get("input"), &DiskSource) {
+        Ok(provenance) => Ok(Response::builder().header("Content-Type", "application/json").body(serde_json::to_string(&provenance).unwrap())),
+        Err(error) => Ok(Response::builder().status(400).header("Content-Type", "text/plain").body(error.to_string()))
     }
-    let statement = DeclStatement{
-        size_per_dimension: size_per_dimension,
-        name: name,
-        decl_type: decl_type,
-        direction: direction,
-        template_to_use: template_to_use,
-        expression: expression,
-        arguments: arguments,
-    };
-    statement
-}
+ }
 
-fn parse_compl_variable(container: &Token) -> ExecutionVariable {
-    let mut compl_variable = ExecutionVariable{
-        id: "".to_string(),
-        indexing: vec![],
-        is_constant: false,
-        numerical_value: 0,
-        sub_variable: Some(Box::new(ExecutionVariable{
-            id: "".to_string(),
-            indexing: vec![],
-            sub_variable: None,
-            is_constant: false,
-            numerical_value: 0})),
-    };
-    if let Token::NonTerminal(x) = &container {
-        if let Token::NonTerminal(subntt) = &x.subrules[0] {
-            if let Token::Terminal(tt) = &subntt.subrules[0] {
-                compl_variable.id = tt.content.clone();
-            }
-        
-            let mut before_point_operator = true;
-
-            for op_index in 1..subntt.subrules.len() {
-                let mut inside = "".to_string();
-                if before_point_operator {
-                    if let Token::Terminal(tt) = &subntt.subrules[op_index] {
-                        if tt.rule == Rule::E_19_MemberAccessOperator {
-                            before_point_operator = false;
-                        }
-                    }
-                } else {
-                    if let Token::Terminal(tt) = &subntt.subrules[op_index] {
-                        match compl_variable.sub_variable {
-                            Some(ref mut var) => {var.id = tt.content.clone();},
-                            None => {}
-                        }
-                    }
-                }
-                if let Token::NonTerminal(subnntt) = &subntt.subrules[op_index] {
-                    if subnntt.rule == Rule::ArrayDeclaration {
-                        if let Token::NonTerminal(exp) = &subnntt.subrules[1] {
-                            if let Token::NonTerminal(value) = &exp.subrules[0] {
-                                if let Token::NonTerminal(cv) = &value.subrules[0] {
-                                    if let Token::Terminal(var) = &cv.subrules[0] {
-                                        inside = var.content.clone();
-                                    }
-                                } else if let Token::Terminal(dec) = &value.subrules[0] {
-                                    inside = dec.content.clone();
-                                }
-                            }
-                        }
-                        if before_point_operator {
-                            compl_variable.indexing.push(inside);
-                        } else {
-                            match compl_variable.sub_variable {
-                                Some(ref mut var) => {
-                                    var.indexing.push(inside);  
-                                },
-                                None => {}
-                            }
-                        }
-                        // println!("Argument contents {:?}", subntt.subrules);
-                    }
-                }
+ // `%5B` and `%5D` in `main.board%5B0%5D`, the graph encodes the path segments it sends
+ fn percent_decode(text: &str) -> String {
+    let mut decoded = vec![];
+    let bytes = text.as_bytes();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let hex = bytes.get(idx + 1..idx + 3).and_then(|hex| std::str::from_utf8(hex).ok()).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[idx], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                idx += 3;
+            },
+            (byte, _) => {
+                decoded.push(byte);
+                idx += 1;
             }
-        } else if let Token::Terminal(subtt) = &x.subrules[0] {
-            compl_variable.is_constant = true;
-            compl_variable.numerical_value = subtt.content.parse::<i32>().unwrap();
         }
     }
-    compl_variable
-}
+    String::from_utf8_lossy(&decoded).into_owned()
+ }
 
-fn parse_body_nested(elements: &Vec<Token>, path: &String, path_to_content_map: &HashMap<String, String>) -> Vec<SingleCommand> {
-    let mut lines : Vec<SingleCommand> = vec![];
-    for element in elements {
-        if let Token::NonTerminal(ntt) = element {
-            if (ntt.rule == Rule::ForStatement) {
-                lines.push(parse_for_loop(&ntt.subrules, path, path_to_content_map));
-            }
-            if (ntt.rule == Rule::Expression) {
-                let statement = extract_original_content_from_span(&path_to_content_map, ntt.span, path);
-                // println!("Parsed instruction {:?}", statement);
-                lines.push(SingleCommand::Instruction(Instruction{statement:statement}));
-            }
+ // Highlights the nodes of the signals a value derives from, the components computing them and the edges
+ // between highlighted nodes
+ fn highlight_provenance(nodes: &mut [Node], edges: &mut [Edge], provenance: &execution::provenance::Provenance) {
+    let signals = provenance.signals();
+    let mut highlighted = HashSet::new();
+    for node in nodes.iter_mut() {
+        let component = format!("{}.", node.id);
+        if signals.iter().any(|signal| *signal == node.id || signal.starts_with(&component)) {
+            node.className = format!("{} provenance", node.className);
+            highlighted.insert(node.id.clone());
         }
     }
-    lines
-}
+    for edge in edges.iter_mut() {
+        edge.animated = highlighted.contains(&edge.source) && highlighted.contains(&edge.target);
+    }
+ }
 
-fn parse_for_loop(elements: &Vec<Token>, path: &String, path_to_content_map: &HashMap<String, String>) -> SingleCommand {       
-    let mut for_loop = ForLoop {
-        index: "".to_string(),
-        start_value: 0,
-        condition: BoolExpression{
-            lhs: "".to_string(),
-            rhs: "".to_string(),
-            operation: "".to_string(),
-        },
-        step: 0,
-        body: vec![]
+ ///
+ /// Executes the served circuit with the inputs of two JSON files and compares every signal.
+ ///
+ fn compare_inputs(left_path: &str, right_path: &str, sources: &dyn SourceProvider) -> Result<execution::diff::Diff> {
+    let (left, right) = (read_inputs(left_path)?, read_inputs(right_path)?);
+    let (program, _) = compile_program(circuit_path()?, sources)?;
+    Ok(execution::diff::compare(&program, &left, &right, limits()))
+ }
+
+ // `GET /diff?left=<a.json>&right=<b.json>`: every signal of the served circuit with its value for both sets
+ // of inputs, in execution order, and how each run ended
+ async fn fetch_diff(query: HashMap<String, String>) -> Result<impl warp::Reply, warp::Rejection> {
+    let compared = match (query.get("left"), query.get("right")) {
+        (Some(left), Some(right)) => compare_inputs(left, right, &DiskSource),
+        _ => Err(anyhow!("missing left or right parameter"))
     };
-    // for (___;  ; ) 
-    if let Token::NonTerminal(ntt) = &elements[1] {
-        if ntt.rule == Rule::DeclarationStatement {
-            let statement = parse_declaration_statement(&ntt.subrules, path, path_to_content_map);
-            for_loop.index = statement.name.clone();
-            for_loop.start_value = 0;
-        }
-    } 
-    // for (;___; ) 
-    if let Token::NonTerminal(ntt) = &elements[2] {
-        let mut lhs = "".to_string();
-        let mut operation = "".to_string();
-        let mut rhs = "".to_string();
-        if ntt.rule == Rule::Expression {
-            if let Token::NonTerminal(subntt) = &ntt.subrules[0] {
-                if subntt.rule == Rule::E_Value {
-                    if let Token::NonTerminal(subnntt) = &subntt.subrules[0] {
-                        if let Token::Terminal(subtt) = &subnntt.subrules[0] {
-                            lhs = subtt.content.clone();
-                        }
-                    }
-                }
-            }
-            if let Token::Terminal(subtt) = &ntt.subrules[1] {
-                if subtt.rule == Rule::E_12_RelationalOrderingOperator {
-                    operation = subtt.content.clone();
-                }
-            }
-            if let Token::NonTerminal(subntt) = &ntt.subrules[2] {
-                if subntt.rule == Rule::E_Value {
-                    if let Token::NonTerminal(subnntt) = &subntt.subrules[0] {
-                        if let Token::Terminal(subtt) = &subnntt.subrules[0] {
-                            rhs = subtt.content.clone();
-                        }
-                    }
-                }
-            }
-            for_loop.condition = BoolExpression{
-                lhs: lhs,
-                rhs: rhs,
-                operation: operation
-            }
-        }
+    match compared {
+        Ok(diff) => Ok(Response::builder().header("Content-Type", "application/json").body(serde_json::to_string(&diff).unwrap())),
+        Err(error) => Ok(Response::builder().status(400).header("Content-Type", "text/plain").body(error.to_string()))
     }
-    // for (;;___) 
-    if let Token::NonTerminal(ntt) = &elements[3] {
-        let mut step_value = 0;
-        let mut operator = "".to_string();
-        if ntt.rule == Rule::Expression {
-            if let Token::Terminal(subntt) = &ntt.subrules[1] {
-                if subntt.rule == Rule::E_18_PostfixOperator {
-                    operator = subntt.content.clone();
-                    if operator.contains("++") {
-                        step_value = 1;
-                    } else {
-                        step_value = -1;
-                    }
-                }
-            }
-            for_loop.step = step_value;
+ }
+
+ // Gives signal nodes both values, in their label too, marks those that differ and the first of them, and the
+ // components holding one that differs
+ fn annotate_diff(nodes: &mut [Node], diff: &execution::diff::Diff) {
+    let display = |value: &Option<r1cs::field::FieldElement>| value.as_ref().map(|value| value.to_signed().to_string()).unwrap_or_else(|| "unknown".to_string());
+    let first = diff.first.map(|first| diff.signals[first].name.as_str());
+    for node in nodes.iter_mut() {
+        let name = node.id.clone();
+        let component = format!("{}.", name);
+        if let Some(signal) = diff.get(&name) {
+            let values = DiffValues { left: display(&signal.left), right: display(&signal.right) };
+            // the value is the last word of a label, see `signal_label`
+            let label = node.data.label.rsplit_once(' ').map(|(label, _)| label).unwrap_or(&node.data.label);
+            node.data.label = format!("{} {} | {}", label, values.left, values.right);
+            node.data.values = Some(values);
         }
-    }
-    if let Token::NonTerminal(ntt) = &elements[4] {
-        if ntt.rule == Rule::Body {
-            let parsed_body = parse_body_nested(&ntt.subrules, path, path_to_content_map);
-            for_loop.body = parsed_body;
+        if diff.differing().any(|signal| signal.name == name || signal.name.starts_with(&component)) {
+            node.className = format!("{} diverging", node.className);
+        }
+        if first.is_some_and(|first| first == name || first.starts_with(&component)) {
+            node.className = format!("{} first-diverging", node.className);
         }
     }
-    // println!("Parsed body of for loop: {:?}", for_loop.body);
-    SingleCommand::ForLoop(for_loop)
-}
+ }
 
-fn parse_template_from_ast(template_root_token: &Vec<Token>, path: &String, path_to_content_map: &HashMap<String, String>) -> Template {
-    let mut temp_name = String::from("");
-    let mut template_param_vec : Vec<String> = vec![];
-    let mut output_signals : Vec<Signal> = vec![];
-    let mut private_input_signals : Vec<Signal> = vec![];
-    let mut intermediate_signals: Vec<Signal> = vec![];
-    let mut components: Vec<Component> = vec![];
-    let mut constraints: Vec<Expression> = vec![];
-    let mut commands: Vec<SingleCommand> = vec![];
-
-    if let Token::Terminal(subtt) = &template_root_token[1] {
-        if subtt.rule == Rule::TemplateName {
-            temp_name=subtt.content.clone();
-        }
-    }
-    if let Token::NonTerminal(subtt) = &template_root_token[2] {
-        if subtt.rule == Rule::Parameters {
-            for subsubtoken in &subtt.subrules {
-                if let Token::Terminal(subsubtt) = subsubtoken {
-                    template_param_vec.push(subsubtt.content.clone());
-                }
+ fn graph_data_response(instance: &str, query: &HashMap<String, String>, sources: &dyn SourceProvider) -> Result<impl warp::Reply, warp::Rejection> {
+    let graph = || -> Result<(execution::graph::Graph, Vec<Diagnostic>)> {
+        // a witness of circom is drawn from its symbols, nothing is executed
+        if let Some((symbols, witness)) = WITNESS.get() {
+            let graph = execution::graph::from_values(symbols, witness, instance).ok_or_else(|| anyhow!("the witness has no instance {}", instance))?;
+            return Ok((graph, vec![]));
+        }
+        let inputs = query.get("input").map(|input_path| read_inputs(input_path)).transpose()?;
+        let (program, _) = compile_program(circuit_path()?, sources)?;
+        let diagnostics = analysis::analyze(&program, &lints::LintConfig::default());
+        let (trace, circuit) = execution::trace::record(&program, inputs.as_ref(), limits());
+        let circuit = circuit.map_err(|error| anyhow!("{}", error))?;
+        let graph = execution::graph::from_execution(&circuit, &trace, instance).ok_or_else(|| anyhow!("the circuit has no instance {}", instance))?;
+        Ok((graph, diagnostics))
+    };
+    let (graph, diagnostics) = match graph() {
+        Ok(graph) => graph,
+        Err(error) => {
+            return Ok(Response::builder().status(400).header("Content-Type", "text/plain").body(error.to_string()));
+        }
+    };
+    let (mut nodes, mut edges) = serialize_for_visual(&graph, &diagnostics);
+    // `?provenance=<signal>`
+    if let Some(signal) = query.get("provenance") {
+        match explain_signal(signal, query.get("input"), sources) {
+            Ok(provenance) => highlight_provenance(&mut nodes, &mut edges, &provenance),
+            Err(error) => {
+                return Ok(Response::builder().status(400).header("Content-Type", "text/plain").body(error.to_string()));
             }
         }
     }
-    if let Token::NonTerminal(subtt) = &template_root_token[3] {
-        if subtt.rule == Rule::Body {
-            for subsubtoken in &subtt.subrules {
-                if let Token::NonTerminal(subsubtt) = subsubtoken {
-                    if subsubtt.rule == Rule::DeclarationStatement {
-                        let statement = parse_declaration_statement(&subsubtt.subrules, path, path_to_content_map);
-                        commands.push(SingleCommand::DeclarationStatement(statement.clone()));
-                        if statement.decl_type == DeclType::Signal {
-                            if statement.direction == SignalDirection::Output {
-                                output_signals.push(Signal{
-                                    name: statement.name.clone(),
-                                    direction: statement.direction,
-                                    size_per_dimension: statement.size_per_dimension.clone(),
-                                    expression: statement.expression.clone(),
-                                });
-                            } else if statement.direction == SignalDirection::Input {
-                                private_input_signals.push(Signal{
-                                    name: statement.name.clone(),
-                                    direction: statement.direction,
-                                    size_per_dimension: statement.size_per_dimension.clone(),
-                                    expression: statement.expression.clone(),
-                                });
-                            } else {
-                                intermediate_signals.push(Signal{
-                                    name: statement.name.clone(),
-                                    direction: statement.direction,
-                                    size_per_dimension: statement.size_per_dimension.clone(),
-                                    expression: statement.expression.clone(),
-                                });
-                            }
-                        }
-                        if statement.decl_type == DeclType::Component {
-                            components.push(Component{
-                                name: statement.name.clone(),
-                                size_per_dimension: statement.size_per_dimension.clone(),
-                                template_to_use: statement.template_to_use.clone(),
-                                arguments: statement.arguments.clone(),
-                            });
-                        }
-                    }
-                    if subsubtt.rule == Rule::ForStatement {
-                        let for_loop = parse_for_loop(&subsubtt.subrules, path, path_to_content_map);
-                        commands.push(for_loop);
-                    }
-                    if subsubtt.rule == Rule::Expression {
-                        let mut statement = extract_original_content_from_span(&path_to_content_map, subsubtt.span, path);
-                        commands.push(SingleCommand::Instruction(Instruction{
-                            statement: statement.clone()
-                        }));
-                    }
-                } else if let Token::Terminal(subsubtt) = subsubtoken {
-                }
+    // `?left=<a.json>&right=<b.json>`
+    if let (Some(left), Some(right)) = (query.get("left"), query.get("right")) {
+        match compare_inputs(left, right, sources) {
+            Ok(diff) => annotate_diff(&mut nodes, &diff),
+            Err(error) => {
+                return Ok(Response::builder().status(400).header("Content-Type", "text/plain").body(error.to_string()));
             }
         }
     }
+    let graph_data = serde_json::json!({ "initialNodes": nodes, "initialEdges": edges });
     
-    Template{
-        name: temp_name,
-        params: template_param_vec,
-        private_input_signals: private_input_signals,
-        output_signals: output_signals,
-        intermediate_signals: intermediate_signals,
-        components: components,
-        constraints: constraints,
-        instructions: commands,
-        path: path.clone(),
-        }
+    Ok(Response::builder().header("Content-Type", "application/json").body(serde_json::to_string(&graph_data).unwrap()))
 }
 
-// AST root level function
-fn find_templates(subrules:&Vec<Token>, path: &String, path_to_content_map: &HashMap<String, String>) -> (Option<Component>, Vec<Template>) {
-    let mut templates : Vec<Template> = Vec::new();
-    let mut main_component = None;
 
-    for token in subrules {
-        if let Token::NonTerminal(ntt) = token {
-            if ntt.rule == Rule::TemplateBlock {
-                let temp = parse_template_from_ast(&ntt.subrules, path, path_to_content_map); 
-                templates.push(temp);
+ ///
+ /// `snarkrs check [--allow|--warn|--deny <lint>]... <file>`: compiles the circuit and prints the findings
+ /// of the static analyses, each flag setting the level of a lint. Exits with 1 when the circuit does not
+ /// compile or a finding is an error.
+ ///
+ fn run_check(args: &[String]) -> i32 {
+    let mut config = lints::LintConfig::default();
+    let mut path = None;
+    let mut remaining = args.iter();
+    while let Some(arg) = remaining.next() {
+        let level = match arg.as_str() {
+            "--allow" => None,
+            "--warn" => Some(Severity::Warning),
+            "--deny" => Some(Severity::Error),
+            _ => {
+                path = Some(arg.as_str());
+                continue;
             }
-            // this is probably main component definition
-            if ntt.rule == Rule::DeclarationStatement {
-                let decl_statement = parse_declaration_statement(&ntt.subrules, path, path_to_content_map);
-                // println!("Parsed declaration statement {:?}", decl_statement);
-                if decl_statement.decl_type == DeclType::Component{
-                    if decl_statement.name.eq(&String::from("main")) {
-                        main_component = Some(Component{
-                            name: decl_statement.name,
-                            template_to_use: decl_statement.template_to_use,
-                            size_per_dimension: decl_statement.size_per_dimension,
-                            arguments: decl_statement.arguments
-                        });
-                    }
-                    else {
-                        println!("Found non-main component instance in root level!");
-                    }
-                }
+        };
+        match remaining.next() {
+            Some(lint) if lints::LINTS.contains(&lint.as_str()) => config.set(lint, level),
+            Some(lint) => {
+                eprintln!("unknown lint `{}`, expected one of: {}", lint, lints::LINTS.join(", "));
+                return 1;
+            },
+            None => {
+                eprintln!("{} expects a lint name", arg);
+                return 1;
             }
-        } else if let Token::Terminal(tt) = token {
         }
     }
-    (main_component, templates)
-}
+    let path_str = match path {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: check [--allow|--warn|--deny <lint>]... <file>");
+            return 1;
+        }
+    };
 
-fn get_used_templates(template_map: &HashMap<String, Template>, start_node: Component) -> HashMap<String, Template> {
-    let mut queue: VecDeque<Component> = VecDeque::new();
-    let mut set = HashMap::<String, Template>::new();
+    let program = match compile_program(path_str, &DiskSource) {
+        Ok((program, _)) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
 
-    queue.push_back(start_node);
-    while queue.len() > 0 {
-        match queue.pop_front() {
-            Some(curr_component) => {
-                let current_template = curr_component.template_to_use;
-                if template_map.contains_key(&current_template) {
-                    set.insert(current_template.clone(), template_map[&current_template].clone());
+    let diagnostics = analysis::analyze(&program, &config);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
 
-                    for component in &template_map[&current_template].components {
-                        queue.push_back(component.clone());
-                    }
-                }
-            },
-            None => {
-                println!("Empty");
-            },
+    let errors = diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error).count();
+    println!("{}: {} warning(s), {} error(s)", path_str, diagnostics.len() - errors, errors);
+    if errors > 0 { 1 } else { 0 }
+ }
+
+ ///
+ /// `snarkrs constraints <file> [--O0|--O1|--O2]`: executes the circuit and prints its R1CS constraints with
+ /// signal names. When simplified, also prints what each pass removed, the combination replacing every
+ /// removed signal and the new wire of every signal that moved.
+ ///
+ fn run_constraints(args: &[String]) -> i32 {
+    let mut path = None;
+    let mut level = 0;
+    for arg in args {
+        match optimisation_level(arg) {
+            Some(selected) => level = selected,
+            None => path = Some(arg.as_str())
         }
     }
+    let path_str = match path {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: constraints <file> [--O0|--O1|--O2]");
+            return 1;
+        }
+    };
 
-    set
-}
+    let program = match compile_program(path_str, &DiskSource) {
+        Ok((program, _)) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+    let circuit = match execution::execute_with_limits(&program, None, limits()) {
+        Ok(circuit) => circuit,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
 
-#[derive(Serialize, Debug, Clone)]
-struct NodePosition{
-    x: usize,
-    y: usize,
-}
-#[derive(Serialize, Debug, Clone)]
-struct NodeData {
-    label: String,
-    inputHandles: i32
-}
+    let simplified = r1cs::simplify::simplify(&circuit.r1cs(), &r1cs::simplify::passes(level));
+    let original_name = |wire: usize| circuit.signals.get(wire).map(|signal| signal.name.clone()).unwrap_or_else(|| format!("w{}", wire));
+    let name = |wire: usize| original_name(simplified.r1cs.wire_to_label[wire]);
+    for (idx, constraint) in simplified.r1cs.constraints.iter().enumerate() {
+        println!("{}: {}", idx, constraint.format(&name));
+    }
+    if level > 0 {
+        print_simplification(&simplified, &original_name);
+    }
+    println!("{}: {} wire(s) ({} output(s), {} public input(s), {} private input(s)), {} constraint(s)",
+        path_str, simplified.r1cs.n_wires, circuit.n_outputs, circuit.n_public_inputs, circuit.n_private_inputs, simplified.r1cs.constraints.len());
+    0
+ }
 
+ // `--O0`, `--O1` or `--O2`, as for circom
+ fn optimisation_level(arg: &str) -> Option<usize> {
+    match arg {
+        "--O0" => Some(0),
+        "--O1" => Some(1),
+        "--O2" => Some(2),
+        _ => None
+    }
+ }
 
-#[derive(Serialize, Debug, Clone)]
-struct Style{
-    backgroundColor: String,
-    width: i32,
-    height: i32
-}
+ fn print_simplification(simplified: &r1cs::simplify::Simplified, name: &dyn Fn(usize) -> String) {
+    for report in &simplified.reports {
+        println!("{}: removed {} constraint(s) and {} wire(s)", report.pass, report.removed_constraints, report.removed_wires);
+    }
+    for (wire, renamed) in simplified.renaming.iter().enumerate() {
+        match renamed {
+            Some(renamed) if *renamed != wire => println!("  {}: w{} -> w{}", name(wire), wire, renamed),
+            Some(_) => {},
+            None => println!("  {} = {}", name(wire), simplified.substitutions[&wire].format(name))
+        }
+    }
+ }
 
-#[derive(Serialize, Debug, Clone)]
-struct Node {
-    id: String,
-    r#type: String,
-    data: NodeData,
-    position: NodePosition,
-    className: String,
-    style: Option<Style>,
-    draggable: Option<bool>,
-    selectable: Option<bool>
-}
+ ///
+ /// `snarkrs compile <file> [--r1cs] [--json] [--sym] [--O0|--O1|--O2] [-o <dir>]`: executes the circuit and
+ /// writes the selected outputs with circom's file names (`<dir>/<file stem>.r1cs`,
+ /// `<dir>/<file stem>_constraints.json`, `<dir>/<file stem>.sym`). The constraints are simplified as circom
+ /// does for the same level, without simplification by default.
+ ///
+ fn run_compile(args: &[String]) -> i32 {
+    let mut path = None;
+    let mut output_dir = ".".to_string();
+    let mut write_r1cs = false;
+    let mut write_json = false;
+    let mut write_sym = false;
+    let mut level = 0;
+    let mut remaining = args.iter();
+    while let Some(arg) = remaining.next() {
+        if let Some(selected) = optimisation_level(arg) {
+            level = selected;
+            continue;
+        }
+        match arg.as_str() {
+            "--r1cs" => write_r1cs = true,
+            "--json" => write_json = true,
+            "--sym" => write_sym = true,
+            "-o" => match remaining.next() {
+                Some(dir) => output_dir = dir.clone(),
+                None => {
+                    eprintln!("-o expects a directory");
+                    return 1;
+                }
+            },
+            _ => path = Some(arg.as_str())
+        }
+    }
+    let path_str = match path {
+        Some(path) if write_r1cs || write_json || write_sym => path,
+        _ => {
+            eprintln!("usage: compile <file> [--r1cs] [--json] [--sym] [--O0|--O1|--O2] [-o <dir>]");
+            return 1;
+        }
+    };
 
-#[derive(Serialize, Debug, Clone)]
-struct Edge{
-    id: String,
-    source: String,
-    target: String,
-    targetHandle: Option<String>
-}
+    let program = match compile_program(path_str, &DiskSource) {
+        Ok((program, _)) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+    let circuit = match execution::execute_with_limits(&program, None, limits()) {
+        Ok(circuit) => circuit,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
 
-fn serialize_for_visual(main_component: Component, heap: Heap) -> Result<(Vec<Node>, Vec<Edge>, Heap)> {
-    let mut nodes : Vec<Node> = vec![];
-    let mut edges : Vec<Edge> = vec![];
-    let mut curr_x = 20;
-    let mut curr_y = 20;
+    let simplified = r1cs::simplify::simplify(&circuit.r1cs(), &r1cs::simplify::passes(level));
+    for report in &simplified.reports {
+        println!("{}: removed {} constraint(s) and {} wire(s)", report.pass, report.removed_constraints, report.removed_wires);
+    }
 
-    let main_template = heap.set_of_used_templates.get(&main_component.template_to_use).expect("main initialization is wrong");
+    let stem = Path::new(path_str).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_else(|| "circuit".to_string());
+    let mut outputs: Vec<(String, Vec<u8>)> = vec![];
+    if write_r1cs {
+        let mut bytes = vec![];
+        r1cs::binary::write(&simplified.r1cs, &mut bytes).expect("writing to memory cannot fail");
+        outputs.push((format!("{}.r1cs", stem), bytes));
+    }
+    if write_json {
+        outputs.push((format!("{}_constraints.json", stem), r1cs::json::to_json(&simplified.r1cs.constraints).into_bytes()));
+    }
+    if write_sym {
+        outputs.push((format!("{}.sym", stem), r1cs::sym::to_sym(&simplified.symbols(&circuit.symbols())).into_bytes()));
+    }
+    for (name, bytes) in outputs {
+        let output = Path::new(&output_dir).join(name);
+        if let Err(error) = fs::write(&output, bytes) {
+            eprintln!("cannot write {}: {}", output.display(), error);
+            return 1;
+        }
+        println!("written {}", output.display());
+    }
+    println!("{} constraint(s), {} wire(s)", simplified.r1cs.constraints.len(), simplified.r1cs.n_wires);
+    0
+ }
 
-    // println!("{:?}", main_template);
-    // println!("{:?}", heap);
+ ///
+ /// `snarkrs inspect <file.r1cs|file.json> [--sym <file.sym>] [--constraint <n>]... [--all]`: prints the
+ /// header of a constraint system and the selected constraints, named after the `.sym` file when given.
+ ///
+ fn run_inspect(args: &[String]) -> i32 {
+    let mut path = None;
+    let mut sym_path = None;
+    let mut selected = vec![];
+    let mut all = false;
+    let mut remaining = args.iter();
+    while let Some(arg) = remaining.next() {
+        match arg.as_str() {
+            "--all" => all = true,
+            "--sym" | "--constraint" => {
+                let value = match remaining.next() {
+                    Some(value) => value,
+                    None => {
+                        eprintln!("{} expects a value", arg);
+                        return 1;
+                    }
+                };
+                if arg == "--sym" {
+                    sym_path = Some(value.as_str());
+                } else {
+                    match value.parse::<usize>() {
+                        Ok(idx) => selected.push(idx),
+                        Err(_) => {
+                            eprintln!("--constraint expects a constraint index, got `{}`", value);
+                            return 1;
+                        }
+                    }
+                }
+            },
+            _ => path = Some(arg.as_str())
+        }
+    }
+    let path_str = match path {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: inspect <file.r1cs|file.json> [--sym <file.sym>] [--constraint <n>]... [--all]");
+            return 1;
+        }
+    };
 
-    nodes.push(Node{
-        id: main_component.name.clone(),
-        r#type: "default".to_string(),
-        className: "annotation".to_string(),
-        data: NodeData{
-            label: main_template.name.clone(),
-            inputHandles: 1
-        },
-        
-        draggable: Some(false),
-        selectable: Some(false),
-        style: None,
-        position:  NodePosition{x:0, y:0}});
+    let (system, names) = match load_r1cs(path_str, sym_path) {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
 
-    nodes.push(Node{
-        id: main_template.name.clone(),
-        r#type: "group".to_string(),
-        data: NodeData{label:main_template.name.clone(), inputHandles: 1},
-        position: NodePosition{x:0, y:100},
-        className: "light".to_string(),
-        style: Some(Style{ backgroundColor: String::from("rgba(255, 0, 0, 0.2)"), width: 900, height: 600}),
-        draggable: Some(true),
-        selectable: Some(true)
-    });
+    println!("{}", path_str);
+    println!("  prime: {} ({})", system.prime, libsnarkrs::r1cs::field::prime_name(&system.prime).unwrap_or("unknown"));
+    println!("  field size: {} bytes", r1cs::binary::field_size(&system.prime));
+    println!("  wires: {} ({} labels)", system.n_wires, system.n_labels);
+    println!("  constraints: {}", system.constraints.len());
+    println!("  public outputs: {}, public inputs: {}, private inputs: {}", system.n_pub_out, system.n_pub_in, system.n_prv_in);
+    if !system.custom_gates.is_empty() {
+        println!("  custom gates: {} ({} applications)", system.custom_gates.len(), system.custom_gate_applications.len());
+    }
 
-    curr_x = 20;
-    curr_y = 100;
-    for input_signal in &main_template.private_input_signals {
-        let cont = get_actual_value_for_signals_components(&input_signal.size_per_dimension, &input_signal.name, &heap.variable_to_value_map);
-        for a_input_signal in cont { 
-            nodes.push(Node{
-                id: a_input_signal.clone(),
-                r#type: "input".to_string(),
-                data: NodeData{label:format!("{} - value {}", a_input_signal.clone(), &heap.variable_to_value_map[&a_input_signal]), inputHandles: 1},
-                position: NodePosition{x:curr_x, y:curr_y},
-                className: "light".to_string(),
-                style: None,
-                draggable: Some(true),
-                selectable: Some(true)
-            });
-            curr_x = curr_x + 150;
-        }
-    }
-
-    curr_y = curr_y + 50;
-    curr_x = 20;
-    for interm_signal in &main_template.intermediate_signals {
-        println!("{} {:?}", interm_signal.name, &heap.variable_to_value_map);
-
-        nodes.push(Node{
-            id: interm_signal.name.clone(),
-            r#type: "default".to_string(),
-            data: NodeData{label:format!("{} value - {}", interm_signal.name.clone(), heap.variable_to_value_map[&interm_signal.name]), inputHandles: 1},
-            position: NodePosition{x:curr_x, y:curr_y},
-            className: "light".to_string(),
-            style: None,
-            draggable: Some(true),
-            selectable: Some(true)
-        });
-        curr_x = curr_x + 150;
-        for neighbour in heap.var_depending_on[&interm_signal.name].iter() {        
-            edges.push(Edge{
-                id: format!("e{}-{}", neighbour, interm_signal.name),
-                source: neighbour.clone(),
-                target: interm_signal.name.clone(),
-                targetHandle: None,
-            });
-        }
-    }
-
-    curr_y = curr_y + 50;
-    curr_x = 20;
-    for (comp_name, sub_comp) in heap.variable_to_component_map.clone().into_iter() {
-        nodes.push(Node{
-            id: comp_name.clone(),
-            r#type: "customNode".to_string(),
-            data: NodeData{label:comp_name.clone(), inputHandles: 2},
-            position: NodePosition{x:curr_x, y:curr_y},
-            className: "light".to_string(),
-            style: Some(Style{ backgroundColor: String::from("rgba(255, 0, 0, 0.2)"), width: 100, height:120}),
-            draggable: Some(true),
-            selectable: Some(true)
-        });
-        curr_x = curr_x + 200;
-        for (tmp_item, values) in heap.var_depending_on.iter() {
-            if !tmp_item.starts_with(&comp_name) {
-                continue;
-            }
-            for v in values.iter(){
-                edges.push(Edge{
-                    id: format!("e{}-{}", v, comp_name),
-                    source: v.clone(),
-                    target: comp_name.clone(),
-                    targetHandle: Some(tmp_item.clone())
-                });
+    if all {
+        selected = (0..system.constraints.len()).collect();
+    }
+    let name = |wire: usize| names.get(&wire).cloned().unwrap_or_else(|| format!("w{}", wire));
+    for idx in selected {
+        match system.constraints.get(idx) {
+            Some(constraint) => println!("{}: {}", idx, constraint.format_over(&system.prime, &name)),
+            None => {
+                eprintln!("there is no constraint {}, the system has {}", idx, system.constraints.len());
+                return 1;
             }
         }
     }
+    0
+ }
 
-    curr_y = curr_y + 200;
-    curr_x = 20;
-    for signal in &main_template.output_signals {
-        nodes.push(Node{
-            id: signal.name.clone(),
-            r#type: "output".to_string(),
-            data: NodeData{label:format!("{} value - {}", signal.name.clone(), heap.variable_to_value_map[&signal.name]), inputHandles: 1},
-            position: NodePosition{x:curr_x, y:curr_y},
-            className: "light".to_string(),
-            style: None,
-            draggable: Some(true),
-            selectable: Some(true)
-        });
-        curr_x = curr_x + 200;
-        for neighbour in heap.var_depending_on[&signal.name].iter() {
-            // println!("- neighbour {}", neighbour);
-        
-            edges.push(Edge{
-                id: format!("e{}-{}", neighbour, signal.name),
-                source: neighbour.replace(".out", "").clone(),
-                target: signal.name.clone(),
-                targetHandle: None
-            });
-        }
-    }
-    // println!("{:?}", nodes);
-    // println!("{:?}", edges);
-    Ok((nodes, edges, heap))
-}
-
-fn produce_signals(base_name: String, limit_per_dimension: &[i32]) -> Vec<String> {
-    let mut names = vec![];
-
-    if limit_per_dimension.len() == 0 {
-        names.push(base_name);
-        return names;
-    } else {
-        for x in 0..limit_per_dimension[0] as usize {
-            let temp_name = format!("{}[{}]", base_name, x.to_string());
-            for tmp_name in produce_signals(temp_name, &limit_per_dimension[1..]) {
-                names.push(tmp_name);
-            }
+ ///
+ /// `snarkrs witness <file> <input.json> [<output.wtns>] [--O0|--O1|--O2]`: executes the circuit with the
+ /// given inputs and writes every signal value, in wire order, to `witness.wtns` unless told otherwise. With a
+ /// simplification level, only the wires `compile` keeps at that level are written.
+ ///
+ fn run_witness(args: &[String]) -> i32 {
+    let level = args.iter().filter_map(|arg| optimisation_level(arg)).next_back().unwrap_or(0);
+    let args: Vec<&String> = args.iter().filter(|arg| optimisation_level(arg).is_none()).collect();
+    let (path_str, input_path) = match args[..] {
+        [path, input, ..] => (path.as_str(), input.as_str()),
+        _ => {
+            eprintln!("usage: witness <file> <input.json> [<output.wtns>] [--O0|--O1|--O2]");
+            return 1;
         }
-    } 
-    names
-}
+    };
+    let output = args.get(2).map(|output| output.as_str()).unwrap_or("witness.wtns");
 
-fn get_actual_value_for_signals_components(csize_per_dimension: &Vec<String>, cname: &String, variable_to_value_map: &HashMap<String, i32>) -> Vec<String> {
-    let mut actual_value_vector = vec![];
+    let inputs = match fs::read_to_string(input_path).and_then(|text| execution::inputs::from_json(&text)) {
+        Ok(inputs) => inputs,
+        Err(error) => {
+            eprintln!("{}: {}", input_path, error);
+            return 1;
+        }
+    };
+    let program = match compile_program(path_str, &DiskSource) {
+        Ok((program, _)) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+    let circuit = match execution::execute_with_limits(&program, Some(&inputs), limits()) {
+        Ok(circuit) => circuit,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+    let witness = match circuit.witness() {
+        Ok(witness) => r1cs::simplify::simplify(&circuit.r1cs(), &r1cs::simplify::passes(level)).witness(&witness),
+        Err(signal) => {
+            eprintln!("the value of {} could not be computed", signal);
+            return 1;
+        }
+    };
 
-    if csize_per_dimension.len() == 0 {
-        actual_value_vector.push(cname.clone());
-        return actual_value_vector;
+    let written = File::create(output).and_then(|mut file| r1cs::wtns::write(&witness, &mut file));
+    if let Err(error) = written {
+        eprintln!("cannot write {}: {}", output, error);
+        return 1;
     }
-    let mut total_count = 1;
-    let mut limit_per_dimension = vec![];
-    for dimension in csize_per_dimension {
-        match variable_to_value_map.get(dimension) {
-            Some(size) => {
-                limit_per_dimension.push(size.clone());
-                total_count = total_count * size;
-            },
-            None => {
-                match dimension.parse::<i32>() {
-                    Ok(size) => {
-                        limit_per_dimension.push(size.clone());
-                        total_count = total_count * size;
-                    },
-                    Err(e) => {
-                        println!("PANIC!!! Argument {} not initialized!", dimension);
-                        break;
-                    }
-                }
+    println!("written {}: {} value(s)", output, witness.len());
+    0
+ }
 
-            }
+ ///
+ /// `snarkrs trace <file> [<input.json>] [-o <trace.json>]`: executes the circuit, with the given inputs to
+ /// compute values, and writes every statement run with the signals and variables it changed to `trace.json`
+ /// unless told otherwise. The trace is written when the execution fails too, to replay how it got there.
+ ///
+ fn run_trace(args: &[String]) -> i32 {
+    let mut paths = vec![];
+    let mut output = "trace.json";
+    let mut remaining = args.iter();
+    while let Some(arg) = remaining.next() {
+        match arg.as_str() {
+            "-o" => match remaining.next() {
+                Some(value) => output = value.as_str(),
+                None => {
+                    eprintln!("-o expects a value");
+                    return 1;
+                }
+            },
+            _ => paths.push(arg.as_str())
         }
     }
-    actual_value_vector.append(&mut produce_signals(cname.clone(), &limit_per_dimension[0..]));
-    actual_value_vector
-}
+    let (path_str, input_path) = match paths[..] {
+        [path] => (path, None),
+        [path, input] => (path, Some(input)),
+        _ => {
+            eprintln!("usage: trace <file> [<input.json>] [-o <trace.json>]");
+            return 1;
+        }
+    };
 
-fn evaluate(bool_exp: BoolExpression, variable_to_value_map:&mut HashMap<String, i32>) -> bool{
-    let mut lhs_value :i32 = 0;
-    let mut rhs_value :i32 = 0;
-    match variable_to_value_map.get(&bool_exp.lhs) {
-        Some(lhs_v) => {lhs_value=lhs_v.clone()},
-        None => {println!("PANIC!! 1072");}
+    let inputs = match input_path.map(|input_path| fs::read_to_string(input_path).and_then(|text| execution::inputs::from_json(&text))) {
+        Some(Ok(inputs)) => Some(inputs),
+        Some(Err(error)) => {
+            eprintln!("{}: {}", input_path.unwrap_or_default(), error);
+            return 1;
+        },
+        None => None
+    };
+    let program = match compile_program(path_str, &DiskSource) {
+        Ok((program, _)) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+    let (trace, result) = execution::trace::record(&program, inputs.as_ref(), limits());
+    if let Err(error) = fs::write(output, trace.to_json()) {
+        eprintln!("cannot write {}: {}", output, error);
+        return 1;
     }
-    match variable_to_value_map.get(&bool_exp.rhs) {
-        Some(rhs_v) => {rhs_value=rhs_v.clone()},
-        None => {println!("PANIC!! 1076");}
+    println!("written {}: {} step(s)", output, trace.steps.len());
+    match result {
+        Ok(_) => 0,
+        Err(error) => {
+            eprintln!("{}", error);
+            1
+        }
     }
-    let operation = bool_exp.operation.trim();
-    if operation.eq("<") {
-        return lhs_value < rhs_value;
+ }
+
+ ///
+ /// `snarkrs replay <trace.json>`: walks a trace written by `snarkrs trace` forwards and backwards, with the
+ /// commands typed on stdin, showing what each step changed and the values once it ran.
+ ///
+ fn run_replay(args: &[String]) -> i32 {
+    let path = match args {
+        [path] => path,
+        _ => {
+            eprintln!("usage: replay <trace.json>");
+            return 1;
+        }
+    };
+    let trace = match fs::read_to_string(path).and_then(|text| execution::trace::Trace::from_json(&text)) {
+        Ok(trace) => trace,
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            return 1;
+        }
+    };
+    if let Some(error) = &trace.error {
+        eprintln!("{}", error);
     }
-    if operation.eq("<=") {
-        return lhs_value <= rhs_value;
+    let mut replay = execution::trace::Replay::new(&trace);
+    println!("{}", replay.step());
+    println!("type :help for the commands, :quit to leave");
+    match replay.run(&mut std::io::stdin().lock(), &mut std::io::stdout()) {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("{}", error);
+            1
+        }
     }
-    if operation.eq(">") {
-        return lhs_value > rhs_value;
+ }
+
+ ///
+ /// `snarkrs check-witness <circuit> <witness> [--sym <file.sym>]`: evaluates every constraint with the
+ /// witness and reports the ones that do not hold, with the instance and statement that generated them when
+ /// the circuit is a `.circom` file. Otherwise the circuit is a `.r1cs` file or a `--json` dump, named with
+ /// `--sym`. The witness is the inputs of a `.circom` circuit as JSON, or a `.wtns` file of an `.r1cs` or a
+ /// dump: circom numbers the wires of a `.wtns` after simplifying, they do not match the executed circuit.
+ /// Exits with 1 when a constraint does not hold.
+ ///
+ fn run_check_witness(args: &[String]) -> i32 {
+    let mut paths = vec![];
+    let mut sym_path = None;
+    let mut remaining = args.iter();
+    while let Some(arg) = remaining.next() {
+        match arg.as_str() {
+            "--sym" => match remaining.next() {
+                Some(value) => sym_path = Some(value.as_str()),
+                None => {
+                    eprintln!("--sym expects a value");
+                    return 1;
+                }
+            },
+            _ => paths.push(arg.as_str())
+        }
     }
-    if operation.eq(">=") {
-        return lhs_value >= rhs_value;
+    let (path_str, witness_path) = match paths[..] {
+        [path, witness] => (path, witness),
+        _ => {
+            eprintln!("usage: check-witness <file.circom|file.r1cs|file.json> <file.wtns|input.json> [--sym <file.sym>]");
+            return 1;
+        }
+    };
+    if path_str.ends_with(".circom") && !witness_path.ends_with(".json") {
+        eprintln!("{}: the wires of a .wtns are those circom kept after simplification, check it against the .r1cs of the same compilation", witness_path);
+        return 1;
     }
-    false
-}
 
-fn generate_string_from_variable(var: &ExecutionVariable, variable_to_value_map: &mut HashMap<String, i32>) -> (String, String) {
-    let mut result = format!("{}", var.id).to_string();
-    for x in &var.indexing {
-        match variable_to_value_map.get(x) {
-            Some(value) => {result = format!("{}[{}]", result, value).to_string();},
-            None => {result = format!("{}[{}]", result, x).to_string();}
-        }
-    }
-    let mut signal = "".to_string();
-    match &var.sub_variable {
-        Some(sub_var) => {
-            if sub_var.id.len() > 0 {
-                result = format!("{}.{}", result, signal);
-                signal = format!("{}", sub_var.id).to_string();
-                for x in &sub_var.indexing {
-                    match variable_to_value_map.get(x) {
-                        Some(value) => {signal = format!("{}[{}]", signal, value).to_string();},
-                        None => {signal = format!("{}[{}]", signal, x).to_string();}
-                    }
+    // the executed circuit knows where each constraint comes from
+    let mut executed = None;
+    let (constraints, names) = match path_str.ends_with(".circom") {
+        true => {
+            let program = match compile_program(path_str, &DiskSource) {
+                Ok((program, _)) => program,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    return 1;
                 }
-            }
+            };
+            let inputs = match fs::read_to_string(witness_path).and_then(|text| execution::inputs::from_json(&text)) {
+                Ok(inputs) => inputs,
+                Err(error) => {
+                    eprintln!("{}: {}", witness_path, error);
+                    return 1;
+                }
+            };
+            let circuit = execution::execute_with_limits(&program, Some(&inputs), limits());
+            let circuit = match circuit {
+                Ok(circuit) => circuit,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    return 1;
+                }
+            };
+            let names: HashMap<usize, String> = circuit.signals.iter().enumerate().map(|(wire, signal)| (wire, signal.name.clone())).collect();
+            let constraints = circuit.constraints.clone();
+            executed = Some((program, circuit));
+            (constraints, names)
         },
-        None => {println!("PANIC!! 1116");}
+        false => match load_r1cs(path_str, sym_path) {
+            Ok((system, _)) if !system.is_bn254() => {
+                eprintln!("{}", not_bn254(path_str, &system.prime));
+                return 1;
+            },
+            Ok((system, names)) => (system.constraints, names),
+            Err(error) => {
+                eprintln!("{}", error);
+                return 1;
+            }
+        }
     };
-    (result, signal.to_string())
-}
 
-fn execute(single_command:&SingleCommand, heap: &mut Heap, template_map: &HashMap<String, Template>) {
-    println!("~ {:?}", single_command);
-    match single_command {
-        SingleCommand::ForLoop(for_loop) => {
-            // println!("For loop {}",for_loop.index);
-            // println!("Heap {:?}", heap);
-            let mut curr_value = for_loop.start_value;
-            heap.variable_to_value_map.insert(for_loop.index.clone(), for_loop.start_value.clone());
-            let mut condition = evaluate(for_loop.condition.clone(), &mut heap.variable_to_value_map);
-            while condition {
-                for command in for_loop.body.clone() {
-                    execute(&command, heap, template_map);
-                }
-                curr_value = curr_value + for_loop.step as i32;
-                heap.variable_to_value_map.insert(for_loop.index.clone(), curr_value);
-                condition = evaluate(for_loop.condition.clone(), &mut heap.variable_to_value_map);
-                if !condition {
-                    heap.variable_to_value_map.remove(&for_loop.index);
-                }
+    let witness = match (&executed, witness_path.ends_with(".json")) {
+        (Some((_, circuit)), true) => match circuit.witness() {
+            Ok(witness) => witness,
+            Err(signal) => {
+                eprintln!("the value of {} could not be computed", signal);
+                return 1;
             }
         },
-        SingleCommand::Instruction(instruction) => {
-           match &instruction.statement {
-                Stmt::Assign(assign) => {
-                    match assign.assign_op {
-                        Operator::Assignment => {
-                            let evaluated_target = expression_parser::evaluate(&Expr::ComplexVariable(assign.target.clone()), &mut heap.variable_to_value_map);
-                            let evaluated_value = expression_parser::evaluate(&assign.value, &mut heap.variable_to_value_map);
-                            println!("Executing evaluated_target {:?}", evaluated_target);
-                            println!("Executing evaluated_value {:?}", evaluated_value);
-                            match evaluated_target {
-                                (EvaluationResult::Identifier(iden), vec) => {
-                                    match evaluated_value {
-                                        (EvaluationResult::ComponentInstance(component_instance), vec) => {
-                                            let tmp_comp = Component{
-                                                name: iden.clone(),
-                                                template_to_use: component_instance.name.clone(),
-                                                size_per_dimension: vec![],
-                                                arguments: component_instance.parameter_list.clone(),
-                                            };
-                                            heap.variable_to_component_map.insert(iden.clone(), tmp_comp);
-                                            heap.var_depending_on.insert(iden, Box::new(vec));
-                                        },
-                                        (EvaluationResult::Value(num), vec) => {
-                                            heap.variable_to_value_map.insert(iden, num);
-                                        },
-                                        _ => {}
-                                    }
-                                },
-                                _ => {}
-                            }
-                        },
-                        _ => {}
-                    }
-                
-                }, 
-                Stmt::Constraint(constraint) => {
-                    let const_trgt = constraint.target.serialize(&mut heap.variable_to_value_map);
-                    let evaluated_target = expression_parser::evaluate(&Expr::ComplexVariable(constraint.target.clone()), &mut heap.variable_to_value_map);
-                    let (evaluated_value, vec2) = expression_parser::evaluate(&constraint.value, &mut heap.variable_to_value_map);
-                    // println!("Executing evaluated_target {:?}", evaluated_target);
-                    println!("Executing evaluated_value {:?}", evaluated_value);
-                    println!("const_trgt {:?}", const_trgt);
-                    // println!("heap {:?}", heap);
-                    heap.var_depending_on.insert(const_trgt.clone(), Box::new(vec2));
-                    match evaluated_target {
-                        (EvaluationResult::Identifier(iden), vec1) => {
-                            match evaluated_value {
-                                EvaluationResult::Value(num) => {
-                                    heap.variable_to_value_map.insert(iden, num);
-                                },
-                                EvaluationResult::Identifier(iden3) => {
-                                    // println!("constraint values {:?}", constraint.value);
-                                    // println!("iden3 {:?}", iden3);
-                                    match &constraint.value {
-                                        Expr::ComplexVariable(compl) => {
-                                            if heap.variable_to_component_map.contains_key(&compl.id) {
-                                                let comp: &Component = heap.variable_to_component_map.get(&compl.id).unwrap();
-                                                let mut sub_heap = heap.variable_to_heap_map.get_mut(&compl.id).unwrap();
-                                                for (k, v) in heap.variable_to_value_map.iter() {
-                                                    if k.starts_with(&compl.id) {
-                                                        let m = format!("{}.",compl.id).to_string();
-                                                        sub_heap.variable_to_value_map.insert(k.replace(&m, ""), *v);
-                                                    }
-                                                }
-                                                // println!("sub_heap before {:?}", sub_heap.variable_to_value_map);
-                                                execute_component(comp, &mut sub_heap, template_map);
-                                                // sub_heap.set_of_used_templates = HashMap::new();
-                                                let m = format!("{}.out",compl.id).to_string();
-                                                println!("after heap {:?}", sub_heap.variable_to_value_map);
-                                                heap.variable_to_value_map.insert(m, *sub_heap.variable_to_value_map.get("out").unwrap());
-                                            }
-                                        },
-                                        Expr::Conditional { condition, true_value, false_value } => {
-                                            println!("conditional {:?}", true_value);
-                                            let _evaluated_condition = expression_parser::evaluate(condition, &mut heap.variable_to_value_map);
-                                            
-                                            let (true_case, vec3): (EvaluationResult, Vec<String>) = expression_parser::evaluate(true_value, &mut heap.variable_to_value_map);
-                                            let _false_case = expression_parser::evaluate(false_value, &mut heap.variable_to_value_map);
-                                            match true_case {
-                                                EvaluationResult::Value(v) => {
-                                                    heap.variable_to_value_map.insert(const_trgt.clone(), v);
-                                                },
-                                                _ => {}
-                                            }
-                                        },
-                                        _ => {}
-                                    }
-                                    let tmp = heap.variable_to_value_map.get(&iden3).unwrap();
-                                    heap.variable_to_value_map.insert(iden, *tmp);
-                                },
-                                _ => {}
-                            }
-                        },
-                        _ => {}
-                    }
-                },
-                Stmt::ConditionalAssign(cond_assign) => {
-                    println!("Executing cond_assign {:?}", cond_assign);
-                }, 
-                // Stmt::Constraint(constraint) => {
-                //     println!("Executing constraint {:?}", constraint);
-                // },
-                Stmt::RegularExpr(expr) => {
-                    expression_parser::evaluate(expr, &mut heap.variable_to_value_map);
-                    println!("Executing expr {:?}", expr);
-                }, 
-                // Stmt::Assert(assert) => {
-                //     println!("Executing assert {:?}", assert);
-                // }, 
-                _ => {
+        (None, true) => {
+            eprintln!("a JSON witness holds inputs, it needs the .circom circuit");
+            return 1;
+        },
+        (_, false) => match File::open(witness_path).and_then(|mut file| r1cs::wtns::read(&mut file)) {
+            Ok(witness) if !witness.is_bn254() => {
+                eprintln!("{}", not_bn254(witness_path, &witness.prime));
+                return 1;
+            },
+            Ok(witness) => witness.values,
+            Err(error) => {
+                eprintln!("{}: {}", witness_path, error);
+                return 1;
+            }
+        }
+    };
 
-                }
+    let unsatisfied = match r1cs::satisfaction::check(&constraints, &witness) {
+        Ok(unsatisfied) => unsatisfied,
+        Err(idx) => {
+            eprintln!("constraint {} uses a wire past the {} value(s) of the witness", idx, witness.len());
+            return 1;
+        }
+    };
+    let name = |wire: usize| names.get(&wire).cloned().unwrap_or_else(|| format!("w{}", wire));
+    for failure in &unsatisfied {
+        println!("constraint {} does not hold: {}", failure.index, constraints[failure.index].format(&name));
+        println!("\tA = {}, B = {}, C = {}", failure.a.to_signed(), failure.b.to_signed(), failure.c.to_signed());
+        if let Some((program, circuit)) = &executed {
+            if let Some(instance) = circuit.instance_of(failure.index) {
+                let source_line = match program.sources.get(&instance.file) {
+                    Some(source) => compile_errors::common_displayer::source_line(source, &circuit.spans[failure.index]),
+                    None => String::new()
+                };
+                println!("\tin {} ({}), {}: {}", instance.path, instance.template, instance.file, source_line);
             }
-        },
-        SingleCommand::DeclarationStatement(decl_statement) => {
-            if decl_statement.decl_type == DeclType::Signal || decl_statement.decl_type == DeclType::Variable {
-                let signal_vector = get_actual_value_for_signals_components(&decl_statement.size_per_dimension, &decl_statement.name, &mut heap.variable_to_value_map);
-                for signal in signal_vector{
-                    heap.variable_set.insert(signal.clone());
-                    match &decl_statement.expression {
-                        Stmt::RegularExpr(expr) => {
-                            let val = expression_parser::evaluate(&expr, &mut heap.variable_to_value_map);
-                            // println!("Valuelue {:?}", val);
-                            match val {
-                                (EvaluationResult::Value(number), vec) => {
-                                    heap.variable_to_value_map.insert(signal.clone(), number.clone());
-                                    heap.var_depending_on.insert(signal.clone(), Box::new(vec));
-                                    // println!("heap new {:?}", &heap.var_depending_on);
-                                },
-                                _ => {}
-                            }
-                        },
-                        _ => {}
-                    }
-                    if decl_statement.decl_type == DeclType::Variable {
-                        heap.variable_to_value_map.insert(signal.clone(), 0);
-                    }
+        }
+    }
+    println!("{}: {} of {} constraint(s) hold", witness_path, constraints.len() - unsatisfied.len(), constraints.len());
+    if unsatisfied.is_empty() { 0 } else { 1 }
+ }
+
+ ///
+ /// `snarkrs prove <file.circom> <input.json> [--O0|--O1|--O2] [-o <dir>]`, or
+ /// `snarkrs prove <file.r1cs> <file.wtns> [-o <dir>]`: runs a Groth16 setup for the circuit, proves the
+ /// witness and verifies the proof, then writes `proof.json`, `public.json` and `verification_key.json` as
+ /// snarkjs does. A `.circom` circuit is simplified as with `--O2` unless told otherwise. The setup is not
+ /// secret, the keys are only for testing.
+ ///
+ fn run_prove(args: &[String]) -> i32 {
+    let mut paths = vec![];
+    let mut level = 2;
+    let mut output_dir = ".".to_string();
+    let mut remaining = args.iter();
+    while let Some(arg) = remaining.next() {
+        if let Some(selected) = optimisation_level(arg) {
+            level = selected;
+            continue;
+        }
+        match arg.as_str() {
+            "-o" => match remaining.next() {
+                Some(dir) => output_dir = dir.clone(),
+                None => {
+                    eprintln!("-o expects a directory");
+                    return 1;
                 }
+            },
+            _ => paths.push(arg.as_str())
+        }
+    }
+    let (path_str, witness_path) = match paths[..] {
+        [path, witness] => (path, witness),
+        _ => {
+            eprintln!("usage: prove <file.circom> <input.json> [--O0|--O1|--O2] [-o <dir>] | prove <file.r1cs> <file.wtns> [-o <dir>]");
+            return 1;
+        }
+    };
 
-            } else {
-                let component_vector = get_actual_value_for_signals_components(&decl_statement.size_per_dimension, &decl_statement.name, &mut heap.variable_to_value_map);
-                for component in &component_vector{
-                    heap.component_set.insert(component.clone());
+    let (system, witness) = match path_str.ends_with(".circom") {
+        true => {
+            let inputs = match fs::read_to_string(witness_path).and_then(|text| execution::inputs::from_json(&text)) {
+                Ok(inputs) => inputs,
+                Err(error) => {
+                    eprintln!("{}: {}", witness_path, error);
+                    return 1;
                 }
-                if component_vector.len() == 1 {
-                    // println!("decl_statement {:?}", decl_statement);
-                    let tmp_component = Component{
-                        name: component_vector[0].clone(),
-                        template_to_use: decl_statement.template_to_use.clone(),
-                        size_per_dimension: vec![],
-                        arguments: vec![],
-                    };
-                    heap.variable_to_component_map.insert(component_vector[0].clone(), tmp_component.clone());
-                    heap.variable_to_heap_map.insert(component_vector[0].clone(), Heap{
-                        current_component: tmp_component,
-                        variable_set: HashSet::new(),
-                        component_set: HashSet::new(),
-                        variable_to_value_map: HashMap::new(),
-                        variable_to_component_map: HashMap::new(),
-                        variable_to_heap_map : HashMap::new(),
-                        var_depending_on: HashMap::new(),
-                        set_of_used_templates: heap.set_of_used_templates.clone()
-                    });
+            };
+            let program = match compile_program(path_str, &DiskSource) {
+                Ok((program, _)) => program,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    return 1;
                 }
-            }
+            };
+            let circuit = match execution::execute_with_limits(&program, Some(&inputs), limits()) {
+                Ok(circuit) => circuit,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    return 1;
+                }
+            };
+            let witness = match circuit.witness() {
+                Ok(witness) => witness,
+                Err(signal) => {
+                    eprintln!("the value of {} could not be computed", signal);
+                    return 1;
+                }
+            };
+            let simplified = r1cs::simplify::simplify(&circuit.r1cs(), &r1cs::simplify::passes(level));
+            let witness = simplified.witness(&witness);
+            (simplified.r1cs, witness)
         },
-    }
-
-}
-
-fn execute_component(component: &Component, mut heap: &mut Heap, template_map: &HashMap<String, Template>) {
-    match template_map.get(&component.template_to_use) {
-        Some(template) => {
-            // println!("Executing component {:?} with {} commands", component.name, template.instructions.len());
-            for command in &template.instructions {
-                // println!("Executing command {:?}", command);
-                execute(&command, &mut heap, &template_map);
+        false => {
+            let loaded = File::open(path_str).and_then(|mut file| r1cs::binary::read(&mut file))
+                .map_err(|error| anyhow!("{}: {}", path_str, error))
+                .and_then(|system| match system.is_bn254() {
+                    true => Ok(system),
+                    false => Err(not_bn254(path_str, &system.prime))
+                })
+                .and_then(|system| File::open(witness_path).and_then(|mut file| r1cs::wtns::read(&mut file))
+                    .map_err(|error| anyhow!("{}: {}", witness_path, error))
+                    .and_then(|witness| match witness.is_bn254() {
+                        true => Ok((system, witness.values)),
+                        false => Err(not_bn254(witness_path, &witness.prime))
+                    }));
+            match loaded {
+                Ok(loaded) => loaded,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    return 1;
+                }
             }
+        }
+    };
+
+    // a witness that does not satisfy the constraints gives a proof that does not verify, say why instead
+    match r1cs::satisfaction::check(&system.constraints, &witness) {
+        Ok(unsatisfied) if unsatisfied.is_empty() => {},
+        Ok(unsatisfied) => {
+            eprintln!("{} constraint(s) do not hold, see `check-witness`", unsatisfied.len());
+            return 1;
         },
-        None => {}
+        Err(idx) => {
+            eprintln!("constraint {} uses a wire past the {} value(s) of the witness", idx, witness.len());
+            return 1;
+        }
     }
-    // println!("Finished component {:?}, heap: {:?}", component, heap.variable_to_value_map);
-}
-
-#[derive(Eq, PartialEq, Debug, Clone)]
-struct Heap{
-    current_component: Component,
-    variable_set: HashSet<String>,
-    component_set: HashSet<String>,
-    variable_to_value_map: HashMap<String, i32>,
-    variable_to_component_map: HashMap<String, Component>,
-    variable_to_heap_map: HashMap<String, Heap>,
-    var_depending_on: HashMap<String, Box<Vec<String>>>,
-    set_of_used_templates: HashMap<String, Template>
-}
 
-fn extract_original_content_from_span(path_to_content_map: &HashMap<String, String>, span: (usize, usize), file_path: &String) -> expression_parser::Stmt {
-    let mut result = "";
-    match path_to_content_map.get(file_path) {
-        Some(content) => {
-            result = &content[span.0..span.1];
+    let seed = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|elapsed| elapsed.as_nanos() as u64).unwrap_or(0);
+    let proved = libsnarkrs::groth16::setup(&system, seed).and_then(|keys| {
+        let proof = libsnarkrs::groth16::prove(&system, &keys, &witness, seed.wrapping_add(1))?;
+        let public = libsnarkrs::groth16::public_signals(&system, &witness);
+        let verified = libsnarkrs::groth16::verify(&keys.verifying_key, &public, &proof)?;
+        Ok((keys, proof, public, verified))
+    });
+    let (keys, proof, public, verified) = match proved {
+        Ok(proved) => proved,
+        Err(error) => {
+            eprintln!("proving failed: {}", error);
+            return 1;
         }
-        None => {}
+    };
+    if !verified {
+        eprintln!("the proof does not verify");
+        return 1;
     }
-    // println!("Try to parse {}", result);
 
-    expression_parser::parse_statement(result)
+    let outputs = [
+        ("proof.json", libsnarkrs::groth16::snarkjs::proof(&proof)),
+        ("public.json", libsnarkrs::groth16::snarkjs::public(&public)),
+        ("verification_key.json", libsnarkrs::groth16::snarkjs::verification_key(&keys.verifying_key))
+    ];
+    for (name, content) in outputs {
+        let output = Path::new(&output_dir).join(name);
+        if let Err(error) = fs::write(&output, serde_json::to_string_pretty(&content).unwrap()) {
+            eprintln!("cannot write {}: {}", output.display(), error);
+            return 1;
+        }
+        println!("written {}", output.display());
+    }
+    println!("proof verified: {} constraint(s), {} public signal(s)", system.constraints.len(), public.len());
+    0
  }
 
- fn extract_values(path_str: String) -> Result<(Vec<Node>, Vec<Edge>, Heap)> {
-    let mut template_map: HashMap<String, Template> = HashMap::new();
-    let mut main_component: Component = Component{
-        name: String::from(""),
-        template_to_use: String::from(""),
-        size_per_dimension: vec![],
-        arguments: vec![],
+ ///
+ /// `snarkrs diff <file> <left.json> <right.json> [--all]`: executes the circuit with both sets of inputs and
+ /// prints the signals whose values differ, or all of them, in execution order. The first one that differs
+ /// besides the inputs of main is marked with `>`. Exits with 1 when the two runs do not end the same way.
+ ///
+ fn run_diff(args: &[String]) -> i32 {
+    let all = args.iter().any(|arg| arg == "--all");
+    let paths: Vec<&str> = args.iter().filter(|arg| *arg != "--all").map(|arg| arg.as_str()).collect();
+    let (path_str, left_path, right_path) = match paths[..] {
+        [path, left, right] => (path, left, right),
+        _ => {
+            eprintln!("usage: diff <file> <left.json> <right.json> [--all]");
+            return 1;
+        }
     };
-    let mut path_to_content_map = HashMap::<String, String>::new();
-    let path = std::fs::canonicalize(path_str).expect("Invalid Path");
-
-    let ctx = compile::build_context(&path);    
-
-    for (path, source_file) in ctx.files {
-        let path_as_string = path.clone().into_os_string().into_string().unwrap();
-        // if !path_as_string.contains("multi") {
-        //     continue;
-        // }
-        let content = fs::read_to_string(&path_as_string).expect("Failed to read file");
-        
-        path_to_content_map.insert(path_as_string.clone(), content);
-        // println!("{:?}", path_as_string);
-        if let libsnarkrs::parser::compile::LoadAttempt::Loaded(file) = source_file {
-            // write!(output_file, "{:?}", file.root.ast);
-            if let libsnarkrs::parser::ast::tokens::Token::NonTerminal(token) = &file.root.ast[0] {
-                // println!("{:?}", token.rule);
-                let (main_component_tmp, templates) = find_templates(&token.subrules, &path_as_string, &path_to_content_map);
-                match main_component_tmp {
-                    Some(component) => {
-                        main_component = component;
-                    },
-                    None => {}
-                }
-                // println!("These are {:?} templates. ", templates);
-                for template in templates {
-                    let tmp_name = template.name.clone();
-                    template_map.insert(tmp_name, template.clone());
-                    
-                    // println!(" - {}", template.name);
-                    // for param in template.params {
-                    //     // println!(" Params: {}", param);
-                    // }
-                    // println!("Private input length {}", template.private_input_signals.len());
-                    // for pi_signal in template.private_input_signals {
-                    //     println!(" Private input signal: {}", pi_signal.name);
-                    //     println!(" Private signal dimension: {}", pi_signal.size_per_dimension.len());
-                    // }
-                    // for o_signal in template.output_signals {
-                    //     println!(" Output signal: {}", o_signal.name);
-                    //     println!(" Output signal dimension: {}", o_signal.size_per_dimension.len());
-                    // }
-                    // for i_signal in &template.intermediate_signals {
-                    //     println!(" Intermediate signal: {}", i_signal.name.clone());
-                    //     println!(" Intermediate signal dimension: {}", i_signal.size_per_dimension.len());
-                    // }
-                    // for components in template.components {
-                    //     println!(" Components: {}", components.name);
-                    //     println!(" Component dimension: {}", components.size_per_dimension.len());
-                    //     println!(" Component is of type: {}", components.template_to_use);
-                    // }
+    let read = |path: &str| fs::read_to_string(path).and_then(|text| execution::inputs::from_json(&text)).map_err(|error| format!("{}: {}", path, error));
+    let (left, right) = match (read(left_path), read(right_path)) {
+        (Ok(left), Ok(right)) => (left, right),
+        (Err(error), _) | (_, Err(error)) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+    let program = match compile_program(path_str, &DiskSource) {
+        Ok((program, _)) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+
+    let diff = execution::diff::compare(&program, &left, &right, limits());
+    let display = |value: &Option<r1cs::field::FieldElement>| value.as_ref().map(|value| value.to_signed().to_string()).unwrap_or_else(|| "unknown".to_string());
+    for (idx, signal) in diff.signals.iter().enumerate() {
+        if !all && !signal.differs() {
+            continue;
+        }
+        let marker = if diff.first == Some(idx) { ">" } else { " " };
+        println!("{} {} = {} | {}    ({} in {}:{})", marker, signal.name, display(&signal.left), display(&signal.right), signal.template, signal.file, signal.line);
+    }
+    for (side, path, outcome) in [("left", left_path, &diff.left), ("right", right_path, &diff.right)] {
+        match &outcome.error {
+            Some(error) => println!("{} ({}): stopped, {}", side, path, error),
+            None if outcome.unsatisfied.is_empty() => println!("{} ({}): passed", side, path),
+            None => {
+                println!("{} ({}): {} constraint(s) not satisfied", side, path, outcome.unsatisfied.len());
+                for constraint in &outcome.unsatisfied {
+                    println!("    {}", constraint);
                 }
             }
-
         }
     }
+    println!("{} of {} signal(s) differ", diff.differing().count(), diff.signals.len());
+    if diff.left.passed() == diff.right.passed() { 0 } else { 1 }
+ }
 
-    //Compilation is done. Proceed with execution
-    let set_of_used_templates = get_used_templates(&template_map, main_component.clone());
-
-    println!("This is what I know about main compoment: {:?}", main_component);
-
-    let mut heap = Heap{
-        variable_set: HashSet::new(),
-        component_set: HashSet::new(),
-        current_component: main_component.clone(),
-        variable_to_value_map: HashMap::new(),
-        variable_to_component_map: HashMap::new(),
-        variable_to_heap_map: HashMap::new(),
-        var_depending_on: HashMap::new(),
-        set_of_used_templates: set_of_used_templates,
-    };
-    let mut running = true;
-
-    let mut test_variable_to_value_map = HashMap::from([
-        ("in1".to_string(), 3),
-        ("in2".to_string(), 1),
-        ("in3".to_string(), 7),
-        ("in4".to_string(), 1),
-        ("mult".to_string(), 21)
-    ]);
-    // let mut test_variable_to_value_map = HashMap::from([
-    //     ("board[1][0]".to_string(), 0 as i32),
-    //     ("board[0][2]".to_string(), 0 as i32),
-    //     ("board[2][1]".to_string(), 1 as i32),
-    //     ("board[0][1]".to_string(), 0 as i32),
-    //     ("board[1][1]".to_string(), 1 as i32),
-    //     ("board[1][2]".to_string(), 1 as i32),
-    //     ("board[2][0]".to_string(), 1 as i32),
-    //     ("board[2][2]".to_string(), 0 as i32),
-    //     ("board[0][0]".to_string(), 0 as i32),
-    //     ("ii".to_string(), 1 as i32),
-    //     ("jj".to_string(), 1 as i32)
-    // ]);
-    heap.variable_to_value_map = test_variable_to_value_map;
-    let mut actual_input_signal_vector : Vec<String> = vec![];
-    let mut actual_output_signal_vector : Vec<String> = vec![];
-    let mut actual_component_vector : Vec<String> = vec![];
-    let mut actual_intermediate_signal_vector : Vec<String> = vec![];
-    // struct Var {
-    //     name: String,
-    //     type: String
-    // }
-    // let mut heap : HashSet<Var>;
-    let mut current_component = main_component.clone();
-    while running {
-        if current_component.template_to_use.len() == 0 {
-            break;
-        }
-        match template_map.get(&current_component.template_to_use) {
-            Some(template) => {
-                for param_index in 0..template.params.len() {
-                    heap.variable_to_value_map.insert(template.params[param_index].clone(), current_component.arguments[param_index]);
-                }
-                // println!("{:?}", heap.variable_to_value_map);
-                
-                // declarations done
-                running = false;
-                for command in &template.instructions {
-                    execute(&command, &mut heap, &template_map);
+ ///
+ /// `snarkrs repl <file> [<input.json>] [--at <instance>]`: executes the circuit, with the given inputs to compute
+ /// values, then evaluates expressions typed on stdin in an instance of it, `main` or the one given. When the
+ /// execution fails, the REPL opens in the instance that failed.
+ ///
+ fn run_repl(args: &[String]) -> i32 {
+    let mut paths = vec![];
+    let mut at = None;
+    let mut remaining = args.iter();
+    while let Some(arg) = remaining.next() {
+        match arg.as_str() {
+            "--at" => match remaining.next() {
+                Some(value) => at = Some(value.as_str()),
+                None => {
+                    eprintln!("--at expects an instance");
+                    return 1;
                 }
-                // println!("HEAP DUMP =========");
-                // println!("{:?}", heap.var_depending_on);
-                // println!("{:?}", heap.variable_to_value_map);
             },
-            None => {
-                running = false;
+            _ => paths.push(arg.as_str())
+        }
+    }
+    let (path_str, input_path) = match paths[..] {
+        [path] => (path, None),
+        [path, input] => (path, Some(input)),
+        _ => {
+            eprintln!("usage: repl <file> [<input.json>] [--at <instance>]");
+            return 1;
+        }
+    };
+
+    let inputs = match input_path.map(|input_path| fs::read_to_string(input_path).and_then(|text| execution::inputs::from_json(&text))) {
+        Some(Ok(inputs)) => Some(inputs),
+        Some(Err(error)) => {
+            eprintln!("{}: {}", input_path.unwrap_or_default(), error);
+            return 1;
+        },
+        None => None
+    };
+    let program = match compile_program(path_str, &DiskSource) {
+        Ok((program, _)) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+    let mut repl = execution::repl::Repl::new(&program, inputs.as_ref(), limits());
+    if let Some(error) = repl.error() {
+        eprintln!("{}", error);
+    }
+    if let Some(at) = at {
+        if let Err(error) = repl.cd(at) {
+            eprintln!("{}", error);
+            return 1;
+        }
+    }
+    println!("type :help for the commands, :quit to leave");
+    match repl.run(&mut std::io::stdin().lock(), &mut std::io::stdout()) {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("{}", error);
+            1
+        }
+    }
+ }
+
+ ///
+ /// `snarkrs dap [--port <port>]`: a Debug Adapter Protocol server debugging `.circom` files, on stdin and
+ /// stdout or for one client after another on a local TCP port.
+ ///
+ fn run_dap(args: &[String]) -> i32 {
+    let load = |path: &str| compile_program(path, &DiskSource).map(|(program, _)| program).map_err(|error| error.to_string());
+    let served = match args {
+        [] => libsnarkrs::dap::Adapter::new(&mut std::io::stdout().lock(), &load, limits()).run(&mut std::io::BufReader::new(std::io::stdin())),
+        [flag, port] if flag == "--port" => match port.parse::<u16>() {
+            Ok(port) => serve_dap(port, &load),
+            Err(_) => {
+                eprintln!("--port expects a port number");
+                return 1;
             }
+        },
+        _ => {
+            eprintln!("usage: dap [--port <port>]");
+            return 1;
+        }
+    };
+    match served {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("{}", error);
+            1
         }
     }
+ }
 
-    // println!("{:?}", actual_input_signal_vector);
-    // println!("{:?}", actual_output_signal_vector);
-    // println!("{:?}", actual_intermediate_signal_vector);
-    // println!("{:?}", actual_component_vector);
+ fn serve_dap(port: u16, load: &dyn Fn(&str) -> std::result::Result<template::Program, String>) -> std::io::Result<()> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("debug adapter listening on 127.0.0.1:{}", port);
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let mut reader = std::io::BufReader::new(stream.try_clone()?);
+        if let Err(error) = libsnarkrs::dap::Adapter::new(&mut stream, load, limits()).run(&mut reader) {
+            eprintln!("{}", error);
+        }
+    }
+    Ok(())
+ }
 
-    //Drawing
-    // println!("{:?}", heap);
-    // println!("heap {:?}", heap);
-    serialize_for_visual(main_component.clone(), heap)
+ ///
+ /// `snarkrs lsp`: a Language Server Protocol server for `.circom` files on stdin and stdout.
+ ///
+ fn run_lsp() -> i32 {
+    let (connection, io_threads) = lsp_server::Connection::stdio();
+    let served = libsnarkrs::lsp::run(&connection, &DiskSource);
+    drop(connection);
+    match served.map_err(|error| error.to_string()).and_then(|()| io_threads.join().map_err(|error| error.to_string())) {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("{}", error);
+            1
+        }
+    }
  }
 
- #[derive(Deserialize, Serialize)]
- struct GraphData {
-     data: String,
+ // Takes `<name> <value>` out of `args`, wherever it is
+ fn take_flag(args: &mut Vec<String>, name: &str) -> std::result::Result<Option<String>, String> {
+    match args.iter().position(|arg| arg == name) {
+        Some(idx) if idx + 1 < args.len() => {
+            let value = args.remove(idx + 1);
+            args.remove(idx);
+            Ok(Some(value))
+        },
+        Some(_) => Err(format!("{} expects a value", name)),
+        None => Ok(None)
+    }
  }
 
- async fn fetch_graph_data(component: String, previous_component: String) -> Result<impl warp::Reply, warp::Rejection> {
-    let (mut nodes, mut edges, mut heap) = extract_values("./src/lib/parser/sample_circuits/multiplier4.circom".to_string()).unwrap();
-    if !component.contains("main") {
-        println!("{} {} {:?}", component, previous_component, heap.variable_to_component_map);
-        let mut heap_to_use = &heap;
-        if !previous_component.eq("main") {
-            heap_to_use = heap.variable_to_heap_map.get(&previous_component).unwrap();
+ ///
+ /// Reads `--max-statements`, `--max-depth`, `--max-recursion` and `--max-array-size`, taking them out of
+ /// `args`. They bound every execution of the circuit: the commands, the debugger, the REPL and the graph with
+ /// its provenance and diff.
+ ///
+ fn take_limits(args: &mut Vec<String>) -> std::result::Result<execution::Limits, String> {
+    let mut limits = execution::Limits::default();
+    let flags: [(&str, &mut usize); 4] = [
+        ("--max-statements", &mut limits.statements),
+        ("--max-depth", &mut limits.depth),
+        ("--max-recursion", &mut limits.recursion),
+        ("--max-array-size", &mut limits.array_size)
+    ];
+    for (name, limit) in flags {
+        if let Some(value) = take_flag(args, name)? {
+            *limit = value.parse().map_err(|_| format!("{} expects a number, got `{}`", name, value))?;
         }
-        
-        let comp = heap_to_use.variable_to_component_map.get(&component).unwrap();
-        let local_heap = heap_to_use.variable_to_heap_map.get(&component).unwrap();
-        (nodes, edges, _) = serialize_for_visual( comp.clone(), local_heap.clone()).unwrap()
     }
-    let graph_data = serde_json::json!({ "initialNodes": nodes, "initialEdges": edges });
-    
-    Ok(Response::builder().header("Content-Type", "application/json").body(serde_json::to_string(&graph_data).unwrap()))
-}
+    Ok(limits)
+ }
 
+ ///
+ /// Sets up the log from `--log <filter>` and `--log-file <path>`, taking them out of `args`. The filter has the
+ /// `RUST_LOG` syntax, a level per module (`warn,libsnarkrs::execution=trace`), and defaults to `RUST_LOG` then
+ /// `info`. The log goes to stderr, or to the file, where every signal and variable change of an execution is
+ /// recorded unless the filter says otherwise.
+ ///
+ fn init_logging(args: &mut Vec<String>) -> std::result::Result<(), String> {
+    let filter = take_flag(args, "--log")?;
+    let log_file = take_flag(args, "--log-file")?;
+
+    let default = if log_file.is_some() { "info,libsnarkrs::execution=trace" } else { "info" };
+    let filter = filter.or_else(|| env::var("RUST_LOG").ok()).unwrap_or_else(|| default.to_string());
+    let mut builder = env_logger::Builder::new();
+    builder.parse_filters(&filter);
+    if let Some(path) = log_file {
+        let file = File::create(&path).map_err(|error| format!("cannot write {}: {}", path, error))?;
+        builder.target(env_logger::Target::Pipe(Box::new(file)));
+    }
+    builder.try_init().map_err(|error| error.to_string())
+ }
 
  #[tokio::main]
  async fn main() {
+    let mut args: Vec<String> = env::args().collect();
+    if let Err(error) = init_logging(&mut args) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+    match take_limits(&mut args) {
+        Ok(limits) => {
+            LIMITS.set(limits).ok();
+        },
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    }
+    if args.len() > 1 && args[1] == "dap" {
+        process::exit(run_dap(&args[2..]));
+    }
+    if args.len() > 2 && args[1] == "diff" {
+        process::exit(run_diff(&args[2..]));
+    }
+    if args.len() > 2 && args[1] == "repl" {
+        process::exit(run_repl(&args[2..]));
+    }
+    if args.len() > 1 && args[1] == "lsp" {
+        process::exit(run_lsp());
+    }
+    if args.len() > 2 && args[1] == "check" {
+        process::exit(run_check(&args[2..]));
+    }
+    if args.len() > 2 && args[1] == "compile" {
+        process::exit(run_compile(&args[2..]));
+    }
+    if args.len() > 2 && args[1] == "witness" {
+        process::exit(run_witness(&args[2..]));
+    }
+    if args.len() > 2 && args[1] == "trace" {
+        process::exit(run_trace(&args[2..]));
+    }
+    if args.len() > 2 && args[1] == "replay" {
+        process::exit(run_replay(&args[2..]));
+    }
+    if args.len() > 2 && args[1] == "check-witness" {
+        process::exit(run_check_witness(&args[2..]));
+    }
+    if args.len() > 2 && args[1] == "prove" {
+        process::exit(run_prove(&args[2..]));
+    }
+    if args.len() > 2 && args[1] == "inspect" {
+        process::exit(run_inspect(&args[2..]));
+    }
+    if args.len() > 2 && args[1] == "constraints" {
+        process::exit(run_constraints(&args[2..]));
+    }
+
+    // `snarkrs [<file.circom>] [--wtns <file.wtns> --sym <file.sym>]`: serves the graph of the circuit, or of
+    // a witness generated by circom from the hierarchy of its `.sym` file, without executing anything
+    let usage = "usage: snarkrs [<file.circom>] [--wtns <file.wtns> --sym <file.sym>]";
+    let (mut circuit, mut wtns_path, mut sym_path) = (None, None, None);
+    let mut remaining = args.iter().skip(1);
+    while let Some(arg) = remaining.next() {
+        match arg.as_str() {
+            "--wtns" => wtns_path = remaining.next(),
+            "--sym" => sym_path = remaining.next(),
+            _ if circuit.is_none() && !arg.starts_with('-') => circuit = Some(arg.clone()),
+            _ => {
+                eprintln!("{}", usage);
+                process::exit(1);
+            }
+        }
+    }
+    match (wtns_path, sym_path) {
+        (Some(wtns_path), Some(sym_path)) => match load_witness(wtns_path, sym_path) {
+            Ok(witness) => {
+                log::info!("loaded {} value(s) from {}", witness.1.len(), wtns_path);
+                WITNESS.set(witness).ok();
+            },
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        },
+        (None, None) if circuit.is_some() => {},
+        _ => {
+            eprintln!("{}", usage);
+            process::exit(1);
+        }
+    }
+    if let Some(circuit) = circuit {
+        CIRCUIT.set(circuit).ok();
+    }
+
     let cors = warp::cors()
     .allow_any_origin()
     .allow_headers(vec!["*"])
     .allow_methods(vec!["GET", "POST", "DELETE", "PUT", "OPTIONS"]);
 
-    let route1 = warp::path!("graph-data" / String / String)
+    let route1 = warp::path!("graph-data" / String)
     .and(warp::get())
+    .and(warp::query::<HashMap<String, String>>())
     .and_then(fetch_graph_data).with(cors.clone());
 
-    println!("serving on 0.0.0.0:3030");
+    let route_unsaved = warp::path!("graph-data" / String)
+    .and(warp::post())
+    .and(warp::query::<HashMap<String, String>>())
+    .and(warp::body::json())
+    .and_then(fetch_graph_data_unsaved).with(cors.clone());
+
+    let route_r1cs = warp::path!("r1cs-data")
+    .and(warp::get())
+    .and(warp::query::<HashMap<String, String>>())
+    .and_then(fetch_r1cs_data).with(cors.clone());
+
+    let route_provenance = warp::path!("provenance" / String)
+    .and(warp::get())
+    .and(warp::query::<HashMap<String, String>>())
+    .and_then(fetch_provenance).with(cors.clone());
+
+    let route_diff = warp::path!("diff")
+    .and(warp::get())
+    .and(warp::query::<HashMap<String, String>>())
+    .and_then(fetch_diff).with(cors.clone());
 
-    let file_path = "/Users/veljko/Documents/GitHub/shortcircuit/src/lib/parser/sample_circuits/multiplier4.circom";
+    log::info!("serving on 0.0.0.0:3030");
 
     // Create a route that serves the file
     let route2 = warp::path("get_file").and(warp::get()).map(move || {
-        match fs::read(file_path) {
-            Ok(content) => {
+        match CIRCUIT.get().map(fs::read) {
+            Some(Ok(content)) => {
                 return Response::builder().header("Content-Type", "application/text").body(content).unwrap();
             }
-            Err(_) => return Response::builder().status(404).body(Vec::new()).unwrap(),
+            _ => return Response::builder().status(404).body(Vec::new()).unwrap(),
         };
     }).with(cors).with(warp::log("warp_server"));
 
-    let routes = route1.or(route2);
+    let routes = route1.or(route_unsaved).or(route_r1cs).or(route_provenance).or(route_diff).or(route2);
     warp::serve(routes).run(([0, 0, 0, 0], 3030)).await;
 
 }
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use crate::analysis::{self, Diagnostic, Severity};
+use crate::parser::expression_parser::{BinOp, Expr, Operator, Stmt};
+use crate::parser::template::{DeclType, Program, SingleCommand, Template};
+
+pub const NON_QUADRATIC_CONSTRAINT: &str = "non-quadratic-constraint";
+
+///
+/// Degree of an expression that is not a polynomial in the signals at all: a division by a signal, a
+/// comparison or bitwise operation on signals, a signal exponent.
+///
+pub const NON_POLYNOMIAL: usize = usize::MAX;
+
+///
+/// Multiplicative degree of expressions in the signals of one template. Numbers and parameters are
+/// constants, every signal (own or of a sub-component) has degree 1, and a var has the degree of the
+/// expressions it was last assigned, so `var acc = a * b; out <== acc * c;` is cubic.
+///
+#[derive(Default)]
+pub struct Degrees {
+    signals: std::collections::HashSet<String>,
+    components: std::collections::HashSet<String>,
+    vars: HashMap<String, usize>
+}
+
+impl Degrees {
+
+    pub fn of(&self, expr: & Expr) -> usize {
+        match expr {
+            Expr::Empty | Expr::Number(_) => 0,
+            Expr::ComplexVariable(variable) => {
+                if self.signals.contains(&variable.id) || (variable.sub_variable.is_some() && self.components.contains(&variable.id)) {
+                    1
+                } else {
+                    self.vars.get(&variable.id).cloned().unwrap_or(0)
+                }
+            },
+            Expr::BinaryOperation(operation) => {
+                let left = self.of(&operation.left);
+                let right = self.of(&operation.right);
+                match operation.op {
+                    BinOp::Add | BinOp::Subtract => left.max(right),
+                    BinOp::Multiply => left.saturating_add(right),
+                    BinOp::Divide => if right == 0 { left } else { NON_POLYNOMIAL },
+                    BinOp::Exponent => match (left, &*operation.right) {
+                        (0, _) if right == 0 => 0,
+                        (_, Expr::Number(exponent)) if right == 0 => exponent.to_usize().map(|exponent| left.saturating_mul(exponent)).unwrap_or(NON_POLYNOMIAL),
+                        _ => NON_POLYNOMIAL
+                    },
+                    BinOp::Quotient | BinOp::Modulo => if left.max(right) == 0 { 0 } else { NON_POLYNOMIAL }
+                }
+            },
+            Expr::LogicalOperation { left, right, .. } | Expr::BitwiseOperation { left, right, .. } => {
+                if self.of(left).max(self.of(right)) == 0 { 0 } else { NON_POLYNOMIAL }
+            },
+            Expr::UnaryOperation { expr, .. } => self.of(expr),
+            Expr::Conditional { condition, true_value, false_value } => {
+                if self.of(condition) == 0 {
+                    self.of(true_value).max(self.of(false_value))
+                } else {
+                    NON_POLYNOMIAL
+                }
+            },
+            Expr::ComponentInstance(_) => 0,
+            Expr::Array(items) => items.iter().map(|item| self.of(item)).max().unwrap_or(0)
+        }
+    }
+
+    ///
+    /// Smallest subexpression that is already above degree 2: following the operands for as long as one of
+    /// them is itself non-quadratic.
+    ///
+    pub fn offending<'a>(&self, expr: &'a Expr) -> &'a Expr {
+        let operands: Vec<&Expr> = match expr {
+            Expr::BinaryOperation(operation) => vec!(&*operation.left, &*operation.right),
+            Expr::LogicalOperation { left, right, .. } | Expr::BitwiseOperation { left, right, .. } => vec!(&**left, &**right),
+            Expr::UnaryOperation { expr, .. } => vec!(&**expr),
+            Expr::Conditional { condition, true_value, false_value } => vec!(&**condition, &**true_value, &**false_value),
+            _ => vec![]
+        };
+        match operands.into_iter().find(|operand| self.of(operand) > 2) {
+            Some(operand) => self.offending(operand),
+            None => expr
+        }
+    }
+
+}
+
+///
+/// How to split an offending product: move its highest degree factor into a new signal.
+///
+fn suggestion(degrees: & Degrees, offending: & Expr) -> Option<String> {
+    match offending {
+        Expr::BinaryOperation(operation) if operation.op == BinOp::Multiply => {
+            let (factor, rest) = if degrees.of(&operation.left) >= degrees.of(&operation.right) {
+                (&operation.left, &operation.right)
+            } else {
+                (&operation.right, &operation.left)
+            };
+            Some(format!("introduce `signal aux <== {};` and use `aux * {}`", factor, rest))
+        },
+        Expr::BinaryOperation(operation) if operation.op == BinOp::Exponent => {
+            Some(format!("introduce `signal aux <== {} * {};` and multiply by `aux` instead", operation.left, operation.left))
+        },
+        _ => None
+    }
+}
+
+struct Checker<'a> {
+    program: &'a Program,
+    template: &'a Template,
+    degrees: Degrees,
+    diagnostics: Vec<Diagnostic>
+}
+
+impl<'a> Checker<'a> {
+
+    fn check_constraint(&mut self, subject: & str, expr: & Expr, span: (usize, usize)) {
+        let degree = self.degrees.of(expr);
+        if degree <= 2 {
+            return;
+        }
+        let offending = self.degrees.offending(expr);
+        let message = match self.degrees.of(offending) {
+            NON_POLYNOMIAL => format!("`{}` is not a polynomial in the signals, it cannot be part of a constraint", offending),
+            offending_degree => format!("`{}` has degree {} in the signals, constraints must be quadratic", offending, offending_degree)
+        };
+        let mut diagnostic = Diagnostic::new(NON_QUADRATIC_CONSTRAINT, Severity::Error, self.program, self.template, subject, span, message);
+        if let Some(help) = suggestion(&self.degrees, offending) {
+            diagnostic = diagnostic.with_help(help);
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn assign_var(&mut self, var: & str, op: & Operator, value: & Expr) {
+        let value_degree = self.degrees.of(value);
+        let current = self.degrees.vars.get(var).cloned().unwrap_or(0);
+        let degree = match op {
+            Operator::PlusAssignment | Operator::MinusAssignment => current.max(value_degree),
+            Operator::TimesAssignment => current.saturating_add(value_degree),
+            Operator::DivideAssignment => if value_degree == 0 { current } else { NON_POLYNOMIAL },
+            _ => value_degree
+        };
+        self.degrees.vars.insert(var.to_string(), degree);
+    }
+
+    fn walk(&mut self, commands: & [SingleCommand]) {
+        for command in commands {
+            match command {
+                SingleCommand::ForLoop(for_loop) => self.walk(&for_loop.body),
+                SingleCommand::WhileLoop(while_loop) => self.walk(&while_loop.body),
+                SingleCommand::Return(_) | SingleCommand::Unsupported(_) => {},
+                SingleCommand::IfStatement(if_statement) => {
+                    for branch in &if_statement.branches {
+                        self.walk(&branch.body);
+                    }
+                    self.walk(&if_statement.else_body);
+                },
+                SingleCommand::DeclarationStatement(decl_statement) => match decl_statement.decl_type {
+                    DeclType::Signal => {
+                        self.degrees.signals.insert(decl_statement.name.clone());
+                        if let (Some(Operator::LeftConstraint), Stmt::RegularExpr(value)) = (&decl_statement.assign_op, &decl_statement.expression) {
+                            self.check_constraint(&decl_statement.name, value, decl_statement.span);
+                        }
+                    },
+                    DeclType::Variable => {
+                        let degree = match &decl_statement.expression {
+                            Stmt::RegularExpr(value) => self.degrees.of(value),
+                            _ => 0
+                        };
+                        self.degrees.vars.insert(decl_statement.name.clone(), degree);
+                    },
+                    DeclType::Component => {
+                        self.degrees.components.insert(decl_statement.name.clone());
+                    }
+                },
+                SingleCommand::Instruction(instruction) => match &instruction.statement {
+                    Stmt::Constraint(constraint) => {
+                        self.check_constraint(&analysis::variable_key(&constraint.target), &constraint.value, instruction.span);
+                    },
+                    Stmt::SymmetricConstraint(constraint) => {
+                        let difference = Expr::BinaryOperation(crate::parser::expression_parser::BinaryOperation {
+                            left: Box::new(constraint.left.clone()),
+                            op: BinOp::Subtract,
+                            right: Box::new(constraint.right.clone())
+                        });
+                        self.check_constraint("", &difference, instruction.span);
+                    },
+                    Stmt::Assign(assign) if assign.target.sub_variable.is_none() && self.degrees.vars.contains_key(&assign.target.id) => {
+                        self.assign_var(&assign.target.id, &assign.assign_op, &assign.value);
+                    },
+                    _ => {}
+                }
+            }
+        }
+    }
+
+}
+
+///
+/// Constraints (`<==`, `==>`, `===`) of a template whose expression is above degree 2 in the signals.
+///
+pub fn analyze_template(program: & Program, template: & Template) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        program,
+        template,
+        degrees: Degrees::default(),
+        diagnostics: vec![]
+    };
+    checker.walk(&template.instructions);
+    checker.diagnostics
+}
+
+///
+/// Non-quadratic constraints of every instance reachable from main.
+///
+pub fn analyze(program: & Program) -> Vec<Diagnostic> {
+    analysis::per_instance(program, &|template| analyze_template(program, template))
+}
+
+#[cfg(test)]
+mod degree_test {
+
+    use std::path::PathBuf;
+
+    use crate::analysis::degree;
+    use crate::parser::compile;
+    use crate::parser::source::{DiskSource, MemorySource, SourceProvider};
+    use crate::parser::symbols;
+    use crate::parser::template;
+
+    use galvanic_assert::matchers::*;
+
+    fn findings(path: &str, sources: &dyn SourceProvider) -> Vec<(String, String, Option<String>)> {
+        let mut ctx = compile::build_context_from_source(&PathBuf::from(path), sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = template::load_program(&ctx, &table);
+
+        degree::analyze(&program).into_iter()
+            .map(|diagnostic| (diagnostic.subject, diagnostic.message, diagnostic.help))
+            .collect()
+    }
+
+    #[test]
+    fn test_sample_circuits_are_quadratic() {
+
+        expect_that!(&findings("./src/lib/parser/sample_circuits/multiplier4.circom", &DiskSource), eq(vec![]));
+        expect_that!(&findings("./src/lib/parser/sample_circuits/warships_raw.circom", &DiskSource), eq(vec![]));
+
+    }
+
+    #[test]
+    fn test_offending_subexpression_and_fix() {
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", "template Main(n) {\n    signal input a;\n    signal input b;\n    signal input c;\n    signal output x;\n    signal output y;\n    var acc = a * b;\n    x <== n * a * b + 1;\n    y <== (acc * c) + a;\n    a * b * c === x ** 3;\n}\ncomponent main = Main(2);\n");
+
+        expect_that!(&findings("/virtual/main.circom", &sources), eq(vec!(
+            ("y".to_string(), "`acc * c` has degree 3 in the signals, constraints must be quadratic".to_string(),
+                Some("introduce `signal aux <== acc;` and use `aux * c`".to_string())),
+            ("".to_string(), "`a * b * c` has degree 3 in the signals, constraints must be quadratic".to_string(),
+                Some("introduce `signal aux <== a * b;` and use `aux * c`".to_string()))
+        )));
+
+    }
+}
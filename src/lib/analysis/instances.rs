@@ -0,0 +1,131 @@
+use crate::parser::expression_parser::{Expr, Stmt};
+use crate::parser::template::{DeclType, Program, SingleCommand, Template};
+
+///
+/// A component of the circuit, found statically by following the components each template declares or
+/// instantiates. Component arrays are a single instance, `main.checks[*][*]`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    pub path: String,
+    pub template: String
+}
+
+fn collect_components(commands: & [SingleCommand], program: & Program, dimensions: &mut Vec<(String, usize)>, components: &mut Vec<(String, String)>) {
+    for command in commands {
+        match command {
+            SingleCommand::DeclarationStatement(decl_statement) if decl_statement.decl_type == DeclType::Component => {
+                dimensions.push((decl_statement.name.clone(), decl_statement.size_per_dimension.len()));
+                if !decl_statement.template_to_use.is_empty() {
+                    components.push((decl_statement.name.clone(), decl_statement.template_to_use.clone()));
+                }
+            },
+            SingleCommand::Instruction(instruction) => {
+                if let Stmt::Assign(assign) = &instruction.statement {
+                    if let Expr::ComponentInstance(instance) = &assign.value {
+                        if program.templates.contains_key(&instance.name.id) {
+                            components.push((assign.target.id.clone(), instance.name.id.clone()));
+                        }
+                    }
+                }
+            },
+            SingleCommand::ForLoop(for_loop) => collect_components(&for_loop.body, program, dimensions, components),
+            SingleCommand::WhileLoop(while_loop) => collect_components(&while_loop.body, program, dimensions, components),
+            SingleCommand::IfStatement(if_statement) => {
+                for branch in &if_statement.branches {
+                    collect_components(&branch.body, program, dimensions, components);
+                }
+                collect_components(&if_statement.else_body, program, dimensions, components);
+            },
+            _ => {}
+        }
+    }
+}
+
+///
+/// Sub-components of a template as `(name, template)`, in source order, each name once.
+///
+pub fn sub_components(program: & Program, template: & Template) -> Vec<(String, String)> {
+    let mut dimensions = vec![];
+    let mut components: Vec<(String, String)> = vec![];
+    collect_components(&template.instructions, program, &mut dimensions, &mut components);
+
+    let mut unique: Vec<(String, String)> = vec![];
+    for (name, template_name) in components {
+        if unique.iter().all(|(seen, _)| *seen != name) {
+            let dims = dimensions.iter().find(|(declared, _)| *declared == name).map(|(_, dims)| *dims).unwrap_or(0);
+            unique.push((format!("{}{}", name, "[*]".repeat(dims)), template_name));
+        }
+    }
+    unique
+}
+
+///
+/// Every instance reachable from the main component, depth-first in source order, main first. A template
+/// that instantiates itself (directly or not) is listed once per chain, its recursive instances are not expanded.
+///
+pub fn instances(program: & Program) -> Vec<Instance> {
+    let main_component = match &program.main_component {
+        Some(main_component) => main_component,
+        None => return vec![]
+    };
+
+    let mut found: Vec<Instance> = vec![];
+    let mut stack: Vec<(Instance, Vec<String>)> = vec!((Instance {
+        path: main_component.name.clone(),
+        template: main_component.template_to_use.clone()
+    }, vec![]));
+
+    while let Some((instance, mut ancestors)) = stack.pop() {
+        let template = match program.templates.get(&instance.template) {
+            Some(template) => template,
+            None => continue
+        };
+        ancestors.push(instance.template.clone());
+
+        for (name, template_name) in sub_components(program, template).into_iter().rev() {
+            if ancestors.contains(&template_name) {
+                continue;
+            }
+            stack.push((Instance {
+                path: format!("{}.{}", instance.path, name),
+                template: template_name
+            }, ancestors.clone()));
+        }
+        found.push(instance);
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod instances_test {
+
+    use std::path::PathBuf;
+
+    use crate::analysis::instances;
+    use crate::parser::compile;
+    use crate::parser::source::DiskSource;
+    use crate::parser::symbols;
+    use crate::parser::template;
+
+    use galvanic_assert::matchers::*;
+
+    #[test]
+    fn test_recursive_templates_are_not_expanded() {
+
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("./src/lib/parser/sample_circuits/recursion.circom"), &DiskSource);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = template::load_program(&ctx, &table);
+
+        let found: Vec<(String, String)> = instances::instances(&program).into_iter()
+            .map(|instance| (instance.path, instance.template))
+            .collect();
+
+        expect_that!(&found, eq(vec!(
+            ("main".to_string(), "MultiAND".to_string()),
+            ("main.and".to_string(), "AND".to_string())
+        )));
+
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::analysis::{self, degree, under_constrained, Diagnostic, Severity};
+use crate::parser::expression_parser::{Expr, Stmt};
+use crate::parser::template::{self, DeclType, Program, SignalDirection, SingleCommand, Template};
+
+pub const UNUSED_TEMPLATE: &str = "unused-template";
+pub const UNUSED_SIGNAL: &str = "unused-signal";
+pub const UNUSED_COMPONENT: &str = "unused-component";
+pub const UNUSED_VAR: &str = "unused-var";
+
+///
+/// Every lint the analyses can report, the names accepted by `LintConfig` and by suppression comments.
+///
+pub const LINTS: [&str; 7] = [
+    under_constrained::UNCONSTRAINED_SIGNAL,
+    under_constrained::UNCONSTRAINED_INPUT,
+    degree::NON_QUADRATIC_CONSTRAINT,
+    UNUSED_TEMPLATE,
+    UNUSED_SIGNAL,
+    UNUSED_COMPONENT,
+    UNUSED_VAR
+];
+
+///
+/// Comment that silences lints on its own line or on the line right below it,
+/// `signal tmp; // shortcircuit-allow: unused-signal` or `// shortcircuit-allow: unused-var, unused-signal`.
+///
+pub const SUPPRESSION_MARKER: &str = "shortcircuit-allow:";
+
+///
+/// Per-lint severity. A lint that is not configured keeps the severity its analysis reports, `None` turns it off.
+///
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    levels: HashMap<String, Option<Severity>>
+}
+
+impl LintConfig {
+
+    pub fn set(&mut self, lint: & str, level: Option<Severity>) {
+        self.levels.insert(lint.to_string(), level);
+    }
+
+    pub fn level(&self, lint: & str, default: Severity) -> Option<Severity> {
+        match self.levels.get(lint) {
+            Some(level) => *level,
+            None => Some(default)
+        }
+    }
+
+}
+
+fn line_of(source: & str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count()
+}
+
+fn allows(line: & str, lint: & str) -> bool {
+    match line.find(SUPPRESSION_MARKER) {
+        Some(idx) => line[idx + SUPPRESSION_MARKER.len()..].split(',').any(|allowed| allowed.trim() == lint),
+        None => false
+    }
+}
+
+///
+/// Whether a suppression comment on the line of the diagnostic, or on the line above, allows its lint.
+///
+pub fn is_suppressed(program: & Program, diagnostic: & Diagnostic) -> bool {
+    let source = match program.sources.get(&diagnostic.file.to_string_lossy().to_string()) {
+        Some(source) => source,
+        None => return false
+    };
+    let line = line_of(source, diagnostic.span.0);
+    let lines: Vec<&str> = source.lines().collect();
+
+    let own = lines.get(line).map(|text| allows(text, diagnostic.lint)).unwrap_or(false);
+    let above = line > 0 && lines.get(line - 1).map(|text| text.trim_start().starts_with("//") && allows(text, diagnostic.lint)).unwrap_or(false);
+    own || above
+}
+
+///
+/// Applies the configured severities and the suppression comments to the findings of any analysis.
+///
+pub fn apply(config: & LintConfig, program: & Program, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    diagnostics.into_iter()
+        .filter(|diagnostic| !is_suppressed(program, diagnostic))
+        .filter_map(|mut diagnostic| {
+            diagnostic.severity = config.level(diagnostic.lint, diagnostic.severity)?;
+            Some(diagnostic)
+        })
+        .collect()
+}
+
+fn expr_reads(expr: & Expr, reads: &mut HashSet<String>) {
+    let mut variables = vec![];
+    analysis::expr_variables(expr, &mut variables);
+    for variable in variables {
+        reads.insert(variable.id.clone());
+        reads.insert(analysis::variable_key(variable));
+        for index in &variable.indexing {
+            expr_reads(index, reads);
+        }
+        if let Some(sub_variable) = &variable.sub_variable {
+            for index in &sub_variable.indexing {
+                expr_reads(index, reads);
+            }
+        }
+    }
+}
+
+///
+/// Names a template reads, and the signals, vars and components it declares. Being the target of an
+/// assignment or a constraint is not a read, appearing in a value, an index, a condition or a size is.
+///
+#[derive(Default)]
+struct Usage<'a> {
+    reads: HashSet<String>,
+    declarations: Vec<&'a template::DeclStatement>,
+    component_templates: HashMap<String, String>
+}
+
+impl<'a> Usage<'a> {
+
+    fn read_stmt(&mut self, statement: & Stmt) {
+        match statement {
+            Stmt::Constraint(constraint) => {
+                for index in &constraint.target.indexing {
+                    expr_reads(index, &mut self.reads);
+                }
+                expr_reads(&constraint.value, &mut self.reads);
+            },
+            Stmt::Assign(assign) => {
+                for index in &assign.target.indexing {
+                    expr_reads(index, &mut self.reads);
+                }
+                if let Expr::ComponentInstance(instance) = &assign.value {
+                    self.component_templates.insert(assign.target.id.clone(), instance.name.id.clone());
+                }
+                expr_reads(&assign.value, &mut self.reads);
+            },
+            Stmt::ConditionalAssign(conditional) => {
+                expr_reads(&conditional.condition, &mut self.reads);
+                expr_reads(&conditional.true_value, &mut self.reads);
+                expr_reads(&conditional.false_value, &mut self.reads);
+            },
+            Stmt::RegularExpr(expr) => expr_reads(expr, &mut self.reads),
+            Stmt::Assert(assert) => expr_reads(&assert.value, &mut self.reads),
+            Stmt::SymmetricConstraint(constraint) => {
+                expr_reads(&constraint.left, &mut self.reads);
+                expr_reads(&constraint.right, &mut self.reads);
+            },
+            Stmt::Invalid(_) | Stmt::Empty => {}
+        }
+    }
+
+    fn walk(&mut self, commands: &'a [SingleCommand]) {
+        for command in commands {
+            match command {
+                SingleCommand::DeclarationStatement(decl_statement) => {
+                    self.declarations.push(decl_statement);
+                    for dimension in &decl_statement.size_per_dimension {
+                        self.reads.insert(dimension.clone());
+                    }
+                    if !decl_statement.template_to_use.is_empty() {
+                        self.component_templates.insert(decl_statement.name.clone(), decl_statement.template_to_use.clone());
+                    }
+                    match &decl_statement.expression {
+                        Stmt::Assign(assign) => expr_reads(&assign.value, &mut self.reads),
+                        other => self.read_stmt(other)
+                    }
+                },
+                SingleCommand::Instruction(instruction) => self.read_stmt(&instruction.statement),
+                SingleCommand::ForLoop(for_loop) => {
+                    self.reads.insert(for_loop.condition.lhs.trim().to_string());
+                    self.reads.insert(for_loop.condition.rhs.trim().to_string());
+                    self.walk(&for_loop.body);
+                },
+                SingleCommand::WhileLoop(while_loop) => {
+                    self.read_stmt(&while_loop.test);
+                    self.walk(&while_loop.body);
+                },
+                SingleCommand::Return(statement) => self.read_stmt(&statement.value),
+                SingleCommand::Unsupported(_) => {},
+                SingleCommand::IfStatement(if_statement) => {
+                    for branch in &if_statement.branches {
+                        self.read_stmt(&branch.condition);
+                        self.walk(&branch.body);
+                    }
+                    self.walk(&if_statement.else_body);
+                }
+            }
+        }
+    }
+
+    ///
+    /// A component is consumed when one of its outputs is read. Without a known template, any signal counts.
+    ///
+    fn consumes(&self, program: & Program, component: & str) -> bool {
+        let prefix = format!("{}.", component);
+        let outputs: Option<Vec<&String>> = self.component_templates.get(component)
+            .and_then(|name| program.templates.get(name))
+            .map(|template| template.output_signals.iter().map(|signal| &signal.name).collect());
+
+        self.reads.iter().any(|read| match read.strip_prefix(&prefix) {
+            Some(signal) => outputs.as_ref().map(|outputs| outputs.iter().any(|output| *output == signal)).unwrap_or(true),
+            None => false
+        })
+    }
+
+}
+
+///
+/// Intermediate signals that are never read, components none of whose outputs is read, and vars that are
+/// never read.
+///
+pub fn analyze_template(program: & Program, template: & Template) -> Vec<Diagnostic> {
+    let mut usage = Usage::default();
+    usage.walk(&template.instructions);
+
+    let mut diagnostics = vec![];
+    for decl_statement in &usage.declarations {
+        let name = &decl_statement.name;
+        let finding = match decl_statement.decl_type {
+            DeclType::Signal if decl_statement.direction == SignalDirection::Intermediate && !usage.reads.contains(name) => {
+                Some((UNUSED_SIGNAL, format!("intermediate signal `{}` is never read", name)))
+            },
+            DeclType::Variable if !usage.reads.contains(name) => {
+                Some((UNUSED_VAR, format!("var `{}` is never read", name)))
+            },
+            DeclType::Component if !usage.consumes(program, name) => {
+                Some((UNUSED_COMPONENT, format!("no output of component `{}` is ever read", name)))
+            },
+            _ => None
+        };
+        if let Some((lint, message)) = finding {
+            diagnostics.push(Diagnostic::new(lint, Severity::Warning, program, template, name, decl_statement.span, message));
+        }
+    }
+    diagnostics
+}
+
+///
+/// Templates of the program that main never instantiates, directly or not. Reported once per template,
+/// pointing at its header.
+///
+pub fn unused_templates(program: & Program) -> Vec<Diagnostic> {
+    let used = match &program.main_component {
+        Some(main_component) => template::get_used_templates(&program.templates, main_component.clone()),
+        None => HashMap::new()
+    };
+
+    let mut unused: Vec<&Template> = program.templates.values().filter(|template| !used.contains_key(&template.name)).collect();
+    unused.sort_by(|a, b| (&a.path, a.span).cmp(&(&b.path, b.span)));
+
+    unused.into_iter().map(|template| {
+        Diagnostic::new(UNUSED_TEMPLATE, Severity::Warning, program, template, &template.name, (template.span.0, template.span.0),
+            format!("template `{}` is never instantiated from main", template.name))
+    }).collect()
+}
+
+///
+/// Unused templates, then the unused signals, components and vars of every instance reachable from main.
+///
+pub fn analyze(program: & Program) -> Vec<Diagnostic> {
+    let mut diagnostics = unused_templates(program);
+    diagnostics.append(&mut analysis::per_instance(program, &|template| analyze_template(program, template)));
+    diagnostics
+}
+
+#[cfg(test)]
+mod lints_test {
+
+    use std::path::PathBuf;
+
+    use crate::analysis::{lints, Severity};
+    use crate::parser::compile;
+    use crate::parser::source::MemorySource;
+    use crate::parser::symbols;
+    use crate::parser::template;
+
+    use galvanic_assert::matchers::*;
+
+    fn program(main: &str) -> template::Program {
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/lib.circom", "template Sub() {\n    signal input a;\n    signal output b;\n    b <== a;\n}\ntemplate Unused() {\n    signal input a;\n}\n");
+        sources.insert("/virtual/main.circom", main);
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("/virtual/main.circom"), &sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        template::load_program(&ctx, &table)
+    }
+
+    fn findings(program: &template::Program, config: &lints::LintConfig) -> Vec<(String, String, Severity)> {
+        lints::apply(config, program, lints::analyze(program)).into_iter()
+            .map(|diagnostic| (diagnostic.lint.to_string(), diagnostic.subject, diagnostic.severity))
+            .collect()
+    }
+
+    const MAIN: &str = "include \"lib.circom\";\ntemplate Main(n) {\n    signal input x;\n    signal output y;\n    signal tmp;\n    signal used;\n    var dead = 3;\n    var size = n;\n    signal arr[size];\n    component first = Sub();\n    component second = Sub();\n    tmp <== x * x;\n    used <== x + 1;\n    first.a <== used;\n    second.a <== x;\n    y <== second.b;\n}\ncomponent main = Main(2);\n";
+
+    #[test]
+    fn test_unused_lints() {
+
+        let program = program(MAIN);
+
+        expect_that!(&findings(&program, &lints::LintConfig::default()), eq(vec!(
+            ("unused-template".to_string(), "Unused".to_string(), Severity::Warning),
+            ("unused-signal".to_string(), "tmp".to_string(), Severity::Warning),
+            ("unused-var".to_string(), "dead".to_string(), Severity::Warning),
+            ("unused-signal".to_string(), "arr".to_string(), Severity::Warning),
+            ("unused-component".to_string(), "first".to_string(), Severity::Warning)
+        )));
+
+    }
+
+    #[test]
+    fn test_severity_and_suppression() {
+
+        let program = program(&MAIN
+            .replace("    signal tmp;\n", "    signal tmp; // shortcircuit-allow: unused-signal\n")
+            .replace("    var dead = 3;\n", "    // shortcircuit-allow: unused-component, unused-var\n    var dead = 3;\n"));
+
+        let mut config = lints::LintConfig::default();
+        config.set(lints::UNUSED_TEMPLATE, None);
+        config.set(lints::UNUSED_COMPONENT, Some(Severity::Error));
+
+        expect_that!(&findings(&program, &config), eq(vec!(
+            ("unused-signal".to_string(), "arr".to_string(), Severity::Warning),
+            ("unused-component".to_string(), "first".to_string(), Severity::Error)
+        )));
+
+    }
+}
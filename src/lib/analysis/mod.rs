@@ -0,0 +1,167 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::parser::errors::common_displayer;
+use crate::parser::expression_parser::{Expr, Variable};
+use crate::parser::template::{Program, Template};
+
+pub mod instances;
+pub mod under_constrained;
+pub mod degree;
+pub mod lints;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(formatter, "warning"),
+            Severity::Error => write!(formatter, "error")
+        }
+    }
+}
+
+///
+/// A finding of one of the static analyses. Templates are analyzed once, the findings are then reported for
+/// every instance of the template reachable from main, `instance` being its path (`main.isEqual`).
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub lint: &'static str,
+    pub severity: Severity,
+    pub instance: String,
+    pub template: String,
+    // signal, component or var the finding is about, `component.signal` for sub-component signals
+    pub subject: String,
+    pub file: PathBuf,
+    pub span: (usize, usize),
+    pub source_line: String,
+    pub message: String,
+    pub help: Option<String>
+}
+
+impl Diagnostic {
+
+    pub(crate) fn new(lint: &'static str, severity: Severity, program: & Program, template: & Template, subject: & str, span: (usize, usize), message: String) -> Diagnostic {
+        let source_line = match program.sources.get(&template.path) {
+            Some(source) => common_displayer::source_line(source, &span),
+            None => String::new()
+        };
+        Diagnostic {
+            lint,
+            severity,
+            instance: String::new(),
+            template: template.name.clone(),
+            subject: subject.to_string(),
+            file: PathBuf::from(&template.path),
+            span,
+            source_line,
+            message,
+            help: None
+        }
+    }
+
+    pub(crate) fn with_help(mut self, help: String) -> Diagnostic {
+        self.help = Some(help);
+        self
+    }
+
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}[{}]:\nin {:?}\n\n\t{}\n\n", self.severity, self.lint, self.file, self.source_line)?;
+        // findings about a template as a whole have no instance
+        if self.instance.is_empty() {
+            writeln!(formatter, "\t{}: {}", self.template, self.message)?;
+        } else {
+            writeln!(formatter, "\t{} ({}): {}", self.instance, self.template, self.message)?;
+        }
+        match &self.help {
+            Some(help) => writeln!(formatter, "\thelp: {}", help),
+            None => Ok(())
+        }
+    }
+}
+
+///
+/// Findings of every analysis on the program, with the lint levels of `config` applied.
+///
+pub fn analyze(program: & Program, config: & lints::LintConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = under_constrained::analyze(program);
+    diagnostics.append(&mut degree::analyze(program));
+    diagnostics.append(&mut lints::analyze(program));
+    lints::apply(config, program, diagnostics)
+}
+
+///
+/// Reports template level findings once per instance of the template, in instance order.
+///
+pub(crate) fn per_instance(program: & Program, findings: &dyn Fn(& Template) -> Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let mut cache: std::collections::HashMap<String, Vec<Diagnostic>> = std::collections::HashMap::new();
+    let mut diagnostics = vec![];
+
+    for instance in instances::instances(program) {
+        let template = match program.templates.get(&instance.template) {
+            Some(template) => template,
+            None => continue
+        };
+        let template_findings = cache.entry(instance.template.clone()).or_insert_with(|| findings(template));
+        for finding in template_findings.iter() {
+            let mut diagnostic = finding.clone();
+            diagnostic.instance = instance.path.clone();
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    diagnostics
+}
+
+///
+/// Name a variable is tracked under: the root identifier, or `component.signal` for sub-component signals.
+/// Indices are ignored, every element of an array counts as the array.
+///
+pub(crate) fn variable_key(variable: & Variable) -> String {
+    match &variable.sub_variable {
+        Some(sub_variable) => format!("{}.{}", variable.id, sub_variable.id),
+        None => variable.id.clone()
+    }
+}
+
+///
+/// Variables read by an expression, not counting the ones only used as array indices.
+///
+pub(crate) fn expr_variables<'a>(expr: &'a Expr, variables: &mut Vec<&'a Variable>) {
+    match expr {
+        Expr::Empty | Expr::Number(_) => {},
+        Expr::ComplexVariable(variable) => variables.push(variable),
+        Expr::BinaryOperation(operation) => {
+            expr_variables(&operation.left, variables);
+            expr_variables(&operation.right, variables);
+        },
+        Expr::LogicalOperation { left, right, .. } | Expr::BitwiseOperation { left, right, .. } => {
+            expr_variables(left, variables);
+            expr_variables(right, variables);
+        },
+        Expr::UnaryOperation { expr, .. } => expr_variables(expr, variables),
+        Expr::Conditional { condition, true_value, false_value } => {
+            expr_variables(condition, variables);
+            expr_variables(true_value, variables);
+            expr_variables(false_value, variables);
+        },
+        Expr::ComponentInstance(instance) => {
+            for argument in &instance.parameter_list {
+                expr_variables(argument, variables);
+            }
+        },
+        Expr::Array(items) => {
+            for item in items {
+                expr_variables(item, variables);
+            }
+        }
+    }
+}
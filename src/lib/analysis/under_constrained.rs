@@ -0,0 +1,207 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::analysis::{self, Diagnostic, Severity};
+use crate::parser::expression_parser::{Expr, Operator, Stmt};
+use crate::parser::template::{DeclType, Program, SignalDirection, SingleCommand, Template};
+
+pub const UNCONSTRAINED_SIGNAL: &str = "unconstrained-signal";
+pub const UNCONSTRAINED_INPUT: &str = "unconstrained-input";
+
+///
+/// What a template does with its names: which ones end up in a constraint, which signals are only assigned
+/// with `<--`, and which signals every var was computed from.
+///
+#[derive(Default)]
+struct Usage {
+    constrained: HashSet<String>,
+    var_sources: HashMap<String, HashSet<String>>,
+    assigned: Vec<(String, (usize, usize))>,
+    inputs: Vec<(String, (usize, usize))>
+}
+
+impl Usage {
+
+    fn constrain(&mut self, expr: & Expr) {
+        let mut variables = vec![];
+        analysis::expr_variables(expr, &mut variables);
+        for variable in variables {
+            self.constrained.insert(analysis::variable_key(variable));
+        }
+    }
+
+    fn feed_var(&mut self, var: & str, expr: & Expr) {
+        let mut variables = vec![];
+        analysis::expr_variables(expr, &mut variables);
+        let sources = self.var_sources.entry(var.to_string()).or_default();
+        for variable in variables {
+            sources.insert(analysis::variable_key(variable));
+        }
+    }
+
+    fn walk(&mut self, commands: & [SingleCommand]) {
+        for command in commands {
+            match command {
+                SingleCommand::ForLoop(for_loop) => self.walk(&for_loop.body),
+                SingleCommand::WhileLoop(while_loop) => self.walk(&while_loop.body),
+                SingleCommand::Return(_) | SingleCommand::Unsupported(_) => {},
+                SingleCommand::IfStatement(if_statement) => {
+                    for branch in &if_statement.branches {
+                        self.walk(&branch.body);
+                    }
+                    self.walk(&if_statement.else_body);
+                },
+                SingleCommand::DeclarationStatement(decl_statement) => {
+                    let value = match &decl_statement.expression {
+                        Stmt::RegularExpr(value) => Some(value),
+                        _ => None
+                    };
+                    match decl_statement.decl_type {
+                        DeclType::Signal => {
+                            if decl_statement.direction == SignalDirection::Input {
+                                self.inputs.push((decl_statement.name.clone(), decl_statement.span));
+                            }
+                            match (&decl_statement.assign_op, value) {
+                                (Some(Operator::LeftSignalAssign), Some(_)) => {
+                                    self.assigned.push((decl_statement.name.clone(), decl_statement.span));
+                                },
+                                (_, Some(value)) => {
+                                    self.constrained.insert(decl_statement.name.clone());
+                                    self.constrain(value);
+                                },
+                                _ => {}
+                            }
+                        },
+                        DeclType::Variable => {
+                            self.var_sources.entry(decl_statement.name.clone()).or_default();
+                            if let Some(value) = value {
+                                self.feed_var(&decl_statement.name, value);
+                            }
+                        },
+                        DeclType::Component => {}
+                    }
+                },
+                SingleCommand::Instruction(instruction) => match &instruction.statement {
+                    Stmt::Constraint(constraint) => {
+                        self.constrained.insert(analysis::variable_key(&constraint.target));
+                        self.constrain(&constraint.value);
+                    },
+                    Stmt::SymmetricConstraint(constraint) => {
+                        self.constrain(&constraint.left);
+                        self.constrain(&constraint.right);
+                    },
+                    Stmt::Assign(assign) => match assign.assign_op {
+                        Operator::LeftSignalAssign | Operator::RightSignalAssign => {
+                            self.assigned.push((analysis::variable_key(&assign.target), instruction.span));
+                        },
+                        _ => {
+                            if assign.target.sub_variable.is_none() && self.var_sources.contains_key(&assign.target.id) {
+                                self.feed_var(&assign.target.id, &assign.value);
+                            }
+                        }
+                    },
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    ///
+    /// A var used in a constraint constrains every signal it was computed from.
+    ///
+    fn propagate_through_vars(&mut self) {
+        let mut pending: Vec<String> = self.constrained.iter().filter(|name| self.var_sources.contains_key(*name)).cloned().collect();
+        while let Some(var) = pending.pop() {
+            for source in self.var_sources[&var].clone() {
+                if self.constrained.insert(source.clone()) && self.var_sources.contains_key(&source) {
+                    pending.push(source);
+                }
+            }
+        }
+    }
+
+}
+
+///
+/// Signals of a template assigned with `<--`/`-->` that never appear in a constraint, and inputs that no
+/// constraint depends on. Reads through vars, `var acc = in; out <== acc;` constrains `in`.
+///
+pub fn analyze_template(program: & Program, template: & Template) -> Vec<Diagnostic> {
+    let mut usage = Usage::default();
+    usage.walk(&template.instructions);
+    usage.propagate_through_vars();
+
+    let mut diagnostics = vec![];
+    let mut reported: HashSet<&String> = HashSet::new();
+
+    for (signal, span) in &usage.assigned {
+        if usage.constrained.contains(signal) || !reported.insert(signal) {
+            continue;
+        }
+        diagnostics.push(Diagnostic::new(UNCONSTRAINED_SIGNAL, Severity::Warning, program, template, signal, *span,
+            format!("`{}` is assigned with `<--` but never constrained", signal))
+            .with_help(format!("constrain `{}` with `===`, or assign it with `<==` if the expression is quadratic", signal)));
+    }
+
+    for (input, span) in &usage.inputs {
+        if usage.constrained.contains(input) {
+            continue;
+        }
+        diagnostics.push(Diagnostic::new(UNCONSTRAINED_INPUT, Severity::Warning, program, template, input, *span,
+            format!("input `{}` does not influence any constraint", input)));
+    }
+
+    diagnostics
+}
+
+///
+/// Under-constrained signals of every instance reachable from main.
+///
+pub fn analyze(program: & Program) -> Vec<Diagnostic> {
+    analysis::per_instance(program, &|template| analyze_template(program, template))
+}
+
+#[cfg(test)]
+mod under_constrained_test {
+
+    use std::path::PathBuf;
+
+    use crate::analysis::under_constrained;
+    use crate::parser::compile;
+    use crate::parser::source::{DiskSource, MemorySource, SourceProvider};
+    use crate::parser::symbols;
+    use crate::parser::template;
+
+    use galvanic_assert::matchers::*;
+
+    fn findings(path: &str, sources: &dyn SourceProvider) -> Vec<(String, String, String)> {
+        let mut ctx = compile::build_context_from_source(&PathBuf::from(path), sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = template::load_program(&ctx, &table);
+
+        under_constrained::analyze(&program).into_iter()
+            .map(|diagnostic| (diagnostic.lint.to_string(), diagnostic.instance, diagnostic.subject))
+            .collect()
+    }
+
+    #[test]
+    fn test_is_zero_is_constrained() {
+
+        expect_that!(&findings("./src/lib/parser/sample_circuits/multiplier4.circom", &DiskSource), eq(vec![]));
+
+    }
+
+    #[test]
+    fn test_assigned_but_never_constrained() {
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", "template Inv() {\n    signal input in;\n    signal output out;\n    signal inv;\n    inv <-- 1 / in;\n    out <-- in * 2;\n    out === in + in;\n}\ntemplate Main() {\n    signal input a;\n    signal input unused;\n    signal output b;\n    component first = Inv();\n    component second = Inv();\n    var twice = a + a;\n    first.in <== twice;\n    second.in <-- a;\n    b <== first.out + second.out;\n}\ncomponent main = Main();\n");
+
+        expect_that!(&findings("/virtual/main.circom", &sources), eq(vec!(
+            ("unconstrained-signal".to_string(), "main".to_string(), "second.in".to_string()),
+            ("unconstrained-input".to_string(), "main".to_string(), "unused".to_string()),
+            ("unconstrained-signal".to_string(), "main.first".to_string(), "inv".to_string()),
+            ("unconstrained-signal".to_string(), "main.second".to_string(), "inv".to_string())
+        )));
+
+    }
+}
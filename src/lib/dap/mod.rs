@@ -0,0 +1,576 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::{json, Value};
+
+use crate::execution::debug::{self, Breakpoint, Event, Interrupt, NamedValue, Pause, PauseReason, Session};
+use crate::execution::{inputs, Limits};
+use crate::parser::template::Program;
+use crate::r1cs::field::FieldElement;
+
+// a circuit has a single thread of execution
+const THREAD: i64 = 1;
+const SIGNALS: i64 = 1;
+const VARIABLES: i64 = 2;
+const COMPONENTS: i64 = 3;
+// the signals of the sub-component `i` are under `FIRST_COMPONENT + i`
+const FIRST_COMPONENT: i64 = 100;
+// the scopes of the stack frame `f` are under `f * FRAME` plus the references above
+const FRAME: i64 = 1 << 20;
+
+///
+/// A message of the Debug Adapter Protocol, `None` once the client closed the stream.
+///
+pub fn read_message(reader: &mut dyn BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "a message without Content-Length"))?;
+    let mut content = vec![0; length];
+    reader.read_exact(&mut content)?;
+    serde_json::from_slice(&content).map(Some).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))
+}
+
+pub fn write_message(writer: &mut dyn Write, message: & Value) -> io::Result<()> {
+    let content = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    writer.flush()
+}
+
+fn display(value: & Option<FieldElement>) -> String {
+    match value {
+        Some(value) => value.to_signed().to_string(),
+        None => "unknown".to_string()
+    }
+}
+
+fn canonical(path: & str) -> String {
+    fs::canonicalize(path).map(|path| path.to_string_lossy().into_owned()).unwrap_or_else(|_| path.to_string())
+}
+
+fn stopped_reason(reason: & PauseReason) -> &'static str {
+    match reason {
+        PauseReason::Step => "step",
+        PauseReason::Pause => "pause",
+        PauseReason::Breakpoint(Breakpoint::Line { .. }) => "breakpoint",
+        PauseReason::Breakpoint(Breakpoint::Template(_)) => "function breakpoint",
+        PauseReason::Breakpoint(Breakpoint::Signal(_)) => "data breakpoint"
+    }
+}
+
+///
+/// Debugs circuits for a DAP client: a single thread running the instances, a stack frame per instance from
+/// the paused one up to `main`, and scopes for the signals, variables and sub-components of each frame.
+/// Line breakpoints are verified on the lines a statement starts at, function breakpoints name a template
+/// (`IsZero`) or a full signal (`main.isz.out`). `launch` takes the `program` to debug, the `input` JSON
+/// file to compute values and `stopOnEntry`, the `initialized` event follows it. `pause` stops the run at
+/// its next statement. Every session runs within the limits the adapter was made with.
+///
+pub struct Adapter<'a> {
+    writer: &'a mut dyn Write,
+    load: &'a dyn Fn(& str) -> Result<Program, String>,
+    limits: &'a Limits,
+    seq: i64,
+    lines_start_at_1: bool,
+    session: Option<Session>,
+    stop_on_entry: bool,
+    // set before or during the session, by source file
+    lines: HashMap<String, Vec<Breakpoint>>,
+    functions: Vec<Breakpoint>,
+    // lines with a statement by file, once launched
+    breakable: Option<HashMap<String, HashSet<usize>>>,
+    paused: Option<Pause>,
+    // a pause asked for stopped the run, the `stopped` event follows the response to `pause`
+    interrupted: bool,
+    // the session while it runs, for the reader to pause it
+    running: Arc<Mutex<Option<Interrupt>>>,
+    // lets the reader go on with the next message, once per message
+    handled: Option<Sender<()>>,
+    reading: bool
+}
+
+impl<'a> Adapter<'a> {
+
+    pub fn new(writer: &'a mut dyn Write, load: &'a dyn Fn(& str) -> Result<Program, String>, limits: &'a Limits) -> Adapter<'a> {
+        Adapter {
+            writer,
+            load,
+            limits,
+            seq: 0,
+            lines_start_at_1: true,
+            session: None,
+            stop_on_entry: false,
+            lines: HashMap::new(),
+            functions: vec![],
+            breakable: None,
+            paused: None,
+            interrupted: false,
+            running: Arc::new(Mutex::new(None)),
+            handled: None,
+            reading: false
+        }
+    }
+
+    ///
+    /// Answers requests until the client disconnects or closes the stream. The messages are read on their
+    /// own thread, the next one once the previous one is answered or runs the circuit.
+    ///
+    pub fn run(&mut self, reader: &mut (dyn BufRead + Send)) -> io::Result<()> {
+        let (messages_tx, messages) = mpsc::channel();
+        let (handled, handled_rx) = mpsc::channel();
+        let running = self.running.clone();
+        thread::scope(|scope| {
+            scope.spawn(move || loop {
+                let message = read_message(reader);
+                if let Ok(Some(message)) = &message {
+                    if message["type"] == "request" && message["command"] == "pause" {
+                        if let Some(interrupt) = running.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_ref() {
+                            interrupt.pause();
+                        }
+                    }
+                }
+                let last = !matches!(message, Ok(Some(_)));
+                if messages_tx.send(message).is_err() || last || handled_rx.recv().is_err() {
+                    break;
+                }
+            });
+            self.handled = Some(handled);
+            let served = self.serve(&messages);
+            // stops the reader waiting for the next message to be let through
+            self.handled = None;
+            served
+        })
+    }
+
+    fn serve(&mut self, messages: & Receiver<io::Result<Option<Value>>>) -> io::Result<()> {
+        while let Some(message) = messages.recv().unwrap_or(Ok(None))? {
+            if message["type"] == "request" && !self.handle(&message)? {
+                break;
+            }
+            if !std::mem::take(&mut self.reading) {
+                self.read_next();
+            }
+        }
+        Ok(())
+    }
+
+    fn read_next(&self) {
+        if let Some(handled) = &self.handled {
+            handled.send(()).ok();
+        }
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(self.writer, &message)
+    }
+
+    fn event(&mut self, event: & str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn respond(&mut self, request: & Value, result: Result<Value, String>) -> io::Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": result.is_ok()
+        });
+        match result {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message)
+        }
+        self.send(response)
+    }
+
+    fn line(&self, line: usize) -> usize {
+        match self.lines_start_at_1 {
+            true => line,
+            false => line - 1
+        }
+    }
+
+    // false once the client disconnected
+    fn handle(&mut self, request: & Value) -> io::Result<bool> {
+        let arguments = &request["arguments"];
+        let command = request["command"].as_str().unwrap_or("");
+        match command {
+            "initialize" => {
+                self.lines_start_at_1 = arguments["linesStartAt1"].as_bool().unwrap_or(true);
+                self.respond(request, Ok(json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsFunctionBreakpoints": true,
+                    "supportsEvaluateForHovers": true
+                })))?;
+            },
+            "launch" => {
+                let result = self.launch(arguments);
+                let launched = result.is_ok();
+                self.respond(request, result.map(|_| json!({})))?;
+                // breakpoints are verified against the program
+                if launched {
+                    self.event("initialized", json!({}))?;
+                }
+            },
+            "setBreakpoints" => {
+                let file = canonical(arguments["source"]["path"].as_str().unwrap_or(""));
+                let lines: Vec<usize> = arguments["breakpoints"].as_array().into_iter().flatten()
+                    .filter_map(|breakpoint| breakpoint["line"].as_u64())
+                    .map(|line| line as usize + usize::from(!self.lines_start_at_1))
+                    .collect();
+                let breakable = self.breakable.as_ref().map(|breakable| breakable.iter()
+                    .filter(|(path, _)| **path == file || path.ends_with(&format!("/{}", file)) || file.ends_with(&format!("/{}", path)))
+                    .flat_map(|(_, lines)| lines.iter().copied())
+                    .collect::<HashSet<usize>>());
+                let mut verified = vec![];
+                let breakpoints = lines.iter().map(|line| match &breakable {
+                    Some(breakable) if breakable.contains(line) => {
+                        verified.push(Breakpoint::Line { file: file.clone(), line: *line });
+                        json!({ "verified": true, "line": self.line(*line) })
+                    },
+                    Some(_) => json!({ "verified": false, "line": self.line(*line), "message": "no statement starts on this line" }),
+                    None => json!({ "verified": false, "line": self.line(*line), "message": "no circuit is launched" })
+                }).collect::<Vec<Value>>();
+                self.lines.insert(file, verified);
+                self.sync_breakpoints();
+                self.respond(request, Ok(json!({ "breakpoints": breakpoints })))?;
+            },
+            "setFunctionBreakpoints" => {
+                self.functions = arguments["breakpoints"].as_array().into_iter().flatten()
+                    .filter_map(|breakpoint| breakpoint["name"].as_str())
+                    .map(|name| match name.contains('.') {
+                        true => Breakpoint::Signal(name.to_string()),
+                        false => Breakpoint::Template(name.to_string())
+                    })
+                    .collect();
+                let breakpoints = self.functions.iter().map(|_| json!({ "verified": true })).collect::<Vec<Value>>();
+                self.sync_breakpoints();
+                self.respond(request, Ok(json!({ "breakpoints": breakpoints })))?;
+            },
+            "configurationDone" => {
+                self.respond(request, Ok(json!({})))?;
+                match self.stop_on_entry {
+                    true => self.go(Session::step, Some("entry"))?,
+                    false => self.go(Session::resume, None)?
+                }
+            },
+            "threads" => self.respond(request, Ok(json!({ "threads": [{ "id": THREAD, "name": "main" }] })))?,
+            "stackTrace" => {
+                let frames: Vec<Value> = self.paused.iter().flat_map(|pause| pause.frames.iter()).enumerate()
+                    .map(|(id, frame)| json!({
+                        "id": id,
+                        "name": format!("{} ({})", frame.instance, frame.template),
+                        "source": {
+                            "name": PathBuf::from(&frame.file).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+                            "path": frame.file
+                        },
+                        "line": self.line(frame.line),
+                        "column": self.line(1)
+                    }))
+                    .collect();
+                let total = frames.len();
+                self.respond(request, Ok(json!({ "stackFrames": frames, "totalFrames": total })))?;
+            },
+            "scopes" => {
+                let frames = self.paused.as_ref().map(|pause| pause.frames.len()).unwrap_or(0) as i64;
+                let scopes = match arguments["frameId"].as_i64() {
+                    Some(frame) if (0..frames).contains(&frame) => json!([
+                        { "name": "Signals", "variablesReference": frame * FRAME + SIGNALS, "expensive": false },
+                        { "name": "Variables", "variablesReference": frame * FRAME + VARIABLES, "expensive": false },
+                        { "name": "Sub-components", "variablesReference": frame * FRAME + COMPONENTS, "expensive": false }
+                    ]),
+                    _ => json!([])
+                };
+                self.respond(request, Ok(json!({ "scopes": scopes })))?;
+            },
+            "variables" => {
+                let variables = self.variables(arguments["variablesReference"].as_i64().unwrap_or(0));
+                self.respond(request, Ok(json!({ "variables": variables })))?;
+            },
+            "continue" => {
+                self.respond(request, Ok(json!({ "allThreadsContinued": true })))?;
+                self.go(Session::resume, None)?;
+            },
+            "next" => {
+                self.respond(request, Ok(json!({})))?;
+                self.go(Session::step_over, None)?;
+            },
+            "stepIn" => {
+                self.respond(request, Ok(json!({})))?;
+                self.go(Session::step, None)?;
+            },
+            "stepOut" => {
+                self.respond(request, Ok(json!({})))?;
+                self.go(Session::step_out, None)?;
+            },
+            "pause" => {
+                let result = match &self.session {
+                    Some(session) if !session.is_finished() => {
+                        // the run may have stopped for another reason before reaching a statement
+                        session.interrupt().clear();
+                        Ok(json!({}))
+                    },
+                    _ => Err("no circuit is running".to_string())
+                };
+                self.respond(request, result)?;
+                if std::mem::take(&mut self.interrupted) {
+                    self.event("stopped", json!({ "reason": "pause", "threadId": THREAD, "allThreadsStopped": true }))?;
+                }
+            },
+            "evaluate" => {
+                let frame = arguments["frameId"].as_u64().unwrap_or(0) as usize;
+                let result = match &self.session {
+                    Some(session) => session.evaluate_in(frame, arguments["expression"].as_str().unwrap_or(""))
+                        .map(|value| json!({ "result": display(&value), "variablesReference": 0 })),
+                    None => Err("no circuit is running".to_string())
+                };
+                self.respond(request, result)?;
+            },
+            "terminate" => {
+                self.session.take();
+                self.paused = None;
+                self.respond(request, Ok(json!({})))?;
+                self.event("terminated", json!({}))?;
+            },
+            "disconnect" => {
+                self.session.take();
+                self.respond(request, Ok(json!({})))?;
+                return Ok(false);
+            },
+            other => self.respond(request, Err(format!("`{}` is not supported", other)))?
+        }
+        Ok(true)
+    }
+
+    fn launch(&mut self, arguments: & Value) -> Result<(), String> {
+        let path = arguments["program"].as_str().ok_or("launch needs the `program` to debug")?;
+        let program = (self.load)(path)?;
+        let inputs = match arguments["input"].as_str() {
+            Some(input) => Some(fs::read_to_string(input).and_then(|text| inputs::from_json(&text)).map_err(|error| format!("{}: {}", input, error))?),
+            None => None
+        };
+        self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        self.breakable = Some(debug::breakable_lines(&program));
+        self.session = Some(Session::start(program, inputs, self.limits.clone()));
+        self.sync_breakpoints();
+        Ok(())
+    }
+
+    fn sync_breakpoints(&self) {
+        if let Some(session) = &self.session {
+            for breakpoint in session.breakpoints() {
+                session.remove_breakpoint(&breakpoint);
+            }
+            for breakpoint in self.lines.values().flatten().chain(&self.functions) {
+                session.add_breakpoint(breakpoint.clone());
+            }
+        }
+    }
+
+    // runs the session and tells the client where it stopped
+    fn go(&mut self, command: fn(&mut Session) -> Event, reason: Option<&str>) -> io::Result<()> {
+        let interrupt = match &self.session {
+            Some(session) => session.interrupt(),
+            None => return Ok(())
+        };
+        *self.running.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(interrupt);
+        self.read_next();
+        self.reading = true;
+        let event = self.session.as_mut().map(command);
+        self.running.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+        match event {
+            None => Ok(()),
+            Some(Event::Paused(pause)) if pause.reason == PauseReason::Pause => {
+                self.paused = Some(pause);
+                self.interrupted = true;
+                Ok(())
+            },
+            Some(Event::Paused(pause)) => {
+                let reason = reason.unwrap_or_else(|| stopped_reason(&pause.reason));
+                self.paused = Some(pause);
+                self.event("stopped", json!({ "reason": reason, "threadId": THREAD, "allThreadsStopped": true }))
+            },
+            Some(Event::Finished(result)) => {
+                self.paused = None;
+                let code = match result {
+                    Ok(_) => 0,
+                    Err(error) => {
+                        self.event("output", json!({ "category": "stderr", "output": format!("{}\n", error) }))?;
+                        1
+                    }
+                };
+                self.event("exited", json!({ "exitCode": code }))?;
+                self.event("terminated", json!({}))
+            }
+        }
+    }
+
+    fn variables(&self, reference: i64) -> Vec<Value> {
+        let frame = match self.paused.as_ref().and_then(|pause| pause.frames.get((reference / FRAME) as usize)) {
+            Some(frame) if reference > 0 => frame,
+            _ => return vec![]
+        };
+        let values = |values: & [NamedValue]| values.iter()
+            .map(|value| json!({ "name": value.name, "value": display(&value.value), "variablesReference": 0 }))
+            .collect();
+        let base = reference - reference % FRAME;
+        match reference % FRAME {
+            SIGNALS => values(&frame.signals),
+            VARIABLES => values(&frame.variables),
+            COMPONENTS => frame.components.iter().enumerate()
+                .map(|(idx, component)| json!({ "name": component.name, "value": component.instance, "variablesReference": base + FIRST_COMPONENT + idx as i64 }))
+                .collect(),
+            reference => match frame.components.get((reference - FIRST_COMPONENT) as usize) {
+                Some(component) if reference >= FIRST_COMPONENT => values(&component.signals),
+                _ => vec![]
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod dap_test {
+
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    use serde_json::{json, Value};
+
+    use crate::dap::{self, Adapter};
+    use crate::execution::Limits;
+    use crate::parser::compile;
+    use crate::parser::source::MemorySource;
+    use crate::parser::symbols;
+    use crate::parser::template::{self, Program};
+
+    use galvanic_assert::matchers::*;
+
+    const SOURCE: &str = "template Square() {\n    signal input a;\n    signal output b;\n    b <== a * a;\n}\ntemplate Main() {\n    signal input x;\n    signal output y;\n    var acc = 0;\n    for (var i = 0; i < 2; i++) {\n        acc += i;\n    }\n    component square = Square();\n    square.a <== x + acc;\n    y <== square.b;\n}\ncomponent main = Main();\n";
+
+    fn load(path: & str) -> Result<Program, String> {
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", SOURCE);
+        let mut ctx = compile::build_context_from_source(&PathBuf::from(path), &sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        Ok(template::load_program(&ctx, &table))
+    }
+
+    // what the adapter sends back for the requests, by message
+    fn exchange(requests: Vec<Value>) -> Vec<Value> {
+        let mut input = vec![];
+        for (seq, mut request) in requests.into_iter().enumerate() {
+            request["seq"] = json!(seq + 1);
+            request["type"] = json!("request");
+            dap::write_message(&mut input, &request).unwrap();
+        }
+        let mut output = vec![];
+        Adapter::new(&mut output, &load, &Limits::default()).run(&mut Cursor::new(input)).unwrap();
+        let mut reader = Cursor::new(output);
+        let mut messages = vec![];
+        while let Some(message) = dap::read_message(&mut reader).unwrap() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    fn summary(message: & Value) -> String {
+        match message["type"].as_str() {
+            Some("event") => format!("event {}", message["event"].as_str().unwrap()),
+            _ => format!("{} {}", message["command"].as_str().unwrap(), message["success"])
+        }
+    }
+
+    #[test]
+    fn test_debug_session() {
+
+        let messages = exchange(vec![
+            json!({ "command": "initialize", "arguments": { "adapterID": "circom" } }),
+            json!({ "command": "launch", "arguments": { "program": "/virtual/main.circom" } }),
+            json!({ "command": "setBreakpoints", "arguments": { "source": { "path": "/virtual/main.circom" }, "breakpoints": [{ "line": 14 }, { "line": 16 }] } }),
+            json!({ "command": "setFunctionBreakpoints", "arguments": { "breakpoints": [{ "name": "Square" }] } }),
+            json!({ "command": "configurationDone" }),
+            json!({ "command": "variables", "arguments": { "variablesReference": 2 } }),
+            json!({ "command": "evaluate", "arguments": { "expression": "acc * 2", "frameId": 0 } }),
+            json!({ "command": "next" }),
+            json!({ "command": "continue" }),
+            json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }),
+            json!({ "command": "scopes", "arguments": { "frameId": 1 } }),
+            json!({ "command": "variables", "arguments": { "variablesReference": (1 << 20) + 2 } }),
+            json!({ "command": "evaluate", "arguments": { "expression": "acc * 2", "frameId": 1 } }),
+            json!({ "command": "pause", "arguments": { "threadId": 1 } }),
+            json!({ "command": "stepOut" }),
+            json!({ "command": "disconnect" }),
+            json!({ "command": "threads" })
+        ]);
+
+        let summaries: Vec<String> = messages.iter().map(summary).collect();
+        expect_that!(&summaries, eq(vec![
+            "initialize true", "launch true", "event initialized", "setBreakpoints true", "setFunctionBreakpoints true",
+            "configurationDone true", "event stopped", "variables true", "evaluate true", "next true", "event stopped",
+            "continue true", "event stopped", "stackTrace true", "scopes true", "variables true", "evaluate true",
+            "pause true", "stepOut true", "event exited", "event terminated", "disconnect true"
+        ].into_iter().map(String::from).collect::<Vec<String>>()));
+
+        // the closing brace of `Main` has no statement to stop at
+        expect_that!(&messages[3]["body"]["breakpoints"], eq(json!([
+            { "verified": true, "line": 14 },
+            { "verified": false, "line": 16, "message": "no statement starts on this line" }
+        ])));
+        expect_that!(&messages[6]["body"]["reason"], eq(json!("breakpoint")));
+        expect_that!(&messages[7]["body"]["variables"], eq(json!([
+            { "name": "acc", "value": "1", "variablesReference": 0 },
+            { "name": "i", "value": "2", "variablesReference": 0 }
+        ])));
+        expect_that!(&messages[8]["body"]["result"], eq(json!("2")));
+        expect_that!(&messages[10]["body"]["reason"], eq(json!("step")));
+        expect_that!(&messages[12]["body"]["reason"], eq(json!("function breakpoint")));
+        let frames: Vec<(Value, Value)> = messages[13]["body"]["stackFrames"].as_array().unwrap().iter().map(|frame| (frame["name"].clone(), frame["line"].clone())).collect();
+        expect_that!(&frames, eq(vec![(json!("main.square (Square)"), json!(1)), (json!("main (Main)"), json!(15))]));
+        // the frame of `main` still has its values while `main.square` runs
+        let scopes: Vec<Value> = messages[14]["body"]["scopes"].as_array().unwrap().iter().map(|scope| scope["variablesReference"].clone()).collect();
+        expect_that!(&scopes, eq(vec![json!((1 << 20) + 1), json!((1 << 20) + 2), json!((1 << 20) + 3)]));
+        expect_that!(&messages[15]["body"]["variables"], eq(messages[7]["body"]["variables"].clone()));
+        expect_that!(&messages[16]["body"]["result"], eq(json!("2")));
+        // already paused, there is nothing to stop
+        expect_that!(&messages[18]["command"], eq(json!("stepOut")));
+        expect_that!(&messages[19]["body"]["exitCode"], eq(json!(0)));
+
+    }
+
+    #[test]
+    fn test_launch_errors() {
+
+        let messages = exchange(vec![
+            json!({ "command": "launch", "arguments": {} }),
+            json!({ "command": "launch", "arguments": { "program": "/virtual/main.circom", "input": "/nonexistent/input.json" } }),
+            json!({ "command": "evaluate", "arguments": { "expression": "x" } }),
+            json!({ "command": "pause", "arguments": { "threadId": 1 } })
+        ]);
+        let errors: Vec<Value> = messages.iter().map(|message| message["message"].clone()).collect();
+        expect_that!(&errors[0], eq(json!("launch needs the `program` to debug")));
+        expect_that!(&errors[1].as_str().map(|error| error.starts_with("/nonexistent/input.json: ")), eq(Some(true)));
+        expect_that!(&errors[2], eq(json!("no circuit is running")));
+        expect_that!(&errors[3], eq(json!("no circuit is running")));
+
+    }
+}
@@ -0,0 +1,596 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::execution::trace::Change;
+use crate::execution::{self, Circuit, Limits};
+use crate::parser::errors::execution::ExecutionError;
+use crate::parser::template::{Program, SingleCommand};
+use crate::r1cs::field::FieldElement;
+
+///
+/// Where a debugging session stops when resumed. A line breakpoint names the file by its path or by its
+/// last components (`multiplier4.circom`), lines start at 1. A signal breakpoint names a full signal
+/// (`main.isEqual.out`) or a whole array (`main.board`).
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    Line { file: String, line: usize },
+    Template(String),
+    Signal(String)
+}
+
+impl Breakpoint {
+
+    fn matches(&self, point: & Point) -> bool {
+        match (self, point) {
+            (Breakpoint::Line { file, line }, Point::Statement { file: current, line: current_line, .. }) =>
+                line == current_line && (current == file || current.ends_with(&format!("/{}", file))),
+            (Breakpoint::Template(name), Point::Entered { template }) => name == template,
+            (Breakpoint::Signal(name), Point::SignalAssigned { name: assigned }) =>
+                assigned == name || assigned.starts_with(&format!("{}[", name)),
+            _ => false
+        }
+    }
+
+}
+
+///
+/// The lines a line breakpoint can stop at by file, those where a statement, a loop test or an if condition
+/// starts.
+///
+pub fn breakable_lines(program: & Program) -> HashMap<String, HashSet<usize>> {
+    let mut lines: HashMap<String, HashSet<usize>> = HashMap::new();
+    for template in program.templates.values().chain(program.functions.values()) {
+        let source = program.sources.get(&template.path).map(|source| source.as_str()).unwrap_or("");
+        let mut starts = vec![];
+        statement_starts(&template.instructions, &mut starts);
+        let file = lines.entry(template.path.clone()).or_default();
+        file.extend(starts.into_iter().map(|offset| source.bytes().take(offset).filter(|byte| *byte == b'\n').count() + 1));
+    }
+    lines
+}
+
+// where the executor gives the hook a chance to pause among the commands
+fn statement_starts(commands: & [SingleCommand], starts: &mut Vec<usize>) {
+    for command in commands {
+        match command {
+            SingleCommand::Instruction(instruction) => starts.push(instruction.span.0),
+            SingleCommand::DeclarationStatement(declaration) => starts.push(declaration.span.0),
+            SingleCommand::Return(statement) => starts.push(statement.span.0),
+            SingleCommand::Unsupported(_) => {},
+            SingleCommand::ForLoop(for_loop) => {
+                starts.push(for_loop.span.0);
+                statement_starts(&for_loop.body, starts);
+            },
+            SingleCommand::WhileLoop(while_loop) => {
+                starts.push(while_loop.span.0);
+                statement_starts(&while_loop.body, starts);
+            },
+            SingleCommand::IfStatement(if_statement) => {
+                for branch in &if_statement.branches {
+                    starts.push(branch.span.0);
+                    statement_starts(&branch.body, starts);
+                }
+                statement_starts(&if_statement.else_body, starts);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PauseReason {
+    // the statement after the previous pause
+    Step,
+    // the statement after `Interrupt::pause`
+    Pause,
+    Breakpoint(Breakpoint)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedValue {
+    pub name: String,
+    pub value: Option<FieldElement>
+}
+
+///
+/// A sub-component of the paused instance by element name (`isz`, `bits[2]`), with its full path and its
+/// signals. Before it runs, only the inputs its parent assigned are known.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedComponent {
+    pub name: String,
+    pub instance: String,
+    pub signals: Vec<NamedValue>
+}
+
+///
+/// An instance running, the statement it is at, and what it holds as `Pause` has it for the paused one.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub instance: String,
+    pub template: String,
+    pub file: String,
+    pub span: (usize, usize),
+    pub line: usize,
+    pub variables: Vec<NamedValue>,
+    pub signals: Vec<NamedValue>,
+    pub components: Vec<NamedComponent>
+}
+
+///
+/// State of a paused execution: the instance running (`main.isEqual`), the statement about to run or that
+/// just assigned a signal, the variables of the instance by element (`x[1]`), its signals in declaration
+/// order and its sub-components. `frames` starts with the paused instance, followed by the instances that
+/// started it up to `main`, each at the statement that did. Values are unknown while only the constraints
+/// are generated.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pause {
+    pub reason: PauseReason,
+    pub instance: String,
+    pub template: String,
+    pub file: String,
+    pub span: (usize, usize),
+    pub line: usize,
+    pub frames: Vec<Frame>,
+    pub variables: Vec<NamedValue>,
+    pub signals: Vec<NamedValue>,
+    pub components: Vec<NamedComponent>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Paused(Pause),
+    Finished(Result<Circuit, Box<ExecutionError>>)
+}
+
+// places the executor may pause at
+pub(crate) enum Point {
+    // before a statement, a loop test or an if condition
+    Statement { instance: String, template: String, file: String, span: (usize, usize), line: usize },
+    // when an instance starts running
+    Entered { template: String },
+    SignalAssigned { name: String }
+}
+
+type Evaluation = Result<Option<FieldElement>, String>;
+
+pub(crate) trait Hook {
+
+    fn reason(&mut self, point: & Point) -> Option<PauseReason>;
+
+    // blocks until the execution goes on, false to stop it. `evaluate` gives the value of an expression in
+    // the instance of a frame of the pause, 0 for the paused one
+    fn pause(&mut self, pause: Pause, evaluate: &mut dyn FnMut(usize, & str) -> Evaluation) -> bool;
+
+    // a signal or variable changed in the last `Point::Statement` of the instance
+    fn record(&mut self, _instance: & str, _change: Change) {}
+
+    // a signal or variable element the last `Point::Statement` of the instance used
+    fn read(&mut self, _instance: & str, _name: String, _value: Option<FieldElement>) {}
+
+}
+
+enum Command {
+    // to the next statement of an instance at most this deep, `main` is at depth 0
+    Step(usize),
+    Resume,
+    // in the instance of a frame
+    Evaluate(usize, String, Sender<Evaluation>)
+}
+
+// number of instances above, 0 for `main`
+fn depth(instance: & str) -> usize {
+    instance.matches('.').count()
+}
+
+// the session side of the executor thread
+struct Controller {
+    stepping: Option<usize>,
+    breakpoints: Arc<Mutex<Vec<Breakpoint>>>,
+    interrupted: Arc<AtomicBool>,
+    commands: Receiver<Command>,
+    events: Sender<Event>
+}
+
+impl Controller {
+
+    fn wait(&mut self, evaluate: &mut dyn FnMut(usize, & str) -> Evaluation) -> bool {
+        loop {
+            match self.commands.recv() {
+                Ok(Command::Step(depth)) => {
+                    self.stepping = Some(depth);
+                    return true;
+                },
+                Ok(Command::Resume) => {
+                    self.stepping = None;
+                    return true;
+                },
+                Ok(Command::Evaluate(frame, text, reply)) => {
+                    reply.send(evaluate(frame, &text)).ok();
+                },
+                Err(_) => return false
+            }
+        }
+    }
+
+}
+
+impl Hook for Controller {
+
+    fn reason(&mut self, point: & Point) -> Option<PauseReason> {
+        if matches!(point, Point::Statement { .. }) && self.interrupted.swap(false, Ordering::SeqCst) {
+            return Some(PauseReason::Pause);
+        }
+        if let (Some(max), Point::Statement { instance, .. }) = (self.stepping, point) {
+            if depth(instance) <= max {
+                return Some(PauseReason::Step);
+            }
+        }
+        let breakpoints = self.breakpoints.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        breakpoints.iter().find(|breakpoint| breakpoint.matches(point)).map(|breakpoint| PauseReason::Breakpoint(breakpoint.clone()))
+    }
+
+    fn pause(&mut self, pause: Pause, evaluate: &mut dyn FnMut(usize, & str) -> Evaluation) -> bool {
+        self.events.send(Event::Paused(pause)).is_ok() && self.wait(evaluate)
+    }
+
+}
+
+///
+/// Pauses a running session at its next statement, from any thread.
+///
+#[derive(Debug, Clone)]
+pub struct Interrupt(Arc<AtomicBool>);
+
+impl Interrupt {
+
+    pub fn pause(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    ///
+    /// Forgets a pause the session did not reach a statement for, as when it paused for another reason
+    /// first.
+    ///
+    pub fn clear(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+}
+
+///
+/// An execution run one statement at a time, or until a breakpoint. It runs on its own thread and does not
+/// start before the first `step` or `resume`; dropping the session stops it.
+///
+pub struct Session {
+    commands: Option<Sender<Command>>,
+    events: Receiver<Event>,
+    breakpoints: Arc<Mutex<Vec<Breakpoint>>>,
+    interrupted: Arc<AtomicBool>,
+    // of the instance paused in
+    depth: usize,
+    finished: Option<Event>,
+    thread: Option<JoinHandle<()>>
+}
+
+impl Session {
+
+    ///
+    /// Executes `main` of the program, with values for its inputs to compute the witness.
+    ///
+    pub fn start(program: Program, inputs: Option<HashMap<String, FieldElement>>, limits: Limits) -> Session {
+        let (commands, commands_rx) = mpsc::channel();
+        let (events_tx, events) = mpsc::channel();
+        let breakpoints = Arc::new(Mutex::new(vec![]));
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let mut controller = Controller { stepping: None, breakpoints: breakpoints.clone(), interrupted: interrupted.clone(), commands: commands_rx, events: events_tx.clone() };
+        let thread = thread::spawn(move || {
+            if !controller.wait(&mut |_, _| Err("the execution has not started".to_string())) {
+                return;
+            }
+            let result = execution::execute_with_hook(&program, inputs.as_ref(), &limits, Box::new(controller));
+            events_tx.send(Event::Finished(result)).ok();
+        });
+        Session { commands: Some(commands), events, breakpoints, interrupted, depth: 0, finished: None, thread: Some(thread) }
+    }
+
+    pub fn add_breakpoint(&self, breakpoint: Breakpoint) {
+        self.lock().push(breakpoint);
+    }
+
+    pub fn remove_breakpoint(&self, breakpoint: & Breakpoint) -> bool {
+        let mut breakpoints = self.lock();
+        let count = breakpoints.len();
+        breakpoints.retain(|existing| existing != breakpoint);
+        breakpoints.len() != count
+    }
+
+    pub fn breakpoints(&self) -> Vec<Breakpoint> {
+        self.lock().clone()
+    }
+
+    ///
+    /// Runs to the next statement of any instance.
+    ///
+    pub fn step(&mut self) -> Event {
+        self.send(Command::Step(usize::MAX))
+    }
+
+    ///
+    /// Runs to the next statement of the paused instance or of one above it, the sub-components the
+    /// statement starts run to their end unless they reach a breakpoint.
+    ///
+    pub fn step_over(&mut self) -> Event {
+        self.send(Command::Step(self.depth))
+    }
+
+    ///
+    /// Runs until the paused instance is done, to the statement of the instance above that started it.
+    ///
+    pub fn step_out(&mut self) -> Event {
+        match self.depth {
+            0 => self.send(Command::Resume),
+            depth => self.send(Command::Step(depth - 1))
+        }
+    }
+
+    ///
+    /// Runs to the next breakpoint, or to the end.
+    ///
+    pub fn resume(&mut self) -> Event {
+        self.send(Command::Resume)
+    }
+
+    ///
+    /// Value of an expression in the paused instance (`a * 2`, `isz.out`, `x[i]`), None while only the
+    /// constraints are generated. Reading a sub-component that did not run yet is an error rather than
+    /// running it.
+    ///
+    pub fn evaluate(&self, text: & str) -> Result<Option<FieldElement>, String> {
+        self.evaluate_in(0, text)
+    }
+
+    ///
+    /// Value of an expression in the instance of a frame of the pause, 0 for the paused instance, 1 for the
+    /// one that started it.
+    ///
+    pub fn evaluate_in(&self, frame: usize, text: & str) -> Result<Option<FieldElement>, String> {
+        let commands = match (&self.finished, &self.commands) {
+            (None, Some(commands)) => commands,
+            _ => return Err("the execution is not paused".to_string())
+        };
+        let (reply, replies) = mpsc::channel();
+        commands.send(Command::Evaluate(frame, text.to_string(), reply)).ok();
+        replies.recv().unwrap_or_else(|_| Err("the execution is not paused".to_string()))
+    }
+
+    ///
+    /// What pauses the session while another thread waits for it to step or resume.
+    ///
+    pub fn interrupt(&self) -> Interrupt {
+        Interrupt(self.interrupted.clone())
+    }
+
+    ///
+    /// Whether the execution ran to its end, or failed.
+    ///
+    pub fn is_finished(&self) -> bool {
+        self.finished.is_some()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Breakpoint>> {
+        self.breakpoints.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn send(&mut self, command: Command) -> Event {
+        if let Some(finished) = &self.finished {
+            return finished.clone();
+        }
+        if let Some(commands) = &self.commands {
+            commands.send(command).ok();
+        }
+        let event = self.events.recv().expect("the execution thread ends with a finished event");
+        match &event {
+            Event::Paused(pause) => self.depth = depth(&pause.instance),
+            Event::Finished(_) => self.finished = Some(event.clone())
+        }
+        event
+    }
+
+}
+
+impl Drop for Session {
+
+    fn drop(&mut self) {
+        // without commands the paused execution stops with an interrupted error
+        self.commands.take();
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod debug_test {
+
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use crate::execution::debug::{Breakpoint, Event, NamedComponent, NamedValue, Pause, PauseReason, Session};
+    use crate::execution::Limits;
+    use crate::parser::compile;
+    use crate::parser::errors::execution::ExecutionErrorKind;
+    use crate::parser::source::MemorySource;
+    use crate::parser::symbols;
+    use crate::parser::template::{self, Program};
+    use crate::r1cs::field::FieldElement;
+
+    use galvanic_assert::matchers::*;
+
+    const SOURCE: &str = "template Square() {\n    signal input a;\n    signal output b;\n    b <== a * a;\n}\ntemplate Main() {\n    signal input x;\n    signal output y;\n    var acc = 0;\n    for (var i = 0; i < 2; i++) {\n        acc += i;\n    }\n    component square = Square();\n    square.a <== x + acc;\n    y <== square.b;\n}\ncomponent main = Main();\n";
+
+    fn program() -> Program {
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", SOURCE);
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("/virtual/main.circom"), &sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        template::load_program(&ctx, &table)
+    }
+
+    fn paused(event: Event) -> Pause {
+        match event {
+            Event::Paused(pause) => pause,
+            Event::Finished(result) => panic!("finished with {:?}", result.map(|circuit| circuit.signals.len()))
+        }
+    }
+
+    fn value(values: & [NamedValue], name: & str) -> Option<FieldElement> {
+        values.iter().find(|value| value.name == name).and_then(|value| value.value.clone())
+    }
+
+    #[test]
+    fn test_step_through() {
+
+        let mut inputs = HashMap::new();
+        inputs.insert("x".to_string(), FieldElement::from_i64(3));
+        let mut session = Session::start(program(), Some(inputs), Limits::default());
+
+        let first = paused(session.step());
+        expect_that!(&(first.reason.clone(), first.instance.as_str(), first.template.as_str(), first.file.as_str(), first.line), eq((PauseReason::Step, "main", "Main", "/virtual/main.circom", 7)));
+        let lines: Vec<usize> = (0..8).map(|_| paused(session.step()).line).collect();
+        expect_that!(&lines, eq(vec![8, 9, 10, 11, 10, 11, 10, 13]));
+
+        let pause = paused(session.step());
+        expect_that!(&(pause.line, value(&pause.variables, "acc"), value(&pause.signals, "x")), eq((14, Some(FieldElement::one()), Some(FieldElement::from_i64(3)))));
+        // reading square.b runs the sub-component
+        expect_that!(&paused(session.step()).line, eq(15));
+        let inner = paused(session.step());
+        expect_that!(&(inner.instance.as_str(), inner.template.as_str(), inner.line, inner.signals.len()), eq(("main.square", "Square", 2, 0)));
+        let declared = paused(session.step());
+        expect_that!(&(declared.line, value(&declared.signals, "a")), eq((3, Some(FieldElement::from_i64(4)))));
+
+        match session.resume() {
+            Event::Finished(Ok(circuit)) => {
+                expect_that!(&circuit.witness(), eq(Ok(vec![1, 16, 3, 16, 4].into_iter().map(FieldElement::from_i64).collect::<Vec<FieldElement>>())));
+            },
+            event => panic!("{:?}", event)
+        }
+        expect_that!(&session.is_finished(), eq(true));
+        expect_that!(&matches!(session.step(), Event::Finished(Ok(_))), eq(true));
+
+    }
+
+    #[test]
+    fn test_breakpoints() {
+
+        let mut session = Session::start(program(), None, Limits::default());
+        let template = Breakpoint::Template("Square".to_string());
+        let line = Breakpoint::Line { file: "main.circom".to_string(), line: 11 };
+        let signal = Breakpoint::Signal("main.square.b".to_string());
+        session.add_breakpoint(line.clone());
+        session.add_breakpoint(template.clone());
+        session.add_breakpoint(signal.clone());
+
+        let first = paused(session.resume());
+        expect_that!(&(first.reason, first.line), eq((PauseReason::Breakpoint(line.clone()), 11)));
+        expect_that!(&paused(session.resume()).line, eq(11));
+        expect_that!(&session.remove_breakpoint(&line), eq(true));
+        expect_that!(&session.breakpoints(), eq(vec![template.clone(), signal.clone()]));
+
+        let entered = paused(session.resume());
+        expect_that!(&(entered.reason, entered.instance.as_str(), entered.line), eq((PauseReason::Breakpoint(template), "main.square", 1)));
+        let assigned = paused(session.resume());
+        expect_that!(&(assigned.reason, assigned.line), eq((PauseReason::Breakpoint(signal), 4)));
+        // only the constraints are generated, values are unknown
+        expect_that!(&value(&assigned.signals, "b"), eq(None));
+        expect_that!(&matches!(session.resume(), Event::Finished(Ok(_))), eq(true));
+
+    }
+
+    #[test]
+    fn test_interrupt() {
+
+        let mut session = Session::start(program(), None, Limits::default());
+        session.add_breakpoint(Breakpoint::Template("Square".to_string()));
+        let interrupt = session.interrupt();
+        interrupt.pause();
+        let first = paused(session.resume());
+        expect_that!(&(first.reason, first.line), eq((PauseReason::Pause, 7)));
+        expect_that!(&paused(session.resume()).reason, eq(PauseReason::Breakpoint(Breakpoint::Template("Square".to_string()))));
+        // a pause the session did not reach a statement for is forgotten
+        interrupt.pause();
+        interrupt.clear();
+        expect_that!(&matches!(session.resume(), Event::Finished(Ok(_))), eq(true));
+
+    }
+
+    #[test]
+    fn test_step_over_and_inspect() {
+
+        let mut inputs = HashMap::new();
+        inputs.insert("x".to_string(), FieldElement::from_i64(3));
+        let mut session = Session::start(program(), Some(inputs), Limits::default());
+        expect_that!(&session.evaluate("x"), eq(Err("the execution has not started".to_string())));
+
+        expect_that!(&paused(session.step()).line, eq(7));
+        let lines: Vec<usize> = (0..9).map(|_| paused(session.step_over()).line).collect();
+        expect_that!(&lines, eq(vec![8, 9, 10, 11, 10, 11, 10, 13, 14]));
+        expect_that!(&session.evaluate("acc * 2 + x"), eq(Ok(Some(FieldElement::from_i64(5)))));
+        expect_that!(&session.evaluate("square.b"), eq(Err("reading `square.b` before its component ran is not supported".to_string())));
+        expect_that!(&session.evaluate("nothing"), eq(Err("`nothing` is not declared".to_string())));
+        expect_that!(&session.evaluate("y <== x"), eq(Err("`y <== x` is not an expression".to_string())));
+        expect_that!(&session.evaluate("acc +"), eq(Err("`acc +` ends too early".to_string())));
+
+        let assigned = paused(session.step_over());
+        let input = NamedValue { name: "a".to_string(), value: Some(FieldElement::from_i64(4)) };
+        expect_that!(&(assigned.line, assigned.components), eq((15, vec![NamedComponent { name: "square".to_string(), instance: "main.square".to_string(), signals: vec![input] }])));
+        paused(session.step());
+        let inner = paused(session.step());
+        let frames: Vec<(&str, &str, usize)> = inner.frames.iter().map(|frame| (frame.instance.as_str(), frame.template.as_str(), frame.line)).collect();
+        expect_that!(&frames, eq(vec![("main.square", "Square", 3), ("main", "Main", 15)]));
+        expect_that!(&session.evaluate("a * a"), eq(Ok(Some(FieldElement::from_i64(16)))));
+        // the instance that started the paused one
+        expect_that!(&value(&inner.frames[1].variables, "acc"), eq(Some(FieldElement::one())));
+        expect_that!(&inner.frames[1].components.len(), eq(1));
+        expect_that!(&session.evaluate_in(1, "x + acc"), eq(Ok(Some(FieldElement::from_i64(4)))));
+        expect_that!(&session.evaluate_in(1, "a"), eq(Err("`a` is not declared".to_string())));
+        expect_that!(&session.evaluate_in(2, "x"), eq(Err("there is no frame 2".to_string())));
+
+        expect_that!(&matches!(session.step_out(), Event::Finished(Ok(_))), eq(true));
+        expect_that!(&session.evaluate("x").is_err(), eq(true));
+
+    }
+
+    #[test]
+    fn test_stop_and_errors() {
+
+        let mut session = Session::start(program(), None, Limits::default());
+        paused(session.step());
+        drop(session);
+
+        let mut session = Session::start(program(), Some(HashMap::new()), Limits::default());
+        match session.resume() {
+            Event::Finished(Err(error)) => {
+                expect_that!(&error.kind, eq(ExecutionErrorKind::MissingInput("x".to_string())));
+            },
+            event => panic!("{:?}", event)
+        }
+
+        // the fourth statement is the loop
+        let limits = Limits { statements: 3, ..Limits::default() };
+        let mut session = Session::start(program(), None, limits);
+        match session.resume() {
+            Event::Finished(Err(error)) => {
+                expect_that!(&(error.kind.code(), error.span_error), eq((515, (160, 213))));
+            },
+            event => panic!("{:?}", event)
+        }
+
+    }
+}
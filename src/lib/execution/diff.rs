@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::execution::trace::{self, ChangeKind, Trace};
+use crate::execution::{Circuit, Limits};
+use crate::parser::errors::execution::ExecutionError;
+use crate::parser::template::Program;
+use crate::r1cs::field::FieldElement;
+use crate::r1cs::satisfaction;
+
+///
+/// The values a signal got in both runs, and the statement that first assigned it.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SignalDiff {
+    pub name: String,
+    pub instance: String,
+    pub template: String,
+    pub left: Option<FieldElement>,
+    pub right: Option<FieldElement>,
+    pub file: String,
+    pub line: usize
+}
+
+impl SignalDiff {
+
+    pub fn differs(&self) -> bool {
+        self.left != self.right
+    }
+
+}
+
+///
+/// How a run ended: the error that stopped it, or the constraints its witness does not satisfy.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Outcome {
+    pub error: Option<String>,
+    pub unsatisfied: Vec<String>
+}
+
+impl Outcome {
+
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.unsatisfied.is_empty()
+    }
+
+}
+
+///
+/// Every signal of the circuit in execution order with its value in both runs. `first` is the index of the
+/// first one that differs other than the inputs of main, which differ by design.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diff {
+    pub signals: Vec<SignalDiff>,
+    pub first: Option<usize>,
+    pub left: Outcome,
+    pub right: Outcome
+}
+
+impl Diff {
+
+    pub fn differing(&self) -> impl Iterator<Item = &SignalDiff> {
+        self.signals.iter().filter(|signal| signal.differs())
+    }
+
+    pub fn get(&self, name: & str) -> Option<&SignalDiff> {
+        self.signals.iter().find(|signal| signal.name == name)
+    }
+
+}
+
+fn outcome(result: Result<Circuit, Box<ExecutionError>>) -> Outcome {
+    let circuit = match result {
+        Ok(circuit) => circuit,
+        Err(error) => return Outcome { error: Some(format!("{}: {}", error.instance, error.kind)), unsatisfied: vec![] }
+    };
+    let unsatisfied = match circuit.witness().map(|witness| satisfaction::check(&circuit.constraints, &witness)) {
+        Ok(Ok(unsatisfied)) => unsatisfied.iter().map(|unsatisfied| circuit.format_constraint(&circuit.constraints[unsatisfied.index])).collect(),
+        Ok(Err(_)) => vec![],
+        Err(error) => return Outcome { error: Some(error), unsatisfied: vec![] }
+    };
+    Outcome { error: None, unsatisfied }
+}
+
+// last value of every signal, with the step that first assigned it
+fn signal_values(trace: & Trace) -> HashMap<&str, (usize, Option<FieldElement>)> {
+    let mut values: HashMap<&str, (usize, Option<FieldElement>)> = HashMap::new();
+    for (position, step) in trace.steps.iter().enumerate() {
+        for change in step.changes.iter().filter(|change| change.kind == ChangeKind::Signal) {
+            values.entry(change.name.as_str()).or_insert((position, None)).1 = change.new.clone();
+        }
+    }
+    values
+}
+
+// a statement assigns its target once the sub-components it reads ran, their steps come after its own: the
+// step after the statement and all it ran, statements it ran sort first on a tie
+fn completed(trace: & Trace, position: usize) -> usize {
+    let nested = format!("{}.", trace.steps[position].instance);
+    trace.steps[position + 1..].iter().position(|step| !step.instance.starts_with(&nested))
+        .map(|after| position + 1 + after)
+        .unwrap_or(trace.steps.len())
+}
+
+///
+/// Runs the circuit with both sets of inputs (see `inputs::from_json`) and compares every signal. Signals are
+/// in the order the left run assigned them, followed by those only the right run assigned. A run that fails
+/// leaves the signals it did not reach without a value.
+///
+pub fn compare(program: & Program, left: & HashMap<String, FieldElement>, right: & HashMap<String, FieldElement>, limits: & Limits) -> Diff {
+    let (left_trace, left_result) = trace::record(program, Some(left), limits);
+    let (right_trace, right_result) = trace::record(program, Some(right), limits);
+    let left_values = signal_values(&left_trace);
+    let right_values = signal_values(&right_trace);
+
+    let mut templates: HashMap<&str, &str> = HashMap::new();
+    for step in left_trace.steps.iter().chain(right_trace.steps.iter()) {
+        templates.entry(step.instance.as_str()).or_insert(step.template.as_str());
+    }
+
+    let mut signals: Vec<SignalDiff> = vec![];
+    let mut seen = HashSet::new();
+    for (trace, values) in [(&left_trace, &left_values), (&right_trace, &right_values)] {
+        let mut assigned: Vec<(&str, usize)> = values.iter().map(|(name, (position, _))| (*name, *position)).collect();
+        // a statement can assign several signals, as the inputs of main
+        assigned.sort_by_key(|(name, position)| (completed(trace, *position), std::cmp::Reverse(*position), trace.steps[*position].changes.iter().position(|change| change.name == *name)));
+        for (name, position) in assigned {
+            if !seen.insert(name) {
+                continue;
+            }
+            let instance = name.rsplit_once('.').map(|(instance, _)| instance).unwrap_or(name);
+            let step = &trace.steps[position];
+            signals.push(SignalDiff {
+                name: name.to_string(),
+                instance: instance.to_string(),
+                template: templates.get(instance).unwrap_or(&"").to_string(),
+                left: left_values.get(name).and_then(|(_, value)| value.clone()),
+                right: right_values.get(name).and_then(|(_, value)| value.clone()),
+                file: step.file.clone(),
+                line: step.line
+            });
+        }
+    }
+
+    let inputs: Vec<&str> = program.main_component.as_ref()
+        .and_then(|main| program.templates.get(&main.template_to_use))
+        .map(|template| template.private_input_signals.iter().map(|signal| signal.name.as_str()).collect())
+        .unwrap_or_default();
+    let is_input = |signal: &SignalDiff| signal.instance == "main" && inputs.iter().any(|input| {
+        let name = &signal.name["main.".len()..];
+        name == *input || name.strip_prefix(input).is_some_and(|indices| indices.starts_with('['))
+    });
+
+    Diff {
+        first: signals.iter().position(|signal| signal.differs() && !is_input(signal)),
+        signals,
+        left: outcome(left_result),
+        right: outcome(right_result)
+    }
+}
+
+#[cfg(test)]
+mod diff_test {
+
+    use std::path::PathBuf;
+
+    use crate::execution::{diff, inputs, Limits};
+    use crate::parser::compile;
+    use crate::parser::source::MemorySource;
+    use crate::parser::symbols;
+    use crate::parser::template;
+    use crate::r1cs::field::FieldElement;
+
+    use galvanic_assert::matchers::*;
+
+    const SOURCE: &str = "template IsZero() {\n    signal input in;\n    signal output out;\n    signal inv;\n    inv <-- in != 0 ? 1 / in : 0;\n    out <== -in * inv + 1;\n    in * out === 0;\n}\ntemplate Main() {\n    signal input x;\n    signal input k;\n    signal output y;\n    signal doubled;\n    doubled <== k * 2;\n    component isz = IsZero();\n    isz.in <== x;\n    y <== isz.out;\n    y === 1;\n}\ncomponent main = Main();\n";
+
+    #[test]
+    fn test_compare() {
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", SOURCE);
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("/virtual/main.circom"), &sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = template::load_program(&ctx, &table);
+        let left = inputs::from_json("{\"x\": \"0\", \"k\": \"2\"}").unwrap();
+        let right = inputs::from_json("{\"x\": \"3\", \"k\": \"2\"}").unwrap();
+
+        let diff = diff::compare(&program, &left, &right, &Limits::default());
+        let value = |value: i64| Some(FieldElement::from_i64(value));
+        let summary: Vec<(&str, &str, Option<FieldElement>, Option<FieldElement>, usize)> = diff.signals.iter()
+            .map(|signal| (signal.name.as_str(), signal.template.as_str(), signal.left.clone(), signal.right.clone(), signal.line))
+            .collect();
+        expect_that!(&summary, eq(vec![
+            ("main.x", "Main", value(0), value(3), 10),
+            ("main.k", "Main", value(2), value(2), 11),
+            ("main.doubled", "Main", value(4), value(4), 14),
+            ("main.isz.in", "IsZero", value(0), value(3), 16),
+            ("main.isz.inv", "IsZero", value(0), Some(FieldElement::from_i64(3).inverse().unwrap()), 5),
+            ("main.isz.out", "IsZero", value(1), value(0), 6),
+            ("main.y", "Main", value(1), value(0), 17)
+        ]));
+        // `main.x` differs too, but it is an input
+        expect_that!(&diff.first.map(|first| diff.signals[first].name.as_str()), eq(Some("main.isz.in")));
+        expect_that!(&diff.differing().count(), eq(5));
+        expect_that!(&(diff.left.passed(), diff.right.passed(), diff.right.unsatisfied.len()), eq((true, false, 1)));
+
+        // both runs stop at the limits given
+        let limits = Limits { statements: 4, ..Limits::default() };
+        let stopped = diff::compare(&program, &left, &right, &limits);
+        expect_that!(&stopped.left.error.as_ref().map(|error| error.contains("more than 4 statements")), eq(Some(true)));
+        expect_that!(&stopped.right.error.as_ref().map(|error| error.contains("more than 4 statements")), eq(Some(true)));
+
+    }
+}
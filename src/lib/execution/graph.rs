@@ -0,0 +1,265 @@
+use std::collections::HashSet;
+
+use crate::execution::trace::{ChangeKind, Trace};
+use crate::execution::Circuit;
+use crate::parser::template::SignalDirection;
+use crate::r1cs::field::FieldElement;
+use crate::r1cs::sym::Symbol;
+
+///
+/// Signal of the drawn instance, by its full name. The direction is unknown for a witness.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignalNode {
+    pub name: String,
+    pub direction: Option<SignalDirection>,
+    pub value: Option<FieldElement>
+}
+
+///
+/// Sub-component of the drawn instance, by its full path (`main.mul[1]`), with the full names of its inputs.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentNode {
+    pub name: String,
+    pub template: Option<String>,
+    pub inputs: Vec<String>
+}
+
+///
+/// `target` got its value from `source`, through the input `input` when `target` is a sub-component.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub source: String,
+    pub target: String,
+    pub input: Option<String>
+}
+
+///
+/// One instance of a circuit as the visual debugger draws it: its signals, its sub-components and which of
+/// them every signal assignment of the instance used. Every node is named as in `Circuit::symbols`, see
+/// `node_of`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    pub instance: String,
+    pub template: Option<String>,
+    pub signals: Vec<SignalNode>,
+    pub components: Vec<ComponentNode>,
+    pub edges: Vec<Edge>
+}
+
+///
+/// Node of `instance` standing for the signal `name`: the signal itself when the instance declares it
+/// (`main.in[0]`), the sub-component holding it when it is deeper (`main.mul[1]` for `main.mul[1].c.out`),
+/// none when it is outside the instance. This is the only place a signal name is mapped to a node.
+///
+pub fn node_of(instance: & str, name: & str) -> Option<String> {
+    let rest = name.strip_prefix(instance)?.strip_prefix('.')?;
+    match rest.find('.') {
+        Some(dot) => Some(format!("{}.{}", instance, &rest[..dot])),
+        None => Some(name.to_string())
+    }
+}
+
+///
+/// Graph of the instance at `instance` (`main.mul[1]`) of an execution recorded by `trace::record`, none
+/// when the circuit has no such instance.
+///
+pub fn from_execution(circuit: & Circuit, trace: & Trace, instance: & str) -> Option<Graph> {
+    let position = circuit.instances.iter().position(|candidate| candidate.path == instance)?;
+    let signals = circuit.instances[position].signals.iter()
+        .map(|wire| &circuit.signals[*wire])
+        .map(|signal| SignalNode { name: signal.name.clone(), direction: Some(signal.direction.clone()), value: signal.value.clone() })
+        .collect();
+    let components = circuit.instances.iter()
+        .filter(|child| child.parent == Some(position))
+        .map(|child| ComponentNode {
+            name: child.path.clone(),
+            template: Some(child.template.clone()),
+            inputs: child.signals.iter()
+                .map(|wire| &circuit.signals[*wire])
+                .filter(|signal| signal.direction == SignalDirection::Input)
+                .map(|signal| signal.name.clone())
+                .collect()
+        })
+        .collect();
+
+    let names: HashSet<&str> = circuit.signals.iter().map(|signal| signal.name.as_str()).collect();
+    let mut edges = vec![];
+    for (index, step) in trace.steps.iter().enumerate().filter(|(_, step)| step.instance == instance) {
+        let sources: Vec<String> = signal_reads(trace, &names, index).iter().filter_map(|read| node_of(instance, read)).collect();
+        for change in step.changes.iter().filter(|change| change.kind == ChangeKind::Signal) {
+            let target = match node_of(instance, &change.name) {
+                Some(target) => target,
+                None => continue
+            };
+            let input = if target == change.name { None } else { Some(change.name.clone()) };
+            for source in &sources {
+                let edge = Edge { source: source.clone(), target: target.clone(), input: input.clone() };
+                if edge.source != edge.target && !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            }
+        }
+    }
+
+    Some(Graph {
+        instance: instance.to_string(),
+        template: Some(circuit.instances[position].template.clone()),
+        signals,
+        components,
+        edges
+    })
+}
+
+///
+/// Graph of the instance at `instance` from the symbols of a compilation and a witness of it, as circom
+/// writes them to `.sym` and `.wtns` files, without executing anything. It has no edges, none when no
+/// symbol is within the instance.
+///
+pub fn from_values(symbols: & [Symbol], witness: & [FieldElement], instance: & str) -> Option<Graph> {
+    let mut graph = Graph { instance: instance.to_string(), template: None, signals: vec![], components: vec![], edges: vec![] };
+    for symbol in symbols {
+        let value = symbol.wire.and_then(|wire| witness.get(wire)).cloned();
+        match node_of(instance, &symbol.name) {
+            Some(node) if node == symbol.name => {
+                graph.signals.push(SignalNode { name: node, direction: None, value });
+            },
+            Some(node) if !graph.components.iter().any(|component| component.name == node) => {
+                graph.components.push(ComponentNode { name: node, template: None, inputs: vec![] });
+            },
+            _ => {}
+        }
+    }
+    match graph.signals.is_empty() && graph.components.is_empty() {
+        true => None,
+        false => Some(graph)
+    }
+}
+
+// signals the step at `step` read, directly or through the variables it read, followed back to the steps
+// that set them
+fn signal_reads(trace: & Trace, signals: & HashSet<&str>, step: usize) -> Vec<String> {
+    let mut found = vec![];
+    let mut visited = HashSet::new();
+    let mut pending = vec![step];
+    while let Some(position) = pending.pop() {
+        if !visited.insert(position) {
+            continue;
+        }
+        for read in &trace.steps[position].reads {
+            if signals.contains(read.name.as_str()) {
+                if !found.contains(&read.name) {
+                    found.push(read.name.clone());
+                }
+            } else if let Some(written) = read.step {
+                pending.push(written);
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod graph_test {
+
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use crate::execution::graph::{self, Edge};
+    use crate::execution::trace;
+    use crate::execution::Limits;
+    use crate::parser::compile;
+    use crate::parser::source::MemorySource;
+    use crate::parser::symbols;
+    use crate::parser::template::{self, SignalDirection};
+    use crate::r1cs::field::FieldElement;
+
+    use galvanic_assert::matchers::*;
+
+    const SOURCE: &str = "template Square() {\n    signal input a;\n    signal output b;\n    b <== a * a;\n}\ntemplate Main() {\n    signal input x[2];\n    signal output y;\n    var acc = 0;\n    for (var i = 0; i < 2; i++) {\n        acc += x[i];\n    }\n    component square[2];\n    square[0] = Square();\n    square[0].a <== acc;\n    square[1] = Square();\n    square[1].a <== square[0].b;\n    y <== square[1].b + x[0];\n}\ncomponent main = Main();\n";
+
+    fn edge(source: &str, target: &str, input: Option<&str>) -> Edge {
+        Edge { source: source.to_string(), target: target.to_string(), input: input.map(|input| input.to_string()) }
+    }
+
+    #[test]
+    fn test_from_execution() {
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", SOURCE);
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("/virtual/main.circom"), &sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = template::load_program(&ctx, &table);
+        let inputs: HashMap<String, FieldElement> = [("x[0]", 1), ("x[1]", 2)].iter().map(|(name, value)| (name.to_string(), FieldElement::from_i64(*value))).collect();
+        let (trace, circuit) = trace::record(&program, Some(&inputs), &Limits::default());
+        let circuit = circuit.unwrap();
+
+        let main = graph::from_execution(&circuit, &trace, "main").unwrap();
+        let signals: Vec<(&str, Option<SignalDirection>, Option<FieldElement>)> = main.signals.iter()
+            .map(|signal| (signal.name.as_str(), signal.direction.clone(), signal.value.clone()))
+            .collect();
+        expect_that!(&signals, eq(vec![
+            ("main.y", Some(SignalDirection::Output), Some(FieldElement::from_i64(82))),
+            ("main.x[0]", Some(SignalDirection::Input), Some(FieldElement::from_i64(1))),
+            ("main.x[1]", Some(SignalDirection::Input), Some(FieldElement::from_i64(2)))
+        ]));
+        let components: Vec<(&str, Vec<String>)> = main.components.iter().map(|component| (component.name.as_str(), component.inputs.clone())).collect();
+        expect_that!(&components, eq(vec![
+            ("main.square[0]", vec!["main.square[0].a".to_string()]),
+            ("main.square[1]", vec!["main.square[1].a".to_string()])
+        ]));
+        // `acc` is followed back to both elements of `x`
+        expect_that!(&main.edges, eq(vec![
+            edge("main.x[1]", "main.square[0]", Some("main.square[0].a")),
+            edge("main.x[0]", "main.square[0]", Some("main.square[0].a")),
+            edge("main.square[0]", "main.square[1]", Some("main.square[1].a")),
+            edge("main.square[1]", "main.y", None),
+            edge("main.x[0]", "main.y", None)
+        ]));
+
+        let square = graph::from_execution(&circuit, &trace, "main.square[1]").unwrap();
+        expect_that!(&square.template, eq(Some("Square".to_string())));
+        expect_that!(&square.edges, eq(vec![edge("main.square[1].a", "main.square[1].b", None)]));
+        expect_that!(&graph::from_execution(&circuit, &trace, "main.square").is_none(), eq(true));
+    }
+
+    #[test]
+    fn test_from_values() {
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", SOURCE);
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("/virtual/main.circom"), &sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = template::load_program(&ctx, &table);
+        let inputs: HashMap<String, FieldElement> = [("x[0]", 1), ("x[1]", 2)].iter().map(|(name, value)| (name.to_string(), FieldElement::from_i64(*value))).collect();
+        let circuit = crate::execution::execute_with_inputs(&program, &inputs).unwrap();
+        let witness = circuit.witness().unwrap();
+
+        // the symbols name the same nodes as the execution
+        let executed = graph::from_execution(&circuit, &trace::Trace::default(), "main").unwrap();
+        let read = graph::from_values(&circuit.symbols(), &witness, "main").unwrap();
+        let names = |graph: &graph::Graph| (
+            graph.signals.iter().map(|signal| (signal.name.clone(), signal.value.clone())).collect::<Vec<_>>(),
+            graph.components.iter().map(|component| component.name.clone()).collect::<Vec<_>>()
+        );
+        expect_that!(&names(&read), eq(names(&executed)));
+        expect_that!(&read.edges.is_empty(), eq(true));
+        expect_that!(&graph::from_values(&circuit.symbols(), &witness, "main.square[2]").is_none(), eq(true));
+    }
+
+    #[test]
+    fn test_node_of() {
+
+        expect_that!(&graph::node_of("main", "main.in[0]"), eq(Some("main.in[0]".to_string())));
+        expect_that!(&graph::node_of("main", "main.c[1][0].x[2]"), eq(Some("main.c[1][0]".to_string())));
+        expect_that!(&graph::node_of("main.c[1][0]", "main.c[1][0].x[2]"), eq(Some("main.c[1][0].x[2]".to_string())));
+        expect_that!(&graph::node_of("main.c[1][0]", "main.c[1][0].d.x"), eq(Some("main.c[1][0].d".to_string())));
+        // neither a sibling nor an element of the instance
+        expect_that!(&graph::node_of("main.c", "main.cc.x"), eq(None));
+        expect_that!(&graph::node_of("main.c", "main.c[1].x"), eq(None));
+    }
+
+}
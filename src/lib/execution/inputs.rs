@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::io;
+
+use serde_json::Value;
+
+use crate::r1cs::field::FieldElement;
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn flatten(name: String, value: & Value, inputs: &mut HashMap<String, FieldElement>) -> io::Result<()> {
+    let parsed = match value {
+        Value::Array(items) => {
+            for (idx, item) in items.iter().enumerate() {
+                flatten(format!("{}[{}]", name, idx), item, inputs)?;
+            }
+            return Ok(());
+        },
+        Value::String(text) => FieldElement::parse(text),
+        Value::Number(number) => FieldElement::parse(&number.to_string()),
+        _ => None
+    };
+    match parsed {
+        Some(parsed) => {
+            inputs.insert(name, parsed);
+            Ok(())
+        },
+        None => Err(invalid(format!("`{}` is not a number", name)))
+    }
+}
+
+///
+/// Values of the inputs of main, as given to snarkjs: `{"in": "3", "board": [["0", "1"], ...]}`.
+/// Arrays are flattened to one value per element, named like the signal (`board[0][1]`).
+///
+pub fn from_json(text: & str) -> io::Result<HashMap<String, FieldElement>> {
+    let root: Value = serde_json::from_str(text).map_err(|error| invalid(error.to_string()))?;
+    let mut inputs = HashMap::new();
+    match root {
+        Value::Object(fields) => {
+            for (name, value) in fields.iter() {
+                flatten(name.clone(), value, &mut inputs)?;
+            }
+            Ok(inputs)
+        },
+        _ => Err(invalid("inputs must be a JSON object".to_string()))
+    }
+}
@@ -0,0 +1,1683 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::execution::debug::{Frame, Hook, NamedComponent, NamedValue, Pause, PauseReason, Point};
+use crate::execution::trace::{Change, ChangeKind};
+use crate::execution::value::{Evaluated, Symbolic};
+use crate::parser::errors;
+use crate::parser::errors::execution::{ExecutionError, ExecutionErrorKind, Limit};
+use crate::parser::expression_parser::{self, Assign, BinOp, ComponentInstance, Expr, Operator, Stmt, Variable};
+use crate::parser::template::{DeclStatement, DeclType, ForLoop, IfStatement, Program, SignalDirection, SingleCommand, Template, WhileLoop};
+use crate::r1cs::field::{self, FieldElement};
+use crate::r1cs::sym::Symbol;
+use crate::r1cs::{Constraint, LinearCombination, R1cs, ONE};
+
+pub mod debug;
+pub mod diff;
+pub mod graph;
+pub mod inputs;
+pub mod provenance;
+pub mod repl;
+pub mod trace;
+pub mod value;
+
+///
+/// Signal of the executed circuit, `name` is its full path (`main.isEqual[0].in[1]`).
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signal {
+    pub name: String,
+    pub instance: usize,
+    pub direction: SignalDirection,
+    pub value: Option<FieldElement>
+}
+
+///
+/// Component instance of the executed circuit, `signals` and `constraints` index into the circuit. `file`
+/// is the source of its template.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    pub path: String,
+    pub template: String,
+    pub file: String,
+    pub parent: Option<usize>,
+    pub signals: Vec<usize>,
+    pub constraints: Vec<usize>
+}
+
+///
+/// Result of executing `main`. Signals are indexed by wire, in circom's order: wire 0 is the constant one,
+/// then the outputs of main, its public inputs, its private inputs, and every other signal instance by
+/// instance. Instances are listed depth first with sub-components sorted by name, constraints follow the
+/// same order. `spans` locates the statement of every constraint in the file of its instance.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Circuit {
+    pub instances: Vec<Instance>,
+    pub signals: Vec<Signal>,
+    pub constraints: Vec<Constraint>,
+    pub spans: Vec<(usize, usize)>,
+    pub n_outputs: usize,
+    pub n_public_inputs: usize,
+    pub n_private_inputs: usize
+}
+
+impl Circuit {
+
+    pub fn r1cs(&self) -> R1cs {
+        R1cs {
+            prime: field::prime().clone(),
+            n_wires: self.signals.len(),
+            n_pub_out: self.n_outputs,
+            n_pub_in: self.n_public_inputs,
+            n_prv_in: self.n_private_inputs,
+            n_labels: self.signals.len(),
+            wire_to_label: (0..self.signals.len()).collect(),
+            constraints: self.constraints.clone(),
+            custom_gates: vec![],
+            custom_gate_applications: vec![]
+        }
+    }
+
+    ///
+    /// Symbols of every signal but the constant one, labels are the wires of the unsimplified circuit and
+    /// components are indices into `instances`.
+    ///
+    pub fn symbols(&self) -> Vec<Symbol> {
+        self.signals.iter().enumerate().skip(1).map(|(wire, signal)| Symbol {
+            label: wire,
+            wire: Some(wire),
+            component: signal.instance,
+            name: signal.name.clone()
+        }).collect()
+    }
+
+    ///
+    /// Value of every wire, the name of the first signal without a value otherwise.
+    ///
+    pub fn witness(&self) -> Result<Vec<FieldElement>, String> {
+        self.signals.iter().map(|signal| signal.value.clone().ok_or_else(|| signal.name.clone())).collect()
+    }
+
+    ///
+    /// `(A) * (B) - (C) = 0` with signal names instead of wires.
+    ///
+    pub fn format_constraint(&self, constraint: & Constraint) -> String {
+        constraint.format(&|wire| self.signals.get(wire).map(|signal| signal.name.clone()).unwrap_or_else(|| format!("w{}", wire)))
+    }
+
+    ///
+    /// Instance that generated the constraint at `index`.
+    ///
+    pub fn instance_of(&self, index: usize) -> Option<& Instance> {
+        self.instances.iter().find(|instance| instance.constraints.contains(&index))
+    }
+
+}
+
+///
+/// Executes `main` and generates the constraints of every `<==`, `==>` and `===` it runs.
+///
+pub fn execute(program: & Program) -> Result<Circuit, Box<ExecutionError>> {
+    Executor::new(program, None, None).run()
+}
+
+///
+/// Executes `main` with values for its inputs (see `inputs::from_json`), every signal of the circuit then
+/// has a value and `Circuit::witness` gives the full witness.
+///
+pub fn execute_with_inputs(program: & Program, inputs: & HashMap<String, FieldElement>) -> Result<Circuit, Box<ExecutionError>> {
+    Executor::new(program, Some(inputs), None).run()
+}
+
+///
+/// Bounds of an execution, going past one stops it with `ExecutionErrorKind::LimitExceeded`. The defaults
+/// leave room for circomlib circuits while stopping a loop that never ends or a template that instantiates
+/// itself without end before it takes the process down. Nested components run nested, `depth` also bounds
+/// the stack an execution needs: the default one fits in the 2 MiB of a spawned thread.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limits {
+    // statements, loop tests and if conditions executed
+    pub statements: usize,
+    // components nested within main, and function calls nested within a call
+    pub depth: usize,
+    // times a template is nested within itself, through other templates or not, and times a function is
+    // called while it runs, directly or through other functions
+    pub recursion: usize,
+    // elements of a single array of signals, variables or components
+    pub array_size: usize
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            statements: 1_000_000,
+            depth: 32,
+            recursion: 16,
+            array_size: 1 << 20
+        }
+    }
+}
+
+///
+/// `execute` or `execute_with_inputs`, within other limits than the default ones.
+///
+pub fn execute_with_limits(program: & Program, inputs: Option<& HashMap<String, FieldElement>>, limits: & Limits) -> Result<Circuit, Box<ExecutionError>> {
+    let mut executor = Executor::new(program, inputs, None);
+    executor.limits = limits.clone();
+    executor.run()
+}
+
+// `execute_with_limits` pausing wherever `hook` asks
+pub(crate) fn execute_with_hook<'a>(program: &'a Program, inputs: Option<&'a HashMap<String, FieldElement>>, limits: & Limits, hook: Box<dyn Hook + 'a>) -> Result<Circuit, Box<ExecutionError>> {
+    let mut executor = Executor::new(program, inputs, Some(hook));
+    executor.limits = limits.clone();
+    executor.run()
+}
+
+// row-major array of any dimension, a scalar has no dimension
+#[derive(Debug, Clone)]
+struct Array<T> {
+    dims: Vec<usize>,
+    items: Vec<T>
+}
+
+impl<T: Clone> Array<T> {
+
+    fn new(dims: Vec<usize>, fill: T) -> Array<T> {
+        let size = dims.iter().product();
+        Array { dims, items: vec![fill; size] }
+    }
+
+    fn offset(&self, indices: & [usize]) -> Option<usize> {
+        if indices.len() != self.dims.len() {
+            return None;
+        }
+        let mut offset = 0;
+        for (index, dim) in indices.iter().zip(&self.dims) {
+            if index >= dim {
+                return None;
+            }
+            offset = offset * dim + index;
+        }
+        Some(offset)
+    }
+
+}
+
+fn element_name(name: & str, indices: & [usize]) -> String {
+    let mut element = name.to_string();
+    for index in indices {
+        element.push_str(&format!("[{}]", index));
+    }
+    element
+}
+
+// every index tuple of an array of dimensions `dims`, in row-major order
+fn all_indices(dims: & [usize]) -> Vec<Vec<usize>> {
+    let mut all = vec![vec![]];
+    for dim in dims {
+        all = all.into_iter().flat_map(|prefix| (0..*dim).map(move |index| {
+            let mut indices = prefix.clone();
+            indices.push(index);
+            indices
+        })).collect();
+    }
+    all
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    // instantiated, waiting for its inputs
+    Pending,
+    Running,
+    Done
+}
+
+struct InstanceState<'a> {
+    path: String,
+    // local name split into base name and indices, sub-components are laid out in this order
+    key: (String, Vec<usize>),
+    template: &'a Template,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    vars: HashMap<String, Array<Evaluated>>,
+    signals: HashMap<String, Array<usize>>,
+    components: HashMap<String, Array<Option<usize>>>,
+    // signal ids in declaration order
+    declared: Vec<usize>,
+    // inputs the parent assigned before the instance ran, by element name
+    pending: HashMap<String, usize>,
+    // with the span of the statement generating each
+    constraints: Vec<(Constraint, (usize, usize))>,
+    status: Status
+}
+
+struct SignalState {
+    instance: usize,
+    name: String,
+    direction: SignalDirection,
+    value: Option<FieldElement>,
+    assigned: bool
+}
+
+///
+/// Runs templates the way circom does: a sub-component runs once all its inputs could have been assigned,
+/// that is when the parent first reads one of its signals or when the parent is done. Signals are numbered
+/// in creation order while executing, `finish` lays them out in circom's order.
+///
+struct Executor<'a> {
+    program: &'a Program,
+    instances: Vec<InstanceState<'a>>,
+    signals: Vec<SignalState>,
+    // values of the inputs of main, only when generating a witness
+    inputs: Option<&'a HashMap<String, FieldElement>>,
+    // decides where a debugging session pauses, see `debug::Session`
+    hook: Option<Box<dyn Hook + 'a>>,
+    // offset of every line of a file, computed for the hook
+    line_starts: HashMap<String, Vec<usize>>,
+    // instances that started running another, with the span of the statement that did
+    callers: Vec<(usize, (usize, usize))>,
+    // evaluating for a debugger, which must not run sub-components
+    inspecting: bool,
+    // functions running in the current instance, innermost last
+    calls: Vec<String>,
+    // value of the `return` the innermost call ran, the statements around it are skipped until the call ends
+    returned: Option<Array<Evaluated>>,
+    limits: Limits,
+    // statements executed so far, see `Limits::statements`
+    statements: usize,
+    current: usize,
+    span: (usize, usize)
+}
+
+impl<'a> Executor<'a> {
+
+    fn new(program: &'a Program, inputs: Option<&'a HashMap<String, FieldElement>>, hook: Option<Box<dyn Hook + 'a>>) -> Executor<'a> {
+        Executor {
+            program,
+            inputs,
+            hook,
+            line_starts: HashMap::new(),
+            callers: vec![],
+            inspecting: false,
+            calls: vec![],
+            returned: None,
+            limits: Limits::default(),
+            statements: 0,
+            instances: vec![],
+            signals: vec![SignalState {
+                instance: 0,
+                name: "one".to_string(),
+                direction: SignalDirection::Input,
+                value: Some(FieldElement::one()),
+                assigned: true
+            }],
+            current: 0,
+            span: (0, 0)
+        }
+    }
+
+    fn error(&self, kind: ExecutionErrorKind) -> ExecutionError {
+        match self.instances.get(self.current) {
+            Some(instance) => ExecutionError {
+                file_error: PathBuf::from(&instance.template.path),
+                source_error: match self.program.sources.get(&instance.template.path) {
+                    Some(source) => errors::common_displayer::source_line(source, &self.span),
+                    None => String::new()
+                },
+                span_error: self.span,
+
+                instance: instance.path.clone(),
+                template: instance.template.name.clone(),
+                kind
+            },
+            None => ExecutionError {
+                file_error: PathBuf::new(),
+                source_error: String::new(),
+                span_error: self.span,
+
+                instance: "main".to_string(),
+                template: String::new(),
+                kind
+            }
+        }
+    }
+
+    fn run(mut self) -> Result<Circuit, Box<ExecutionError>> {
+        self.execute_main()?;
+        Ok(self.finish())
+    }
+
+    // runs `main`, the state of every instance stays for `finish` or a REPL
+    fn execute_main(&mut self) -> Result<(), Box<ExecutionError>> {
+        let main = match &self.program.main_component {
+            Some(main) => main,
+            None => return Err(Box::new(self.error(ExecutionErrorKind::UndeclaredName("main".to_string()))))
+        };
+        let template = match self.program.templates.get(&main.template_to_use) {
+            Some(template) => template,
+            None => return Err(Box::new(self.error(ExecutionErrorKind::UndeclaredName(main.template_to_use.clone()))))
+        };
+        // `arguments` only holds the literal parameters of `component main = ...`
+        if main.arguments.len() != template.params.len() {
+            self.span = template.span;
+            return Err(Box::new(self.error(ExecutionErrorKind::Unsupported("a parameter of main other than a number".to_string()))));
+        }
+        let arguments = main.arguments.clone();
+        let main = self.create_instance("main".to_string(), ("main".to_string(), vec![]), template, None, arguments);
+        // a failing statement returns before `current` and `span` are restored, they locate the error
+        if let Err(kind) = self.execute_instance(main) {
+            return Err(Box::new(self.error(kind)));
+        }
+        if let Some(inputs) = self.inputs {
+            let mut given: Vec<&String> = inputs.keys().collect();
+            given.sort();
+            let declared = &self.instances[main].declared;
+            if let Some(unknown) = given.into_iter().find(|name| !declared.iter().any(|id| self.signals[*id].direction == SignalDirection::Input && &self.signals[*id].name == *name)) {
+                self.span = template.span;
+                return Err(Box::new(self.error(ExecutionErrorKind::UnknownInput(unknown.clone()))));
+            }
+        }
+        Ok(())
+    }
+
+    fn create_instance(&mut self, path: String, key: (String, Vec<usize>), template: &'a Template, parent: Option<usize>, arguments: Vec<FieldElement>) -> usize {
+        let mut vars = HashMap::new();
+        for (param, argument) in template.params.iter().zip(arguments) {
+            vars.insert(param.clone(), Array::new(vec![], Evaluated::constant(argument)));
+        }
+        self.instances.push(InstanceState {
+            path,
+            key,
+            template,
+            parent,
+            children: vec![],
+            vars,
+            signals: HashMap::new(),
+            components: HashMap::new(),
+            declared: vec![],
+            pending: HashMap::new(),
+            constraints: vec![],
+            status: Status::Pending
+        });
+        self.instances.len() - 1
+    }
+
+    fn execute_instance(&mut self, id: usize) -> Result<(), ExecutionErrorKind> {
+        let (caller, caller_span) = (self.current, self.span);
+        self.callers.push((caller, caller_span));
+        self.current = id;
+        self.instances[id].status = Status::Running;
+        let template = self.instances[id].template;
+        self.span = template.span;
+        log::debug!("running {} ({})", self.instances[id].path, template.name);
+        self.pause_at(|_| Point::Entered { template: template.name.clone() })?;
+
+        self.run_commands(&template.instructions)?;
+
+        self.span = template.span;
+        if let Some(signal) = self.instances[id].pending.keys().min() {
+            let component = self.instances[id].path.clone();
+            return Err(ExecutionErrorKind::InvalidComponentSignal { component, signal: signal.clone() });
+        }
+        for child in self.instances[id].children.clone() {
+            if self.instances[child].status != Status::Pending {
+                continue;
+            }
+            // a component runs once an input is assigned, without any it would leave its constraints out
+            if self.instances[child].pending.is_empty() {
+                if let Some(input) = self.instances[child].template.private_input_signals.first() {
+                    return Err(ExecutionErrorKind::UnassignedInput { component: self.instances[child].path.clone(), signal: input.name.clone() });
+                }
+            }
+            self.execute_instance(child)?;
+        }
+
+        self.instances[id].status = Status::Done;
+        self.callers.pop();
+        self.current = caller;
+        self.span = caller_span;
+        Ok(())
+    }
+
+    fn run_commands(&mut self, commands: & [SingleCommand]) -> Result<(), ExecutionErrorKind> {
+        for command in commands {
+            match command {
+                SingleCommand::Instruction(instruction) => {
+                    self.span = instruction.span;
+                    self.begin_statement()?;
+                    self.run_statement(&instruction.statement)?;
+                },
+                SingleCommand::DeclarationStatement(declaration) => {
+                    self.span = declaration.span;
+                    self.begin_statement()?;
+                    self.declare(declaration)?;
+                },
+                SingleCommand::ForLoop(for_loop) => self.run_for(for_loop)?,
+                SingleCommand::WhileLoop(while_loop) => self.run_while(while_loop)?,
+                SingleCommand::IfStatement(if_statement) => self.run_if(if_statement)?,
+                SingleCommand::Return(statement) => {
+                    self.span = statement.span;
+                    self.begin_statement()?;
+                    if self.calls.is_empty() {
+                        return Err(ExecutionErrorKind::Unsupported("`return` in a template".to_string()));
+                    }
+                    self.returned = match &statement.value {
+                        Stmt::RegularExpr(value) => Some(self.argument(value)?),
+                        _ => return Err(ExecutionErrorKind::Unsupported("this return value".to_string()))
+                    };
+                },
+                SingleCommand::Unsupported(statement) => {
+                    self.span = statement.span;
+                    return Err(ExecutionErrorKind::Unsupported(format!("`{}`", statement.keyword)));
+                }
+            }
+            if self.returned.is_some() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn run_statement(&mut self, statement: & Stmt) -> Result<(), ExecutionErrorKind> {
+        match statement {
+            Stmt::Constraint(constraint) => {
+                let value = self.evaluate(&constraint.value)?;
+                self.constrain_signal(&constraint.target, value, &format!("{} <== {}", constraint.target, constraint.value))
+            },
+            Stmt::Assign(assign) => match (&assign.assign_op, &assign.value) {
+                (Operator::LeftSignalAssign, _) | (Operator::RightSignalAssign, _) => {
+                    let value = self.evaluate(&assign.value)?;
+                    let signal = self.signal_target(&assign.target)?;
+                    self.assign_signal(signal, value.value)
+                },
+                (Operator::Assignment, Expr::ComponentInstance(instance)) if self.is_component(&assign.target) => self.instantiate(&assign.target, instance),
+                (op, _) => {
+                    let value = self.evaluate(&assign.value)?;
+                    self.assign_var(&assign.target, op, value)
+                }
+            },
+            Stmt::SymmetricConstraint(constraint) => {
+                let left = self.evaluate(&constraint.left)?;
+                let right = self.evaluate(&constraint.right)?;
+                self.constrain(&left, &right, &format!("{} === {}", constraint.left, constraint.right))
+            },
+            Stmt::Assert(assert) => {
+                let value = self.evaluate(&assert.value)?;
+                match value.value {
+                    Some(value) if value.is_zero() => Err(ExecutionErrorKind::AssertionFailed(assert.value.to_string())),
+                    _ => Ok(())
+                }
+            },
+            Stmt::ConditionalAssign(_) | Stmt::RegularExpr(_) => Ok(()),
+            // what the expression parser could not read, the semantic checker reports it before any execution
+            Stmt::Invalid(_) | Stmt::Empty => Err(ExecutionErrorKind::Unsupported("this statement".to_string()))
+        }
+    }
+
+    fn declare(&mut self, declaration: & DeclStatement) -> Result<(), ExecutionErrorKind> {
+        let mut dims = vec![];
+        for dimension in &declaration.dimensions {
+            dims.push(self.evaluate_usize(dimension)?);
+        }
+        let size = dims.iter().try_fold(1usize, |size, dim| size.checked_mul(*dim));
+        if size.is_none_or(|size| size > self.limits.array_size) {
+            return Err(ExecutionErrorKind::LimitExceeded { limit: Limit::ArraySize, maximum: self.limits.array_size });
+        }
+        match declaration.decl_type {
+            DeclType::Variable => {
+                let array = match (&declaration.expression, &declaration.assign_op) {
+                    (Stmt::Empty, None) => Array::new(dims, Evaluated::constant(FieldElement::zero())),
+                    (Stmt::RegularExpr(expr), Some(Operator::Assignment)) => {
+                        let mut items = vec![];
+                        self.initializer(&declaration.name, &dims, expr, &mut items)?;
+                        Array { dims, items }
+                    },
+                    _ => return Err(ExecutionErrorKind::Unsupported(format!("the initial value of `{}`", declaration.name)))
+                };
+                for (indices, item) in all_indices(&array.dims).iter().zip(&array.items) {
+                    let value = item.value.clone();
+                    self.record(|executor| Change::new(ChangeKind::Variable, executor.qualified(&element_name(&declaration.name, indices)), None, value));
+                }
+                self.instances[self.current].vars.insert(declaration.name.clone(), array);
+                Ok(())
+            },
+            DeclType::Signal => {
+                let mut ids = vec![];
+                for indices in all_indices(&dims) {
+                    ids.push(self.declare_signal(element_name(&declaration.name, &indices), &declaration.direction)?);
+                }
+                self.instances[self.current].signals.insert(declaration.name.clone(), Array { dims, items: ids });
+                match (&declaration.expression, &declaration.assign_op) {
+                    (Stmt::RegularExpr(expr), Some(Operator::LeftConstraint)) => {
+                        let value = self.evaluate(expr)?;
+                        let target = Variable { id: declaration.name.clone(), indexing: vec![], sub_variable: None };
+                        self.constrain_signal(&target, value, &format!("{} <== {}", declaration.name, expr))
+                    },
+                    (Stmt::RegularExpr(expr), Some(Operator::LeftSignalAssign)) => {
+                        let value = self.evaluate(expr)?;
+                        let target = Variable { id: declaration.name.clone(), indexing: vec![], sub_variable: None };
+                        let signal = self.signal_target(&target)?;
+                        self.assign_signal(signal, value.value)
+                    },
+                    (Stmt::Empty, None) => Ok(()),
+                    _ => Err(ExecutionErrorKind::Unsupported(format!("the initial value of `{}`", declaration.name)))
+                }
+            },
+            DeclType::Component => {
+                self.instances[self.current].components.insert(declaration.name.clone(), Array::new(dims, None));
+                match &declaration.expression {
+                    Stmt::Assign(Assign { target, value: Expr::ComponentInstance(instance), .. }) => self.instantiate(target, instance),
+                    Stmt::Empty => Ok(()),
+                    _ => Err(ExecutionErrorKind::Unsupported(format!("the initial value of `{}`", declaration.name)))
+                }
+            }
+        }
+    }
+
+    fn declare_signal(&mut self, name: String, direction: & SignalDirection) -> Result<usize, ExecutionErrorKind> {
+        let id = match self.instances[self.current].pending.remove(&name) {
+            Some(id) if *direction == SignalDirection::Input => id,
+            Some(_) => {
+                let component = self.instances[self.current].path.clone();
+                return Err(ExecutionErrorKind::InvalidComponentSignal { component, signal: name });
+            },
+            None => {
+                // inputs of main get their value from outside
+                let is_main_input = self.current == 0 && *direction == SignalDirection::Input;
+                let value = match self.inputs {
+                    Some(inputs) if is_main_input => match inputs.get(&name) {
+                        Some(value) => Some(value.clone()),
+                        None => return Err(ExecutionErrorKind::MissingInput(name))
+                    },
+                    _ => None
+                };
+                if is_main_input {
+                    let new = value.clone();
+                    self.record(|executor| Change::new(ChangeKind::Signal, executor.qualified(&name), None, new));
+                }
+                self.signals.push(SignalState {
+                    instance: self.current,
+                    name,
+                    direction: direction.clone(),
+                    value,
+                    assigned: is_main_input
+                });
+                self.signals.len() - 1
+            }
+        };
+        self.instances[self.current].declared.push(id);
+        Ok(id)
+    }
+
+    fn run_for(&mut self, for_loop: & ForLoop) -> Result<(), ExecutionErrorKind> {
+        self.span = for_loop.span;
+        if for_loop.step == 0 {
+            return Err(ExecutionErrorKind::Unsupported("a for loop update other than `++` or `--`".to_string()));
+        }
+        let test = match &for_loop.test {
+            Stmt::RegularExpr(test) => test,
+            Stmt::Empty => return Err(ExecutionErrorKind::Unsupported("a for loop without condition".to_string())),
+            _ => return Err(ExecutionErrorKind::Unsupported("this for loop condition".to_string()))
+        };
+        let start = match &for_loop.start {
+            Stmt::RegularExpr(start) => Some(start),
+            Stmt::Empty => None,
+            _ => return Err(ExecutionErrorKind::Unsupported("this for loop start".to_string()))
+        };
+        let step = Evaluated::constant(FieldElement::from_i64(for_loop.step as i64));
+        let mut first = true;
+        loop {
+            self.span = for_loop.span;
+            self.begin_statement()?;
+            // the start and the step are part of the loop line, not of the statements around it
+            if let (true, Some(start)) = (first, start) {
+                let start = self.evaluate(start)?;
+                let old = self.instances[self.current].vars.get(&for_loop.index).and_then(|array| array.items.first()).and_then(|item| item.value.clone());
+                let new = start.value.clone();
+                self.record(|executor| Change::new(ChangeKind::Variable, executor.qualified(&for_loop.index), old, new));
+                self.instances[self.current].vars.insert(for_loop.index.clone(), Array::new(vec![], start));
+            }
+            if !first {
+                let index = Variable { id: for_loop.index.clone(), indexing: vec![], sub_variable: None };
+                self.assign_var(&index, &Operator::PlusAssignment, step.clone())?;
+            }
+            first = false;
+            if self.evaluate_known(test)?.is_zero() {
+                return Ok(());
+            }
+            self.run_commands(&for_loop.body)?;
+            if self.returned.is_some() {
+                return Ok(());
+            }
+        }
+    }
+
+    fn run_while(&mut self, while_loop: & WhileLoop) -> Result<(), ExecutionErrorKind> {
+        let test = match &while_loop.test {
+            Stmt::RegularExpr(test) => test,
+            _ => return Err(ExecutionErrorKind::Unsupported("this while condition".to_string()))
+        };
+        loop {
+            self.span = while_loop.span;
+            self.begin_statement()?;
+            if self.evaluate_known(test)?.is_zero() {
+                return Ok(());
+            }
+            self.run_commands(&while_loop.body)?;
+            if self.returned.is_some() {
+                return Ok(());
+            }
+        }
+    }
+
+    // elements of the initial value of a var of dimensions `dims`, in row-major order: a single value or
+    // arrays nested as deep as the var
+    fn initializer(&mut self, name: & str, dims: & [usize], expr: & Expr, items: &mut Vec<Evaluated>) -> Result<(), ExecutionErrorKind> {
+        match (dims.split_first(), expr) {
+            (None, Expr::Array(_)) => {},
+            (None, expr) => {
+                items.push(self.evaluate(expr)?);
+                return Ok(());
+            },
+            (Some((dim, dims)), Expr::Array(elements)) if elements.len() == *dim => {
+                for element in elements {
+                    self.initializer(name, dims, element, items)?;
+                }
+                return Ok(());
+            },
+            (Some(_), Expr::ComponentInstance(call)) if self.program.functions.contains_key(&call.name.id) => {
+                let returned = self.call(call)?;
+                if returned.dims == dims {
+                    items.extend(returned.items);
+                    return Ok(());
+                }
+            },
+            (Some(_), _) => {}
+        }
+        Err(ExecutionErrorKind::Unsupported(format!("initializing `{}` with `{}`", name, expr)))
+    }
+
+    fn run_if(&mut self, if_statement: & IfStatement) -> Result<(), ExecutionErrorKind> {
+        for branch in &if_statement.branches {
+            self.span = branch.span;
+            self.begin_statement()?;
+            let holds = match &branch.condition {
+                Stmt::RegularExpr(condition) => !self.evaluate_known(condition)?.is_zero(),
+                _ => return Err(ExecutionErrorKind::Unsupported("this if condition".to_string()))
+            };
+            if holds {
+                return self.run_commands(&branch.body);
+            }
+        }
+        self.run_commands(&if_statement.else_body)
+    }
+
+    fn evaluate_known(&mut self, expr: & Expr) -> Result<FieldElement, ExecutionErrorKind> {
+        let value = self.evaluate(expr)?;
+        match value.known() {
+            Some(value) => Ok(value.clone()),
+            None => Err(ExecutionErrorKind::UnknownValue(expr.to_string()))
+        }
+    }
+
+    fn evaluate_usize(&mut self, expr: & Expr) -> Result<usize, ExecutionErrorKind> {
+        match self.evaluate_known(expr)?.to_usize() {
+            Some(value) => Ok(value),
+            None => Err(ExecutionErrorKind::Unsupported(format!("the size or index `{}`", expr)))
+        }
+    }
+
+    fn indices(&mut self, indexing: & [Expr]) -> Result<Vec<usize>, ExecutionErrorKind> {
+        let mut indices = vec![];
+        for index in indexing {
+            indices.push(self.evaluate_usize(index)?);
+        }
+        Ok(indices)
+    }
+
+    fn out_of_bounds(name: & str, indices: & [usize]) -> ExecutionErrorKind {
+        ExecutionErrorKind::IndexOutOfBounds { name: name.to_string(), indices: element_name("", indices) }
+    }
+
+    fn evaluate(&mut self, expr: & Expr) -> Result<Evaluated, ExecutionErrorKind> {
+        match expr {
+            Expr::Empty => Err(ExecutionErrorKind::Unsupported("an empty expression".to_string())),
+            Expr::Number(number) => Ok(Evaluated::constant(number.clone())),
+            Expr::ComplexVariable(variable) => self.read(variable),
+            Expr::BinaryOperation(operation) => {
+                let left = self.evaluate(&operation.left)?;
+                let right = self.evaluate(&operation.right)?;
+                match value::binary(&operation.op, &left, &right) {
+                    Some(result) => Ok(result),
+                    None => Err(ExecutionErrorKind::DivisionByZero(expr.to_string()))
+                }
+            },
+            Expr::LogicalOperation { left, op, right } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                Ok(value::logical(op, &left, &right))
+            },
+            Expr::BitwiseOperation { left, op, right } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                Ok(value::bitwise(op, &left, &right))
+            },
+            Expr::UnaryOperation { op, expr } => {
+                let operand = self.evaluate(expr)?;
+                Ok(value::unary(op, &operand))
+            },
+            Expr::Conditional { condition, true_value, false_value } => {
+                let condition = self.evaluate(condition)?;
+                let known = condition.known().is_some();
+                match condition.value {
+                    // a condition on signal values picks a branch, but not a constraint
+                    Some(condition) => {
+                        let chosen = self.evaluate(if condition.is_zero() { false_value } else { true_value })?;
+                        match known {
+                            true => Ok(chosen),
+                            false => Ok(Evaluated { value: chosen.value, symbolic: Symbolic::NonQuadratic })
+                        }
+                    },
+                    None => Ok(Evaluated::unknown())
+                }
+            },
+            Expr::ComponentInstance(instance) if self.program.functions.contains_key(&instance.name.id) => {
+                let returned = self.call(instance)?;
+                match returned.dims.is_empty() {
+                    true => Ok(returned.items[0].clone()),
+                    false => Err(ExecutionErrorKind::Unsupported(format!("the array returned by `{}` outside of a var initializer", expr)))
+                }
+            },
+            Expr::ComponentInstance(instance) => match self.program.templates.contains_key(&instance.name.id) {
+                true => Err(ExecutionErrorKind::Unsupported(format!("instantiating `{}` inside an expression", instance.name.id))),
+                false => Err(ExecutionErrorKind::UndeclaredName(instance.name.id.clone()))
+            },
+            Expr::Array(_) => Err(ExecutionErrorKind::Unsupported(format!("the array `{}` outside of a var initializer", expr)))
+        }
+    }
+
+    fn read(&mut self, variable: & Variable) -> Result<Evaluated, ExecutionErrorKind> {
+        let indices = self.indices(&variable.indexing)?;
+        let sub_variable = match &variable.sub_variable {
+            Some(sub_variable) => sub_variable,
+            None => {
+                let instance = &self.instances[self.current];
+                if let Some(array) = instance.vars.get(&variable.id) {
+                    let item = match array.offset(&indices) {
+                        Some(offset) => array.items[offset].clone(),
+                        None => return Err(Self::out_of_bounds(&variable.id, &indices))
+                    };
+                    self.report_read(|executor| (executor.qualified(&element_name(&variable.id, &indices)), item.value.clone()));
+                    return Ok(item);
+                }
+                let signal = match instance.signals.get(&variable.id) {
+                    Some(array) => match array.offset(&indices) {
+                        Some(offset) => array.items[offset],
+                        None => return Err(Self::out_of_bounds(&variable.id, &indices))
+                    },
+                    None => return Err(ExecutionErrorKind::UndeclaredName(variable.to_string()))
+                };
+                self.report_read(|executor| executor.signal_read(signal));
+                return Ok(Evaluated::signal(signal, self.signals[signal].value.clone()));
+            }
+        };
+
+        let child = self.component(&variable.id, &indices)?;
+        if self.instances[child].status == Status::Pending && self.inspecting {
+            return Err(ExecutionErrorKind::Unsupported(format!("reading `{}` before its component ran", variable)));
+        }
+        if self.instances[child].status == Status::Pending {
+            self.execute_instance(child)?;
+        }
+        let sub_indices = self.indices(&sub_variable.indexing)?;
+        let signal = match self.instances[child].signals.get(&sub_variable.id) {
+            Some(array) => match array.offset(&sub_indices) {
+                Some(offset) => array.items[offset],
+                None => return Err(Self::out_of_bounds(&format!("{}.{}", element_name(&variable.id, &indices), sub_variable.id), &sub_indices))
+            },
+            None => return Err(ExecutionErrorKind::UndeclaredName(variable.to_string()))
+        };
+        self.report_read(|executor| executor.signal_read(signal));
+        Ok(Evaluated::signal(signal, self.signals[signal].value.clone()))
+    }
+
+    // value of a call argument or of a returned expression: a var or signal named with fewer indices than it
+    // has dimensions gives the sub-array they select, anything else a single value
+    fn argument(&mut self, expr: & Expr) -> Result<Array<Evaluated>, ExecutionErrorKind> {
+        let variable = match expr {
+            Expr::ComplexVariable(variable) if variable.sub_variable.is_none() => variable,
+            _ => return Ok(Array::new(vec![], self.evaluate(expr)?))
+        };
+        let instance = &self.instances[self.current];
+        let all_dims = match (instance.vars.get(&variable.id), instance.signals.get(&variable.id)) {
+            (Some(array), _) => array.dims.clone(),
+            (None, Some(array)) => array.dims.clone(),
+            (None, None) => vec![]
+        };
+        if all_dims.len() <= variable.indexing.len() {
+            return Ok(Array::new(vec![], self.evaluate(expr)?));
+        }
+        let indices = self.indices(&variable.indexing)?;
+        let (selected, dims) = all_dims.split_at(indices.len());
+        if indices.iter().zip(selected).any(|(index, dim)| index >= dim) {
+            return Err(Self::out_of_bounds(&variable.id, &indices));
+        }
+        let size: usize = dims.iter().product();
+        let start = indices.iter().zip(selected).fold(0, |offset, (index, dim)| offset * dim + index) * size;
+        let mut items = vec![];
+        for (offset, rest) in (start..start + size).zip(all_indices(dims)) {
+            let instance = &self.instances[self.current];
+            match instance.vars.get(&variable.id) {
+                Some(array) => {
+                    let item = array.items[offset].clone();
+                    let name = element_name(&variable.id, &[indices.clone(), rest].concat());
+                    self.report_read(|executor| (executor.qualified(&name), item.value.clone()));
+                    items.push(item);
+                },
+                None => {
+                    let signal = instance.signals[&variable.id].items[offset];
+                    self.report_read(|executor| executor.signal_read(signal));
+                    items.push(Evaluated::signal(signal, self.signals[signal].value.clone()));
+                }
+            }
+        }
+        Ok(Array { dims: dims.to_vec(), items })
+    }
+
+    // runs a function in a frame of the current instance that only holds its parameters, and gives what its
+    // `return` gave
+    fn call(&mut self, call: & ComponentInstance) -> Result<Array<Evaluated>, ExecutionErrorKind> {
+        let function = match self.program.functions.get(&call.name.id) {
+            Some(function) => function,
+            None => return Err(ExecutionErrorKind::UndeclaredName(call.name.id.clone()))
+        };
+        let mut vars = HashMap::new();
+        for (param, argument) in function.params.iter().zip(&call.parameter_list) {
+            vars.insert(param.clone(), self.argument(argument)?);
+        }
+        if self.calls.len() >= self.limits.depth {
+            return Err(ExecutionErrorKind::LimitExceeded { limit: Limit::Depth, maximum: self.limits.depth });
+        }
+        if self.calls.iter().filter(|name| **name == function.name).count() >= self.limits.recursion {
+            return Err(ExecutionErrorKind::LimitExceeded { limit: Limit::Recursion, maximum: self.limits.recursion });
+        }
+
+        let span = self.span;
+        let instance = &mut self.instances[self.current];
+        let template = std::mem::replace(&mut instance.template, function);
+        let caller_vars = std::mem::replace(&mut instance.vars, vars);
+        let signals = std::mem::take(&mut instance.signals);
+        let components = std::mem::take(&mut instance.components);
+        self.calls.push(function.name.clone());
+        self.span = function.span;
+        for param in &function.params {
+            let array = self.instances[self.current].vars[param].clone();
+            for (indices, item) in all_indices(&array.dims).iter().zip(array.items) {
+                self.record(|executor| Change::new(ChangeKind::Variable, executor.qualified(&element_name(param, indices)), None, item.value));
+            }
+        }
+        let ran = self.pause_at(|_| Point::Entered { template: function.name.clone() })
+            .and_then(|_| self.run_commands(&function.instructions));
+        let returned = self.returned.take();
+        // a failing call keeps its frame, which locates the error, unless a debugger goes on running
+        match ran {
+            Err(kind) if !self.inspecting => return Err(kind),
+            _ => {}
+        }
+
+        self.calls.pop();
+        self.span = span;
+        let instance = &mut self.instances[self.current];
+        instance.template = template;
+        instance.vars = caller_vars;
+        instance.signals = signals;
+        instance.components = components;
+        ran?;
+        match returned {
+            Some(returned) => Ok(returned),
+            None => Err(ExecutionErrorKind::Unsupported(format!("`{}` ending without `return`", function.name)))
+        }
+    }
+
+    fn is_component(&self, variable: & Variable) -> bool {
+        variable.sub_variable.is_none() && self.instances[self.current].components.contains_key(&variable.id)
+    }
+
+    fn component(&self, name: & str, indices: & [usize]) -> Result<usize, ExecutionErrorKind> {
+        match self.instances[self.current].components.get(name) {
+            Some(array) => match array.offset(indices) {
+                Some(offset) => match array.items[offset] {
+                    Some(child) => Ok(child),
+                    None => Err(ExecutionErrorKind::ComponentNotInstantiated(element_name(name, indices)))
+                },
+                None => Err(Self::out_of_bounds(name, indices))
+            },
+            None => Err(ExecutionErrorKind::UndeclaredName(name.to_string()))
+        }
+    }
+
+    fn instantiate(&mut self, target: & Variable, instance: & ComponentInstance) -> Result<(), ExecutionErrorKind> {
+        let indices = self.indices(&target.indexing)?;
+        let template = match self.program.templates.get(&instance.name.id) {
+            Some(template) => template,
+            None => return Err(ExecutionErrorKind::UndeclaredName(instance.name.id.clone()))
+        };
+        let mut arguments = vec![];
+        for parameter in &instance.parameter_list {
+            arguments.push(self.evaluate_known(parameter)?);
+        }
+        let ancestors: Vec<usize> = std::iter::successors(Some(self.current), |id| self.instances[*id].parent).collect();
+        if ancestors.len() > self.limits.depth {
+            return Err(ExecutionErrorKind::LimitExceeded { limit: Limit::Depth, maximum: self.limits.depth });
+        }
+        if ancestors.iter().filter(|id| self.instances[**id].template.name == template.name).count() > self.limits.recursion {
+            return Err(ExecutionErrorKind::LimitExceeded { limit: Limit::Recursion, maximum: self.limits.recursion });
+        }
+        let offset = match self.instances[self.current].components.get(&target.id) {
+            Some(array) => match array.offset(&indices) {
+                Some(offset) => offset,
+                None => return Err(Self::out_of_bounds(&target.id, &indices))
+            },
+            None => return Err(ExecutionErrorKind::UndeclaredName(target.id.clone()))
+        };
+
+        let name = element_name(&target.id, &indices);
+        let path = format!("{}.{}", self.instances[self.current].path, name);
+        let child = self.create_instance(path, (target.id.clone(), indices), template, Some(self.current), arguments);
+        let parent = &mut self.instances[self.current];
+        parent.children.push(child);
+        if let Some(array) = parent.components.get_mut(&target.id) {
+            array.items[offset] = Some(child);
+        }
+        Ok(())
+    }
+
+    fn signal_target(&mut self, target: & Variable) -> Result<usize, ExecutionErrorKind> {
+        let indices = self.indices(&target.indexing)?;
+        let sub_variable = match &target.sub_variable {
+            Some(sub_variable) => sub_variable,
+            None => return match self.instances[self.current].signals.get(&target.id) {
+                Some(array) => match array.offset(&indices) {
+                    Some(offset) => Ok(array.items[offset]),
+                    None => Err(Self::out_of_bounds(&target.id, &indices))
+                },
+                None => Err(ExecutionErrorKind::UndeclaredName(target.to_string()))
+            }
+        };
+
+        let child = self.component(&target.id, &indices)?;
+        let sub_indices = self.indices(&sub_variable.indexing)?;
+        let name = element_name(&sub_variable.id, &sub_indices);
+        if self.instances[child].status == Status::Pending {
+            if let Some(signal) = self.instances[child].pending.get(&name) {
+                return Ok(*signal);
+            }
+            self.signals.push(SignalState {
+                instance: child,
+                name: name.clone(),
+                direction: SignalDirection::Input,
+                value: None,
+                assigned: false
+            });
+            let signal = self.signals.len() - 1;
+            self.instances[child].pending.insert(name, signal);
+            return Ok(signal);
+        }
+        let component = self.instances[child].path.clone();
+        Err(ExecutionErrorKind::InvalidComponentSignal { component, signal: name })
+    }
+
+    fn assign_signal(&mut self, signal: usize, value: Option<FieldElement>) -> Result<(), ExecutionErrorKind> {
+        if self.signals[signal].assigned {
+            let name = format!("{}.{}", self.instances[self.signals[signal].instance].path, self.signals[signal].name);
+            return Err(ExecutionErrorKind::SignalAssignedTwice(name));
+        }
+        let old = self.signals[signal].value.take();
+        self.signals[signal].assigned = true;
+        self.signals[signal].value = value.clone();
+        let name = |executor: &Self| format!("{}.{}", executor.instances[executor.signals[signal].instance].path, executor.signals[signal].name);
+        self.record(|executor| Change::new(ChangeKind::Signal, name(executor), old, value));
+        self.pause_at(|executor| Point::SignalAssigned { name: name(executor) })
+    }
+
+    // full name of a local of the current instance, or of the function running in it
+    fn qualified(&self, name: & str) -> String {
+        let mut qualified = self.instances[self.current].path.clone();
+        for function in &self.calls {
+            qualified.push_str(&format!(".{}()", function));
+        }
+        format!("{}.{}", qualified, name)
+    }
+
+    // tells the hook and the execution log about a signal or variable that changed, `change` is only built
+    // when debugging or logging at trace level
+    fn record(&mut self, change: impl FnOnce(&Self) -> Change) {
+        let logged = log::log_enabled!(log::Level::Trace);
+        if self.hook.is_none() && !logged {
+            return;
+        }
+        let change = change(self);
+        if logged {
+            let file = &self.instances[self.current].template.path;
+            let line = self.line(file, self.span.0);
+            let value = change.new.as_ref().map(|value| value.to_signed().to_string()).unwrap_or_else(|| "unknown".to_string());
+            log::trace!("{}:{}: {} = {}", file, line, change.name, value);
+        }
+        if let Some(mut hook) = self.hook.take() {
+            hook.record(&self.instances[self.current].path, change);
+            self.hook = Some(hook);
+        }
+    }
+
+    // tells the hook about a signal or variable element the statement used, `read` gives its name and value
+    fn report_read(&mut self, read: impl FnOnce(&Self) -> (String, Option<FieldElement>)) {
+        if let Some(mut hook) = self.hook.take() {
+            let (name, value) = read(self);
+            hook.read(&self.instances[self.current].path, name, value);
+            self.hook = Some(hook);
+        }
+    }
+
+    fn signal_read(&self, signal: usize) -> (String, Option<FieldElement>) {
+        let state = &self.signals[signal];
+        (format!("{}.{}", self.instances[state.instance].path, state.name), state.value.clone())
+    }
+
+    // gives the hook a chance to pause, `point` is only built when debugging
+    fn pause_at(&mut self, point: impl FnOnce(&Self) -> Point) -> Result<(), ExecutionErrorKind> {
+        let mut hook = match self.hook.take() {
+            Some(hook) => hook,
+            None => return Ok(())
+        };
+        let resumed = match hook.reason(&point(self)) {
+            Some(reason) => {
+                let pause = self.pause(reason);
+                hook.pause(pause, &mut |frame, text| self.inspect(frame, text))
+            },
+            None => true
+        };
+        self.hook = Some(hook);
+        match resumed {
+            true => Ok(()),
+            false => Err(ExecutionErrorKind::Interrupted)
+        }
+    }
+
+    // counts the statement at `span` against the limits and gives the hook a chance to pause before it
+    fn begin_statement(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.statements += 1;
+        if self.statements > self.limits.statements {
+            return Err(ExecutionErrorKind::LimitExceeded { limit: Limit::Statements, maximum: self.limits.statements });
+        }
+        if self.hook.is_none() {
+            return Ok(());
+        }
+        let file = self.instances[self.current].template.path.clone();
+        let span = self.span;
+        let line = self.line(&file, span.0);
+        self.pause_at(|executor| Point::Statement {
+            instance: executor.instances[executor.current].path.clone(),
+            template: executor.instances[executor.current].template.name.clone(),
+            file,
+            span,
+            line
+        })
+    }
+
+    // 1-based line of an offset in `file`
+    fn line(&mut self, file: & str, offset: usize) -> usize {
+        let program = self.program;
+        let starts = self.line_starts.entry(file.to_string()).or_insert_with(|| {
+            let source = program.sources.get(file).map(|source| source.as_str()).unwrap_or("");
+            source.match_indices('\n').map(|(idx, _)| idx + 1).collect()
+        });
+        starts.partition_point(|start| *start <= offset) + 1
+    }
+
+    // what the current instance and the ones that started it hold, for a debugger
+    fn pause(&mut self, reason: PauseReason) -> Pause {
+        let frames: Vec<Frame> = self.frame_instances().into_iter().map(|(id, span)| self.frame(id, span)).collect();
+        let Frame { instance, template, file, span, line, variables, signals, components } = frames[0].clone();
+        Pause { reason, instance, template, file, span, line, frames, variables, signals, components }
+    }
+
+    // the current instance at the current statement, then each one above it at the statement that started it
+    fn frame_instances(&self) -> Vec<(usize, (usize, usize))> {
+        let mut frames = vec![(self.current, self.span)];
+        // the first caller is the one of main, there is nothing above it
+        frames.extend(self.callers.iter().skip(1).rev().cloned());
+        frames
+    }
+
+    fn frame(&mut self, id: usize, span: (usize, usize)) -> Frame {
+        let file = self.instances[id].template.path.clone();
+        let line = self.line(&file, span.0);
+        let instance = &self.instances[id];
+        let mut names: Vec<&String> = instance.vars.keys().collect();
+        names.sort();
+        let variables = names.into_iter()
+            .flat_map(|name| {
+                let array = &instance.vars[name];
+                all_indices(&array.dims).into_iter().zip(&array.items).map(move |(indices, item)| NamedValue {
+                    name: element_name(name, &indices),
+                    value: item.value.clone()
+                })
+            })
+            .collect();
+        let signals = self.named_signals(instance.declared.iter());
+        let mut names: Vec<&String> = instance.components.keys().collect();
+        names.sort();
+        let components = names.into_iter()
+            .flat_map(|name| {
+                let array = &instance.components[name];
+                all_indices(&array.dims).into_iter().zip(&array.items).filter_map(move |(indices, item)| item.map(|child| (element_name(name, &indices), child)))
+            })
+            .map(|(name, child)| {
+                let child = &self.instances[child];
+                // the inputs assigned by the parent until the component runs
+                let mut pending: Vec<&usize> = child.pending.values().collect();
+                pending.sort();
+                NamedComponent { name, instance: child.path.clone(), signals: self.named_signals(child.declared.iter().chain(pending)) }
+            })
+            .collect();
+        Frame { instance: instance.path.clone(), template: instance.template.name.clone(), file, span, line, variables, signals, components }
+    }
+
+    fn named_signals<'s>(&self, ids: impl Iterator<Item = &'s usize>) -> Vec<NamedValue> {
+        ids.map(|id| NamedValue { name: self.signals[*id].name.clone(), value: self.signals[*id].value.clone() }).collect()
+    }
+
+    // value of an expression in the instance of frame `frame` of the last pause, for a debugger
+    fn inspect(&mut self, frame: usize, text: & str) -> Result<Option<FieldElement>, String> {
+        let expr = match expression_parser::parse_statement(text) {
+            Ok(Stmt::RegularExpr(expr)) => expr,
+            Ok(_) => return Err(format!("`{}` is not an expression", text)),
+            Err(message) => return Err(message)
+        };
+        let (id, _) = *self.frame_instances().get(frame).ok_or_else(|| format!("there is no frame {}", frame))?;
+        let current = std::mem::replace(&mut self.current, id);
+        self.inspecting = true;
+        let value = self.evaluate(&expr);
+        self.inspecting = false;
+        self.current = current;
+        value.map(|value| value.value).map_err(|kind| kind.to_string())
+    }
+
+    fn constrain_signal(&mut self, target: & Variable, value: Evaluated, text: & str) -> Result<(), ExecutionErrorKind> {
+        let signal = self.signal_target(target)?;
+        self.assign_signal(signal, value.value.clone())?;
+        let target = Evaluated::signal(signal, value.value.clone());
+        self.constrain(&target, &value, text)
+    }
+
+    // `left - right = 0` as `A * B - C = 0`
+    fn constrain(&mut self, left: & Evaluated, right: & Evaluated, text: & str) -> Result<(), ExecutionErrorKind> {
+        let constraint = match value::sub(left, right).symbolic {
+            Symbolic::Linear(linear) if linear.is_empty() => return Ok(()),
+            Symbolic::Linear(linear) => Constraint { a: LinearCombination::default(), b: LinearCombination::default(), c: linear.negate() },
+            Symbolic::Quadratic { a, b, c } => Constraint { a, b, c: c.negate() },
+            Symbolic::NonQuadratic => return Err(ExecutionErrorKind::NonQuadratic(text.to_string()))
+        };
+        self.instances[self.current].constraints.push((constraint, self.span));
+        Ok(())
+    }
+
+    fn assign_var(&mut self, target: & Variable, op: & Operator, value: Evaluated) -> Result<(), ExecutionErrorKind> {
+        if target.sub_variable.is_some() {
+            return Err(ExecutionErrorKind::Unsupported(format!("assigning `{}` with `{}`", target, op_text(op))));
+        }
+        let indices = self.indices(&target.indexing)?;
+        let offset = match self.instances[self.current].vars.get(&target.id) {
+            Some(array) => match array.offset(&indices) {
+                Some(offset) => offset,
+                None => return Err(Self::out_of_bounds(&target.id, &indices))
+            },
+            None => return Err(ExecutionErrorKind::UndeclaredName(target.to_string()))
+        };
+        let current = self.instances[self.current].vars[&target.id].items[offset].clone();
+        if *op != Operator::Assignment {
+            self.report_read(|executor| (executor.qualified(&element_name(&target.id, &indices)), current.value.clone()));
+        }
+        let operation = match op {
+            Operator::Assignment => None,
+            Operator::PlusAssignment => Some(BinOp::Add),
+            Operator::MinusAssignment => Some(BinOp::Subtract),
+            Operator::TimesAssignment => Some(BinOp::Multiply),
+            Operator::ExponentAssignment => Some(BinOp::Exponent),
+            Operator::DivideAssignment => Some(BinOp::Divide),
+            Operator::QuotientAssignment => Some(BinOp::Quotient),
+            Operator::ModuloAssignment => Some(BinOp::Modulo),
+            other => return Err(ExecutionErrorKind::Unsupported(format!("assigning `{}` with `{}`", target, op_text(other))))
+        };
+        let assigned = match operation {
+            None => value,
+            Some(operation) => match value::binary(&operation, &current, &value) {
+                Some(result) => result,
+                None => return Err(ExecutionErrorKind::DivisionByZero(target.to_string()))
+            }
+        };
+        let (old, new) = (current.value.clone(), assigned.value.clone());
+        if let Some(array) = self.instances[self.current].vars.get_mut(&target.id) {
+            array.items[offset] = assigned;
+        }
+        self.record(|executor| Change::new(ChangeKind::Variable, executor.qualified(&element_name(&target.id, &indices)), old, new));
+        Ok(())
+    }
+
+    fn finish(&self) -> Circuit {
+        // depth first, sub-components by name then index, skipping those that never ran
+        let mut order = vec![];
+        let mut stack = vec![0];
+        while let Some(id) = stack.pop() {
+            if self.instances[id].status != Status::Done {
+                continue;
+            }
+            order.push(id);
+            let mut children = self.instances[id].children.clone();
+            children.sort_by(|left, right| self.instances[*left].key.cmp(&self.instances[*right].key));
+            stack.extend(children.into_iter().rev());
+        }
+
+        let main = &self.instances[0];
+        let direction = |id: &usize, direction: SignalDirection| self.signals[*id].direction == direction;
+        let is_public = |id: &usize| {
+            let name = &self.signals[*id].name;
+            let base = name.split('[').next().unwrap_or(name);
+            self.program.public_signals.iter().any(|public| public.trim() == base)
+        };
+        let outputs: Vec<usize> = main.declared.iter().filter(|id| direction(id, SignalDirection::Output)).cloned().collect();
+        let public: Vec<usize> = main.declared.iter().filter(|id| direction(id, SignalDirection::Input) && is_public(id)).cloned().collect();
+        let private: Vec<usize> = main.declared.iter().filter(|id| direction(id, SignalDirection::Input) && !is_public(id)).cloned().collect();
+
+        let mut wires = vec![0];
+        wires.extend(&outputs);
+        wires.extend(&public);
+        wires.extend(&private);
+        for id in &order {
+            let declared = &self.instances[*id].declared;
+            if *id != 0 {
+                wires.extend(declared.iter().filter(|signal| direction(signal, SignalDirection::Output)));
+                wires.extend(declared.iter().filter(|signal| direction(signal, SignalDirection::Input)));
+            }
+            wires.extend(declared.iter().filter(|signal| direction(signal, SignalDirection::Intermediate)));
+        }
+
+        let mut wire_of = vec![usize::MAX; self.signals.len()];
+        for (wire, signal) in wires.iter().enumerate() {
+            wire_of[*signal] = wire;
+        }
+        let renumber = |signal: usize| wire_of[signal];
+
+        let position: HashMap<usize, usize> = order.iter().enumerate().map(|(position, id)| (*id, position)).collect();
+        let mut instances = vec![];
+        let mut constraints = vec![];
+        let mut spans = vec![];
+        for id in &order {
+            let state = &self.instances[*id];
+            let first = constraints.len();
+            for (constraint, span) in &state.constraints {
+                constraints.push(Constraint {
+                    a: constraint.a.renumber(&renumber),
+                    b: constraint.b.renumber(&renumber),
+                    c: constraint.c.renumber(&renumber)
+                });
+                spans.push(*span);
+            }
+            let mut signals: Vec<usize> = state.declared.iter().map(|signal| wire_of[*signal]).collect();
+            signals.sort();
+            instances.push(Instance {
+                path: state.path.clone(),
+                template: state.template.name.clone(),
+                file: state.template.path.clone(),
+                parent: state.parent.and_then(|parent| position.get(&parent).cloned()),
+                signals,
+                constraints: (first..constraints.len()).collect()
+            });
+        }
+
+        let signals = wires.iter().map(|signal| {
+            let state = &self.signals[*signal];
+            Signal {
+                name: match *signal {
+                    ONE => state.name.clone(),
+                    _ => format!("{}.{}", self.instances[state.instance].path, state.name)
+                },
+                instance: position.get(&state.instance).cloned().unwrap_or(0),
+                direction: state.direction.clone(),
+                value: state.value.clone()
+            }
+        }).collect();
+
+        Circuit {
+            instances,
+            signals,
+            constraints,
+            spans,
+            n_outputs: outputs.len(),
+            n_public_inputs: public.len(),
+            n_private_inputs: private.len()
+        }
+    }
+
+}
+
+fn op_text(op: & Operator) -> &'static str {
+    match op {
+        Operator::Assignment => "=",
+        Operator::PlusAssignment => "+=",
+        Operator::MinusAssignment => "-=",
+        Operator::TimesAssignment => "*=",
+        Operator::ExponentAssignment => "**=",
+        Operator::DivideAssignment => "/=",
+        Operator::QuotientAssignment => "\\=",
+        Operator::ModuloAssignment => "%=",
+        Operator::LeftConstraint => "<==",
+        Operator::RightConstraint => "==>",
+        Operator::LeftSignalAssign => "<--",
+        Operator::RightSignalAssign => "-->",
+        _ => "an operator"
+    }
+}
+
+#[cfg(test)]
+mod execution_test {
+
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::execution::{self, inputs, Circuit};
+    use crate::parser::compile;
+    use crate::parser::errors::execution::{ExecutionErrorKind, Limit};
+    use crate::parser::source::{DiskSource, MemorySource, SourceProvider};
+    use crate::parser::symbols;
+    use crate::parser::template;
+    use crate::r1cs::field::FieldElement;
+    use crate::parser::errors;
+    use crate::r1cs::{binary, json, satisfaction, sym, wtns};
+
+    use galvanic_assert::matchers::*;
+
+    fn run_with(path: &str, sources: &dyn SourceProvider, inputs: Option<&HashMap<String, FieldElement>>) -> Result<Circuit, ExecutionErrorKind> {
+        let mut ctx = compile::build_context_from_source(&PathBuf::from(path), sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = template::load_program(&ctx, &table);
+        match inputs {
+            Some(inputs) => execution::execute_with_inputs(&program, inputs),
+            None => execution::execute(&program)
+        }.map_err(|error| error.kind)
+    }
+
+    fn run(path: &str, sources: &dyn SourceProvider) -> Result<Circuit, ExecutionErrorKind> {
+        run_with(path, sources, None)
+    }
+
+    fn run_main(body: &str) -> Result<Circuit, ExecutionErrorKind> {
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", &format!("template Sub() {{\n    signal input a;\n    signal output b;\n    b <== a * a;\n}}\ntemplate Main(n) {{\n{}\n}}\ncomponent main = Main(3);\n", body));
+        run("/virtual/main.circom", &sources)
+    }
+
+    #[test]
+    fn test_matches_circom() {
+
+        let circuit = run("./src/lib/parser/sample_circuits/warships_raw.circom", &DiskSource).unwrap();
+        let expected = std::fs::read_to_string("./src/lib/parser/sample_circuits/warships_raw_constraints.json").unwrap();
+
+        expect_that!(&json::to_json(&circuit.constraints), eq(expected));
+        expect_that!(&circuit.signals.len(), eq(193));
+        expect_that!(&(circuit.n_outputs, circuit.n_public_inputs, circuit.n_private_inputs), eq((1, 9, 2)));
+        expect_that!(&circuit.signals[85].name, eq("main.isEqualI[0][0].out".to_string()));
+
+        let symbols = sym::to_sym(&circuit.symbols());
+        expect_that!(&symbols.lines().next(), eq(Some("1,1,0,main.answer")));
+        expect_that!(&symbols.lines().nth(84), eq(Some("85,85,19,main.isEqualI[0][0].out")));
+        expect_that!(&symbols.lines().nth(88), eq(Some("89,89,20,main.isEqualI[0][0].isz.in")));
+
+    }
+
+    #[test]
+    fn test_witness_matches_circom() {
+
+        let inputs = inputs::from_json(&fs::read_to_string("./src/lib/parser/sample_circuits/input.json").unwrap()).unwrap();
+        let circuit = run_with("./src/lib/parser/sample_circuits/warships_raw.circom", &DiskSource, Some(&inputs)).unwrap();
+        let witness = circuit.witness().unwrap();
+        expect_that!(&witness[1], eq(FieldElement::one()));
+
+        // circom's witness only keeps the wires left after simplification, each one carries a label of ours
+        let r1cs = binary::read(&mut fs::File::open("./src/lib/parser/sample_circuits/warships_raw.r1cs").unwrap()).unwrap();
+        let expected = fs::read("./src/lib/parser/sample_circuits/witness.wtns").unwrap();
+        for (wire, label) in r1cs.wire_to_label.iter().enumerate() {
+            expect_that!(&witness[*label], eq(FieldElement::from_le_bytes(&expected[76 + wire * 32..76 + (wire + 1) * 32])));
+        }
+
+        let mut written = vec![];
+        wtns::write(&witness, &mut written).unwrap();
+        expect_that!(&written[..60].to_vec(), eq(expected[..60].to_vec()));
+        expect_that!(&(written[60], written.len()), eq((193, 76 + 193 * 32)));
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", "template Main() {\n    signal input a;\n    signal output b;\n    b <== a * a;\n}\ncomponent main = Main();\n");
+        let mut inputs = HashMap::new();
+        expect_that!(&run_with("/virtual/main.circom", &sources, Some(&inputs)).err(), eq(Some(ExecutionErrorKind::MissingInput("a".to_string()))));
+        inputs.insert("a".to_string(), FieldElement::from_i64(3));
+        expect_that!(&run_with("/virtual/main.circom", &sources, Some(&inputs)).unwrap().witness(), eq(Ok(vec![FieldElement::one(), FieldElement::from_i64(9), FieldElement::from_i64(3)])));
+        inputs.insert("c".to_string(), FieldElement::one());
+        expect_that!(&run_with("/virtual/main.circom", &sources, Some(&inputs)).err(), eq(Some(ExecutionErrorKind::UnknownInput("c".to_string()))));
+
+    }
+
+    #[test]
+    fn test_symbols() {
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", "template Leaf() {\n    signal input x[2];\n    signal output y;\n    y <== x[0] * x[1];\n}\ntemplate Pair() {\n    signal input a;\n    signal output b;\n    component leaf = Leaf();\n    leaf.x[0] <== a;\n    leaf.x[1] <== a + 1;\n    b <== leaf.y;\n}\ntemplate Main() {\n    signal input in;\n    signal output out[2][2];\n    component c[2][2];\n    for (var i = 0; i < 2; i++) {\n        for (var j = 0; j < 2; j++) {\n            c[i][j] = Pair();\n            c[i][j].a <== in + i * 2 + j;\n            out[i][j] <== c[i][j].b;\n        }\n    }\n}\ncomponent main = Main();\n");
+        let mut inputs = HashMap::new();
+        inputs.insert("in".to_string(), FieldElement::from_i64(1));
+        let circuit = run_with("/virtual/main.circom", &sources, Some(&inputs)).unwrap();
+        let symbols = circuit.symbols();
+
+        // every index of a nested array of components, then the names within the component
+        let symbol = symbols.iter().find(|symbol| symbol.name == "main.c[1][0].leaf.x[1]").unwrap();
+        expect_that!(&circuit.instances[symbol.component].path, eq("main.c[1][0].leaf".to_string()));
+        expect_that!(&symbols.iter().any(|symbol| symbol.name == "main.out[1][0]"), eq(true));
+        let names: Vec<&str> = symbols.iter().filter(|symbol| symbol.name.starts_with("main.c[0][1].")).map(|symbol| symbol.name.as_str()).collect();
+        expect_that!(&names, eq(vec!["main.c[0][1].b", "main.c[0][1].a", "main.c[0][1].leaf.y", "main.c[0][1].leaf.x[0]", "main.c[0][1].leaf.x[1]"]));
+
+        // what `compile --sym` and `witness` write reads back the same, and names the same values
+        let read = sym::parse(&sym::to_sym(&symbols)).unwrap();
+        expect_that!(&read, eq(symbols.clone()));
+        let mut file = vec![];
+        wtns::write(&circuit.witness().unwrap(), &mut file).unwrap();
+        let witness = wtns::read(&mut file.as_slice()).unwrap().values;
+        let values = wtns::values_by_name(&witness, &read);
+        expect_that!(&values.get("main.c[1][0].leaf.x[1]").cloned(), eq(Some(FieldElement::from_i64(4))));
+        expect_that!(&values.get("main.out[1][0]").cloned(), eq(Some(FieldElement::from_i64(12))));
+        expect_that!(&values.len(), eq(symbols.len()));
+
+    }
+
+    #[test]
+    fn test_unsatisfied_constraint_origin() {
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", "template Square() {\n    signal input a;\n    signal output b;\n    b <== a * a;\n}\ntemplate Main() {\n    signal input x;\n    signal output y;\n    component square = Square();\n    square.a <== x;\n    y <== square.b;\n}\ncomponent main = Main();\n");
+        let mut inputs = HashMap::new();
+        inputs.insert("x".to_string(), FieldElement::from_i64(3));
+        let circuit = run_with("/virtual/main.circom", &sources, Some(&inputs)).unwrap();
+        let mut witness = circuit.witness().unwrap();
+        expect_that!(&satisfaction::check(&circuit.constraints, &witness), eq(Ok(vec![])));
+
+        let b = circuit.signals.iter().position(|signal| signal.name == "main.square.b").unwrap();
+        witness[b] = FieldElement::from_i64(10);
+        let unsatisfied = satisfaction::check(&circuit.constraints, &witness).unwrap();
+        expect_that!(&unsatisfied.len(), eq(2));
+        let square = &unsatisfied[1];
+        expect_that!(&(square.a.clone(), square.b.clone(), square.c.clone()), eq((FieldElement::from_i64(-3), FieldElement::from_i64(3), FieldElement::from_i64(-10))));
+        expect_that!(&circuit.format_constraint(&circuit.constraints[square.index]), eq("(-main.square.a) * (main.square.a) - (-main.square.b) = 0".to_string()));
+        let instance = circuit.instance_of(square.index).unwrap();
+        expect_that!(&(instance.path.as_str(), instance.template.as_str(), instance.file.as_str()), eq(("main.square", "Square", "/virtual/main.circom")));
+        let source = sources.get(&PathBuf::from("/virtual/main.circom")).unwrap();
+        expect_that!(&errors::common_displayer::source_line(source, &circuit.spans[square.index]), eq("b <== a * a;".to_string()));
+
+    }
+
+    #[test]
+    fn test_execution_errors() {
+
+        let circuit = run_main("    signal input x;\n    signal output y[n];\n    component sub[n];\n    for (var i = 0; i < n; i++) {\n        sub[i] = Sub();\n        sub[i].a <== x + i;\n        y[i] <== sub[i].b;\n    }").unwrap();
+        expect_that!(&circuit.constraints.len(), eq(9));
+        expect_that!(&circuit.format_constraint(&circuit.constraints[0]), eq("(0) * (0) - (main.x + -main.sub[0].a) = 0".to_string()));
+
+        expect_that!(&run_main("    signal input x;\n    signal output y;\n    y <== x * x * x;").err(), eq(Some(ExecutionErrorKind::NonQuadratic("y <== x * x * x".to_string()))));
+        expect_that!(&run_main("    signal input x;\n    signal output y[n];\n    y[x] <== 1;").err(), eq(Some(ExecutionErrorKind::UnknownValue("x".to_string()))));
+        expect_that!(&run_main("    signal output y[n];\n    y[n] <== 1;").err(), eq(Some(ExecutionErrorKind::IndexOutOfBounds { name: "y".to_string(), indices: "[3]".to_string() })));
+        expect_that!(&run_main("    signal output y;\n    y <== 1;\n    y <== 2;").err(), eq(Some(ExecutionErrorKind::SignalAssignedTwice("main.y".to_string()))));
+        expect_that!(&run_main("    signal output y;\n    component sub;\n    y <== sub.b;").err(), eq(Some(ExecutionErrorKind::ComponentNotInstantiated("sub".to_string()))));
+        expect_that!(&run_main("    signal output y;\n    component sub = Sub();\n    y <== 1;").err(), eq(Some(ExecutionErrorKind::UnassignedInput { component: "main.sub".to_string(), signal: "a".to_string() })));
+
+    }
+
+    #[test]
+    fn test_if_statement() {
+
+        // `b` of `Main(n)` for `a = 2`
+        let b = |n: i64| {
+            let mut sources = MemorySource::new();
+            sources.insert("/virtual/main.circom", &format!("template Main(n) {{\n    var a = 2;\n    signal output b;\n    var x = 0;\n    if (n == 1) {{\n        x = 10;\n    }} else if (n < 5) x = 20;\n    else {{\n        if (n == 5) x = 30;\n        x += 1;\n    }}\n    b <== a * x;\n}}\ncomponent main = Main({});\n", n));
+            run("/virtual/main.circom", &sources).map(|circuit| circuit.signals[1].value.clone().unwrap())
+        };
+        expect_that!(&b(1), eq(Ok(FieldElement::from_i64(20))));
+        expect_that!(&b(2), eq(Ok(FieldElement::from_i64(40))));
+        expect_that!(&b(5), eq(Ok(FieldElement::from_i64(62))));
+        expect_that!(&b(6), eq(Ok(FieldElement::from_i64(2))));
+
+        // only the branch taken is run
+        expect_that!(&run_main("    signal output y;\n    if (n == 3) {\n        y <== n;\n    } else {\n        y <== n + 1;\n    }").map(|circuit| circuit.constraints.len()), eq(Ok(1)));
+        expect_that!(&run_main("    signal input a;\n    if (a == 3) {\n    }").err(), eq(Some(ExecutionErrorKind::UnknownValue("a == 3".to_string()))));
+    }
+
+    #[test]
+    fn test_literals_and_loops() {
+
+        // `b` of `Main` for `a = 3`
+        let b = |body: &str| {
+            let mut sources = MemorySource::new();
+            sources.insert("/virtual/main.circom", &format!("template Main() {{\n    var a = 3;\n    signal output b;\n{}\n}}\ncomponent main = Main();\n", body));
+            run("/virtual/main.circom", &sources).map(|circuit| circuit.signals[1].value.clone().unwrap())
+        };
+        expect_that!(&b("    var x = 0x10;\n    b <== a * x;"), eq(Ok(FieldElement::from_i64(48))));
+        expect_that!(&b("    var x[3] = [1, 2, 3];\n    b <== a * x[1];"), eq(Ok(FieldElement::from_i64(6))));
+        expect_that!(&b("    var x[2][2] = [[1, 2], [3, 4]];\n    b <== a * x[1][0];"), eq(Ok(FieldElement::from_i64(9))));
+        expect_that!(&b("    var x = 2;\n    while (x < 10) {\n        x = x * 2;\n    }\n    b <== a * x;"), eq(Ok(FieldElement::from_i64(48))));
+        expect_that!(&b("    var n = 0;\n    var r = 1;\n    while (r < 100) {\n        r *= 2;\n        n++;\n    }\n    b <== a * n;"), eq(Ok(FieldElement::from_i64(21))));
+        // past the 32 bits of an integer, reduced in the field
+        expect_that!(&b("    var p = 4294967296;\n    b <== a * p;"), eq(Ok(FieldElement::from_i64(3 * 4294967296))));
+        expect_that!(&b("    b <== a * 21888242871839275222246405745257275088548364400416034343698204186575808495616;"), eq(Ok(FieldElement::from_i64(-3))));
+
+        // nothing is read as a zero
+        expect_that!(&b("    var x[3] = [1, 2];\n    b <== a * x[1];"), eq(Err(ExecutionErrorKind::Unsupported("initializing `x` with `[1, 2]`".to_string()))));
+        expect_that!(&b("    var x[2] = 5;\n    b <== a * x[1];"), eq(Err(ExecutionErrorKind::Unsupported("initializing `x` with `5`".to_string()))));
+        expect_that!(&b("    var x = [1, 2];\n    b <== a * x;"), eq(Err(ExecutionErrorKind::Unsupported("initializing `x` with `[1, 2]`".to_string()))));
+        expect_that!(&b("    b <== a * [1, 2];"), eq(Err(ExecutionErrorKind::Unsupported("the array `[1, 2]` outside of a var initializer".to_string()))));
+        expect_that!(&b("    var x = 2;\n    do {\n        x = x * 2;\n    } while (x < 10);\n    b <== a * x;"), eq(Err(ExecutionErrorKind::Unsupported("`do ... while`".to_string()))));
+
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+
+        // `b` of `Main` for `a = 3`
+        let b = |body: &str| {
+            let mut sources = MemorySource::new();
+            sources.insert("/virtual/main.circom", &format!("template Main() {{\n    var a = 3;\n    signal output b;\n{}\n}}\ncomponent main = Main();\n", body));
+            run("/virtual/main.circom", &sources).map(|circuit| circuit.signals[1].value.clone().unwrap())
+        };
+        // shifts and bitwise operators bind looser than arithmetic, as in circom
+        expect_that!(&b("    var n = a;\n    b <-- 1 << n - 1;"), eq(Ok(FieldElement::from_i64(4))));
+        expect_that!(&b("    var x = a + 2;\n    b <-- x * 3 & 6;"), eq(Ok(FieldElement::from_i64(6))));
+        // `3 | (4 ^ (6 & 5))`
+        expect_that!(&b("    b <-- a | 4 ^ 6 & 5;"), eq(Ok(FieldElement::from_i64(3))));
+        expect_that!(&b("    b <-- 2 ** a ** 2;"), eq(Ok(FieldElement::from_i64(512))));
+        expect_that!(&b("    b <-- a + 1 < 5 && a & 1 == 1;"), eq(Ok(FieldElement::one())));
+
+    }
+
+    #[test]
+    fn test_execution_limits() {
+
+        let load = |source: &str| {
+            let mut sources = MemorySource::new();
+            sources.insert("/virtual/main.circom", source);
+            let mut ctx = compile::build_context_from_source(&PathBuf::from("/virtual/main.circom"), &sources);
+            let table = symbols::build_symbol_table(&mut ctx);
+            template::load_program(&ctx, &table)
+        };
+        let recursive = load("template Rec(n) {\n    signal output out;\n    component next = Rec(n + 1);\n    out <== next.out;\n}\ncomponent main = Rec(0);\n");
+        let error = execution::execute(&recursive).unwrap_err();
+        expect_that!(&error.kind, eq(ExecutionErrorKind::LimitExceeded { limit: Limit::Recursion, maximum: 16 }));
+        // the instance that would nest a `Rec` within 17 others, at the statement doing it
+        expect_that!(&error.instance.matches(".next").count(), eq(16));
+        expect_that!(&(error.template.as_str(), error.span_error), eq(("Rec", (45, 72))));
+        let limits = execution::Limits { recursion: 100, ..execution::Limits::default() };
+        let error = execution::execute_with_limits(&recursive, None, &limits).unwrap_err();
+        expect_that!(&error.kind, eq(ExecutionErrorKind::LimitExceeded { limit: Limit::Depth, maximum: 32 }));
+        expect_that!(&error.instance.matches(".next").count(), eq(32));
+
+        let endless = load("template Main() {\n    var total = 0;\n    for (var i = 0; i >= 0; i++) {\n        total += i;\n    }\n}\ncomponent main = Main();\n");
+        let limits = execution::Limits { statements: 1000, ..execution::Limits::default() };
+        let error = execution::execute_with_limits(&endless, None, &limits).unwrap_err();
+        expect_that!(&(error.kind, error.instance.as_str()), eq((ExecutionErrorKind::LimitExceeded { limit: Limit::Statements, maximum: 1000 }, "main")));
+
+        expect_that!(&run_main("    signal output y[n][1000000];").err(), eq(Some(ExecutionErrorKind::LimitExceeded { limit: Limit::ArraySize, maximum: 1 << 20 })));
+        expect_that!(&run_main("    var big[1000000][1000000][1000000][1000000];").err(), eq(Some(ExecutionErrorKind::LimitExceeded { limit: Limit::ArraySize, maximum: 1 << 20 })));
+        expect_that!(&run_main("    signal output y[n][1000];").is_ok(), eq(true));
+
+        let endless = load("template Main() {\n    var x = 1;\n    while (x > 0) {\n        x++;\n    }\n}\ncomponent main = Main();\n");
+        let error = execution::execute_with_limits(&endless, None, &limits).unwrap_err();
+        expect_that!(&error.kind, eq(ExecutionErrorKind::LimitExceeded { limit: Limit::Statements, maximum: 1000 }));
+
+    }
+
+    #[test]
+    fn test_function_calls() {
+
+        let functions = "function square(x) {\n    return x * x;\n}\nfunction shifted(x) {\n    return (x + 1) * 2;\n}\nfunction fact(n) {\n    if (n <= 1) return 1;\n    return n * fact(n - 1);\n}\nfunction root(limit) {\n    for (var i = 0; i < 100; i++) {\n        if (i * i > limit) {\n            return i;\n        }\n    }\n    return 100;\n}\nfunction double(v) {\n    var out[2];\n    for (var i = 0; i < 2; i++) {\n        out[i] = v[i] * 2;\n    }\n    return out;\n}\nfunction sum(v) {\n    return v[0] + v[1];\n}\nfunction shadow(x) {\n    var a = 5;\n    return x + a;\n}\nfunction forever(n) {\n    return forever(n + 1);\n}\nfunction nothing(n) {\n    var x = n;\n}\n";
+        // `b` of `Main` for `a = 3`, and where the execution failed
+        let b = |body: &str| {
+            let mut sources = MemorySource::new();
+            sources.insert("/virtual/main.circom", &format!("{}template Main() {{\n    signal input a;\n    signal output b;\n{}\n}}\ncomponent main = Main();\n", functions, body));
+            let mut inputs = HashMap::new();
+            inputs.insert("a".to_string(), FieldElement::from_i64(3));
+            let mut ctx = compile::build_context_from_source(&PathBuf::from("/virtual/main.circom"), &sources);
+            let table = symbols::build_symbol_table(&mut ctx);
+            let program = template::load_program(&ctx, &table);
+            execution::execute_with_inputs(&program, &inputs)
+                .map(|circuit| circuit.signals[1].value.clone().unwrap())
+                .map_err(|error| (error.kind, error.template))
+        };
+        expect_that!(&b("    b <== a * square(2);"), eq(Ok(FieldElement::from_i64(12))));
+        expect_that!(&b("    b <== a * fact(5);"), eq(Ok(FieldElement::from_i64(360))));
+        expect_that!(&b("    b <== a * shifted(1);"), eq(Ok(FieldElement::from_i64(12))));
+        expect_that!(&b("    var x = square(square(2)) + 1;\n    b <== a * x;"), eq(Ok(FieldElement::from_i64(51))));
+        // the first `return` ends the loops around it
+        expect_that!(&b("    b <== a * root(10);"), eq(Ok(FieldElement::from_i64(12))));
+        expect_that!(&b("    var x[2] = [1, 2];\n    var y[2] = double(x);\n    b <== a * y[1];"), eq(Ok(FieldElement::from_i64(12))));
+        expect_that!(&b("    var m[2][2] = [[1, 2], [3, 4]];\n    b <== a * sum(m[1]);"), eq(Ok(FieldElement::from_i64(21))));
+        // a function sees its parameters and its own vars, not the signals of the template calling it
+        expect_that!(&b("    b <== a * shadow(1);"), eq(Ok(FieldElement::from_i64(18))));
+        expect_that!(&b("    b <== square(a);"), eq(Ok(FieldElement::from_i64(9))));
+        expect_that!(&b("    signal pair[2];\n    pair[0] <== a;\n    pair[1] <== 1;\n    b <== sum(pair);"), eq(Ok(FieldElement::from_i64(4))));
+
+        expect_that!(&b("    b <== a * forever(0);"), eq(Err((ExecutionErrorKind::LimitExceeded { limit: Limit::Recursion, maximum: 16 }, "forever".to_string()))));
+        expect_that!(&b("    b <== a * nothing(0);"), eq(Err((ExecutionErrorKind::Unsupported("`nothing` ending without `return`".to_string()), "Main".to_string()))));
+        expect_that!(&b("    var x[2] = [1, 2];\n    b <== a * double(x);"), eq(Err((ExecutionErrorKind::Unsupported("the array returned by `double(x)` outside of a var initializer".to_string()), "Main".to_string()))));
+        expect_that!(&b("    b <== a;\n    return b;"), eq(Err((ExecutionErrorKind::Unsupported("`return` in a template".to_string()), "Main".to_string()))));
+        expect_that!(&b("    b <== a * missing(1);"), eq(Err((ExecutionErrorKind::UndeclaredName("missing".to_string()), "Main".to_string()))));
+
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::execution::trace::{ChangeKind, Trace};
+use crate::r1cs::field::FieldElement;
+
+///
+/// A value used by the statement of a node, with the node explaining it. A value nothing set has no node.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Input {
+    pub name: String,
+    pub value: Option<FieldElement>,
+    pub node: Option<usize>
+}
+
+///
+/// The statement that gave a signal or variable element its value, and the values it used. A node without
+/// inputs is a main input or was computed from constants only.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Node {
+    pub name: String,
+    pub kind: ChangeKind,
+    pub value: Option<FieldElement>,
+    pub instance: String,
+    pub template: String,
+    pub file: String,
+    pub span: (usize, usize),
+    pub line: usize,
+    pub expression: String,
+    pub inputs: Vec<Input>
+}
+
+///
+/// Derivation of a value down to the main inputs and constants, the first node is the value asked for. A
+/// value used by several statements has a single node, the nodes form a graph rather than a tree then.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Provenance {
+    pub nodes: Vec<Node>
+}
+
+impl Provenance {
+
+    ///
+    /// Full names of every signal the value depends on, itself included.
+    ///
+    pub fn signals(&self) -> Vec<&str> {
+        self.nodes.iter().filter(|node| node.kind == ChangeKind::Signal).map(|node| node.name.as_str()).collect()
+    }
+
+}
+
+///
+/// Explains the last value of a signal or variable element of a recorded execution (`main.square.b`,
+/// `main.acc`), `sources` are the files of the program by path. None when nothing set it.
+///
+pub fn explain(trace: & Trace, sources: & HashMap<String, String>, name: & str) -> Option<Provenance> {
+    let last = trace.steps.iter().rposition(|step| step.changes.iter().any(|change| change.name == name))?;
+    // the step and name of every node, in node order
+    let mut order = vec![(last, name.to_string())];
+    let mut known: HashMap<(usize, String), usize> = HashMap::new();
+    known.insert((last, name.to_string()), 0);
+    let mut nodes: Vec<Node> = vec![];
+
+    while nodes.len() < order.len() {
+        let (position, name) = order[nodes.len()].clone();
+        let step = &trace.steps[position];
+        let change = step.changes.iter().rev().find(|change| change.name == name).expect("a node is made for a change of its step");
+        let source = sources.get(&step.file).and_then(|source| source.get(step.span.0..step.span.1)).unwrap_or("");
+        let mut inputs = vec![];
+        // reads of values set by the step itself, as a loop test after the step, are not inputs of it
+        for read in step.reads.iter().filter(|read| read.step != Some(position)) {
+            let node = read.step.map(|written| {
+                *known.entry((written, read.name.clone())).or_insert_with(|| {
+                    order.push((written, read.name.clone()));
+                    order.len() - 1
+                })
+            });
+            let input = Input { name: read.name.clone(), value: read.value.clone(), node };
+            if !inputs.contains(&input) {
+                inputs.push(input);
+            }
+        }
+        nodes.push(Node {
+            name,
+            kind: change.kind,
+            value: change.new.clone(),
+            instance: step.instance.clone(),
+            template: step.template.clone(),
+            file: step.file.clone(),
+            span: step.span,
+            line: step.line,
+            // the header of a loop or an if rather than its body
+            expression: source.lines().next().unwrap_or("").trim().trim_end_matches('{').trim_end().to_string(),
+            inputs
+        });
+    }
+    Some(Provenance { nodes })
+}
+
+#[cfg(test)]
+mod provenance_test {
+
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use crate::execution::provenance;
+    use crate::execution::trace::{self, ChangeKind};
+    use crate::execution::Limits;
+    use crate::parser::compile;
+    use crate::parser::source::MemorySource;
+    use crate::parser::symbols;
+    use crate::parser::template;
+    use crate::r1cs::field::FieldElement;
+
+    use galvanic_assert::matchers::*;
+
+    const SOURCE: &str = "template Square() {\n    signal input a;\n    signal output b;\n    b <== a * a;\n}\ntemplate Main() {\n    signal input x;\n    signal output y;\n    var acc = 0;\n    for (var i = 0; i < 2; i++) {\n        acc += i;\n    }\n    component square = Square();\n    square.a <== x + acc;\n    y <== square.b;\n}\ncomponent main = Main();\n";
+
+    #[test]
+    fn test_explain() {
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", SOURCE);
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("/virtual/main.circom"), &sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = template::load_program(&ctx, &table);
+        let mut inputs = HashMap::new();
+        inputs.insert("x".to_string(), FieldElement::from_i64(3));
+        let (trace, _) = trace::record(&program, Some(&inputs), &Limits::default());
+
+        let provenance = provenance::explain(&trace, &program.sources, "main.y").unwrap();
+        let summary: Vec<(&str, Option<FieldElement>, usize, &str, Vec<Option<usize>>)> = provenance.nodes.iter()
+            .map(|node| (node.name.as_str(), node.value.clone(), node.line, node.expression.as_str(), node.inputs.iter().map(|input| input.node).collect()))
+            .collect();
+        let value = |value: i64| Some(FieldElement::from_i64(value));
+        expect_that!(&summary, eq(vec![
+            ("main.y", value(16), 15, "y <== square.b", vec![Some(1)]),
+            ("main.square.b", value(16), 4, "b <== a * a", vec![Some(2)]),
+            ("main.square.a", value(4), 14, "square.a <== x + acc", vec![Some(3), Some(4)]),
+            ("main.x", value(3), 7, "signal input x", vec![]),
+            // the last `acc += i`, from the `i` and `acc` of the iteration before
+            ("main.acc", value(1), 11, "acc += i", vec![Some(5), Some(6)]),
+            ("main.i", value(1), 10, "for (var i = 0; i < 2; i++)", vec![Some(7)]),
+            ("main.acc", value(0), 11, "acc += i", vec![Some(7), Some(8)]),
+            ("main.i", value(0), 10, "for (var i = 0; i < 2; i++)", vec![]),
+            ("main.acc", value(0), 9, "var acc = 0", vec![])
+        ]));
+        expect_that!(&provenance.signals(), eq(vec!["main.y", "main.square.b", "main.square.a", "main.x"]));
+        let input = &provenance.nodes[2].inputs[1];
+        expect_that!(&(input.name.as_str(), input.value.clone()), eq(("main.acc", value(1))));
+        expect_that!(&(provenance.nodes[1].kind, provenance.nodes[1].instance.as_str(), provenance.nodes[1].template.as_str()), eq((ChangeKind::Signal, "main.square", "Square")));
+
+        expect_that!(&provenance::explain(&trace, &program.sources, "main.i").map(|provenance| provenance.nodes.len()), eq(Some(3)));
+        expect_that!(&provenance::explain(&trace, &program.sources, "main.nothing"), eq(None));
+
+    }
+}
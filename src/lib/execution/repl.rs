@@ -0,0 +1,340 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use crate::execution::trace::{display, Recorder, Trace};
+use crate::execution::{all_indices, element_name, Executor, Limits, Status};
+use crate::parser::errors::execution::ExecutionError;
+use crate::parser::template::{Program, SignalDirection};
+use crate::r1cs::field::FieldElement;
+
+const HELP: &str = "\
+<expression>      value of the expression in the current instance, every element for an array or a component
+:instances        every instance, the current one marked with `*`
+:cd <instance>    moves to an instance, by full path (`main.isEqual[1]`), relative to the current one or `..`
+:signals          signals of the current instance
+:vars             variables of the current instance
+:trace            statements the current instance ran and what they changed
+:help             this list
+:quit             leaves";
+
+///
+/// Evaluates expressions over the signals and variables of any instance of a circuit once it ran, with
+/// `parse_statement` and the values the executor kept. When the execution failed, every instance holds
+/// what it had when it stopped and the REPL starts in the one that failed.
+///
+pub struct Repl<'a> {
+    executor: Executor<'a>,
+    trace: Trace,
+    error: Option<Box<ExecutionError>>,
+    // instance expressions are evaluated in
+    scope: usize,
+    // instance the execution failed in
+    stopped: Option<usize>
+}
+
+impl<'a> Repl<'a> {
+
+    pub fn new(program: &'a Program, inputs: Option<&'a HashMap<String, FieldElement>>, limits: & Limits) -> Repl<'a> {
+        let recorder = Rc::new(RefCell::new(Recorder::default()));
+        let mut executor = Executor::new(program, inputs, Some(Box::new(recorder.clone())));
+        executor.limits = limits.clone();
+        let error = executor.execute_main().err();
+        executor.hook = None;
+        let steps = std::mem::take(&mut recorder.borrow_mut().steps);
+        // without a main there is no instance to be in
+        let scope = executor.current.min(executor.instances.len().saturating_sub(1));
+        let stopped = error.as_ref().map(|_| scope);
+        let trace = Trace { steps, error: error.as_ref().map(|error| error.to_string()) };
+        Repl { executor, trace, error, scope, stopped }
+    }
+
+    ///
+    /// Why the execution stopped, if it did not complete.
+    ///
+    pub fn error(&self) -> Option<& ExecutionError> {
+        self.error.as_deref()
+    }
+
+    pub fn scope(&self) -> & str {
+        self.executor.instances.get(self.scope).map(|instance| instance.path.as_str()).unwrap_or("main")
+    }
+
+    ///
+    /// Moves to an instance by full path, relative to the current one or `..` for its parent.
+    ///
+    pub fn cd(&mut self, path: & str) -> Result<(), String> {
+        let instances = &self.executor.instances;
+        let found = match path {
+            "" => Some(0),
+            ".." => instances.get(self.scope).and_then(|instance| instance.parent),
+            path => {
+                let relative = format!("{}.{}", self.scope(), path);
+                instances.iter().position(|instance| instance.path == path)
+                    .or_else(|| instances.iter().position(|instance| instance.path == relative))
+            }
+        };
+        match found.filter(|id| *id < instances.len()) {
+            Some(id) => {
+                self.scope = id;
+                Ok(())
+            },
+            None => Err(format!("no instance `{}` from `{}`", path, self.scope()))
+        }
+    }
+
+    ///
+    /// Runs a command or evaluates an expression, None once asked to quit.
+    ///
+    pub fn command(&mut self, line: & str) -> Option<String> {
+        let line = line.trim();
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line, "")
+        };
+        if self.executor.instances.is_empty() && !line.is_empty() && !matches!(command, ":help" | ":quit" | ":q") {
+            return Some(match self.error() {
+                Some(error) => format!("no instance ran: {}", error.kind),
+                None => "no instance ran".to_string()
+            });
+        }
+        Some(match command {
+            "" => String::new(),
+            ":quit" | ":q" => return None,
+            ":help" => HELP.to_string(),
+            ":instances" => self.instances(),
+            ":cd" => match self.cd(argument) {
+                Ok(()) => format!("{} ({})", self.scope(), self.executor.instances[self.scope].template.name),
+                Err(error) => error
+            },
+            ":signals" => self.signals(),
+            ":vars" => self.vars(),
+            ":trace" => self.steps(),
+            command if command.starts_with(':') => format!("unknown command `{}`, :help lists them", command),
+            _ => self.evaluate(line)
+        })
+    }
+
+    ///
+    /// Reads commands from `reader` until `:quit` or the end of the input, with a prompt naming the instance.
+    ///
+    pub fn run(&mut self, reader: &mut dyn BufRead, writer: &mut dyn Write) -> io::Result<()> {
+        loop {
+            write!(writer, "{}> ", self.scope())?;
+            writer.flush()?;
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return writeln!(writer);
+            }
+            match self.command(&line) {
+                Some(output) if output.is_empty() => {},
+                Some(output) => writeln!(writer, "{}", output)?,
+                None => return Ok(())
+            }
+        }
+    }
+
+    fn instances(&self) -> String {
+        let instances = &self.executor.instances;
+        // depth first, sub-components by name then index as in the circuit
+        let mut order = vec![];
+        let mut stack = vec![0];
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            let mut children = instances[id].children.clone();
+            children.sort_by(|left, right| instances[*left].key.cmp(&instances[*right].key));
+            stack.extend(children.into_iter().rev());
+        }
+        order.into_iter().map(|id| {
+            let instance = &instances[id];
+            format!("{} {} ({}){}",
+                if id == self.scope { "*" } else { " " },
+                instance.path,
+                instance.template.name,
+                if instance.status == Status::Pending { ", not run" } else { "" })
+        }).collect::<Vec<String>>().join("\n")
+    }
+
+    fn variables(&self) -> Vec<(String, Option<FieldElement>)> {
+        let instance = &self.executor.instances[self.scope];
+        let mut names: Vec<&String> = instance.vars.keys().collect();
+        names.sort();
+        names.into_iter().flat_map(|name| {
+            let array = &instance.vars[name];
+            all_indices(&array.dims).into_iter().zip(&array.items).map(move |(indices, item)| (element_name(name, &indices), item.value.clone()))
+        }).collect()
+    }
+
+    // every element of the scope with its value, by name relative to the scope, sub-component signals included
+    fn elements(&self) -> Vec<(String, Option<FieldElement>)> {
+        let executor = &self.executor;
+        let instance = &executor.instances[self.scope];
+        let mut elements = self.variables();
+        elements.extend(instance.declared.iter().map(|id| (executor.signals[*id].name.clone(), executor.signals[*id].value.clone())));
+        for child in instance.children.iter() {
+            let child = &executor.instances[*child];
+            let name = element_name(&child.key.0, &child.key.1);
+            let mut pending: Vec<&usize> = child.pending.values().collect();
+            pending.sort();
+            for id in child.declared.iter().chain(pending) {
+                elements.push((format!("{}.{}", name, executor.signals[*id].name), executor.signals[*id].value.clone()));
+            }
+        }
+        elements
+    }
+
+    fn evaluate(&mut self, text: & str) -> String {
+        // an array, part of one or a component is shown element by element
+        let name: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let elements: Vec<String> = self.elements().into_iter()
+            .filter(|(element, _)| element.starts_with(&format!("{}[", name)) || element.starts_with(&format!("{}.", name)))
+            .map(|(element, value)| format!("{} = {}", element, display(&value)))
+            .collect();
+        if !elements.is_empty() {
+            return elements.join("\n");
+        }
+        self.executor.current = self.scope;
+        match self.executor.inspect(0, text) {
+            Ok(value) => display(&value),
+            Err(error) => format!("error: {}", error)
+        }
+    }
+
+    fn signals(&self) -> String {
+        let executor = &self.executor;
+        executor.instances[self.scope].declared.iter().map(|id| {
+            let signal = &executor.signals[*id];
+            let direction = match signal.direction {
+                SignalDirection::Input => "input ",
+                SignalDirection::Output => "output ",
+                SignalDirection::Intermediate => ""
+            };
+            format!("signal {}{} = {}", direction, signal.name, display(&signal.value))
+        }).collect::<Vec<String>>().join("\n")
+    }
+
+    fn vars(&self) -> String {
+        self.variables().into_iter().map(|(name, value)| format!("{} = {}", name, display(&value))).collect::<Vec<String>>().join("\n")
+    }
+
+    fn steps(&self) -> String {
+        let path = self.scope();
+        let prefix = format!("{}.", path);
+        let sources = &self.executor.program.sources;
+        let mut lines = vec![];
+        for step in self.trace.steps.iter().filter(|step| step.instance == path) {
+            let source = sources.get(&step.file).and_then(|source| source.get(step.span.0..step.span.1)).unwrap_or("");
+            // the header of a loop or an if rather than its body
+            let statement = source.lines().next().unwrap_or("").trim().trim_end_matches('{').trim_end();
+            let changes: Vec<String> = step.changes.iter()
+                .map(|change| format!("{} = {}", change.name.strip_prefix(&prefix).unwrap_or(&change.name), display(&change.new)))
+                .collect();
+            match changes.is_empty() {
+                true => lines.push(format!("{:>4}: {}", step.line, statement)),
+                false => lines.push(format!("{:>4}: {}    # {}", step.line, statement, changes.join(", ")))
+            }
+        }
+        if let Some(error) = self.error().filter(|_| self.stopped == Some(self.scope)) {
+            lines.push(format!("stopped: {}", error.kind));
+        }
+        lines.join("\n")
+    }
+
+}
+
+#[cfg(test)]
+mod repl_test {
+
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use crate::execution::{inputs, Limits};
+    use crate::execution::repl::Repl;
+    use crate::parser::compile;
+    use crate::parser::source::MemorySource;
+    use crate::parser::symbols;
+    use crate::parser::template::{self, Program};
+    use crate::r1cs::field::FieldElement;
+
+    use galvanic_assert::matchers::*;
+
+    const SOURCE: &str = "template IsZero() {\n    signal input in;\n    signal output out;\n    signal inv;\n    inv <-- in != 0 ? 1 / in : 0;\n    out <== -in * inv + 1;\n    in * out === 0;\n}\ntemplate Main() {\n    signal input x[2];\n    signal output zeros;\n    var count = 0;\n    component isz[2];\n    for (var i = 0; i < 2; i++) {\n        isz[i] = IsZero();\n        isz[i].in <== x[i];\n        count += 1;\n    }\n    zeros <== isz[0].out + isz[1].out;\n}\ncomponent main = Main();\n";
+
+    fn load(source: & str) -> Program {
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", source);
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("/virtual/main.circom"), &sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        template::load_program(&ctx, &table)
+    }
+
+    fn inputs() -> HashMap<String, FieldElement> {
+        inputs::from_json("{\"x\": [\"0\", \"1\"]}").unwrap()
+    }
+
+    #[test]
+    fn test_scopes() {
+
+        let program = load(SOURCE);
+        let inputs = inputs();
+        let mut repl = Repl::new(&program, Some(&inputs), &Limits::default());
+        let mut run = |line: &str| repl.command(line).unwrap();
+
+        expect_that!(&run("zeros * 10 + count"), eq("12".to_string()));
+        expect_that!(&run("x"), eq("x[0] = 0\nx[1] = 1".to_string()));
+        expect_that!(&run("isz[1]"), eq("isz[1].in = 1\nisz[1].out = 0\nisz[1].inv = 1".to_string()));
+        expect_that!(&run(":vars"), eq("count = 2\ni = 2".to_string()));
+        expect_that!(&run(":instances"), eq("* main (Main)\n  main.isz[0] (IsZero)\n  main.isz[1] (IsZero)".to_string()));
+        expect_that!(&run(":cd isz[1]"), eq("main.isz[1] (IsZero)".to_string()));
+        expect_that!(&run("in * inv"), eq("1".to_string()));
+        expect_that!(&run(":signals"), eq("signal input in = 1\nsignal output out = 0\nsignal inv = 1".to_string()));
+        expect_that!(&run(":trace"), eq("   2: signal input in\n   3: signal output out\n   4: signal inv\n   5: inv <-- in != 0 ? 1 / in : 0    # inv = 1\n   6: out <== -in * inv + 1    # out = 0\n   7: in * out === 0".to_string()));
+        expect_that!(&run("count"), eq("error: `count` is not declared".to_string()));
+        expect_that!(&run(":cd .."), eq("main (Main)".to_string()));
+        expect_that!(&run(":cd main.isz[0]"), eq("main.isz[0] (IsZero)".to_string()));
+        expect_that!(&run(":cd isz[3]"), eq("no instance `isz[3]` from `main.isz[0]`".to_string()));
+        expect_that!(&run("out <== 1"), eq("error: `out <== 1` is not an expression".to_string()));
+        expect_that!(&run(":frobnicate"), eq("unknown command `:frobnicate`, :help lists them".to_string()));
+        expect_that!(&repl.command(":quit"), eq(None));
+
+    }
+
+    #[test]
+    fn test_whole_expressions() {
+
+        let program = load(SOURCE);
+        let inputs = inputs();
+        let mut repl = Repl::new(&program, Some(&inputs), &Limits::default());
+        let mut run = |line: &str| repl.command(line).unwrap();
+
+        // nothing is evaluated from a line that only starts with an expression
+        expect_that!(&run("2 +"), eq("error: `2 +` ends too early".to_string()));
+        expect_that!(&run("1 2"), eq("error: cannot read `1 2` at `2`".to_string()));
+        expect_that!(&run("3 * 4 garbage"), eq("error: cannot read `3 * 4 garbage` at `garbage`".to_string()));
+        expect_that!(&run("count)"), eq("error: cannot read `count)` at `)`".to_string()));
+        expect_that!(&run("3 * 4"), eq("12".to_string()));
+
+    }
+
+    #[test]
+    fn test_failed_run() {
+
+        let program = load(&SOURCE.replace("    in * out === 0;\n", "    in * out === 0;\n    var check = 1 / in;\n"));
+        let inputs = inputs();
+        let mut repl = Repl::new(&program, Some(&inputs), &Limits::default());
+        expect_that!(&repl.scope().to_string(), eq("main.isz[0]".to_string()));
+        expect_that!(&repl.error().is_some(), eq(true));
+        let trace = repl.command(":trace").unwrap();
+        expect_that!(&trace.lines().last(), eq(Some("stopped: `1 / in` divides by zero")));
+
+        let mut output = vec![];
+        repl.run(&mut "out\n:cd ..\n:q\n".as_bytes(), &mut output).unwrap();
+        expect_that!(&String::from_utf8(output).unwrap(), eq("main.isz[0]> 1\nmain.isz[0]> main (Main)\nmain> ".to_string()));
+
+        let limits = Limits { statements: 10, ..Limits::default() };
+        let repl = Repl::new(&program, Some(&inputs), &limits);
+        expect_that!(&repl.error().map(|error| error.kind.code()), eq(Some(515)));
+
+    }
+}
@@ -0,0 +1,498 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::execution::debug::{Hook, Pause, PauseReason, Point};
+use crate::execution::{self, Circuit, Limits};
+use crate::parser::errors::execution::ExecutionError;
+use crate::parser::template::Program;
+use crate::r1cs::field::FieldElement;
+
+const HELP: &str = "\
+:next, :n         the step after the current one
+:back, :b         the step before the current one
+:goto <step>      the step at that position, from 0
+:last <signal>    back to the last step that assigned the signal, by full name (`main.square.a`)
+:reads            what the current step used, with the step that set it
+:values           every signal and variable once the current step ran
+<name>            value once the current step ran, by full name, every element for an array
+:help             this list
+:quit             leaves";
+
+pub(crate) fn display(value: & Option<FieldElement>) -> String {
+    match value {
+        Some(value) => value.to_signed().to_string(),
+        None => "unknown".to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Signal,
+    Variable
+}
+
+///
+/// A signal or variable element given a value by a statement, by its full name (`main.square.a`,
+/// `main.acc`). Values are unknown while only the constraints are generated.
+///
+/// `after` is the last step run when the value changed, when it is not the step of the statement: `y <== sub.b`
+/// runs `sub` in the middle of the statement and sets `y` after the steps of `sub`.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub name: String,
+    pub old: Option<FieldElement>,
+    pub new: Option<FieldElement>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<usize>
+}
+
+impl Change {
+
+    pub fn new(kind: ChangeKind, name: String, old: Option<FieldElement>, new: Option<FieldElement>) -> Change {
+        Change { kind, name, old, new, after: None }
+    }
+
+}
+
+///
+/// A signal or variable element used by a statement, with the index of the step that last set it, if any.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Read {
+    pub name: String,
+    pub value: Option<FieldElement>,
+    pub step: Option<usize>
+}
+
+///
+/// One statement run, loop test or if condition, with what it used and the changes it made.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    pub instance: String,
+    pub template: String,
+    pub file: String,
+    pub span: (usize, usize),
+    pub line: usize,
+    pub reads: Vec<Read>,
+    pub changes: Vec<Change>
+}
+
+///
+/// Every step of an execution in order, and the error that stopped it if any.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    pub error: Option<String>
+}
+
+impl Trace {
+
+    ///
+    /// Index of the last step before `before` that assigned the signal.
+    ///
+    pub fn last_write(&self, signal: & str, before: usize) -> Option<usize> {
+        self.steps[..before.min(self.steps.len())].iter()
+            .rposition(|step| step.changes.iter().any(|change| change.kind == ChangeKind::Signal && change.name == signal))
+    }
+
+    ///
+    /// Value of every signal and variable once the step at `position` ran, in the order the values changed:
+    /// a statement that ran a sub-component shows its changes from the last step of the sub-component on.
+    ///
+    pub fn values_at(&self, position: usize) -> BTreeMap<String, Option<FieldElement>> {
+        let mut changes: Vec<(usize, &Change)> = self.steps.iter().enumerate()
+            .flat_map(|(index, step)| step.changes.iter().map(move |change| (change.after.unwrap_or(index), change)))
+            .filter(|(at, _)| *at <= position)
+            .collect();
+        changes.sort_by_key(|(at, _)| *at);
+        changes.into_iter().map(|(_, change)| (change.name.clone(), change.new.clone())).collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a trace only holds strings and numbers")
+    }
+
+    pub fn from_json(text: & str) -> io::Result<Trace> {
+        serde_json::from_str(text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))
+    }
+
+}
+
+// a hook that never pauses and keeps every step
+#[derive(Default)]
+pub(crate) struct Recorder {
+    pub(crate) steps: Vec<Step>,
+    // last step of each instance, a sub-component may run in the middle of a statement
+    current: HashMap<String, usize>,
+    // last step that set each signal and variable element
+    written: HashMap<String, usize>
+}
+
+impl Hook for &mut Recorder {
+
+    fn reason(&mut self, point: & Point) -> Option<PauseReason> {
+        if let Point::Statement { instance, template, file, span, line } = point {
+            self.current.insert(instance.clone(), self.steps.len());
+            self.steps.push(Step { instance: instance.clone(), template: template.clone(), file: file.clone(), span: *span, line: *line, reads: vec![], changes: vec![] });
+        }
+        None
+    }
+
+    fn pause(&mut self, _pause: Pause, _evaluate: &mut dyn FnMut(usize, & str) -> Result<Option<FieldElement>, String>) -> bool {
+        true
+    }
+
+    fn record(&mut self, instance: & str, mut change: Change) {
+        if let Some(position) = self.current.get(instance) {
+            let last = self.steps.len() - 1;
+            if last != *position {
+                change.after = Some(last);
+            }
+            self.written.insert(change.name.clone(), *position);
+            self.steps[*position].changes.push(change);
+        }
+    }
+
+    fn read(&mut self, instance: & str, name: String, value: Option<FieldElement>) {
+        if let Some(position) = self.current.get(instance) {
+            let step = self.written.get(&name).copied();
+            self.steps[*position].reads.push(Read { name, value, step });
+        }
+    }
+
+}
+
+// a recorder shared with whoever keeps the executor after the run, as a REPL does
+impl Hook for Rc<RefCell<Recorder>> {
+
+    fn reason(&mut self, point: & Point) -> Option<PauseReason> {
+        (&mut *self.borrow_mut()).reason(point)
+    }
+
+    fn pause(&mut self, pause: Pause, evaluate: &mut dyn FnMut(usize, & str) -> Result<Option<FieldElement>, String>) -> bool {
+        (&mut *self.borrow_mut()).pause(pause, evaluate)
+    }
+
+    fn record(&mut self, instance: & str, change: Change) {
+        (&mut *self.borrow_mut()).record(instance, change)
+    }
+
+    fn read(&mut self, instance: & str, name: String, value: Option<FieldElement>) {
+        (&mut *self.borrow_mut()).read(instance, name, value)
+    }
+
+}
+
+///
+/// Executes `main` as `execute_with_limits` does, recording every step.
+///
+pub fn record(program: & Program, inputs: Option<& HashMap<String, FieldElement>>, limits: & Limits) -> (Trace, Result<Circuit, Box<ExecutionError>>) {
+    let mut recorder = Recorder::default();
+    let result = execution::execute_with_hook(program, inputs, limits, Box::new(&mut recorder));
+    let trace = Trace {
+        steps: recorder.steps,
+        error: result.as_ref().err().map(|error| error.to_string())
+    };
+    (trace, result)
+}
+
+///
+/// A position in a trace that moves both ways, for going back to the statement that set a value.
+///
+pub struct Replay<'t> {
+    trace: &'t Trace,
+    position: usize
+}
+
+impl<'t> Replay<'t> {
+
+    pub fn new(trace: &'t Trace) -> Replay<'t> {
+        Replay { trace, position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn current(&self) -> Option<&'t Step> {
+        self.trace.steps.get(self.position)
+    }
+
+    pub fn forward(&mut self) -> Option<&'t Step> {
+        self.goto(self.position + 1)
+    }
+
+    pub fn backward(&mut self) -> Option<&'t Step> {
+        match self.position {
+            0 => None,
+            position => self.goto(position - 1)
+        }
+    }
+
+    ///
+    /// Moves to the step at `position`, stays put when there is none.
+    ///
+    pub fn goto(&mut self, position: usize) -> Option<&'t Step> {
+        if position >= self.trace.steps.len() {
+            return None;
+        }
+        self.position = position;
+        self.current()
+    }
+
+    ///
+    /// Moves back to the last step that assigned the signal before the current one.
+    ///
+    pub fn last_write(&mut self, signal: & str) -> Option<&'t Step> {
+        let position = self.trace.last_write(signal, self.position)?;
+        self.goto(position)
+    }
+
+    pub fn values(&self) -> BTreeMap<String, Option<FieldElement>> {
+        self.trace.values_at(self.position)
+    }
+
+    ///
+    /// Runs a command or shows a value, None once asked to quit.
+    ///
+    pub fn command(&mut self, line: & str) -> Option<String> {
+        let line = line.trim();
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line, "")
+        };
+        if self.trace.steps.is_empty() && !line.is_empty() && !matches!(command, ":help" | ":quit" | ":q") {
+            return Some("the trace has no step".to_string());
+        }
+        Some(match command {
+            "" => String::new(),
+            ":quit" | ":q" => return None,
+            ":help" => HELP.to_string(),
+            ":next" | ":n" => match self.forward() {
+                Some(_) => self.step(),
+                None => format!("no step after {}", self.position)
+            },
+            ":back" | ":b" => match self.backward() {
+                Some(_) => self.step(),
+                None => "no step before 0".to_string()
+            },
+            ":goto" => match argument.parse::<usize>().ok().and_then(|position| self.goto(position)) {
+                Some(_) => self.step(),
+                None => format!("no step `{}`, the trace has {}", argument, self.trace.steps.len())
+            },
+            ":last" => match self.last_write(argument) {
+                Some(_) => self.step(),
+                None => format!("`{}` is not assigned before step {}", argument, self.position)
+            },
+            ":reads" => self.reads(),
+            ":values" => self.values().iter().map(|(name, value)| format!("{} = {}", name, display(value))).collect::<Vec<String>>().join("\n"),
+            command if command.starts_with(':') => format!("unknown command `{}`, :help lists them", command),
+            _ => self.value(line)
+        })
+    }
+
+    ///
+    /// Reads commands from `reader` until `:quit` or the end of the input, with a prompt giving the step.
+    ///
+    pub fn run(&mut self, reader: &mut dyn BufRead, writer: &mut dyn Write) -> io::Result<()> {
+        loop {
+            write!(writer, "step {}> ", self.position)?;
+            writer.flush()?;
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return writeln!(writer);
+            }
+            match self.command(&line) {
+                Some(output) if output.is_empty() => {},
+                Some(output) => writeln!(writer, "{}", output)?,
+                None => return Ok(())
+            }
+        }
+    }
+
+    ///
+    /// Where the current step is and what it changed.
+    ///
+    pub fn step(&self) -> String {
+        let step = match self.current() {
+            Some(step) => step,
+            None => return "the trace has no step".to_string()
+        };
+        let mut lines = vec![format!("step {} of {}: {} ({}) at {}:{}", self.position, self.trace.steps.len(), step.instance, step.template, step.file, step.line)];
+        lines.extend(step.changes.iter().map(|change| format!("    {} = {}", change.name, display(&change.new))));
+        lines.join("\n")
+    }
+
+    fn reads(&self) -> String {
+        let reads = self.current().map(|step| step.reads.as_slice()).unwrap_or_default();
+        reads.iter().map(|read| match read.step {
+            Some(step) => format!("{} = {}, set by step {}", read.name, display(&read.value), step),
+            None => format!("{} = {}", read.name, display(&read.value))
+        }).collect::<Vec<String>>().join("\n")
+    }
+
+    fn value(&self, text: & str) -> String {
+        let name: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let values = self.values();
+        if let Some(value) = values.get(&name) {
+            return display(value);
+        }
+        let elements: Vec<String> = values.iter()
+            .filter(|(element, _)| element.starts_with(&format!("{}[", name)))
+            .map(|(element, value)| format!("{} = {}", element, display(value)))
+            .collect();
+        match elements.is_empty() {
+            true => format!("`{}` is not assigned by step {}", name, self.position),
+            false => elements.join("\n")
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod trace_test {
+
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::execution::trace::{self, Change, ChangeKind, Replay, Trace};
+    use crate::execution::Limits;
+    use crate::parser::compile;
+    use crate::parser::source::MemorySource;
+    use crate::parser::symbols;
+    use crate::parser::template::{self, Program};
+    use crate::r1cs::field::FieldElement;
+
+    use galvanic_assert::matchers::*;
+
+    fn program(source: & str) -> Program {
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", source);
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("/virtual/main.circom"), &sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        template::load_program(&ctx, &table)
+    }
+
+    fn value(value: i64) -> Option<FieldElement> {
+        Some(FieldElement::from_i64(value))
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+
+        let program = program("template Square() {\n    signal input a;\n    signal output b;\n    b <== a * a;\n}\ntemplate Main() {\n    signal input x;\n    signal output y;\n    var acc = 0;\n    for (var i = 0; i < 2; i++) {\n        acc += i;\n    }\n    component square = Square();\n    square.a <== x + acc;\n    y <== square.b;\n}\ncomponent main = Main();\n");
+        let mut inputs = HashMap::new();
+        inputs.insert("x".to_string(), FieldElement::from_i64(3));
+        let (trace, result) = trace::record(&program, Some(&inputs), &Limits::default());
+        expect_that!(&result.is_ok(), eq(true));
+        expect_that!(&trace.error, eq(None));
+
+        let lines: Vec<(&str, usize)> = trace.steps.iter().map(|step| (step.instance.as_str(), step.line)).collect();
+        expect_that!(&lines, eq(vec![("main", 7), ("main", 8), ("main", 9), ("main", 10), ("main", 11), ("main", 10), ("main", 11), ("main", 10), ("main", 13), ("main", 14), ("main", 15), ("main.square", 2), ("main.square", 3), ("main.square", 4)]));
+        expect_that!(&trace.steps[0].changes, eq(vec![Change::new(ChangeKind::Signal, "main.x".to_string(), None, value(3))]));
+        expect_that!(&trace.steps[6].changes, eq(vec![Change::new(ChangeKind::Variable, "main.acc".to_string(), value(0), value(1))]));
+        expect_that!(&trace.steps[7].changes, eq(vec![Change::new(ChangeKind::Variable, "main.i".to_string(), value(1), value(2))]));
+        expect_that!(&trace.steps[9].changes, eq(vec![Change::new(ChangeKind::Signal, "main.square.a".to_string(), None, value(4))]));
+        let reads: Vec<(&str, Option<FieldElement>, Option<usize>)> = trace.steps[9].reads.iter().map(|read| (read.name.as_str(), read.value.clone(), read.step)).collect();
+        expect_that!(&reads, eq(vec![("main.x", value(3), Some(0)), ("main.acc", value(1), Some(6))]));
+        // `y <== square.b` runs the sub-component in the middle of the statement
+        expect_that!(&trace.steps[13].changes, eq(vec![Change::new(ChangeKind::Signal, "main.square.b".to_string(), None, value(16))]));
+        let y = Change { after: Some(13), ..Change::new(ChangeKind::Signal, "main.y".to_string(), None, value(16)) };
+        expect_that!(&trace.steps[10].changes, eq(vec![y]));
+        // `main.y` only has a value once `main.square` ran
+        expect_that!(&(trace.values_at(12).contains_key("main.y"), trace.values_at(13).get("main.y").cloned()), eq((false, Some(value(16)))));
+
+        let mut replay = Replay::new(&trace);
+        expect_that!(&replay.backward(), eq(None));
+        replay.goto(trace.steps.len() - 1);
+        expect_that!(&replay.values().get("main.acc").cloned(), eq(Some(value(1))));
+        expect_that!(&replay.last_write("main.square.a").map(|step| step.line), eq(Some(14)));
+        expect_that!(&replay.position(), eq(9));
+        expect_that!(&replay.values().get("main.y").cloned(), eq(None));
+        expect_that!(&replay.backward().map(|step| step.line), eq(Some(13)));
+        expect_that!(&replay.values().get("main.acc").cloned(), eq(Some(value(1))));
+        expect_that!(&replay.goto(4).map(|step| step.line), eq(Some(11)));
+        expect_that!(&replay.values().get("main.acc").cloned(), eq(Some(value(0))));
+        expect_that!(&replay.forward().map(|step| step.line), eq(Some(10)));
+        expect_that!(&replay.last_write("main.nothing"), eq(None));
+        expect_that!(&replay.goto(100), eq(None));
+        expect_that!(&replay.position(), eq(5));
+
+        expect_that!(&Trace::from_json(&trace.to_json()).unwrap(), eq(trace.clone()));
+        expect_that!(&trace.to_json().contains("\"kind\": \"signal\""), eq(true));
+
+    }
+
+    #[test]
+    fn test_record_failing_run() {
+
+        let program = program("template Main() {\n    signal input x;\n    signal output y;\n    y <-- x * 2;\n    assert(y == 5);\n}\ncomponent main = Main();\n");
+        let mut inputs = HashMap::new();
+        inputs.insert("x".to_string(), FieldElement::from_i64(2));
+        let (trace, result) = trace::record(&program, Some(&inputs), &Limits::default());
+        expect_that!(&result.is_err(), eq(true));
+        expect_that!(&trace.error.as_ref().map(|error| error.contains("AssertionFailed")), eq(Some(true)));
+        expect_that!(&trace.steps.last().map(|step| step.line), eq(Some(5)));
+        expect_that!(&trace.last_write("main.y", trace.steps.len()), eq(Some(2)));
+
+        let limits = Limits { statements: 2, ..Limits::default() };
+        let (limited, result) = trace::record(&program, Some(&inputs), &limits);
+        expect_that!(&result.err().map(|error| error.kind.code()), eq(Some(515)));
+        expect_that!(&limited.steps.len(), eq(2));
+
+        let replayed = Trace::from_json(&trace.to_json()).unwrap();
+        expect_that!(&replayed.values_at(replayed.steps.len() - 1).get("main.y").cloned(), eq(Some(value(4))));
+        expect_that!(&Trace::from_json("{\"steps\": [], \"error\": 3}").is_err(), eq(true));
+
+    }
+
+    #[test]
+    fn test_replay_exported_file() {
+
+        let program = program("template Square() {\n    signal input a;\n    signal output b;\n    b <== a * a;\n}\ntemplate Main() {\n    signal input x;\n    signal output y;\n    var acc = 0;\n    for (var i = 0; i < 2; i++) {\n        acc += i;\n    }\n    component square = Square();\n    square.a <== x + acc;\n    y <== square.b;\n}\ncomponent main = Main();\n");
+        let mut inputs = HashMap::new();
+        inputs.insert("x".to_string(), FieldElement::from_i64(3));
+        let (trace, _) = trace::record(&program, Some(&inputs), &Limits::default());
+        // as `snarkrs trace` writes it and `snarkrs replay` reads it
+        let path = std::env::temp_dir().join(format!("replay_test_{}.json", std::process::id()));
+        fs::write(&path, trace.to_json()).unwrap();
+        let exported = Trace::from_json(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut replay = Replay::new(&exported);
+        let commands = ":goto 13\n:last main.square.a\n:reads\n:back\nmain.acc\nmain.y\n:goto 99\n:back\n:next\n:values\n:quit\n:next\n";
+        let mut output = vec![];
+        replay.run(&mut commands.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        expect_that!(&output.lines().collect::<Vec<&str>>(), eq(vec![
+            "step 0> step 13 of 14: main.square (Square) at /virtual/main.circom:4",
+            "    main.square.b = 16",
+            "step 13> step 9 of 14: main (Main) at /virtual/main.circom:14",
+            "    main.square.a = 4",
+            "step 9> main.x = 3, set by step 0",
+            "main.acc = 1, set by step 6",
+            "step 9> step 8 of 14: main (Main) at /virtual/main.circom:13",
+            "step 8> 1",
+            "step 8> `main.y` is not assigned by step 8",
+            "step 8> no step `99`, the trace has 14",
+            "step 8> step 7 of 14: main (Main) at /virtual/main.circom:10",
+            "    main.i = 2",
+            "step 7> step 8 of 14: main (Main) at /virtual/main.circom:13",
+            "step 8> main.acc = 1",
+            "main.i = 2",
+            "main.x = 3",
+            "step 8> "
+        ]));
+
+    }
+}
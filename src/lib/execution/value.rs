@@ -0,0 +1,257 @@
+use std::cmp::Ordering;
+
+use crate::parser::expression_parser::{BinOp, BitwiseOp, LogicalOp, UnOp};
+use crate::r1cs::field::FieldElement;
+use crate::r1cs::LinearCombination;
+
+///
+/// An expression over the signals of the circuit, as far as R1CS can represent it.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Symbolic {
+    Linear(LinearCombination),
+    // a * b + c
+    Quadratic { a: LinearCombination, b: LinearCombination, c: LinearCombination },
+    NonQuadratic
+}
+
+///
+/// Result of evaluating an expression: its value when it is known, and what it is in terms of signals.
+/// Numbers, parameters and vars computed from them are constants, their combination only uses wire 0.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Evaluated {
+    pub value: Option<FieldElement>,
+    pub symbolic: Symbolic
+}
+
+impl Evaluated {
+
+    pub fn constant(value: FieldElement) -> Evaluated {
+        Evaluated {
+            symbolic: Symbolic::Linear(LinearCombination::constant(value.clone())),
+            value: Some(value)
+        }
+    }
+
+    pub fn signal(signal: usize, value: Option<FieldElement>) -> Evaluated {
+        Evaluated {
+            value,
+            symbolic: Symbolic::Linear(LinearCombination::wire(signal))
+        }
+    }
+
+    pub fn unknown() -> Evaluated {
+        Evaluated {
+            value: None,
+            symbolic: Symbolic::NonQuadratic
+        }
+    }
+
+    ///
+    /// Value of a constant, `None` as soon as the expression depends on a signal, even one whose value is known.
+    ///
+    pub fn known(&self) -> Option<& FieldElement> {
+        match &self.symbolic {
+            Symbolic::Linear(combination) if combination.constant_value().is_some() => self.value.as_ref(),
+            _ => None
+        }
+    }
+
+}
+
+fn normalize(symbolic: Symbolic) -> Symbolic {
+    match symbolic {
+        Symbolic::Quadratic { a, b, c } if a.is_empty() || b.is_empty() => Symbolic::Linear(c),
+        other => other
+    }
+}
+
+fn scale(symbolic: & Symbolic, factor: & FieldElement) -> Symbolic {
+    normalize(match symbolic {
+        Symbolic::Linear(combination) => Symbolic::Linear(combination.scale(factor)),
+        Symbolic::Quadratic { a, b, c } => Symbolic::Quadratic { a: a.scale(factor), b: b.clone(), c: c.scale(factor) },
+        Symbolic::NonQuadratic => Symbolic::NonQuadratic
+    })
+}
+
+fn symbolic_add(left: & Symbolic, right: & Symbolic) -> Symbolic {
+    match (left, right) {
+        (Symbolic::Linear(left), Symbolic::Linear(right)) => Symbolic::Linear(left.add(right)),
+        (Symbolic::Quadratic { a, b, c }, Symbolic::Linear(linear)) | (Symbolic::Linear(linear), Symbolic::Quadratic { a, b, c }) => {
+            Symbolic::Quadratic { a: a.clone(), b: b.clone(), c: c.add(linear) }
+        },
+        _ => Symbolic::NonQuadratic
+    }
+}
+
+fn symbolic_mul(left: & Evaluated, right: & Evaluated) -> Symbolic {
+    if let Some(factor) = left.known() {
+        return scale(&right.symbolic, factor);
+    }
+    if let Some(factor) = right.known() {
+        return scale(&left.symbolic, factor);
+    }
+    match (&left.symbolic, &right.symbolic) {
+        (Symbolic::Linear(a), Symbolic::Linear(b)) => Symbolic::Quadratic { a: a.clone(), b: b.clone(), c: LinearCombination::default() },
+        _ => Symbolic::NonQuadratic
+    }
+}
+
+fn both<T>(left: & Evaluated, right: & Evaluated, operation: &dyn Fn(& FieldElement, & FieldElement) -> T) -> Option<T> {
+    match (&left.value, &right.value) {
+        (Some(left), Some(right)) => Some(operation(left, right)),
+        _ => None
+    }
+}
+
+// an operation R1CS cannot express, only constants stay representable
+fn opaque(value: Option<FieldElement>, operands: & [& Evaluated]) -> Evaluated {
+    match value {
+        Some(value) if operands.iter().all(|operand| operand.known().is_some()) => Evaluated::constant(value),
+        value => Evaluated { value, symbolic: Symbolic::NonQuadratic }
+    }
+}
+
+pub fn add(left: & Evaluated, right: & Evaluated) -> Evaluated {
+    Evaluated {
+        value: both(left, right, &|left, right| left + right),
+        symbolic: symbolic_add(&left.symbolic, &right.symbolic)
+    }
+}
+
+pub fn negate(operand: & Evaluated) -> Evaluated {
+    Evaluated {
+        value: operand.value.as_ref().map(|value| -value),
+        symbolic: scale(&operand.symbolic, &FieldElement::from_i64(-1))
+    }
+}
+
+pub fn sub(left: & Evaluated, right: & Evaluated) -> Evaluated {
+    add(left, &negate(right))
+}
+
+pub fn mul(left: & Evaluated, right: & Evaluated) -> Evaluated {
+    Evaluated {
+        value: both(left, right, &|left, right| left * right),
+        symbolic: symbolic_mul(left, right)
+    }
+}
+
+fn div(left: & Evaluated, right: & Evaluated) -> Option<Evaluated> {
+    let value = match both(left, right, &|left, right| right.inverse().map(|inverse| left * &inverse)) {
+        Some(None) => return None,
+        Some(quotient) => quotient,
+        None => None
+    };
+    let symbolic = match right.known() {
+        Some(divisor) => scale(&left.symbolic, &divisor.inverse()?),
+        None => Symbolic::NonQuadratic
+    };
+    Some(Evaluated { value, symbolic })
+}
+
+fn pow(base: & Evaluated, exponent: & Evaluated) -> Evaluated {
+    let value = both(base, exponent, &|base, exponent| base.pow(exponent));
+    match (base.known(), exponent.known().and_then(|exponent| exponent.to_usize())) {
+        (None, Some(0)) => Evaluated::constant(FieldElement::one()),
+        (None, Some(1)) => base.clone(),
+        (None, Some(2)) => mul(base, base),
+        _ => opaque(value, &[base, exponent])
+    }
+}
+
+// `\` and `%`, `None` for a division by zero
+fn integer_division(left: & Evaluated, right: & Evaluated, operation: &dyn Fn(& FieldElement, & FieldElement) -> Option<FieldElement>) -> Option<Evaluated> {
+    let value = match both(left, right, operation) {
+        Some(None) => return None,
+        Some(result) => result,
+        None => None
+    };
+    Some(opaque(value, &[left, right]))
+}
+
+///
+/// `None` when the operation divides by zero.
+///
+pub fn binary(op: & BinOp, left: & Evaluated, right: & Evaluated) -> Option<Evaluated> {
+    match op {
+        BinOp::Add => Some(add(left, right)),
+        BinOp::Subtract => Some(sub(left, right)),
+        BinOp::Multiply => Some(mul(left, right)),
+        BinOp::Divide => div(left, right),
+        BinOp::Exponent => Some(pow(left, right)),
+        BinOp::Quotient => integer_division(left, right, &|left, right| left.quotient(right)),
+        BinOp::Modulo => integer_division(left, right, &|left, right| left.modulo(right))
+    }
+}
+
+pub fn logical(op: & LogicalOp, left: & Evaluated, right: & Evaluated) -> Evaluated {
+    let value = both(left, right, &|left, right| {
+        let ordering = left.to_signed().cmp(&right.to_signed());
+        FieldElement::from_bool(match op {
+            LogicalOp::And => !left.is_zero() && !right.is_zero(),
+            LogicalOp::Or => !left.is_zero() || !right.is_zero(),
+            LogicalOp::Equal => left == right,
+            LogicalOp::NotEqual => left != right,
+            LogicalOp::LessThan => ordering == Ordering::Less,
+            LogicalOp::LessThanOrEqual => ordering != Ordering::Greater,
+            LogicalOp::GreaterThan => ordering == Ordering::Greater,
+            LogicalOp::GreaterThanOrEqual => ordering != Ordering::Less
+        })
+    });
+    opaque(value, &[left, right])
+}
+
+pub fn bitwise(op: & BitwiseOp, left: & Evaluated, right: & Evaluated) -> Evaluated {
+    let value = both(left, right, &|left, right| match op {
+        BitwiseOp::And => left.bit_and(right),
+        BitwiseOp::Or => left.bit_or(right),
+        BitwiseOp::Xor => left.bit_xor(right),
+        BitwiseOp::ShiftLeft => left.shift_left(right),
+        BitwiseOp::ShiftRight => left.shift_right(right)
+    });
+    opaque(value, &[left, right])
+}
+
+pub fn unary(op: & UnOp, operand: & Evaluated) -> Evaluated {
+    match op {
+        UnOp::Negate => negate(operand),
+        UnOp::Not => opaque(operand.value.as_ref().map(|value| FieldElement::from_bool(value.is_zero())), &[operand]),
+        UnOp::BitwiseNot => opaque(operand.value.as_ref().map(|value| value.bit_not()), &[operand])
+    }
+}
+
+#[cfg(test)]
+mod value_test {
+
+    use crate::execution::value::{self, Evaluated, Symbolic};
+    use crate::parser::expression_parser::BinOp;
+    use crate::r1cs::field::FieldElement;
+    use crate::r1cs::LinearCombination;
+
+    use galvanic_assert::matchers::*;
+
+    #[test]
+    fn test_degrees() {
+
+        let a = Evaluated::signal(1, None);
+        let b = Evaluated::signal(2, None);
+        let two = Evaluated::constant(FieldElement::from_i64(2));
+
+        // (2a) * b + 2
+        let product = value::add(&value::mul(&value::mul(&two, &a), &b), &two);
+        expect_that!(&product.symbolic, eq(Symbolic::Quadratic {
+            a: LinearCombination::wire(1).scale(&FieldElement::from_i64(2)),
+            b: LinearCombination::wire(2),
+            c: LinearCombination::constant(FieldElement::from_i64(2))
+        }));
+        expect_that!(&value::mul(&product, &a).symbolic, eq(Symbolic::NonQuadratic));
+
+        // dividing by a constant stays linear, by a signal does not
+        expect_that!(&value::binary(&BinOp::Divide, &a, &two).unwrap().symbolic, eq(Symbolic::Linear(LinearCombination::wire(1).scale(&FieldElement::from_i64(2).inverse().unwrap()))));
+        expect_that!(&value::binary(&BinOp::Divide, &two, &a).unwrap().symbolic, eq(Symbolic::NonQuadratic));
+        expect_that!(&value::binary(&BinOp::Divide, &a, &Evaluated::constant(FieldElement::zero())).is_none(), eq(true));
+
+    }
+}
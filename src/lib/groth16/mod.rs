@@ -0,0 +1,161 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_relations::lc;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination as ArkLinearCombination, SynthesisError, Variable};
+use ark_snark::SNARK;
+use ark_std::rand::rngs::StdRng;
+use ark_std::rand::SeedableRng;
+
+use crate::r1cs::field::FieldElement;
+use crate::r1cs::{LinearCombination, R1cs, ONE};
+
+///
+/// snarkjs' JSON files for Groth16 proofs on BN254: points in projective coordinates with `z = 1`, the
+/// point at infinity as `[0, 1, 0]`, every number as a decimal string.
+///
+pub mod snarkjs;
+
+///
+/// Groth16 over BN254 for trying circuits locally. The setup is run by whoever proves, from a seed: anyone
+/// knowing the seed can forge proofs, the keys are only good for tests.
+///
+pub struct Keys {
+    pub proving_key: ProvingKey<Bn254>,
+    pub verifying_key: VerifyingKey<Bn254>
+}
+
+fn to_fr(value: & FieldElement) -> Fr {
+    Fr::from_le_bytes_mod_order(&value.to_le_bytes(32))
+}
+
+// the system as arkworks constraints, public wires first as in the r1cs: wire `i` is `variables[i]`
+struct Circuit<'a> {
+    r1cs: &'a R1cs,
+    witness: Option<&'a [FieldElement]>
+}
+
+impl<'a> Circuit<'a> {
+
+    fn value(&self, wire: usize) -> Result<Fr, SynthesisError> {
+        match self.witness.and_then(|witness| witness.get(wire)) {
+            Some(value) => Ok(to_fr(value)),
+            None => Err(SynthesisError::AssignmentMissing)
+        }
+    }
+
+}
+
+impl<'a> ConstraintSynthesizer<Fr> for Circuit<'a> {
+
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let n_public = self.r1cs.n_pub_out + self.r1cs.n_pub_in;
+        let mut variables = vec![Variable::One];
+        for wire in 1..self.r1cs.n_wires {
+            let variable = match wire <= n_public {
+                true => cs.new_input_variable(|| self.value(wire))?,
+                false => cs.new_witness_variable(|| self.value(wire))?
+            };
+            variables.push(variable);
+        }
+        let combination = |combination: & LinearCombination| -> ArkLinearCombination<Fr> {
+            let mut converted = lc!();
+            for (wire, coefficient) in &combination.terms {
+                let variable = match *wire {
+                    ONE => Variable::One,
+                    wire => variables[wire]
+                };
+                converted += (to_fr(coefficient), variable);
+            }
+            converted
+        };
+        for constraint in &self.r1cs.constraints {
+            cs.enforce_constraint(combination(&constraint.a), combination(&constraint.b), combination(&constraint.c))?;
+        }
+        Ok(())
+    }
+
+}
+
+///
+/// Values of the public signals, outputs then public inputs of main, as `public.json` lists them.
+///
+pub fn public_signals(r1cs: & R1cs, witness: & [FieldElement]) -> Vec<FieldElement> {
+    witness.iter().skip(1).take(r1cs.n_pub_out + r1cs.n_pub_in).cloned().collect()
+}
+
+///
+/// Proving and verifying keys for the system, from a random setup seeded with `seed`.
+///
+pub fn setup(r1cs: & R1cs, seed: u64) -> Result<Keys, SynthesisError> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (proving_key, verifying_key) = Groth16::<Bn254>::circuit_specific_setup(Circuit { r1cs, witness: None }, &mut rng)?;
+    Ok(Keys { proving_key, verifying_key })
+}
+
+///
+/// Proof that the witness, a value for every wire, satisfies the system. The witness is not checked, see
+/// `r1cs::satisfaction` to find why a proof does not verify.
+///
+pub fn prove(r1cs: & R1cs, keys: & Keys, witness: & [FieldElement], seed: u64) -> Result<Proof<Bn254>, SynthesisError> {
+    if witness.len() < r1cs.n_wires {
+        return Err(SynthesisError::AssignmentMissing);
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    Groth16::<Bn254>::prove(&keys.proving_key, Circuit { r1cs, witness: Some(witness) }, &mut rng)
+}
+
+pub fn verify(verifying_key: & VerifyingKey<Bn254>, public: & [FieldElement], proof: & Proof<Bn254>) -> Result<bool, SynthesisError> {
+    let public: Vec<Fr> = public.iter().map(to_fr).collect();
+    Groth16::<Bn254>::verify(verifying_key, &public, proof)
+}
+
+#[cfg(test)]
+mod groth16_test {
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::execution::{self, inputs};
+    use crate::groth16::{self, snarkjs};
+    use crate::parser::compile;
+    use crate::parser::source::DiskSource;
+    use crate::parser::symbols;
+    use crate::parser::template;
+    use crate::r1cs::field::FieldElement;
+    use crate::r1cs::simplify;
+
+    use galvanic_assert::matchers::*;
+
+    #[test]
+    fn test_prove_and_verify() {
+
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("./src/lib/parser/sample_circuits/warships_raw.circom"), &DiskSource);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = template::load_program(&ctx, &table);
+        let inputs = inputs::from_json(&fs::read_to_string("./src/lib/parser/sample_circuits/input.json").unwrap()).unwrap();
+        let circuit = execution::execute_with_inputs(&program, &inputs).unwrap();
+        let simplified = simplify::simplify(&circuit.r1cs(), &simplify::passes(2));
+        let witness = simplified.witness(&circuit.witness().unwrap());
+
+        let keys = groth16::setup(&simplified.r1cs, 1).unwrap();
+        let proof = groth16::prove(&simplified.r1cs, &keys, &witness, 2).unwrap();
+        let public = groth16::public_signals(&simplified.r1cs, &witness);
+        expect_that!(&public.len(), eq(10));
+        expect_that!(&groth16::verify(&keys.verifying_key, &public, &proof), eq(Ok(true)));
+
+        let mut forged = public.clone();
+        forged[0] = FieldElement::zero();
+        expect_that!(&groth16::verify(&keys.verifying_key, &forged, &proof), eq(Ok(false)));
+        expect_that!(&groth16::prove(&simplified.r1cs, &keys, &witness[..10], 2).is_err(), eq(true));
+
+        let key = snarkjs::verification_key(&keys.verifying_key);
+        expect_that!(&(key["protocol"].as_str(), key["curve"].as_str(), key["nPublic"].as_u64()), eq((Some("groth16"), Some("bn128"), Some(10))));
+        expect_that!(&key["IC"].as_array().map(|ic| ic.len()), eq(Some(11)));
+        let proof = snarkjs::proof(&proof);
+        expect_that!(&proof["pi_a"][2].as_str(), eq(Some("1")));
+        expect_that!(&proof["pi_b"][2], eq(serde_json::json!(["1", "0"])));
+        expect_that!(&snarkjs::public(&public)[0], eq(serde_json::json!("1")));
+
+    }
+}
@@ -0,0 +1,69 @@
+use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G2Affine};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_groth16::{Proof, VerifyingKey};
+use num_bigint::BigUint;
+use serde_json::{json, Value};
+
+use crate::r1cs::field::FieldElement;
+
+fn fq(value: & Fq) -> String {
+    BigUint::from(value.into_bigint()).to_string()
+}
+
+fn fq2(value: & Fq2) -> Value {
+    json!([fq(&value.c0), fq(&value.c1)])
+}
+
+fn g1(point: & G1Affine) -> Value {
+    match point.infinity {
+        true => json!(["0", "1", "0"]),
+        false => json!([fq(&point.x), fq(&point.y), "1"])
+    }
+}
+
+fn g2(point: & G2Affine) -> Value {
+    match point.infinity {
+        true => json!([["0", "0"], ["1", "0"], ["0", "0"]]),
+        false => json!([fq2(&point.x), fq2(&point.y), ["1", "0"]])
+    }
+}
+
+///
+/// `proof.json`
+///
+pub fn proof(proof: & Proof<Bn254>) -> Value {
+    json!({
+        "pi_a": g1(&proof.a),
+        "pi_b": g2(&proof.b),
+        "pi_c": g1(&proof.c),
+        "protocol": "groth16",
+        "curve": "bn128"
+    })
+}
+
+///
+/// `public.json`
+///
+pub fn public(public: & [FieldElement]) -> Value {
+    Value::Array(public.iter().map(|value| Value::String(value.to_string())).collect())
+}
+
+///
+/// `verification_key.json`, with the pairing of alpha and beta snarkjs precomputes.
+///
+pub fn verification_key(key: & VerifyingKey<Bn254>) -> Value {
+    let alphabeta = Bn254::pairing(key.alpha_g1, key.beta_g2).0;
+    let fq6 = |value: & ark_bn254::Fq6| json!([fq2(&value.c0), fq2(&value.c1), fq2(&value.c2)]);
+    json!({
+        "protocol": "groth16",
+        "curve": "bn128",
+        "nPublic": key.gamma_abc_g1.len() - 1,
+        "vk_alpha_1": g1(&key.alpha_g1),
+        "vk_beta_2": g2(&key.beta_g2),
+        "vk_gamma_2": g2(&key.gamma_g2),
+        "vk_delta_2": g2(&key.delta_g2),
+        "vk_alphabeta_12": [fq6(&alphabeta.c0), fq6(&alphabeta.c1)],
+        "IC": key.gamma_abc_g1.iter().map(g1).collect::<Vec<Value>>()
+    })
+}
@@ -8,4 +8,10 @@ extern crate core;
 extern crate galvanic_assert;
 
 pub mod parser;
+pub mod analysis;
+pub mod r1cs;
+pub mod execution;
+pub mod groth16;
+pub mod dap;
+pub mod lsp;
 
@@ -0,0 +1,691 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, DiagnosticSeverity, DocumentSymbol, Hover, HoverContents, Location,
+    MarkupContent, MarkupKind, NumberOrString, Position, PublishDiagnosticsParams, Range, SymbolKind as LspSymbolKind,
+    TextDocumentSyncKind, Url
+};
+use serde_json::json;
+
+use crate::analysis::{self, lints};
+use crate::parser::ast;
+use crate::parser::ast::tokens::Token;
+use crate::parser::compile::{self, Context, LoadAttempt};
+use crate::parser::errors::CompileError;
+use crate::parser::semantic;
+use crate::parser::source::{normalize_path, MemorySource, OverlaySource, SourceProvider};
+use crate::parser::symbols::{self, Symbol, SymbolKind, SymbolTable};
+use crate::parser::template::{self, DeclType, Program, Signal, SignalDirection, SingleCommand, Template};
+
+const SOURCE: &str = "snarkrs";
+
+///
+/// A compilation with a document as its root, the program is only loaded when every file parsed.
+///
+struct Compilation {
+    ctx: Context,
+    table: SymbolTable,
+    program: Program
+}
+
+///
+/// Language features for `.circom` files. Open documents shadow the files of `base`, every request compiles
+/// the document it is about as the root of the program, so included files are read from the editor buffers
+/// when they are open and from `base` otherwise.
+///
+pub struct Server<'a> {
+    base: &'a dyn SourceProvider,
+    documents: MemorySource,
+    // files given diagnostics by the last publication, to clear the ones that are fixed since
+    published: HashSet<PathBuf>
+}
+
+impl<'a> Server<'a> {
+
+    pub fn new(base: &'a dyn SourceProvider) -> Server<'a> {
+        Server { base, documents: MemorySource::new(), published: HashSet::new() }
+    }
+
+    pub fn open(&mut self, path: & Path, text: & str) {
+        self.documents.insert(path, text);
+    }
+
+    pub fn close(&mut self, path: & Path) {
+        self.documents.remove(path);
+    }
+
+    fn source(&self, path: & Path) -> String {
+        OverlaySource::new(self.base, self.documents.clone()).read_source(path).unwrap_or_default()
+    }
+
+    fn compile(&self, path: & Path, documents: MemorySource) -> Compilation {
+        let sources = OverlaySource::new(self.base, documents);
+        let mut ctx = compile::build_context_from_source(&path.to_path_buf(), &sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = match ctx.errors.iter().any(|error| matches!(error, CompileError::SyntaxError(_) | CompileError::UnknownFileIncludeError(_))) {
+            true => Program::default(),
+            false => template::load_program(&ctx, &table)
+        };
+        Compilation { ctx, table, program }
+    }
+
+    ///
+    /// Compiles the document with the line at `offset` blanked when it does not parse as is, so the rest of a
+    /// document being typed into (`isEqual.`) still resolves.
+    ///
+    fn compile_around(&self, path: & Path, offset: usize) -> Compilation {
+        let compilation = self.compile(path, self.documents.clone());
+        if !matches!(compilation.ctx.files.get(path), Some(LoadAttempt::Error())) {
+            return compilation;
+        }
+        let source = self.source(path);
+        let (start, end) = line_bounds(&source, offset);
+        let mut documents = self.documents.clone();
+        documents.insert(path, &format!("{}{}{}", &source[..start], " ".repeat(end - start), &source[end..]));
+        self.compile(path, documents)
+    }
+
+    ///
+    /// Diagnostics of the document and of every file it includes, by file. Files reported by the previous
+    /// call and clean now are given an empty list, so the client drops what it shows for them.
+    ///
+    pub fn diagnostics(&mut self, path: & Path) -> Vec<(PathBuf, Vec<lsp_types::Diagnostic>)> {
+        let compilation = self.compile(path, self.documents.clone());
+        let mut by_file: HashMap<PathBuf, Vec<lsp_types::Diagnostic>> = HashMap::new();
+        for file in compilation.ctx.files.keys() {
+            by_file.insert(normalize_path(file), vec![]);
+        }
+
+        for (file, attempt) in compilation.ctx.files.iter() {
+            if let LoadAttempt::Loaded(loaded) = attempt {
+                let source = self.source(file);
+                for (path, span) in includes(loaded) {
+                    let missing = compilation.ctx.errors.iter().any(|error| match error {
+                        CompileError::UnknownFileIncludeError(error) => normalize_path(&error.invalid_file) == normalize_path(&path),
+                        _ => false
+                    });
+                    if missing {
+                        let message = format!("cannot read {:?}", path);
+                        by_file.entry(normalize_path(file)).or_default().push(diagnostic(&source, span, DiagnosticSeverity::ERROR, Some("E201"), message));
+                    }
+                }
+            }
+        }
+        // syntax errors carry no location, the file is parsed again for the one of pest
+        for error in compilation.ctx.errors.iter() {
+            if let CompileError::SyntaxError(error) = error {
+                let source = self.source(&error.file_error);
+                if let Err(parsing) = ast::parse_source(&source, None) {
+                    let span = match parsing.location {
+                        pest::error::InputLocation::Pos(offset) => (offset, offset),
+                        pest::error::InputLocation::Span(span) => span
+                    };
+                    let message = parsing.variant.message().to_string();
+                    by_file.entry(normalize_path(&error.file_error)).or_default().push(diagnostic(&source, span, DiagnosticSeverity::ERROR, Some("E101"), message));
+                }
+            }
+        }
+
+        let mut errors = vec![];
+        for error in compilation.ctx.errors.iter() {
+            if let CompileError::DuplicateSymbolError(error) = error {
+                let message = format!("`{}` is already defined in {:?}", error.symbol, error.first_file);
+                errors.push((error.file_error.clone(), error.span_error, "E301".to_string(), message));
+            }
+        }
+        if by_file.values().all(|diagnostics| diagnostics.is_empty()) {
+            for error in semantic::check_program(&compilation.program, &compilation.table) {
+                if let CompileError::SemanticError(error) = error {
+                    errors.push((error.file_error.clone(), error.span_error, format!("E{}", error.kind.code()), error.kind.to_string()));
+                }
+            }
+        }
+        for (file, span, code, message) in errors.iter() {
+            let source = self.source(file);
+            by_file.entry(normalize_path(file)).or_default().push(diagnostic(&source, *span, DiagnosticSeverity::ERROR, Some(code), message.clone()));
+        }
+
+        if by_file.values().all(|diagnostics| diagnostics.is_empty()) && compilation.program.main_component.is_some() {
+            // a lint is reported once per instance of its template, the editor shows it once. Included
+            // libraries are not linted, most of their templates are never instantiated by a given main
+            let mut seen = HashSet::new();
+            for finding in analysis::analyze(&compilation.program, &lints::LintConfig::default()) {
+                if normalize_path(&finding.file) != normalize_path(path) || !seen.insert((finding.file.clone(), finding.span, finding.lint, finding.subject.clone())) {
+                    continue;
+                }
+                let severity = match finding.severity {
+                    analysis::Severity::Warning => DiagnosticSeverity::WARNING,
+                    analysis::Severity::Error => DiagnosticSeverity::ERROR
+                };
+                let message = match &finding.help {
+                    Some(help) => format!("{}\n{}", finding.message, help),
+                    None => finding.message.clone()
+                };
+                let source = self.source(&finding.file);
+                by_file.entry(normalize_path(&finding.file)).or_default().push(diagnostic(&source, finding.span, severity, Some(finding.lint), message));
+            }
+        }
+
+        for file in self.published.iter() {
+            by_file.entry(file.clone()).or_default();
+        }
+        self.published = by_file.iter().filter(|(_, diagnostics)| !diagnostics.is_empty()).map(|(file, _)| file.clone()).collect();
+        let mut diagnostics: Vec<(PathBuf, Vec<lsp_types::Diagnostic>)> = by_file.into_iter().collect();
+        diagnostics.sort_by(|a, b| a.0.cmp(&b.0));
+        diagnostics
+    }
+
+    ///
+    /// Where the template or function named at `position` is defined, or the file of the include at `position`.
+    ///
+    pub fn definition(&self, path: & Path, position: Position) -> Option<Location> {
+        let source = self.source(path);
+        let offset = offset(&source, position);
+        if let Some(included) = include_at(&source, offset) {
+            let included = normalize_path(&path.parent()?.join(included));
+            return Some(Location { uri: Url::from_file_path(&included).ok()?, range: Range::default() });
+        }
+        let compilation = self.compile_around(path, offset);
+        let (name, _) = word_at(&source, offset)?;
+        let symbol = compilation.table.lookup(name)?;
+        let target = self.source(&symbol.file);
+        Some(Location { uri: Url::from_file_path(normalize_path(&symbol.file)).ok()?, range: range(&target, symbol.name_span) })
+    }
+
+    ///
+    /// The signature of the template or function named at `position`, or the declaration of the signal, of
+    /// the enclosing template or of one of its sub-components (`isEqual.out`).
+    ///
+    pub fn hover(&self, path: & Path, position: Position) -> Option<Hover> {
+        let source = self.source(path);
+        let offset = offset(&source, position);
+        let compilation = self.compile_around(path, offset);
+        let (name, span) = word_at(&source, offset)?;
+
+        let value = match (member_of(&source, span.0), enclosing(&compilation.program, path, offset)) {
+            (Some(component), Some(template)) => {
+                let signal = interface(component_template(&compilation.program, template, component)?).find(|signal| signal.name == name)?;
+                format!("```circom\n{}\n```\n`{}` of component `{}`", declaration(signal), signal.name, component)
+            },
+            (None, template) => match compilation.table.lookup(name) {
+                Some(symbol) => format!("```circom\n{}\n```", signature(symbol, compilation.program.templates.get(name))),
+                None => {
+                    let signal = signals(template?).find(|signal| signal.name == name)?;
+                    format!("```circom\n{}\n```\nin template `{}`", declaration(signal), template?.name)
+                }
+            },
+            _ => return None
+        };
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value }),
+            range: Some(range(&source, span))
+        })
+    }
+
+    ///
+    /// After `component.`, the inputs and outputs of the component's template. Anywhere else, every template
+    /// and function of the program.
+    ///
+    pub fn completion(&self, path: & Path, position: Position) -> Vec<CompletionItem> {
+        let source = self.source(path);
+        let offset = offset(&source, position);
+        let compilation = self.compile_around(path, offset);
+        let start = source[..offset].rfind(|c: char| !is_identifier(c)).map(|idx| idx + 1).unwrap_or(0);
+
+        if let Some(component) = member_of(&source, start) {
+            let template = enclosing(&compilation.program, path, offset)
+                .and_then(|template| component_template(&compilation.program, template, component));
+            return match template {
+                Some(template) => interface(template).map(|signal| CompletionItem {
+                    label: signal.name.clone(),
+                    kind: Some(CompletionItemKind::FIELD),
+                    detail: Some(declaration(signal)),
+                    ..CompletionItem::default()
+                }).collect(),
+                None => vec![]
+            };
+        }
+
+        compilation.table.symbols.iter().map(|symbol| CompletionItem {
+            label: symbol.name.clone(),
+            kind: Some(match symbol.kind {
+                SymbolKind::Template => CompletionItemKind::CLASS,
+                SymbolKind::Function => CompletionItemKind::FUNCTION
+            }),
+            detail: Some(signature(symbol, None)),
+            ..CompletionItem::default()
+        }).collect()
+    }
+
+    ///
+    /// Templates and functions of the document, templates with their signals and sub-components as children.
+    ///
+    #[allow(deprecated)]
+    pub fn symbols(&self, path: & Path) -> Vec<DocumentSymbol> {
+        let source = self.source(path);
+        let compilation = self.compile(path, self.documents.clone());
+        compilation.table.in_file(path).map(|symbol| {
+            let children = compilation.program.templates.get(&symbol.name).filter(|_| symbol.kind == SymbolKind::Template).map(|template| {
+                let mut children: Vec<DocumentSymbol> = signals(template).map(|signal| DocumentSymbol {
+                    name: signal.name.clone(),
+                    detail: Some(declaration(signal)),
+                    kind: LspSymbolKind::FIELD,
+                    tags: None,
+                    deprecated: None,
+                    range: range(&source, signal.span),
+                    selection_range: range(&source, signal.span),
+                    children: None
+                }).collect();
+                children.extend(template.components.iter().map(|component| {
+                    let span = component_span(&template.instructions, &component.name).unwrap_or(symbol.name_span);
+                    DocumentSymbol {
+                        name: component.name.clone(),
+                        detail: Some(component.template_to_use.clone()),
+                        kind: LspSymbolKind::OBJECT,
+                        tags: None,
+                        deprecated: None,
+                        range: range(&source, span),
+                        selection_range: range(&source, span),
+                        children: None
+                    }
+                }));
+                children
+            });
+            DocumentSymbol {
+                name: symbol.name.clone(),
+                detail: Some(signature(symbol, None)),
+                kind: match symbol.kind {
+                    SymbolKind::Template => LspSymbolKind::CLASS,
+                    SymbolKind::Function => LspSymbolKind::FUNCTION
+                },
+                tags: None,
+                deprecated: None,
+                range: range(&source, symbol.span),
+                selection_range: range(&source, symbol.name_span),
+                children
+            }
+        }).collect()
+    }
+
+}
+
+fn signals(template: & Template) -> impl Iterator<Item = &Signal> {
+    template.private_input_signals.iter().chain(template.output_signals.iter()).chain(template.intermediate_signals.iter())
+}
+
+// what a parent template can access of an instance
+fn interface(template: & Template) -> impl Iterator<Item = &Signal> {
+    template.private_input_signals.iter().chain(template.output_signals.iter())
+}
+
+// the declaration of a component, in loops and branches too
+fn component_span(commands: & [SingleCommand], name: & str) -> Option<(usize, usize)> {
+    commands.iter().find_map(|command| match command {
+        SingleCommand::DeclarationStatement(declaration) if declaration.decl_type == DeclType::Component && declaration.name == name => Some(declaration.span),
+        SingleCommand::ForLoop(for_loop) => component_span(&for_loop.body, name),
+        SingleCommand::WhileLoop(while_loop) => component_span(&while_loop.body, name),
+        SingleCommand::IfStatement(statement) => statement.branches.iter().find_map(|branch| component_span(&branch.body, name))
+            .or_else(|| component_span(&statement.else_body, name)),
+        _ => None
+    })
+}
+
+fn enclosing<'a>(program: &'a Program, path: & Path, offset: usize) -> Option<&'a Template> {
+    let path = normalize_path(path);
+    program.templates.values().find(|template| {
+        normalize_path(Path::new(&template.path)) == path && template.span.0 <= offset && offset <= template.span.1
+    })
+}
+
+fn component_template<'a>(program: &'a Program, template: & Template, component: & str) -> Option<&'a Template> {
+    let component = template.components.iter().find(|candidate| candidate.name == component)?;
+    program.templates.get(&component.template_to_use)
+}
+
+fn declaration(signal: & Signal) -> String {
+    let direction = match signal.direction {
+        SignalDirection::Input => "input ",
+        SignalDirection::Output => "output ",
+        SignalDirection::Intermediate => ""
+    };
+    let dimensions: String = signal.size_per_dimension.iter().map(|size| format!("[{}]", size)).collect();
+    format!("signal {}{}{}", direction, signal.name, dimensions)
+}
+
+fn signature(symbol: & Symbol, template: Option<& Template>) -> String {
+    let keyword = match symbol.kind {
+        SymbolKind::Template => "template",
+        SymbolKind::Function => "function"
+    };
+    let mut signature = format!("{} {}({})", keyword, symbol.name, symbol.params.join(", "));
+    if let Some(template) = template {
+        for signal in interface(template) {
+            signature.push_str(&format!("\n    {}", declaration(signal)));
+        }
+    }
+    signature
+}
+
+fn includes(file: & ast::File) -> Vec<(PathBuf, (usize, usize))> {
+    let directory = file.path.parent().map(Path::to_path_buf).unwrap_or_default();
+    match file.root.ast.first() {
+        Some(Token::NonTerminal(circuit)) => circuit.subrules.iter().filter_map(|token| match token {
+            Token::NonTerminal(statement) if statement.rule == ast::Rule::IncludeStatement => {
+                let name = crate::parser::matchers::include_statement::process_include_statement(statement);
+                Some((directory.join(name), statement.span))
+            },
+            _ => None
+        }).collect(),
+        _ => vec![]
+    }
+}
+
+// the path of the include statement of the line at `offset`
+fn include_at(source: & str, offset: usize) -> Option<& str> {
+    let (start, end) = line_bounds(source, offset);
+    let line = source[start..end].trim().strip_prefix("include")?;
+    let line = line.trim_start().strip_prefix('"')?;
+    line.split('"').next()
+}
+
+// the identifier before `.` right before `start`, as `isEqual` for `isEqual.out` or `isEqual[i].out`
+fn member_of(source: & str, start: usize) -> Option<& str> {
+    let mut before = source[..start].strip_suffix('.')?;
+    while let Some(indexed) = before.strip_suffix(']') {
+        before = &indexed[..indexed.rfind('[')?];
+    }
+    let name_start = before.rfind(|c: char| !is_identifier(c)).map(|idx| idx + 1).unwrap_or(0);
+    Some(&before[name_start..]).filter(|name| !name.is_empty())
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn word_at(source: & str, offset: usize) -> Option<(& str, (usize, usize))> {
+    // past the separator, which can take more than one byte
+    let start = source[..offset].char_indices().rev().find(|(_, c)| !is_identifier(*c)).map(|(idx, c)| idx + c.len_utf8()).unwrap_or(0);
+    let end = source[offset..].find(|c: char| !is_identifier(c)).map(|idx| idx + offset).unwrap_or(source.len());
+    Some((&source[start..end], (start, end))).filter(|(word, _)| !word.is_empty())
+}
+
+fn line_bounds(source: & str, offset: usize) -> (usize, usize) {
+    let start = source[..offset].rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    let end = source[offset..].find('\n').map(|idx| idx + offset).unwrap_or(source.len());
+    (start, end)
+}
+
+///
+/// Byte offset of an LSP position, its character counted in UTF-16 code units. Past the end of a line or of
+/// the source, the end of it.
+///
+pub fn offset(source: & str, position: Position) -> usize {
+    let mut start = 0;
+    for _ in 0..position.line {
+        match source[start..].find('\n') {
+            Some(idx) => start += idx + 1,
+            None => return source.len()
+        }
+    }
+    let mut units = 0;
+    for (idx, c) in source[start..].char_indices() {
+        if c == '\n' || units >= position.character as usize {
+            return start + idx;
+        }
+        units += c.len_utf16();
+    }
+    source.len()
+}
+
+pub fn position(source: & str, offset: usize) -> Position {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].chars().map(char::len_utf16).sum::<usize>() as u32
+    }
+}
+
+fn range(source: & str, span: (usize, usize)) -> Range {
+    Range { start: position(source, span.0), end: position(source, span.1) }
+}
+
+fn diagnostic(source: & str, span: (usize, usize), severity: DiagnosticSeverity, code: Option<& str>, message: String) -> lsp_types::Diagnostic {
+    lsp_types::Diagnostic {
+        range: range(source, span),
+        severity: Some(severity),
+        code: code.map(|code| NumberOrString::String(code.to_string())),
+        source: Some(SOURCE.to_string()),
+        message,
+        ..lsp_types::Diagnostic::default()
+    }
+}
+
+fn document(params: & serde_json::Value) -> Option<PathBuf> {
+    Url::parse(params["textDocument"]["uri"].as_str()?).ok()?.to_file_path().ok()
+}
+
+fn cursor(params: & serde_json::Value) -> Option<(PathBuf, Position)> {
+    Some((document(params)?, serde_json::from_value(params["position"].clone()).ok()?))
+}
+
+fn publish(connection: & Connection, server: &mut Server, path: & Path) -> Result<(), Box<dyn Error + Sync + Send>> {
+    for (file, diagnostics) in server.diagnostics(path) {
+        let uri = match Url::from_file_path(&file) {
+            Ok(uri) => uri,
+            Err(_) => continue
+        };
+        let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+        connection.sender.send(Message::Notification(Notification::new("textDocument/publishDiagnostics".to_string(), params)))?;
+    }
+    Ok(())
+}
+
+fn respond(connection: & Connection, server: & Server, request: Request) -> Result<(), Box<dyn Error + Sync + Send>> {
+    let params = &request.params;
+    let result = match request.method.as_str() {
+        "textDocument/definition" => json!(cursor(params).and_then(|(path, position)| server.definition(&path, position))),
+        "textDocument/hover" => json!(cursor(params).and_then(|(path, position)| server.hover(&path, position))),
+        "textDocument/completion" => json!(cursor(params).map(|(path, position)| server.completion(&path, position)).unwrap_or_default()),
+        "textDocument/documentSymbol" => json!(document(params).map(|path| server.symbols(&path)).unwrap_or_default()),
+        method => {
+            let response = Response::new_err(request.id, lsp_server::ErrorCode::MethodNotFound as i32, format!("unsupported request `{}`", method));
+            connection.sender.send(Message::Response(response))?;
+            return Ok(());
+        }
+    };
+    connection.sender.send(Message::Response(Response::new_ok(request.id, result)))?;
+    Ok(())
+}
+
+///
+/// Serves a client on `connection` until it shuts down. Documents are synced in full, diagnostics are
+/// published when a document is opened or saved.
+///
+pub fn run(connection: & Connection, base: & dyn SourceProvider) -> Result<(), Box<dyn Error + Sync + Send>> {
+    let capabilities = json!({
+        "textDocumentSync": {
+            "openClose": true,
+            "change": TextDocumentSyncKind::FULL,
+            "save": true
+        },
+        "definitionProvider": true,
+        "hoverProvider": true,
+        "completionProvider": { "triggerCharacters": ["."] },
+        "documentSymbolProvider": true
+    });
+    connection.initialize(capabilities)?;
+
+    let mut server = Server::new(base);
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                respond(connection, &server, request)?;
+            },
+            Message::Notification(notification) => {
+                let params = &notification.params;
+                let path = match document(params) {
+                    Some(path) => path,
+                    None => continue
+                };
+                match notification.method.as_str() {
+                    "textDocument/didOpen" => {
+                        server.open(&path, params["textDocument"]["text"].as_str().unwrap_or(""));
+                        publish(connection, &mut server, &path)?;
+                    },
+                    "textDocument/didChange" => {
+                        if let Some(text) = params["contentChanges"].as_array().and_then(|changes| changes.last()).and_then(|change| change["text"].as_str()) {
+                            server.open(&path, text);
+                        }
+                    },
+                    "textDocument/didSave" => publish(connection, &mut server, &path)?,
+                    "textDocument/didClose" => server.close(&path),
+                    _ => {}
+                }
+            },
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod lsp_test {
+
+    use std::path::Path;
+
+    use lsp_types::{DiagnosticSeverity, HoverContents, Position};
+
+    use crate::lsp::{self, Server};
+    use crate::parser::source::MemorySource;
+
+    use galvanic_assert::matchers::*;
+
+    const COMPARATORS: &str = "template IsZero() {\n    signal input in;\n    signal output out;\n    signal inv;\n    inv <-- in != 0 ? 1 / in : 0;\n    out <== -in * inv + 1;\n    in * out === 0;\n}\ntemplate IsEqual() {\n    signal input in[2];\n    signal output out;\n    component isz = IsZero();\n    isz.in <== in[1] - in[0];\n    out <== isz.out;\n}\n";
+    const MAIN: &str = "include \"comparators.circom\";\n\ntemplate Main() {\n    signal input a;\n    signal input b;\n    signal output eq;\n    component isEqual = IsEqual();\n    isEqual.in[0] <== a;\n    isEqual.in[1] <== b;\n    eq <== isEqual.out;\n}\ncomponent main = Main();\n";
+
+    fn base() -> MemorySource {
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/comparators.circom", COMPARATORS);
+        sources.insert("/virtual/main.circom", MAIN);
+        sources
+    }
+
+    fn at(line: u32, character: u32) -> Position {
+        Position { line, character }
+    }
+
+    #[test]
+    fn test_diagnostics() {
+
+        let base = base();
+        let mut server = Server::new(&base);
+        let main = Path::new("/virtual/main.circom");
+        let clean: Vec<usize> = server.diagnostics(main).iter().map(|(_, diagnostics)| diagnostics.len()).collect();
+        expect_that!(&clean, eq(vec![0, 0]));
+
+        // an unsaved buffer shadows the file, a missing `;` is reported where the grammar expected it, on the next line
+        server.open(main, &MAIN.replace("signal input b;", "signal input b"));
+        let diagnostics = server.diagnostics(main);
+        let broken: Vec<(String, u32, Option<DiagnosticSeverity>)> = diagnostics.iter()
+            .flat_map(|(file, diagnostics)| diagnostics.iter().map(move |diagnostic| (file.to_string_lossy().to_string(), diagnostic.range.start.line, diagnostic.severity)))
+            .collect();
+        expect_that!(&broken, eq(vec![("/virtual/main.circom".to_string(), 5, Some(DiagnosticSeverity::ERROR))]));
+
+        server.open(main, &MAIN.replace("isEqual.in[1] <== b;", "isEqual.in[1] <== c;").replace("comparators", "missing"));
+        let diagnostics = server.diagnostics(main);
+        let messages: Vec<(u32, String)> = diagnostics.iter().flat_map(|(_, diagnostics)| diagnostics.iter().map(|diagnostic| (diagnostic.range.start.line, diagnostic.message.clone()))).collect();
+        expect_that!(&messages, eq(vec![(0, "cannot read \"/virtual/missing.circom\"".to_string())]));
+
+        server.open(main, &MAIN.replace("isEqual.in[1] <== b;", "isEqual.in[1] <== c;"));
+        let diagnostics = server.diagnostics(main);
+        let messages: Vec<(String, u32, String)> = diagnostics.iter()
+            .flat_map(|(file, diagnostics)| diagnostics.iter().map(move |diagnostic| (file.to_string_lossy().to_string(), diagnostic.range.start.line, diagnostic.message.clone())))
+            .collect();
+        expect_that!(&messages, eq(vec![("/virtual/main.circom".to_string(), 8, "`c` is used before being declared".to_string())]));
+
+        // a statement the file grammar accepts but the executor cannot read
+        server.open(main, &MAIN.replace("isEqual.in[1] <== b;", "isEqual.in[1] <== b.c.d;"));
+        let diagnostics = server.diagnostics(main);
+        let messages: Vec<(u32, String)> = diagnostics.iter().flat_map(|(_, diagnostics)| diagnostics.iter().map(|diagnostic| (diagnostic.range.start.line, diagnostic.message.clone()))).collect();
+        expect_that!(&messages, eq(vec![(8, "cannot read `isEqual.in[1] <== b.c.d` at `.d`".to_string())]));
+
+        server.close(main);
+        let fixed: Vec<usize> = server.diagnostics(main).iter().map(|(_, diagnostics)| diagnostics.len()).collect();
+        expect_that!(&fixed, eq(vec![0, 0]));
+
+    }
+
+    #[test]
+    fn test_large_literals() {
+
+        let base = base();
+        let mut server = Server::new(&base);
+        let large = Path::new("/virtual/large.circom");
+
+        // literals past 32 bits are field elements, reading them never panics
+        server.open(large, "template Main(){ signal input a; signal output b; var p = 4294967296; b <== a * p; } component main = Main();");
+        let diagnostics: Vec<usize> = server.diagnostics(large).iter().map(|(_, diagnostics)| diagnostics.len()).collect();
+        expect_that!(&diagnostics, eq(vec![0]));
+
+        server.open(large, "template Main(n) {\n    signal input a;\n    signal output b;\n    b <== a * n * 0x1000000000000000000000000;\n}\ncomponent main = Main(21888242871839275222246405745257275088548364400416034343698204186575808495617);\n");
+        let diagnostics: Vec<usize> = server.diagnostics(large).iter().map(|(_, diagnostics)| diagnostics.len()).collect();
+        expect_that!(&diagnostics, eq(vec![0]));
+        let hover = match server.hover(large, at(2, 19)).map(|hover| hover.contents) {
+            Some(HoverContents::Markup(markup)) => Some(markup.value),
+            _ => None
+        };
+        expect_that!(&hover, eq(Some("```circom\nsignal output b\n```\nin template `Main`".to_string())));
+
+    }
+
+    #[test]
+    fn test_navigation() {
+
+        let base = base();
+        let mut server = Server::new(&base);
+        let main = Path::new("/virtual/main.circom");
+
+        let definition = server.definition(main, at(6, 27)).unwrap();
+        expect_that!(&(definition.uri.path().to_string(), definition.range.start), eq(("/virtual/comparators.circom".to_string(), at(8, 9))));
+        let include = server.definition(main, at(0, 12)).unwrap();
+        expect_that!(&include.uri.path().to_string(), eq("/virtual/comparators.circom".to_string()));
+
+        let hover = |position: Position| match server.hover(main, position).map(|hover| hover.contents) {
+            Some(HoverContents::Markup(markup)) => Some(markup.value),
+            _ => None
+        };
+        expect_that!(&hover(at(6, 27)), eq(Some("```circom\ntemplate IsEqual()\n    signal input in[2]\n    signal output out\n```".to_string())));
+        expect_that!(&hover(at(9, 20)), eq(Some("```circom\nsignal output out\n```\n`out` of component `isEqual`".to_string())));
+        expect_that!(&hover(at(9, 5)), eq(Some("```circom\nsignal output eq\n```\nin template `Main`".to_string())));
+        expect_that!(&hover(at(1, 0)), eq(None));
+
+        // the line being typed does not parse, the rest of the document still resolves
+        server.open(main, &MAIN.replace("    eq <== isEqual.out;", "    isEqual."));
+        let labels: Vec<String> = server.completion(main, at(9, 12)).into_iter().map(|item| item.label).collect();
+        expect_that!(&labels, eq(vec!["in".to_string(), "out".to_string()]));
+        let labels: Vec<String> = server.completion(main, at(9, 4)).into_iter().map(|item| item.label).collect();
+        expect_that!(&labels, eq(vec!["Main".to_string(), "IsZero".to_string(), "IsEqual".to_string()]));
+
+        server.open(main, MAIN);
+        let outline: Vec<(String, Vec<String>)> = server.symbols(Path::new("/virtual/comparators.circom")).into_iter()
+            .map(|symbol| (symbol.name, symbol.children.unwrap_or_default().into_iter().map(|child| format!("{}: {}", child.name, child.detail.unwrap_or_default())).collect()))
+            .collect();
+        expect_that!(&outline, eq(vec![
+            ("IsZero".to_string(), vec!["in: signal input in".to_string(), "out: signal output out".to_string(), "inv: signal inv".to_string()]),
+            ("IsEqual".to_string(), vec!["in: signal input in[2]".to_string(), "out: signal output out".to_string(), "isz: IsZero".to_string()])
+        ]));
+
+        let position = lsp::position("a\u{1F600}b\ncd", 6);
+        expect_that!(&(position, lsp::offset("a\u{1F600}b\ncd", position)), eq((at(0, 4), 6)));
+        // a word right after a separator of more than one byte
+        expect_that!(&lsp::word_at("a\u{2192}bc", 5), eq(Some(("bc", (4, 6)))));
+
+    }
+}
@@ -1,5 +1,7 @@
 use pest::Parser;
 
+use crate::parser::source;
+
 #[derive(Parser)]
 #[grammar = "lib/parser/grammar.pest"]
 struct CircuitParser;
@@ -51,7 +53,8 @@ pub mod tokens {
 #[derive(Debug)]
 pub enum ParseError {
     IOError(std::io::Error),
-    PestError(pest::error::Error<Rule>)
+    // boxed, a pest error is large and every `Result` carrying it would be as large
+    PestError(Box<pest::error::Error<Rule>>)
 }
 
 ///
@@ -117,7 +120,7 @@ pub fn pest_to_tokens(source: & str, pairs: pest::iterators::Pairs<Rule>) -> tok
 ///
 /// Takes a source buffer as input and returns the converted AST.
 ///
-pub fn parse_source(sources: & str, maybe_rule: Option<Rule>) -> std::result::Result<tokens::RootToken, pest::error::Error<Rule>> {
+pub fn parse_source(sources: & str, maybe_rule: Option<Rule>) -> std::result::Result<tokens::RootToken, Box<pest::error::Error<Rule>>> {
     match
 
         if let Some(rule) = maybe_rule {
@@ -127,7 +130,7 @@ pub fn parse_source(sources: & str, maybe_rule: Option<Rule>) -> std::result::Re
         }
         {
             Ok(ast) => Ok(pest_to_tokens(sources, ast)),
-            Err(error) => Err(error)
+            Err(error) => Err(Box::new(error))
         }
 }
 
@@ -136,8 +139,17 @@ pub fn parse_source(sources: & str, maybe_rule: Option<Rule>) -> std::result::Re
 ///
 pub fn parse_file(path: & std::path::PathBuf) -> Result<File, ParseError> {
 
+    parse_file_from_source(path, &source::DiskSource)
+
+}
+
+///
+/// Same as `parse_file`, but the content is read through the given `SourceProvider` instead of the disk.
+///
+pub fn parse_file_from_source(path: & std::path::PathBuf, sources: & dyn source::SourceProvider) -> Result<File, ParseError> {
+
     let path = std::path::PathBuf::from(path);
-    let content = match std::fs::read_to_string(&path) {
+    let content = match sources.read_source(&path) {
         Ok(val) => val,
         Err(error) => return Err(ParseError::IOError(error))
     };
@@ -158,9 +170,9 @@ mod parser_tests {
 
     use galvanic_assert::matchers::*;
 
-    use parser::ast;
+    use crate::parser::ast;
     use pest::error::{LineColLocation, ErrorVariant};
-    use parser::ast::{parse_file};
+    use crate::parser::ast::{parse_file};
 
     fn test_untupler(expect_rules_values: &Vec<(ast::Rule, u32)>) -> Vec<ast::Rule> {
 
@@ -221,7 +233,7 @@ mod parser_tests {
     fn complete_parse() {
 
         let pathbuf: std::path::PathBuf = match std::fs::canonicalize("./src/lib/parser/test_material/circuits/bitify.circom") {
-            Err(error) => panic!(error),
+            Err(error) => panic!("{}", error),
             Ok(path) => path
         };
 
@@ -247,7 +259,7 @@ mod parser_tests {
 
             match ast::parse_source(&contents, Some(ast::Rule::Circuit)) {
                 Ok(_pairs) => {},
-                Err(err) => panic!(err)
+                Err(err) => panic!("{}", err)
             }
 
         }
@@ -463,7 +475,8 @@ mod parser_tests {
             vec!(
                 (ast::Rule::E_0_CommaOperator, 1),
                 (ast::Rule::E_Brackets, 0),
-                (ast::Rule::E_20_BracedOperatorOpen, 8),
+                // the indexes right after a name belong to `E_ComplexVariableName`, not to a braced operator
+                (ast::Rule::E_20_BracedOperatorOpen, 5),
                 (ast::Rule::Expression, 10)
             ),
 
@@ -498,7 +511,7 @@ mod parser_tests {
                     println!("----------");
 
                 },
-                Err(err) => panic!(err)
+                Err(err) => panic!("{}", err)
             }
 
         }
@@ -870,7 +883,7 @@ mod parser_tests {
                     }
                     println!("----------");
                 },
-                Err(err) => panic!(err)
+                Err(err) => panic!("{}", err)
             }
 
         }
@@ -1,6 +1,7 @@
 use crate::parser::ast;
 use crate::parser::matchers;
 use crate::parser::errors;
+use crate::parser::source;
 
 #[derive(Debug)]
 pub enum LoadAttempt {
@@ -33,16 +34,13 @@ fn context_gather_includes(file: & mut ast::File) {
 
     if let ast::tokens::Token::NonTerminal(circuit) = &file.root.ast[0] {
         for token in &circuit.subrules {
-            match token {
-                ast::tokens::Token::NonTerminal(nt) => {
-                    if nt.rule == ast::Rule::IncludeStatement {
-                        let file_name = matchers::include_statement::process_include_statement(nt);
-                        let mut include_absolute_path = current_file_dir.clone();
-                        include_absolute_path.push(file_name);
-                        includes.push(include_absolute_path);
-                    }
-                },
-                _ => {}
+            if let ast::tokens::Token::NonTerminal(nt) = token {
+                if nt.rule == ast::Rule::IncludeStatement {
+                    let file_name = matchers::include_statement::process_include_statement(nt);
+                    let mut include_absolute_path = current_file_dir.clone();
+                    include_absolute_path.push(file_name);
+                    includes.push(include_absolute_path);
+                }
             }
         }
     } else {
@@ -70,13 +68,13 @@ fn context_load_file_error(error: ast::ParseError, ctx: & mut Context, file_path
 
 }
 
-fn context_load_file(ctx: & mut Context, file_path: & std::path::PathBuf) -> Option<ast::File> {
+fn context_load_file(ctx: & mut Context, sources: & dyn source::SourceProvider, file_path: & std::path::PathBuf) -> Option<ast::File> {
 
     if ctx.include_stack.contains(file_path) {
         return None;
     }
 
-    let res = match ast::parse_file(file_path) {
+    let res = match ast::parse_file_from_source(file_path, sources) {
 
         Ok(mut file) => {
             context_load_file_success(& mut file);
@@ -93,13 +91,10 @@ fn context_load_file(ctx: & mut Context, file_path: & std::path::PathBuf) -> Opt
     ctx.include_stack.push(file_path.clone());
 
     for include in &res.includes {
-        match context_load_file(ctx, include) {
-            Some(file) => {
-                let path = file.path.clone();
+        if let Some(file) = context_load_file(ctx, sources, include) {
+            let path = file.path.clone();
 
-                ctx.files.insert(path, LoadAttempt::Loaded(file));
-            },
-            None => {}
+            ctx.files.insert(path, LoadAttempt::Loaded(file));
         }
     }
 
@@ -117,6 +112,18 @@ fn context_load_file(ctx: & mut Context, file_path: & std::path::PathBuf) -> Opt
 ///
 pub fn build_context(file: & std::path::PathBuf) -> Context {
 
+    build_context_from_source(file, &source::DiskSource)
+
+}
+
+///
+/// Same as `build_context`, but every file (main and includes) is read through the given `SourceProvider`.
+///
+/// This is what lets the server compile unsaved editor buffers layered over the disk, and tests build a
+/// context from in-memory sources.
+///
+pub fn build_context_from_source(file: & std::path::PathBuf, sources: & dyn source::SourceProvider) -> Context {
+
     let mut ctx = Context {
         files: std::collections::HashMap::new(),
         errors: Vec::new(),
@@ -126,7 +133,7 @@ pub fn build_context(file: & std::path::PathBuf) -> Context {
 
     ctx.files.insert(file.clone(), LoadAttempt::Loading());
 
-    let main: LoadAttempt = match context_load_file(& mut ctx, file) {
+    let main: LoadAttempt = match context_load_file(& mut ctx, sources, file) {
         Some(file) => LoadAttempt::Loaded(file),
         None => LoadAttempt::Error()
     };
@@ -140,7 +147,8 @@ pub fn build_context(file: & std::path::PathBuf) -> Context {
 #[cfg(test)]
 mod compile_test {
 
-    use parser::compile;
+    use crate::parser::compile;
+    use crate::parser::source::{DiskSource, MemorySource, OverlaySource};
 
     use galvanic_assert::matchers::*;
 
@@ -169,4 +177,34 @@ mod compile_test {
 
 
     }
+
+    #[test]
+    fn test_build_context_from_memory() {
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", "include \"lib/gates.circom\";\ncomponent main = Gate();\n");
+        sources.insert("/virtual/lib/gates.circom", "template Gate() {\n    signal input a;\n}\n");
+
+        let ctx = compile::build_context_from_source(&std::path::PathBuf::from("/virtual/main.circom"), &sources);
+
+        expect_that!(&ctx.errors.len(), is(eq(0)));
+        expect_that!(&ctx.files.keys().len(), is(eq(2)));
+
+    }
+
+    #[test]
+    fn test_build_context_overlay_shadows_disk() {
+
+        let path = std::fs::canonicalize("./src/lib/parser/test_material/circuits/bitify.circom").expect("Invalid Path");
+
+        let mut buffers = MemorySource::new();
+        buffers.insert(&path, "template Unsaved() {\n    signal input a;\n}\n");
+        let sources = OverlaySource::new(&DiskSource, buffers);
+
+        let ctx = compile::build_context_from_source(&path, &sources);
+
+        expect_that!(&ctx.errors.len(), is(eq(0)));
+        expect_that!(&ctx.files.keys().len(), is(eq(1)));
+
+    }
 }
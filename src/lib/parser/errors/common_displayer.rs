@@ -3,3 +3,12 @@ use std::fmt;
 pub fn common_displayer(formatter: &mut fmt::Formatter, name: & str, code: & usize, source: & std::string::String, _span: & (usize, usize), file: & std::path::PathBuf) -> fmt::Result {
     write!(formatter, "{}[E{}]:\nin {:?}\n\n\t{}\n\n", name, code, file, source)
 }
+
+///
+/// Returns the full source line(s) covered by `span`, used as the `source_error` of located errors.
+///
+pub fn source_line(source: & str, span: & (usize, usize)) -> std::string::String {
+    let start = source[..span.0.min(source.len())].rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    let end = source[span.1.min(source.len())..].find('\n').map(|idx| idx + span.1).unwrap_or(source.len());
+    source[start..end].trim().to_string()
+}
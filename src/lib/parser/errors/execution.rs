@@ -0,0 +1,151 @@
+use std::fmt;
+use crate::parser::errors::common_displayer;
+
+///
+/// What `execution::Limits` bounds.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Statements,
+    Depth,
+    Recursion,
+    ArraySize
+}
+
+///
+/// Ways a circuit can fail while it is executed, once the static checks passed. Each kind has its own error
+/// code in the E5xx range.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionErrorKind {
+    /// E501: an index, size, condition or template parameter depends on a signal, its value is not known
+    /// while the constraints are generated.
+    UnknownValue(std::string::String),
+    /// E502: a name that is not declared at this point of the execution.
+    UndeclaredName(std::string::String),
+    /// E503: an array is accessed outside of its bounds, or with the wrong number of indices.
+    IndexOutOfBounds { name: std::string::String, indices: std::string::String },
+    /// E504: the two sides of a constraint differ by more than a quadratic expression.
+    NonQuadratic(std::string::String),
+    /// E505: a signal receives a value a second time.
+    SignalAssignedTwice(std::string::String),
+    /// E506: a component is used before `c = Template(...)` ran for it.
+    ComponentNotInstantiated(std::string::String),
+    /// E507: an `assert` that does not hold.
+    AssertionFailed(std::string::String),
+    /// E508: a division or modulo by zero.
+    DivisionByZero(std::string::String),
+    /// E509: a construct the interpreter does not execute.
+    Unsupported(std::string::String),
+    /// E510: a sub-component signal its template does not declare, or an input assigned after the
+    /// sub-component already ran.
+    InvalidComponentSignal { component: std::string::String, signal: std::string::String },
+    /// E511: a sub-component with inputs none of which is assigned, it never runs and its constraints
+    /// would be missing.
+    UnassignedInput { component: std::string::String, signal: std::string::String },
+    /// E512: an input of main without a value in the inputs given to the witness generation.
+    MissingInput(std::string::String),
+    /// E513: a value given for a name that is not an input of main.
+    UnknownInput(std::string::String),
+    /// E514: the debugger driving the execution went away before the end.
+    Interrupted,
+    /// E515: the execution went past one of its limits, as a loop that never ends or a template that
+    /// instantiates itself without end would.
+    LimitExceeded { limit: Limit, maximum: usize }
+}
+
+impl ExecutionErrorKind {
+
+    pub fn code(&self) -> usize {
+        match self {
+            ExecutionErrorKind::UnknownValue(_) => 501,
+            ExecutionErrorKind::UndeclaredName(_) => 502,
+            ExecutionErrorKind::IndexOutOfBounds { .. } => 503,
+            ExecutionErrorKind::NonQuadratic(_) => 504,
+            ExecutionErrorKind::SignalAssignedTwice(_) => 505,
+            ExecutionErrorKind::ComponentNotInstantiated(_) => 506,
+            ExecutionErrorKind::AssertionFailed(_) => 507,
+            ExecutionErrorKind::DivisionByZero(_) => 508,
+            ExecutionErrorKind::Unsupported(_) => 509,
+            ExecutionErrorKind::InvalidComponentSignal { .. } => 510,
+            ExecutionErrorKind::UnassignedInput { .. } => 511,
+            ExecutionErrorKind::MissingInput(_) => 512,
+            ExecutionErrorKind::UnknownInput(_) => 513,
+            ExecutionErrorKind::Interrupted => 514,
+            ExecutionErrorKind::LimitExceeded { .. } => 515
+        }
+    }
+
+    pub fn name(&self) -> & str {
+        match self {
+            ExecutionErrorKind::UnknownValue(_) => "UnknownValue",
+            ExecutionErrorKind::UndeclaredName(_) => "UndeclaredName",
+            ExecutionErrorKind::IndexOutOfBounds { .. } => "IndexOutOfBounds",
+            ExecutionErrorKind::NonQuadratic(_) => "NonQuadratic",
+            ExecutionErrorKind::SignalAssignedTwice(_) => "SignalAssignedTwice",
+            ExecutionErrorKind::ComponentNotInstantiated(_) => "ComponentNotInstantiated",
+            ExecutionErrorKind::AssertionFailed(_) => "AssertionFailed",
+            ExecutionErrorKind::DivisionByZero(_) => "DivisionByZero",
+            ExecutionErrorKind::Unsupported(_) => "Unsupported",
+            ExecutionErrorKind::InvalidComponentSignal { .. } => "InvalidComponentSignal",
+            ExecutionErrorKind::UnassignedInput { .. } => "UnassignedInput",
+            ExecutionErrorKind::MissingInput(_) => "MissingInput",
+            ExecutionErrorKind::UnknownInput(_) => "UnknownInput",
+            ExecutionErrorKind::Interrupted => "Interrupted",
+            ExecutionErrorKind::LimitExceeded { .. } => "LimitExceeded"
+        }
+    }
+
+}
+
+impl fmt::Display for ExecutionErrorKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExecutionErrorKind::UnknownValue(expr) => write!(formatter, "the value of `{}` is not known at this point, it cannot depend on signals", expr),
+            ExecutionErrorKind::UndeclaredName(name) => write!(formatter, "`{}` is not declared", name),
+            ExecutionErrorKind::IndexOutOfBounds { name, indices } => write!(formatter, "`{}{}` is outside of the array", name, indices),
+            ExecutionErrorKind::NonQuadratic(expr) => write!(formatter, "`{}` is not a quadratic constraint", expr),
+            ExecutionErrorKind::SignalAssignedTwice(name) => write!(formatter, "`{}` already has a value", name),
+            ExecutionErrorKind::ComponentNotInstantiated(name) => write!(formatter, "`{}` is used before being instantiated", name),
+            ExecutionErrorKind::AssertionFailed(expr) => write!(formatter, "assertion `{}` does not hold", expr),
+            ExecutionErrorKind::DivisionByZero(expr) => write!(formatter, "`{}` divides by zero", expr),
+            ExecutionErrorKind::Unsupported(what) => write!(formatter, "{} is not supported", what),
+            ExecutionErrorKind::InvalidComponentSignal { component, signal } => write!(formatter, "`{}.{}` cannot be assigned here", component, signal),
+            ExecutionErrorKind::UnassignedInput { component, signal } => write!(formatter, "`{}.{}` is never assigned, the component does not run", component, signal),
+            ExecutionErrorKind::MissingInput(name) => write!(formatter, "no value given for the input `{}`", name),
+            ExecutionErrorKind::UnknownInput(name) => write!(formatter, "`{}` is not an input of main", name),
+            ExecutionErrorKind::Interrupted => write!(formatter, "the execution was stopped"),
+            ExecutionErrorKind::LimitExceeded { limit, maximum } => match limit {
+                Limit::Statements => write!(formatter, "more than {} statements were executed", maximum),
+                Limit::Depth => write!(formatter, "components are nested more than {} deep", maximum),
+                Limit::Recursion => write!(formatter, "the template is nested within itself more than {} times", maximum),
+                Limit::ArraySize => write!(formatter, "the array has more than {} elements", maximum)
+            }
+        }
+    }
+}
+
+///
+/// E5xx: Execution Error
+///
+/// The circuit failed while being executed, in the instance `instance` (`main.isEqual`) of `template`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionError {
+    pub file_error: std::path::PathBuf,
+    pub source_error: std::string::String,
+    pub span_error: (usize, usize),
+
+    pub instance: std::string::String,
+    pub template: std::string::String,
+    pub kind: ExecutionErrorKind
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+
+        common_displayer::common_displayer(formatter, self.kind.name(), &self.kind.code(), &self.source_error, &self.span_error, &self.file_error)
+            .and(writeln!(formatter, "\tin {} ({}): {}", self.instance, self.template, self.kind))
+
+    }
+}
@@ -1,6 +1,9 @@
 pub mod include;
 pub mod syntax;
 pub mod common_displayer;
+pub mod symbol;
+pub mod semantic;
+pub mod execution;
 
 use crate::parser::ast::ParseError;
 use crate::parser::errors::include::UnknownFileIncludeError;
@@ -8,7 +11,20 @@ use crate::parser::errors::include::UnknownFileIncludeError;
 #[derive(Debug)]
 pub enum CompileError {
     UnknownFileIncludeError(include::UnknownFileIncludeError),
-    SyntaxError(syntax::SyntaxError)
+    SyntaxError(syntax::SyntaxError),
+    DuplicateSymbolError(symbol::DuplicateSymbolError),
+    SemanticError(semantic::SemanticError)
+}
+
+impl std::fmt::Display for CompileError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CompileError::UnknownFileIncludeError(error) => error.fmt(formatter),
+            CompileError::SyntaxError(error) => error.fmt(formatter),
+            CompileError::DuplicateSymbolError(error) => error.fmt(formatter),
+            CompileError::SemanticError(error) => error.fmt(formatter)
+        }
+    }
 }
 
 pub fn from_pest_parsing(file: & std::path::PathBuf, err: & ParseError) -> CompileError {
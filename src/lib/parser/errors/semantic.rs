@@ -0,0 +1,121 @@
+
+use std::fmt;
+use crate::parser::errors::common_displayer;
+
+///
+/// Static circom rules a template can break. Each kind has its own error code in the E4xx range.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SemanticErrorKind {
+    /// E401: a signal, var or component is used before (or without) being declared.
+    UndeclaredSymbol(std::string::String),
+    /// E402: a name is declared twice in the same template.
+    Redeclaration(std::string::String),
+    /// E403: a template assigns or constrains one of its own input signals.
+    InputSignalAssignment(std::string::String),
+    /// E404: a template assigns or constrains an output of one of its sub-components.
+    SubComponentOutputAssignment { component: std::string::String, signal: std::string::String },
+    /// E405: `<==`, `<--`, `==>` or `-->` targets something that is not a signal.
+    NotASignal(std::string::String),
+    /// E406: a signal is assigned with `=` (or a compound assignment) instead of a signal operator.
+    VariableOperatorOnSignal(std::string::String),
+    /// E407: a component is instantiated from a template that does not exist.
+    UnknownTemplate(std::string::String),
+    /// E408: a template is instantiated, or a function called, with the wrong number of parameters.
+    TemplateArity { template: std::string::String, expected: usize, found: usize },
+    /// E409: a sub-component signal that its template does not declare as input or output.
+    UnknownComponentSignal { component: std::string::String, signal: std::string::String },
+    /// E410: member access or instantiation on something that is not a component.
+    NotAComponent(std::string::String),
+    /// E411: a function declares a signal or a component, only templates can.
+    DeclarationInFunction(std::string::String),
+    /// E412: a component declared outside of a template under another name than `main`.
+    ComponentOutsideTemplate(std::string::String),
+    /// E413: a statement the expression parser cannot read, with where it stopped.
+    InvalidStatement(std::string::String)
+}
+
+impl SemanticErrorKind {
+
+    pub fn code(&self) -> usize {
+        match self {
+            SemanticErrorKind::UndeclaredSymbol(_) => 401,
+            SemanticErrorKind::Redeclaration(_) => 402,
+            SemanticErrorKind::InputSignalAssignment(_) => 403,
+            SemanticErrorKind::SubComponentOutputAssignment { .. } => 404,
+            SemanticErrorKind::NotASignal(_) => 405,
+            SemanticErrorKind::VariableOperatorOnSignal(_) => 406,
+            SemanticErrorKind::UnknownTemplate(_) => 407,
+            SemanticErrorKind::TemplateArity { .. } => 408,
+            SemanticErrorKind::UnknownComponentSignal { .. } => 409,
+            SemanticErrorKind::NotAComponent(_) => 410,
+            SemanticErrorKind::DeclarationInFunction(_) => 411,
+            SemanticErrorKind::ComponentOutsideTemplate(_) => 412,
+            SemanticErrorKind::InvalidStatement(_) => 413
+        }
+    }
+
+    pub fn name(&self) -> & str {
+        match self {
+            SemanticErrorKind::UndeclaredSymbol(_) => "UndeclaredSymbol",
+            SemanticErrorKind::Redeclaration(_) => "Redeclaration",
+            SemanticErrorKind::InputSignalAssignment(_) => "InputSignalAssignment",
+            SemanticErrorKind::SubComponentOutputAssignment { .. } => "SubComponentOutputAssignment",
+            SemanticErrorKind::NotASignal(_) => "NotASignal",
+            SemanticErrorKind::VariableOperatorOnSignal(_) => "VariableOperatorOnSignal",
+            SemanticErrorKind::UnknownTemplate(_) => "UnknownTemplate",
+            SemanticErrorKind::TemplateArity { .. } => "TemplateArity",
+            SemanticErrorKind::UnknownComponentSignal { .. } => "UnknownComponentSignal",
+            SemanticErrorKind::NotAComponent(_) => "NotAComponent",
+            SemanticErrorKind::DeclarationInFunction(_) => "DeclarationInFunction",
+            SemanticErrorKind::ComponentOutsideTemplate(_) => "ComponentOutsideTemplate",
+            SemanticErrorKind::InvalidStatement(_) => "InvalidStatement"
+        }
+    }
+
+}
+
+impl fmt::Display for SemanticErrorKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SemanticErrorKind::UndeclaredSymbol(name) => write!(formatter, "`{}` is used before being declared", name),
+            SemanticErrorKind::Redeclaration(name) => write!(formatter, "`{}` is already declared in this template", name),
+            SemanticErrorKind::InputSignalAssignment(name) => write!(formatter, "`{}` is an input of this template and cannot be assigned here", name),
+            SemanticErrorKind::SubComponentOutputAssignment { component, signal } => write!(formatter, "`{}.{}` is an output of the sub-component and cannot be assigned", component, signal),
+            SemanticErrorKind::NotASignal(name) => write!(formatter, "`{}` is not a signal, signal operators cannot target it", name),
+            SemanticErrorKind::VariableOperatorOnSignal(name) => write!(formatter, "`{}` is a signal, use `<==` or `<--` instead of `=`", name),
+            SemanticErrorKind::UnknownTemplate(name) => write!(formatter, "no template named `{}`", name),
+            SemanticErrorKind::TemplateArity { template, expected, found } => write!(formatter, "`{}` takes {} parameter(s) but {} were given", template, expected, found),
+            SemanticErrorKind::UnknownComponentSignal { component, signal } => write!(formatter, "`{}` has no input or output signal named `{}`", component, signal),
+            SemanticErrorKind::NotAComponent(name) => write!(formatter, "`{}` is not a component", name),
+            SemanticErrorKind::DeclarationInFunction(name) => write!(formatter, "`{}` is a signal or a component, functions cannot declare them", name),
+            SemanticErrorKind::ComponentOutsideTemplate(name) => write!(formatter, "`{}` is declared outside of a template, only `main` can be", name),
+            SemanticErrorKind::InvalidStatement(message) => write!(formatter, "{}", message)
+        }
+    }
+}
+
+///
+/// E4xx: Semantic Error
+///
+/// A template breaks one of the static circom rules. Reported before execution so the debugger never
+/// interprets an invalid circuit.
+///
+#[derive(Debug, Clone)]
+pub struct SemanticError {
+    pub file_error: std::path::PathBuf,
+    pub source_error: std::string::String,
+    pub span_error: (usize, usize),
+
+    pub template: std::string::String,
+    pub kind: SemanticErrorKind
+}
+
+impl fmt::Display for SemanticError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+
+        common_displayer::common_displayer(formatter, self.kind.name(), &self.kind.code(), &self.source_error, &self.span_error, &self.file_error)
+            .and(writeln!(formatter, "\tin template {}: {}", self.template, self.kind))
+
+    }
+}
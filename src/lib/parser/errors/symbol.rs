@@ -0,0 +1,29 @@
+
+use std::fmt;
+use crate::parser::errors::common_displayer;
+
+///
+/// E301: Duplicate Symbol Error
+///
+/// Two templates or functions, possibly in different included files, share the same name. Circom keeps
+/// templates and functions in a single namespace, so a function can also collide with a template.
+///
+#[derive(Debug, Clone)]
+pub struct DuplicateSymbolError {
+    pub file_error: std::path::PathBuf,
+    pub source_error: std::string::String,
+    pub span_error: (usize, usize),
+
+    pub symbol: std::string::String,
+    pub first_file: std::path::PathBuf,
+    pub first_span: (usize, usize)
+}
+
+impl fmt::Display for DuplicateSymbolError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+
+        common_displayer::common_displayer(formatter, "DuplicateSymbol", &301, &self.source_error, &self.span_error, &self.file_error)
+            .and(writeln!(formatter, "\t`{}` is already defined in {:?} at {:?}", self.symbol, self.first_file, self.first_span))
+
+    }
+}
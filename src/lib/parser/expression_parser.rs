@@ -1,8 +1,10 @@
 use pest::Parser;
-use std::process;
-use std::collections::HashMap;
 use std::fmt;
 
+use num_bigint::BigUint;
+
+use crate::r1cs::field::FieldElement;
+
 #[derive(Parser)]
 #[grammar = "lib/parser/expression_grammar.pest"]
 pub struct ExpressionParser;
@@ -80,12 +82,6 @@ pub struct ComponentInstance {
     pub parameter_list: Vec<Expr>
 }
 
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-pub struct EvaluatedComponentInstance {
-    pub name: String,
-    pub parameter_list: Vec<i32>
-}
-
 #[derive(Eq, Hash, PartialEq, Debug, Clone)]
 pub struct BinaryOperation {
     pub left: Box<Expr>,
@@ -96,7 +92,7 @@ pub struct BinaryOperation {
 #[derive(Eq, Hash, PartialEq, Debug, Clone)]
 pub enum Expr {
     Empty, 
-    Number(i32),
+    Number(FieldElement),
     ComplexVariable(Variable),
     BinaryOperation(BinaryOperation),
     LogicalOperation {
@@ -118,7 +114,9 @@ pub enum Expr {
         true_value: Box<Expr>,
         false_value: Box<Expr>,
     },
-    ComponentInstance(ComponentInstance)
+    ComponentInstance(ComponentInstance),
+    // `[1, 2, 3]`, the elements of an array in order, rows of a matrix are arrays too
+    Array(Vec<Expr>)
 }
 
 // impl Expr {
@@ -178,752 +176,667 @@ pub enum Stmt {
     RegularExpr(Expr),
     Assert(Assert),
     SymmetricConstraint(SymmetricConstraint),
+    // text `parse_statement` could not read, with the reason
+    Invalid(String),
     Empty,
 }
 
-#[derive(Eq, Hash, PartialEq, Debug, Clone)]
-pub enum EvaluationResult {
-    Empty,
-    Value(i32),
-    Identifier(String),
-    Boolean(bool),
-    ComponentInstance(EvaluatedComponentInstance)
-}
-pub trait Serializable {
-    fn serialize(&self, heap: &mut HashMap<String, i32>) -> String;
-}
-
-impl Serializable for Variable {
-    fn serialize(&self, heap: &mut HashMap<String, i32>) -> String {
-        let mut res = "".to_string();
-        res = format!("{}{}", res, self.id);
-        let var_name = self;
-        if var_name.indexing.len() > 0 {
-            for index in 0..var_name.indexing.len() {
-                let index_evaluated = evaluate(&var_name.indexing[index], heap);
-                match index_evaluated {
-                    (EvaluationResult::Value(num), vec) => {
-                        res = format!("{}[{}]", res, num.clone());
-                    },
-                    (EvaluationResult::Identifier(num), vec) => {
-                        match heap.get(&num) {
-                            Some(value) => {
-                                res = format!("{}[{}]", res, value.clone());
-                            },
-                            None => {
-                                res = format!("{}[{}]", res, num.clone());
-                            }
-                        }
-                    },
-                    _ => {}
-                }
-            }
-            match &var_name.sub_variable {
-                Some(var) => {
-                    res = format!("{}.{}", res, var.id);
-                    if var.indexing.len() > 0 {
-                        for index in 0..var.indexing.len() {
-                            let index_evaluated = evaluate(&var.indexing[index], heap);
-                            match index_evaluated {
-                                (EvaluationResult::Value(num), vec) => {
-                                    res = format!("{}[{}]", res, num.clone());
-                                },
-                                (EvaluationResult::Identifier(num), vec) => {
-                                    match heap.get(&num) {
-                                        Some(value) => {
-                                            res = format!("{}[{}]", res, value.clone());
-                                        },
-                                        None => {
-                                            res = format!("{}[{}]", res, num.clone());
-                                        }
-                                    }
-                                },
-                                _ => {}
-                            }
-                        }
-                    }
-                },
-                None => {}
-                }
-        } else {
-            match &var_name.sub_variable {
-                Some(var) => {
-                    res = format!("{}.{}", res, var.id);
-                    if var.indexing.len() > 0 {
-                        for index in 0..var.indexing.len() {
-                            let index_evaluated = evaluate(&var.indexing[index], heap);
-                            match index_evaluated {
-                                (EvaluationResult::Value(num), vec) => {
-                                    res = format!("{}[{}]", res, num.clone());
-                                },
-                                (EvaluationResult::Identifier(num), vec) => {
-                                    match heap.get(&num) {
-                                        Some(value) => {
-                                            res = format!("{}[{}]", res, value.clone());
-                                        },
-                                        None => {
-                                            res = format!("{}[{}]", res, num.clone());
-                                        }
-                                    }
-                                },
-                                _ => {}
-                            }
-                        }
-                    }
-                },
-                None => {}
-                }
-        }
-        res
+impl fmt::Display for BinOp {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            BinOp::Add => "+",
+            BinOp::Subtract => "-",
+            BinOp::Multiply => "*",
+            BinOp::Divide => "/",
+            BinOp::Exponent => "**",
+            BinOp::Quotient => "\\",
+            BinOp::Modulo => "%"
+        };
+        write!(formatter, "{}", symbol)
     }
 }
 
-pub fn get_string_from_variable(var_name: &Variable, heap: &mut HashMap<String, i32>) -> String {
-    let mut res = "".to_string();
-    res = format!("{}{}", res, var_name.id);
-    if var_name.indexing.len() > 0 {
-        for index in 0..var_name.indexing.len() {
-            let index_evaluated = evaluate(&var_name.indexing[index], heap);
-            match index_evaluated {
-                (EvaluationResult::Value(num), vec) => {
-                    res = format!("{}[{}]", res, num.clone());
-                },
-                (EvaluationResult::Identifier(num), vec) => {
-                    match heap.get(&num) {
-                        Some(value) => {
-                            res = format!("{}[{}]", res, value.clone());
-                        },
-                        None => {
-                            res = format!("{}[{}]", res, num.clone());
-                        }
-                    }
-                },
-                _ => {}
-            }
+impl fmt::Display for LogicalOp {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            LogicalOp::And => "&&",
+            LogicalOp::Or => "||",
+            LogicalOp::NotEqual => "!=",
+            LogicalOp::Equal => "==",
+            LogicalOp::LessThan => "<",
+            LogicalOp::GreaterThan => ">",
+            LogicalOp::LessThanOrEqual => "<=",
+            LogicalOp::GreaterThanOrEqual => ">="
+        };
+        write!(formatter, "{}", symbol)
+    }
+}
+
+impl fmt::Display for BitwiseOp {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            BitwiseOp::And => "&",
+            BitwiseOp::Or => "|",
+            BitwiseOp::ShiftRight => ">>",
+            BitwiseOp::ShiftLeft => "<<",
+            BitwiseOp::Xor => "^"
+        };
+        write!(formatter, "{}", symbol)
+    }
+}
+
+impl fmt::Display for UnOp {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            UnOp::Negate => "-",
+            UnOp::Not => "!",
+            UnOp::BitwiseNot => "~"
+        };
+        write!(formatter, "{}", symbol)
+    }
+}
+
+// Source form of a variable, `in[i].out` style, indices printed as written
+impl fmt::Display for Variable {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.id)?;
+        for index in &self.indexing {
+            write!(formatter, "[{}]", index)?;
+        }
+        match &self.sub_variable {
+            Some(sub_variable) => write!(formatter, ".{}", sub_variable),
+            None => Ok(())
         }
-        match &var_name.sub_variable {
-            Some(var) => {
-                res = format!("{}.{}", res, var.id);
-                if var.indexing.len() > 0 {
-                    for index in 0..var.indexing.len() {
-                        let index_evaluated = evaluate(&var.indexing[index], heap);
-                        match index_evaluated {
-                            (EvaluationResult::Value(num), vec) => {
-                                res = format!("{}[{}]", res, num.clone());
-                            },
-                            (EvaluationResult::Identifier(num), vec) => {
-                                match heap.get(&num) {
-                                    Some(value) => {
-                                        res = format!("{}[{}]", res, value.clone());
-                                    },
-                                    None => {
-                                        res = format!("{}[{}]", res, num.clone());
-                                    }
-                                }
-                            },
-                            _ => {}
-                        }
+    }
+}
+
+fn binary_precedence(op: &BinOp) -> u8 {
+    match op {
+        BinOp::Add | BinOp::Subtract => 1,
+        BinOp::Multiply | BinOp::Divide | BinOp::Quotient | BinOp::Modulo => 2,
+        BinOp::Exponent => 3
+    }
+}
+
+// Operands are parenthesized only when they bind weaker than the operation they are part of
+fn fmt_operand(formatter: &mut fmt::Formatter, operand: &Expr, precedence: u8) -> fmt::Result {
+    match operand {
+        Expr::BinaryOperation(operation) if binary_precedence(&operation.op) >= precedence => write!(formatter, "{}", operand),
+        Expr::Number(_) | Expr::ComplexVariable(_) | Expr::ComponentInstance(_) | Expr::UnaryOperation { .. } | Expr::Array(_) => write!(formatter, "{}", operand),
+        _ => write!(formatter, "({})", operand)
+    }
+}
+
+// Source form of an expression, used to point at subexpressions in diagnostics
+impl fmt::Display for Expr {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Empty => Ok(()),
+            Expr::Number(number) => write!(formatter, "{}", number),
+            Expr::ComplexVariable(variable) => write!(formatter, "{}", variable),
+            Expr::BinaryOperation(operation) => {
+                let precedence = binary_precedence(&operation.op);
+                // the right operand of `a - (b + c)` keeps its parentheses, and the left one of `(a ** b) ** c`
+                // as `**` groups to the right
+                let (left, right) = match operation.op {
+                    BinOp::Exponent => (precedence + 1, precedence),
+                    _ => (precedence, precedence + 1)
+                };
+                fmt_operand(formatter, &operation.left, left)?;
+                write!(formatter, " {} ", operation.op)?;
+                fmt_operand(formatter, &operation.right, right)
+            },
+            Expr::LogicalOperation { left, op, right } => {
+                fmt_operand(formatter, left, u8::MAX)?;
+                write!(formatter, " {} ", op)?;
+                fmt_operand(formatter, right, u8::MAX)
+            },
+            Expr::BitwiseOperation { left, op, right } => {
+                fmt_operand(formatter, left, u8::MAX)?;
+                write!(formatter, " {} ", op)?;
+                fmt_operand(formatter, right, u8::MAX)
+            },
+            Expr::UnaryOperation { op, expr } => {
+                write!(formatter, "{}", op)?;
+                fmt_operand(formatter, expr, u8::MAX)
+            },
+            Expr::Conditional { condition, true_value, false_value } => {
+                fmt_operand(formatter, condition, u8::MAX)?;
+                write!(formatter, " ? ")?;
+                fmt_operand(formatter, true_value, u8::MAX)?;
+                write!(formatter, " : ")?;
+                fmt_operand(formatter, false_value, u8::MAX)
+            },
+            Expr::ComponentInstance(instance) => {
+                write!(formatter, "{}(", instance.name)?;
+                for (idx, parameter) in instance.parameter_list.iter().enumerate() {
+                    if idx > 0 {
+                        write!(formatter, ", ")?;
                     }
+                    write!(formatter, "{}", parameter)?;
                 }
+                write!(formatter, ")")
             },
-            None => {}
-            }
-    } else {
-        match &var_name.sub_variable {
-            Some(var) => {
-                res = format!("{}.{}", res, var.id);
-                if var.indexing.len() > 0 {
-                    for index in 0..var.indexing.len() {
-                        let index_evaluated = evaluate(&var.indexing[index], heap);
-                        match index_evaluated {
-                            (EvaluationResult::Value(num), vec) => {
-                                res = format!("{}[{}]", res, num.clone());
-                            },
-                            (EvaluationResult::Identifier(num), vec) => {
-                                match heap.get(&num) {
-                                    Some(value) => {
-                                        res = format!("{}[{}]", res, value.clone());
-                                    },
-                                    None => {
-                                        res = format!("{}[{}]", res, num.clone());
-                                    }
-                                }
-                            },
-                            _ => {}
-                        }
+            Expr::Array(items) => {
+                write!(formatter, "[")?;
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        write!(formatter, ", ")?;
                     }
+                    write!(formatter, "{}", item)?;
                 }
-            },
-            None => {}
+                write!(formatter, "]")
             }
+        }
     }
-    res
 }
 
-pub fn evaluate(exp: &Expr, heap: &mut HashMap<String, i32>) -> (EvaluationResult, Vec<String>) {
-    match exp {
-        Expr::Number(value) => {
-            return (EvaluationResult::Value(value.clone()), vec![]);
-        },
-        Expr::ComplexVariable(var_name) => {
-            let res = get_string_from_variable(&var_name, heap);
-            // println!("COMPLEX VAR {:?}", res);
-            match heap.get(&res) {
-                Some(value) => {
-                    return (EvaluationResult::Value(value.clone()), vec![res]);
-                },
-                None => {
-                    return (EvaluationResult::Identifier(res.clone()), vec![res]);
-                }
-            }
-        },
-        Expr::ComponentInstance(component) => {
-            let res = get_string_from_variable(&component.name, heap);
-            let mut params = vec![];
-            for param in &component.parameter_list {
-                match evaluate(param, heap) {
-                    (EvaluationResult::Value(number), vec) => {
-                        params.push(number);
-                    },
-                    _ => {}
-                }
-            }
-            return (EvaluationResult::ComponentInstance(EvaluatedComponentInstance{
-                name: res,
-                parameter_list: params
-            }), vec![]);
-        },
-        Expr::BinaryOperation(bin_op) => {
-            match evaluate(&*bin_op.left, heap) {
-                (EvaluationResult::Value(number_l), mut vec_l) => {
-                    match evaluate(&*bin_op.right, heap) {
-                        (EvaluationResult::Value(number_r), mut vec_r) => {
-                            vec_l.append(vec_r.as_mut());
-                            match bin_op.op {
-                                BinOp::Multiply => {
-                                    return (EvaluationResult::Value(number_l*number_r), vec_l);
-                                },
-                                BinOp::Add => {
-                                    return (EvaluationResult::Value(number_l+number_r), vec_l);
-                                },
-                                BinOp::Subtract => {
-                                    return (EvaluationResult::Value(number_l-number_r), vec_l);
-                                },
-                                BinOp::Divide => {
-                                    return (EvaluationResult::Value(number_l / number_r), vec_l);
-                                },
-                                _ => {}
-                            }
-                        },
-                        _ => {}
-                    }
-                    return (EvaluationResult::Value(number_l), vec_l);
-                },
-                _ => { println!("Missing valuesluelue" );}
-            }
-        },
-        Expr::UnaryOperation{op, expr} => {
-            if *op == UnOp::Negate {
-                match evaluate(&*expr, heap) {
-                    (EvaluationResult::Value(num), vec_l) => {
-                        return (EvaluationResult::Value(-num), vec_l);
-                    },
-                    _ => {}
-                }
-            }
-        },
-        Expr::Conditional { condition, true_value, false_value } => {
-            println!("condition {:?}", condition);
-            match evaluate(&*condition, heap) {
-                (EvaluationResult::Boolean(res), vec1) => {
-                    if res {
-                        match evaluate(&*true_value, heap) {
-                            (EvaluationResult::Value(num), vec_l) => {
-                                return (EvaluationResult::Value(num), vec_l);
-                            },
-                            _ => {}
-                        }
-                    } else {
-                        match evaluate(&*false_value, heap) {
-                            (EvaluationResult::Value(num), vec_l) => {
-                                return (EvaluationResult::Value(num), vec_l);
-                            },
-                            _ => {}
-                        }
-                    }
-                },
-                _ => {println!("error on conditional");}
-            }
-        },
-        Expr::LogicalOperation { left, op, right } => {
-            println!("logop {:?}", left);
-            match evaluate(&*left, heap) {
-                (EvaluationResult::Value(number_l), mut vec_l) => {
-                    match evaluate(&*right, heap) {
-                        (EvaluationResult::Value(number_r), mut vec_r) => {
-                            vec_l.append(vec_r.as_mut());
-                            println!("odje smo {:?}", op);
-                            match op {
-                                LogicalOp::Equal => {
-                                    return (EvaluationResult::Boolean(number_l == number_r), vec_l);
-                                },
-                                _ => {return (EvaluationResult::Boolean(number_l == number_r), vec_l);}
-                            }
-                        },
-                        _ => {}
-                    }
-                    return (EvaluationResult::Value(number_l), vec_l);
-                },
-                _ => { println!("Missing valuesluelue" );}
-            }
-        },
-        _ => {
-
-        }
+// `0x1f` or `31`, reduced in the field
+fn parse_number(text: &str) -> Option<FieldElement> {
+    match text.strip_prefix("0x") {
+        Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16).map(FieldElement::from_biguint),
+        None => BigUint::parse_bytes(text.as_bytes(), 10).map(FieldElement::from_biguint)
     }
-    (EvaluationResult::Empty, vec![])
 }
 
-pub fn parse_operation(pairs: pest::iterators::Pair<Rule>) -> Operator {
+pub fn parse_operation(pairs: pest::iterators::Pair<Rule>) -> Result<Operator, String> {
     let rule: Rule = pairs.as_rule();
     let span: pest::Span = pairs.as_span();
-    let inner_pairs: Vec<pest::iterators::Pair<Rule>> = pairs.into_inner().into_iter().collect();
-
-    // println!("{:?}", rule);
-    // println!("{:?}", span);
-    // println!("{:?}", inner_pairs);
-
-    if inner_pairs.len() == 0 {
-        //TODO fix this  v
-        return Operator::LeftConstraint;
-    }
+    let inner_pairs: Vec<pest::iterators::Pair<Rule>> = pairs.into_inner().collect();
 
     match rule {
-        Rule::ternary_expr | Rule::logical_expr | Rule::arith_expr | Rule::term | Rule::exponent | Rule::bitwise_expr | Rule::shift_expr | Rule::unary_expr | Rule::factor | Rule::complex_variable | Rule::identifier | Rule::number=> {
+        Rule::ternary_expr | Rule::logical_expr | Rule::and_expr | Rule::equality_expr | Rule::relational_expr | Rule::bit_or_expr | Rule::bit_xor_expr | Rule::bit_and_expr | Rule::shift_expr | Rule::arith_expr | Rule::term | Rule::exponent | Rule::unary_expr | Rule::factor | Rule::complex_variable if !inner_pairs.is_empty() => {
             return parse_operation(inner_pairs[0].clone());
         },
-        Rule::logical_op => {
+        Rule::or_op | Rule::and_op | Rule::equality_op | Rule::relational_op => {
             match span.as_str() {
-                "!=" => { return Operator::LogicalOp(LogicalOp::NotEqual);},
-                "&&" => { return Operator::LogicalOp(LogicalOp::And);},
-                "||" => { return Operator::LogicalOp(LogicalOp::Or);},
-                "==" => { return Operator::LogicalOp(LogicalOp::Equal);},
-                "<" => { return Operator::LogicalOp(LogicalOp::LessThan);},
-                "<=" => { return Operator::LogicalOp(LogicalOp::LessThanOrEqual);},
-                ">" => { return Operator::LogicalOp(LogicalOp::GreaterThan);},
-                ">=" => { return Operator::LogicalOp(LogicalOp::GreaterThanOrEqual);},
+                "!=" => { return Ok(Operator::LogicalOp(LogicalOp::NotEqual));},
+                "&&" => { return Ok(Operator::LogicalOp(LogicalOp::And));},
+                "||" => { return Ok(Operator::LogicalOp(LogicalOp::Or));},
+                "==" => { return Ok(Operator::LogicalOp(LogicalOp::Equal));},
+                "<" => { return Ok(Operator::LogicalOp(LogicalOp::LessThan));},
+                "<=" => { return Ok(Operator::LogicalOp(LogicalOp::LessThanOrEqual));},
+                ">" => { return Ok(Operator::LogicalOp(LogicalOp::GreaterThan));},
+                ">=" => { return Ok(Operator::LogicalOp(LogicalOp::GreaterThanOrEqual));},
                 _ => {}
             }
         },
         Rule::add_op | Rule::multiply_op | Rule::exponent_op => {
             match span.as_str() {
-                "+" => { return Operator::BinOp(BinOp::Add);},
-                "-" => { return Operator::BinOp(BinOp::Subtract);},
-                "*" => { return Operator::BinOp(BinOp::Multiply);},
-                "/" => { return Operator::BinOp(BinOp::Divide);},
-                "**" => { return Operator::BinOp(BinOp::Exponent);},
-                "\\" => { return Operator::BinOp(BinOp::Quotient);},
-                "%" => { return Operator::BinOp(BinOp::Modulo);},
+                "+" => { return Ok(Operator::BinOp(BinOp::Add));},
+                "-" => { return Ok(Operator::BinOp(BinOp::Subtract));},
+                "*" => { return Ok(Operator::BinOp(BinOp::Multiply));},
+                "/" => { return Ok(Operator::BinOp(BinOp::Divide));},
+                "**" => { return Ok(Operator::BinOp(BinOp::Exponent));},
+                "\\" => { return Ok(Operator::BinOp(BinOp::Quotient));},
+                "%" => { return Ok(Operator::BinOp(BinOp::Modulo));},
                 _ => {}
             }
         },
-        Rule::shift_op | Rule::bitwise_op => {
+        Rule::shift_op | Rule::bit_and_op | Rule::bit_xor_op | Rule::bit_or_op => {
             match span.as_str() {
-                ">>" => { return Operator::BitwiseOp(BitwiseOp::ShiftRight);},
-                "<<" => { return Operator::BitwiseOp(BitwiseOp::ShiftLeft);},
-                "|" => { return Operator::BitwiseOp(BitwiseOp::Or);},
-                "&" => { return Operator::BitwiseOp(BitwiseOp::And);},
-                "^" => { return Operator::BitwiseOp(BitwiseOp::Xor);},
+                ">>" => { return Ok(Operator::BitwiseOp(BitwiseOp::ShiftRight));},
+                "<<" => { return Ok(Operator::BitwiseOp(BitwiseOp::ShiftLeft));},
+                "|" => { return Ok(Operator::BitwiseOp(BitwiseOp::Or));},
+                "&" => { return Ok(Operator::BitwiseOp(BitwiseOp::And));},
+                "^" => { return Ok(Operator::BitwiseOp(BitwiseOp::Xor));},
                 _ => {}
             }
         },
         Rule::unary_op => {
             match span.as_str() {
-                "!" => { return Operator::UnOp(UnOp::Not);},
-                "-" => { return Operator::UnOp(UnOp::Negate);},
-                "~" => { return Operator::UnOp(UnOp::BitwiseNot);},
+                "!" => { return Ok(Operator::UnOp(UnOp::Not));},
+                "-" => { return Ok(Operator::UnOp(UnOp::Negate));},
+                "~" => { return Ok(Operator::UnOp(UnOp::BitwiseNot));},
                 _ => {}
             }
         },
-        Rule::symmetric_constraint_op => {
-            match span.as_str() {
-                "===" => { return Operator::SymmetricConstraintOp;},
-                _ => {}
-            }
+        Rule::symmetric_constraint_op if span.as_str() == "===" => {
+            return Ok(Operator::SymmetricConstraintOp);
         },
         Rule::left_constraint_op | Rule::right_constraint_op => {
             match span.as_str() {
-                "<==" => { return Operator::LeftConstraint;},
-                "<--" => { return Operator::LeftSignalAssign;},
-                "==>" => { return Operator::RightConstraint;},
-                "-->" => { return Operator::RightSignalAssign;},
+                "<==" => { return Ok(Operator::LeftConstraint);},
+                "<--" => { return Ok(Operator::LeftSignalAssign);},
+                "==>" => { return Ok(Operator::RightConstraint);},
+                "-->" => { return Ok(Operator::RightSignalAssign);},
                 _ => {}
             }
         },
         Rule::assignment_op => {
             match span.as_str() {
-                "=" => { return Operator::Assignment;},
-                "+=" => { return Operator::PlusAssignment;},
-                "-=" => { return Operator::MinusAssignment;},
-                "*=" => { return Operator::TimesAssignment;},
-                "**=" => { return Operator::ExponentAssignment;},
-                "/=" => { return Operator::DivideAssignment;},
-                "\\=" => { return Operator::QuotientAssignment;},
-                "%=" => { return Operator::ModuloAssignment;},
+                "=" => { return Ok(Operator::Assignment);},
+                "+=" => { return Ok(Operator::PlusAssignment);},
+                "-=" => { return Ok(Operator::MinusAssignment);},
+                "*=" => { return Ok(Operator::TimesAssignment);},
+                "**=" => { return Ok(Operator::ExponentAssignment);},
+                "/=" => { return Ok(Operator::DivideAssignment);},
+                "\\=" => { return Ok(Operator::QuotientAssignment);},
+                "%=" => { return Ok(Operator::ModuloAssignment);},
                 _ => {}
             };
         },
-        _ => {
-            println!("something else");
-        }
+        _ => {}
     };
-    Operator::BinOp(BinOp::Add)
+    Err(format!("`{}` is not an operator", span.as_str()))
+}
+
+// operator of a `rule` expression, an error for one of another kind
+fn operator_of<T>(pair: pest::iterators::Pair<Rule>, kind: &dyn Fn(Operator) -> Option<T>) -> Result<T, String> {
+    let text = pair.as_str().to_string();
+    kind(parse_operation(pair)?).ok_or(format!("`{}` is not expected here", text))
+}
+
+// `a op b op c` as `(a op b) op c`, inner pairs alternate between operands and operators
+fn fold_operands<T>(inner_pairs: &[pest::iterators::Pair<Rule>], kind: &dyn Fn(Operator) -> Option<T>, combine: &dyn Fn(Expr, T, Expr) -> Expr) -> Result<Expr, String> {
+    let mut res = parse_expression(inner_pairs[0].clone())?;
+    let mut op_index = 1;
+    while op_index + 1 < inner_pairs.len() {
+        let op = operator_of(inner_pairs[op_index].clone(), kind)?;
+        res = combine(res, op, parse_expression(inner_pairs[op_index + 1].clone())?);
+        op_index += 2;
+    }
+    Ok(res)
+}
+
+// `a op b op c` as `a op (b op c)`
+fn fold_operands_right<T>(inner_pairs: &[pest::iterators::Pair<Rule>], kind: &dyn Fn(Operator) -> Option<T>, combine: &dyn Fn(Expr, T, Expr) -> Expr) -> Result<Expr, String> {
+    let mut res = parse_expression(inner_pairs[inner_pairs.len() - 1].clone())?;
+    let mut op_index = inner_pairs.len() - 1;
+    while op_index >= 2 {
+        op_index -= 2;
+        let op = operator_of(inner_pairs[op_index + 1].clone(), kind)?;
+        res = combine(parse_expression(inner_pairs[op_index].clone())?, op, res);
+    }
+    Ok(res)
 }
 
-pub fn parse_expression(pairs: pest::iterators::Pair<Rule>) -> Expr {
+pub fn parse_expression(pairs: pest::iterators::Pair<Rule>) -> Result<Expr, String> {
     let rule: Rule = pairs.as_rule();
-    // println!("{:?}", rule);
 
     let span: pest::Span = pairs.as_span();
-    let inner_pairs: Vec<pest::iterators::Pair<Rule>> = pairs.into_inner().into_iter().collect();
+    let inner_pairs: Vec<pest::iterators::Pair<Rule>> = pairs.into_inner().collect();
     match rule {
         Rule::ternary_expr => {
             if inner_pairs.len() != 3 {
-                // println!("not real ternary");
                 return parse_expression(inner_pairs[0].clone());
             } else {
-                return Expr::Conditional{
-                    condition: Box::new(parse_expression(inner_pairs[0].clone())),
-                    true_value: Box::new(parse_expression(inner_pairs[1].clone())),
-                    false_value: Box::new(parse_expression(inner_pairs[2].clone()))
-                };
+                return Ok(Expr::Conditional{
+                    condition: Box::new(parse_expression(inner_pairs[0].clone())?),
+                    true_value: Box::new(parse_expression(inner_pairs[1].clone())?),
+                    false_value: Box::new(parse_expression(inner_pairs[2].clone())?)
+                });
             }
         },
-        Rule::logical_expr => {
-            if inner_pairs.len() != 3 {
-                // println!("not real logical");
-                return parse_expression(inner_pairs[0].clone());
-            } else {
-                let op_raw = parse_operation(inner_pairs[1].clone());
-                let mut op = LogicalOp::And;
-                match op_raw {
-                    Operator::LogicalOp(lop) => {op = lop;},
-                    _ => {}
-                };
-                return  Expr::LogicalOperation{
-                    left: Box::new(parse_expression(inner_pairs[0].clone())),
-                    op: op,
-                    right: Box::new(parse_expression(inner_pairs[2].clone()))
-                };
-            }
+        // from the loosest, `||`, to the tightest, comparisons, each level groups to the left
+        Rule::logical_expr | Rule::and_expr | Rule::equality_expr | Rule::relational_expr => {
+            return fold_operands(&inner_pairs, &|op| match op {
+                Operator::LogicalOp(lop) => Some(lop),
+                _ => None
+            }, &|left, op, right| Expr::LogicalOperation{
+                left: Box::new(left),
+                op,
+                right: Box::new(right)
+            });
         },
         Rule::arith_expr => {
             if inner_pairs.len() == 1 {
                 return parse_expression(inner_pairs[0].clone());
             } else {
                 let mut op_index = 1;
-                let mut res : Expr = Expr::Number(0);
+                let mut res : Expr = parse_expression(inner_pairs[0].clone())?;
                 while op_index < inner_pairs.len() - 1 {
-                    let op_raw = parse_operation(inner_pairs[op_index].clone());
-                    let mut op: BinOp = BinOp::Add;
-                    match op_raw {
-                        Operator::BinOp(lop) => {op = lop;},
-                        _ => {}
-                    };
-                    if (op_index == 1) {
-                        res = parse_expression(inner_pairs[op_index-1].clone())
-                    }
+                    let op = operator_of(inner_pairs[op_index].clone(), &|op| match op {
+                        Operator::BinOp(lop) => Some(lop),
+                        _ => None
+                    })?;
                     res = Expr::BinaryOperation(BinaryOperation{
                         left: Box::new(res),
-                        op: op,
-                        right: Box::new(parse_expression(inner_pairs[op_index+1].clone()))
+                        op,
+                        right: Box::new(parse_expression(inner_pairs[op_index+1].clone())?)
                     });
-                    op_index = op_index + 2;
+                    op_index += 2;
                 }
-                return res;
+                return Ok(res);
             }
         },
         Rule::term => {
-            if inner_pairs.len() != 3 {
-                // println!("not real term");
-                return parse_expression(inner_pairs[0].clone());
-            } else {
-                let op_raw = parse_operation(inner_pairs[1].clone());
-                let mut op = BinOp::Multiply;
-                match op_raw {
-                    Operator::BinOp(lop) => {op = lop;},
-                    _ => {}
-                };
-                return Expr::BinaryOperation(BinaryOperation{
-                    left: Box::new(parse_expression(inner_pairs[0].clone())),
-                    op: op,
-                    right: Box::new(parse_expression(inner_pairs[2].clone()))
-                });
-            }
+            return fold_operands(&inner_pairs, &|op| match op {
+                Operator::BinOp(lop) => Some(lop),
+                _ => None
+            }, &|left, op, right| Expr::BinaryOperation(BinaryOperation{
+                left: Box::new(left),
+                op,
+                right: Box::new(right)
+            }));
         },
+        // `2 ** 3 ** 2` is `2 ** 9`
         Rule::exponent => {
-            if inner_pairs.len() != 3 {
-                // println!("not real exponent");
-                return parse_expression(inner_pairs[0].clone());
-            } else {
-                let op_raw = parse_operation(inner_pairs[1].clone());
-                let mut op = BinOp::Exponent;
-                match op_raw {
-                    Operator::BinOp(lop) => {op = lop;},
-                    _ => {}
-                };
-                return Expr::BinaryOperation(BinaryOperation{
-                    left: Box::new(parse_expression(inner_pairs[0].clone())),
-                    op: op,
-                    right: Box::new(parse_expression(inner_pairs[2].clone()))
-                });
-            }
+            return fold_operands_right(&inner_pairs, &|op| match op {
+                Operator::BinOp(lop) => Some(lop),
+                _ => None
+            }, &|left, op, right| Expr::BinaryOperation(BinaryOperation{
+                left: Box::new(left),
+                op,
+                right: Box::new(right)
+            }));
         },
-        Rule::bitwise_expr | Rule::shift_expr => {
-            if inner_pairs.len() != 3 {
-                // println!("not real bitwise");
-                return parse_expression(inner_pairs[0].clone());
-            } else {
-                let op_raw = parse_operation(inner_pairs[1].clone());
-                let mut op = BitwiseOp::And;
-                match op_raw {
-                    Operator::BitwiseOp(lop) => {op = lop;},
-                    _ => {}
-                };
-                return  Expr::BitwiseOperation{
-                    left: Box::new(parse_expression(inner_pairs[0].clone())),
-                    op: op,
-                    right: Box::new(parse_expression(inner_pairs[2].clone()))
-                };
-            }
+        Rule::bit_or_expr | Rule::bit_xor_expr | Rule::bit_and_expr | Rule::shift_expr => {
+            return fold_operands(&inner_pairs, &|op| match op {
+                Operator::BitwiseOp(lop) => Some(lop),
+                _ => None
+            }, &|left, op, right| Expr::BitwiseOperation{
+                left: Box::new(left),
+                op,
+                right: Box::new(right)
+            });
         },
         Rule::unary_expr => {
-            if inner_pairs.len() != 2 {
-                // println!("not real unary");
-                return parse_expression(inner_pairs[0].clone());
-            } else {
-                let op_raw = parse_operation(inner_pairs[1].clone());
-                let mut op = UnOp::Negate;
-                match op_raw {
-                    Operator::UnOp(lop) => {op = lop;},
-                    _ => {}
-                };
-                return Expr::UnaryOperation{
-                    op: op,
-                    expr: Box::new(parse_expression(inner_pairs[1].clone()))
+            // `unary_op* ~ factor`: the operators come first and the factor last, the operator next to it
+            // applies first, `!!x` is `!(!x)`
+            let (factor, ops) = inner_pairs.split_last().ok_or(format!("`{}` has no operand", span.as_str()))?;
+            let mut res = parse_expression(factor.clone())?;
+            for op in ops.iter().rev() {
+                let op = operator_of(op.clone(), &|op| match op {
+                    Operator::UnOp(lop) => Some(lop),
+                    _ => None
+                })?;
+                res = Expr::UnaryOperation{
+                    op,
+                    expr: Box::new(res)
                 };
             }
+            return Ok(res);
         },
         Rule::component_instance => {
-            let name_raw = parse_expression(inner_pairs[0].clone());
-            let mut name : Variable = Variable{
-                id : "".to_string(),
-                indexing: vec![],
-                sub_variable: None,
+            let name = match parse_expression(inner_pairs[0].clone())? {
+                Expr::ComplexVariable(var) => var,
+                _ => return Err(format!("`{}` is not a template name", inner_pairs[0].as_str()))
             };
             let mut params : Vec<Expr> = vec![];
-            if inner_pairs.len() > 1 {
-                for index in 1..inner_pairs.len() {
-                    params.push(parse_expression(inner_pairs[index].clone()));
-                }
+            for param in &inner_pairs[1..] {
+                params.push(parse_expression(param.clone())?);
             }
-            match name_raw {
-                Expr::ComplexVariable(var) => {
-                    name = var;
-                }, 
-                _ => {}
-            };
-            return Expr::ComponentInstance(ComponentInstance{
-                name: name,
+            return Ok(Expr::ComponentInstance(ComponentInstance{
+                name,
                 parameter_list: params
-            }); 
+            }));
         }
         Rule::factor => {
             if inner_pairs.len() > 2 {
-                // println!("not real factor");
                 return parse_expression(inner_pairs[1].clone());
             } else {
                 return parse_expression(inner_pairs[0].clone());
             }
         },
         Rule::complex_variable => {
-            let mut compl_var = parse_expression(inner_pairs[0].clone());
-            let mut cvar : Variable;
-            match compl_var {
-                Expr::ComplexVariable(mut cvar) => {
-                    let mut before_second_identifier = true;
-                    for index in 1..inner_pairs.len() {
-                        if inner_pairs[index].as_rule() == Rule::identifier {
-                            cvar.sub_variable = Some(Box::new(Variable{
-                                id: inner_pairs[index].as_str().to_string(),
-                                indexing: vec![],
-                                sub_variable: None
-                            }));
-                            before_second_identifier = false;
-                            continue;
-                        }
-                        if before_second_identifier {
-                            cvar.indexing.push(parse_expression(inner_pairs[index].clone()));
-                        } else {
-                            match cvar.sub_variable {
-                                Some(ref mut sub_var) => { (*sub_var).indexing.push(parse_expression(inner_pairs[index].clone()));},
-                                None => {}
-                            }
-                        }
+            if let Expr::ComplexVariable(mut cvar) = parse_expression(inner_pairs[0].clone())? {
+                let mut before_second_identifier = true;
+                for pair in &inner_pairs[1..] {
+                    if pair.as_rule() == Rule::identifier {
+                        cvar.sub_variable = Some(Box::new(Variable{
+                            id: pair.as_str().to_string(),
+                            indexing: vec![],
+                            sub_variable: None
+                        }));
+                        before_second_identifier = false;
+                        continue;
                     }
-                    return Expr::ComplexVariable(cvar);
-                },
-                _ => {}
-            };
-            return Expr::ComplexVariable(Variable{
-                id: "".to_string(),
-                indexing: vec![],
-                sub_variable: None,
-            });
+                    if before_second_identifier {
+                        cvar.indexing.push(parse_expression(pair.clone())?);
+                    } else if let Some(ref mut sub_var) = cvar.sub_variable {
+                        sub_var.indexing.push(parse_expression(pair.clone())?);
+                    }
+                }
+                return Ok(Expr::ComplexVariable(cvar));
+            }
         },
         Rule::array_declaration => {
             return parse_expression(inner_pairs[0].clone())
         }
         Rule::identifier => {
-            // println!("real identifier {}", span.as_str());
-            return Expr::ComplexVariable(Variable{
+            return Ok(Expr::ComplexVariable(Variable{
                 id:span.as_str().to_string(),
                 indexing: vec![],
                 sub_variable: None
-            });
+            }));
         },
         Rule::number => {
-            // println!("real number {}", span.as_str());
-            return Expr::Number(span.as_str().parse::<i32>().unwrap());
+            return parse_number(span.as_str()).map(Expr::Number).ok_or(format!("`{}` is not a number", span.as_str()));
         },
-        _ => {
-            println!("{:?}", rule);
-            println!("something else");
-        }
-    };                
-    Expr::Empty
-}
-
-pub fn parse_statement(line: &str) -> Stmt {
-    // println!("LINE TO PARSE: {}", line);
-    match ExpressionParser::parse(Rule::statement, line) {
-        Ok(statement) => {
-            // println!("{:?}", statement);
-
-            for token in statement {
-                let rule: Rule = token.as_rule();
-                let span: pest::Span = token.as_span();
-                let inner_pairs: Vec<pest::iterators::Pair<Rule>> = token.into_inner().into_iter().collect();
-                // println!("Token rule {:?}", rule);
-
-                match rule {
-                    Rule::ternary_expr => {
-                        if inner_pairs.len() != 3 {
-                            return Stmt::RegularExpr(parse_expression(inner_pairs[0].clone()));
-                        } else {
-                            return Stmt::ConditionalAssign(ConditionalAssign{
-                                condition: parse_expression(inner_pairs[0].clone()),
-                                true_value: parse_expression(inner_pairs[1].clone()),
-                                false_value: parse_expression(inner_pairs[2].clone())
-                            });
-                        }
-                    },
-                    Rule::constraint => {
-                        if inner_pairs.len() != 3 {
-                            println!("Error while parsing!");
-                        } else {
-                            let left = parse_expression(inner_pairs[0].clone());
-                            let op_raw = parse_operation(inner_pairs[1].clone());
-                            let right = parse_expression(inner_pairs[2].clone());
-
-                            match op_raw {
-                                Operator::LeftConstraint => {
-                                    match left {
-                                        Expr::ComplexVariable(var) => {
-                                            return Stmt::Constraint(Constraint{
-                                                target: var.clone(),
-                                                value: right.clone(),
-                                            });
-                                        },
-                                        _ => {}
-                                    }
-                                },
-                                Operator::RightConstraint => {
-                                    match right {
-                                        Expr::ComplexVariable(var) => {
-                                            return Stmt::Constraint(Constraint{
-                                                target: var.clone(),
-                                                value: left.clone()
-                                            });
-                                        }, _ => {}
-                                    }
-                                },
-                                Operator::LeftSignalAssign => {
-                                    match left {
-                                        Expr::ComplexVariable(var) => {
-                                            return Stmt::Assign(Assign{
-                                                target: var.clone(),
-                                                value: right.clone(),
-                                                assign_op: Operator::LeftSignalAssign
-                                            });
-                                        }, _ => {}
-                                    }
-                                },
-                                Operator::RightSignalAssign => {
-                                    match right {
-                                        Expr::ComplexVariable(var) => {
-                                            return Stmt::Assign(Assign{
-                                                target: var.clone(),
-                                                value: left.clone(),
-                                                assign_op: Operator::RightSignalAssign
-                                            });
-                                        }, _ => {}
-                                    }
-                                },
-                                _ => {
-                                    println!("Error while parsing!");
-                                }
-                            }
-                        }
-                    },
-                    Rule::assignment => {
-                        if inner_pairs.len() != 3 {
-                            println!("Error while parsing!");
-                        } else {
-                            let left = parse_expression(inner_pairs[0].clone());
-                            let op_raw = parse_operation(inner_pairs[1].clone());
-                            let right = parse_expression(inner_pairs[2].clone());
-                            match left {
-                                Expr::ComplexVariable(var) => {
-                                    return Stmt::Assign(Assign{
-                                        target: var,
-                                        value: right.clone(),
-                                        assign_op: op_raw
-                                    });
-                                }, _ => {}
-                            }
-
-                        }
-                    },
-                    Rule::assertion => {
-                        if inner_pairs.len() == 3 {
-                            return Stmt::SymmetricConstraint(SymmetricConstraint{
-                                left: parse_expression(inner_pairs[0].clone()),
-                                right: parse_expression(inner_pairs[2].clone())
-                            });
-                        } else {
-                            return Stmt::Assert(Assert{
-                                value: parse_expression(inner_pairs[0].clone())
-                            });
-                        }
-                    },
-                    _ => {
-                        println!("something else");
-                    }
-                };                
+        Rule::array_literal => {
+            return inner_pairs.iter().map(|item| parse_expression(item.clone())).collect::<Result<Vec<Expr>, String>>().map(Expr::Array);
+        },
+        _ => {}
+    };
+    Err(format!("`{}` is not an expression", span.as_str()))
+}
+
+// where the statement grammar stopped reading `line`
+fn parse_failure(line: &str, error: &pest::error::Error<Rule>) -> String {
+    let position = match error.location {
+        pest::error::InputLocation::Pos(position) => position,
+        pest::error::InputLocation::Span((start, _)) => start
+    };
+    match line.get(position..).map(|rest| rest.trim()).filter(|rest| !rest.is_empty()) {
+        Some(rest) => format!("cannot read `{}` at `{}`", line.trim(), rest),
+        None => format!("`{}` ends too early", line.trim())
+    }
+}
+
+///
+/// Reads the text of one statement (an expression statement of a template, without its `;`), all of it or
+/// not at all: anything the statement grammar does not cover is an error that says where reading stopped.
+///
+pub fn parse_statement(line: &str) -> Result<Stmt, String> {
+    let token = match ExpressionParser::parse(Rule::line, line) {
+        Ok(mut statement) => match statement.next() {
+            Some(token) => token,
+            None => return Err(format!("`{}` is empty", line.trim()))
+        },
+        Err(error) => return Err(parse_failure(line, &error))
+    };
+    let rule: Rule = token.as_rule();
+    let inner_pairs: Vec<pest::iterators::Pair<Rule>> = token.into_inner().collect();
+
+    match rule {
+        Rule::ternary_expr => {
+            if inner_pairs.len() != 3 {
+                return Ok(Stmt::RegularExpr(parse_expression(inner_pairs[0].clone())?));
+            } else {
+                return Ok(Stmt::ConditionalAssign(ConditionalAssign{
+                    condition: parse_expression(inner_pairs[0].clone())?,
+                    true_value: parse_expression(inner_pairs[1].clone())?,
+                    false_value: parse_expression(inner_pairs[2].clone())?
+                }));
             }
-            },
+        },
+        Rule::constraint => {
+            let left = parse_expression(inner_pairs[0].clone())?;
+            let op_raw = parse_operation(inner_pairs[1].clone())?;
+            let right = parse_expression(inner_pairs[2].clone())?;
+
+            match (op_raw, left, right) {
+                (Operator::LeftConstraint, Expr::ComplexVariable(var), value) | (Operator::RightConstraint, value, Expr::ComplexVariable(var)) => {
+                    return Ok(Stmt::Constraint(Constraint{
+                        target: var,
+                        value,
+                    }));
+                },
+                (assign_op @ Operator::LeftSignalAssign, Expr::ComplexVariable(var), value) | (assign_op @ Operator::RightSignalAssign, value, Expr::ComplexVariable(var)) => {
+                    return Ok(Stmt::Assign(Assign{
+                        target: var,
+                        value,
+                        assign_op
+                    }));
+                },
+                _ => {}
+            }
+        },
+        Rule::assignment => {
+            let left = parse_expression(inner_pairs[0].clone())?;
+            let op_raw = parse_operation(inner_pairs[1].clone())?;
+            let right = parse_expression(inner_pairs[2].clone())?;
+            if let Expr::ComplexVariable(var) = left {
+                return Ok(Stmt::Assign(Assign{
+                    target: var,
+                    value: right,
+                    assign_op: op_raw
+                }));
+            }
+        },
+        Rule::increment => {
+            if let Expr::ComplexVariable(var) = parse_expression(inner_pairs[0].clone())? {
+                return Ok(Stmt::Assign(Assign{
+                    target: var,
+                    value: Expr::Number(FieldElement::one()),
+                    assign_op: if inner_pairs[1].as_str() == "++" { Operator::PlusAssignment } else { Operator::MinusAssignment }
+                }));
+            }
+        },
+        Rule::assertion => {
+            if inner_pairs.len() == 3 {
+                return Ok(Stmt::SymmetricConstraint(SymmetricConstraint{
+                    left: parse_expression(inner_pairs[0].clone())?,
+                    right: parse_expression(inner_pairs[2].clone())?
+                }));
+            } else {
+                return Ok(Stmt::Assert(Assert{
+                    value: parse_expression(inner_pairs[0].clone())?
+                }));
+            }
+        },
         _ => {}
     };
-    Stmt::Empty
+    Err(format!("cannot read `{}`", line.trim()))
+}
+
+#[cfg(test)]
+mod expression_parser_test {
+
+    use crate::parser::expression_parser::*;
+
+    use galvanic_assert::matchers::*;
+
+    fn var(id: & str) -> Expr {
+        Expr::ComplexVariable(Variable { id: id.to_string(), indexing: vec![], sub_variable: None })
+    }
+
+    fn target(id: & str) -> Variable {
+        Variable { id: id.to_string(), indexing: vec![], sub_variable: None }
+    }
+
+    #[test]
+    fn test_identifiers_are_atomic() {
+
+        expect_that!(&parse_statement("in1 + x_2"), eq(Ok(Stmt::RegularExpr(Expr::BinaryOperation(BinaryOperation {
+            left: Box::new(var("in1")),
+            op: BinOp::Add,
+            right: Box::new(var("x_2"))
+        })))));
+        // whitespace ends an identifier rather than being skipped within it
+        expect_that!(&parse_statement("x 1"), eq(Err("cannot read `x 1` at `1`".to_string())));
+        expect_that!(&parse_statement("in 1 + 2"), eq(Err("cannot read `in 1 + 2` at `1 + 2`".to_string())));
+
+    }
+
+    fn expr(text: & str) -> Expr {
+        match parse_statement(text) {
+            Ok(Stmt::RegularExpr(expr)) => expr,
+            other => panic!("{:?}", other)
+        }
+    }
+
+    fn binary(left: Expr, op: BinOp, right: Expr) -> Expr {
+        Expr::BinaryOperation(BinaryOperation { left: Box::new(left), op, right: Box::new(right) })
+    }
+
+    fn logical(left: Expr, op: LogicalOp, right: Expr) -> Expr {
+        Expr::LogicalOperation { left: Box::new(left), op, right: Box::new(right) }
+    }
+
+    fn number(value: i64) -> Expr {
+        Expr::Number(FieldElement::from_i64(value))
+    }
+
+    #[test]
+    fn test_precedence() {
+
+        // `**` groups to the right, the other binary operators to the left
+        expect_that!(&expr("2 ** 3 ** 2"), eq(binary(number(2), BinOp::Exponent, binary(number(3), BinOp::Exponent, number(2)))));
+        expect_that!(&expr("a - b - c"), eq(binary(binary(var("a"), BinOp::Subtract, var("b")), BinOp::Subtract, var("c"))));
+        expect_that!(&expr("a * b ** 2"), eq(binary(var("a"), BinOp::Multiply, binary(var("b"), BinOp::Exponent, number(2)))));
+        expect_that!(&expr("(a ** b) ** c").to_string(), eq("(a ** b) ** c".to_string()));
+        expect_that!(&expr("a ** b ** c").to_string(), eq("a ** b ** c".to_string()));
+
+        // shifts and bitwise operators bind looser than arithmetic, `|` the loosest of them
+        let bitwise = |left: Expr, op: BitwiseOp, right: Expr| Expr::BitwiseOperation { left: Box::new(left), op, right: Box::new(right) };
+        expect_that!(&expr("1 << n - 1"), eq(bitwise(number(1), BitwiseOp::ShiftLeft, binary(var("n"), BinOp::Subtract, number(1)))));
+        expect_that!(&expr("a * b & c"), eq(bitwise(binary(var("a"), BinOp::Multiply, var("b")), BitwiseOp::And, var("c"))));
+        expect_that!(&expr("a | b ^ c & d"), eq(bitwise(var("a"), BitwiseOp::Or, bitwise(var("b"), BitwiseOp::Xor, bitwise(var("c"), BitwiseOp::And, var("d"))))));
+        expect_that!(&expr("a & b == c"), eq(logical(bitwise(var("a"), BitwiseOp::And, var("b")), LogicalOp::Equal, var("c"))));
+        expect_that!(&expr("-a ** 2"), eq(binary(Expr::UnaryOperation { op: UnOp::Negate, expr: Box::new(var("a")) }, BinOp::Exponent, number(2))));
+
+        // `||` binds the loosest, then `&&`, equalities and comparisons, every operand is kept
+        expect_that!(&expr("1 < 2 < 3"), eq(logical(logical(number(1), LogicalOp::LessThan, number(2)), LogicalOp::LessThan, number(3))));
+        expect_that!(&expr("a == 1 && b == 2"), eq(logical(logical(var("a"), LogicalOp::Equal, number(1)), LogicalOp::And, logical(var("b"), LogicalOp::Equal, number(2)))));
+        expect_that!(&expr("a || b && c"), eq(logical(var("a"), LogicalOp::Or, logical(var("b"), LogicalOp::And, var("c")))));
+        expect_that!(&expr("a < b == c >= d"), eq(logical(logical(var("a"), LogicalOp::LessThan, var("b")), LogicalOp::Equal, logical(var("c"), LogicalOp::GreaterThanOrEqual, var("d")))));
+        expect_that!(&expr("a + 1 != b * 2"), eq(logical(binary(var("a"), BinOp::Add, number(1)), LogicalOp::NotEqual, binary(var("b"), BinOp::Multiply, number(2)))));
+
+        // unary operators apply from the one next to their operand outwards
+        let not = |expr: Expr| Expr::UnaryOperation { op: UnOp::Not, expr: Box::new(expr) };
+        expect_that!(&expr("!!x"), eq(not(not(var("x")))));
+        expect_that!(&expr("-~x"), eq(Expr::UnaryOperation { op: UnOp::Negate, expr: Box::new(Expr::UnaryOperation { op: UnOp::BitwiseNot, expr: Box::new(var("x")) }) }));
+
+    }
+
+    #[test]
+    fn test_operator_leaves() {
+
+        let binary = |text: & str| match parse_statement(text) {
+            Ok(Stmt::RegularExpr(Expr::BinaryOperation(operation))) => Some(operation.op),
+            _ => None
+        };
+        let ops: Vec<Option<BinOp>> = ["a + b", "a - b", "a * b", "a / b", "a \\ b", "a % b", "a ** b"].into_iter().map(binary).collect();
+        expect_that!(&ops, eq(vec![BinOp::Add, BinOp::Subtract, BinOp::Multiply, BinOp::Divide, BinOp::Quotient, BinOp::Modulo, BinOp::Exponent].into_iter().map(Some).collect::<Vec<Option<BinOp>>>()));
+
+        let bitwise = |text: & str| match parse_statement(text) {
+            Ok(Stmt::RegularExpr(Expr::BitwiseOperation { op, .. })) => Some(op),
+            _ => None
+        };
+        let ops: Vec<Option<BitwiseOp>> = ["a & b", "a | b", "a ^ b", "a << b", "a >> b"].into_iter().map(bitwise).collect();
+        expect_that!(&ops, eq(vec![BitwiseOp::And, BitwiseOp::Or, BitwiseOp::Xor, BitwiseOp::ShiftLeft, BitwiseOp::ShiftRight].into_iter().map(Some).collect::<Vec<Option<BitwiseOp>>>()));
+
+        let logical = |text: & str| match parse_statement(text) {
+            Ok(Stmt::RegularExpr(Expr::LogicalOperation { op, .. })) => Some(op),
+            _ => None
+        };
+        let ops: Vec<Option<LogicalOp>> = ["a && b", "a || b", "a == b", "a != b", "a < b", "a > b", "a <= b", "a >= b"].into_iter().map(logical).collect();
+        expect_that!(&ops, eq(vec![LogicalOp::And, LogicalOp::Or, LogicalOp::Equal, LogicalOp::NotEqual, LogicalOp::LessThan, LogicalOp::GreaterThan, LogicalOp::LessThanOrEqual, LogicalOp::GreaterThanOrEqual].into_iter().map(Some).collect::<Vec<Option<LogicalOp>>>()));
+
+        let unary = |text: & str| match parse_statement(text) {
+            Ok(Stmt::RegularExpr(Expr::UnaryOperation { op, .. })) => Some(op),
+            _ => None
+        };
+        let ops: Vec<Option<UnOp>> = ["-a", "!a", "~a"].into_iter().map(unary).collect();
+        expect_that!(&ops, eq(vec![UnOp::Negate, UnOp::Not, UnOp::BitwiseNot].into_iter().map(Some).collect::<Vec<Option<UnOp>>>()));
+
+        let assign = |text: & str| match parse_statement(text) {
+            Ok(Stmt::Assign(assign)) => Some(assign.assign_op),
+            _ => None
+        };
+        let ops: Vec<Option<Operator>> = ["x = 1", "x += 1", "x -= 1", "x *= 1", "x **= 1", "x /= 1", "x \\= 1", "x %= 1", "a <-- b", "b --> a"].into_iter().map(assign).collect();
+        expect_that!(&ops, eq(vec![
+            Operator::Assignment, Operator::PlusAssignment, Operator::MinusAssignment, Operator::TimesAssignment, Operator::ExponentAssignment,
+            Operator::DivideAssignment, Operator::QuotientAssignment, Operator::ModuloAssignment, Operator::LeftSignalAssign, Operator::RightSignalAssign
+        ].into_iter().map(Some).collect::<Vec<Option<Operator>>>()));
+
+        // constraints in either direction target the signal
+        let constraint = Stmt::Constraint(Constraint { target: target("a"), value: var("b") });
+        expect_that!(&parse_statement("a <== b"), eq(Ok(constraint.clone())));
+        expect_that!(&parse_statement("b ==> a"), eq(Ok(constraint)));
+        expect_that!(&parse_statement("a === b"), eq(Ok(Stmt::SymmetricConstraint(SymmetricConstraint { left: var("a"), right: var("b") }))));
+
+    }
 }
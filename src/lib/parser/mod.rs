@@ -3,6 +3,10 @@ pub mod compile;
 pub mod matchers;
 pub mod errors;
 pub mod expression_parser;
+pub mod source;
+pub mod symbols;
+pub mod template;
+pub mod semantic;
 
 pub use crate::parser::ast::parse_source;
 pub use crate::parser::ast::Rule;
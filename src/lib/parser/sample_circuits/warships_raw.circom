@@ -11,7 +11,6 @@ template Battleship(N) {
     signal output answer;
 
     var isHit = 0;
-    component isEqualCheck = IsEqual();
 
     component checkField[N][N];
     component isEqualI[N][N];
@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::parser::errors;
+use crate::parser::errors::semantic::{SemanticError, SemanticErrorKind};
+use crate::parser::expression_parser::{ComponentInstance, Expr, Operator, Stmt, Variable};
+use crate::parser::symbols::{SymbolKind, SymbolTable};
+use crate::parser::template::{DeclStatement, DeclType, Program, SignalDirection, SingleCommand, Template};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Declared {
+    Parameter,
+    Variable,
+    Signal(SignalDirection),
+    Component
+}
+
+///
+/// Walks one template in source order, keeping a stack of scopes (the template body, then one per for loop)
+/// and the template each component was instantiated from. Functions are walked the same way, as templates
+/// that cannot declare signals nor components.
+///
+struct TemplateChecker<'a> {
+    program: &'a Program,
+    symbols: &'a SymbolTable,
+    template: &'a Template,
+    function: bool,
+    scopes: Vec<HashMap<String, Declared>>,
+    component_templates: HashMap<String, String>,
+    errors: Vec<SemanticError>
+}
+
+impl<'a> TemplateChecker<'a> {
+
+    fn new(program: &'a Program, symbols: &'a SymbolTable, template: &'a Template, function: bool) -> TemplateChecker<'a> {
+        let mut parameters = HashMap::new();
+        for param in &template.params {
+            parameters.insert(param.clone(), Declared::Parameter);
+        }
+        TemplateChecker {
+            program,
+            symbols,
+            template,
+            function,
+            scopes: vec!(parameters),
+            component_templates: HashMap::new(),
+            errors: vec![]
+        }
+    }
+
+    fn report(&mut self, span: (usize, usize), kind: SemanticErrorKind) {
+        let source_error = match self.program.sources.get(&self.template.path) {
+            Some(source) => errors::common_displayer::source_line(source, &span),
+            None => String::new()
+        };
+        self.errors.push(SemanticError {
+            file_error: PathBuf::from(&self.template.path),
+            source_error,
+            span_error: span,
+
+            template: self.template.name.clone(),
+            kind
+        });
+    }
+
+    fn resolve(&self, name: & str) -> Option<Declared> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name).cloned())
+    }
+
+    fn declare(&mut self, name: & str, declared: Declared, span: (usize, usize)) {
+        if self.resolve(name).is_some() {
+            self.report(span, SemanticErrorKind::Redeclaration(name.to_string()));
+            return;
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), declared);
+        }
+    }
+
+    fn check_commands(&mut self, commands: &'a Vec<SingleCommand>) {
+        for command in commands {
+            match command {
+                SingleCommand::DeclarationStatement(decl_statement) => self.check_declaration(decl_statement),
+                SingleCommand::Instruction(instruction) => self.check_statement(&instruction.statement, instruction.span),
+                SingleCommand::ForLoop(for_loop) => {
+                    self.check_statement(&for_loop.start, for_loop.span);
+                    self.scopes.push(HashMap::new());
+                    if for_loop.declares_index {
+                        self.declare(&for_loop.index, Declared::Variable, for_loop.span);
+                    }
+                    self.check_statement(&for_loop.test, for_loop.span);
+                    self.check_commands(&for_loop.body);
+                    self.scopes.pop();
+                },
+                SingleCommand::WhileLoop(while_loop) => {
+                    self.check_statement(&while_loop.test, while_loop.span);
+                    self.scopes.push(HashMap::new());
+                    self.check_commands(&while_loop.body);
+                    self.scopes.pop();
+                },
+                SingleCommand::Return(statement) => self.check_statement(&statement.value, statement.span),
+                SingleCommand::Unsupported(_) => {},
+                SingleCommand::IfStatement(if_statement) => {
+                    for branch in &if_statement.branches {
+                        self.check_statement(&branch.condition, branch.span);
+                        self.scopes.push(HashMap::new());
+                        self.check_commands(&branch.body);
+                        self.scopes.pop();
+                    }
+                    self.scopes.push(HashMap::new());
+                    self.check_commands(&if_statement.else_body);
+                    self.scopes.pop();
+                }
+            }
+        }
+    }
+
+    fn check_declaration(&mut self, decl_statement: &DeclStatement) {
+        let span = decl_statement.span;
+        if let Stmt::Invalid(message) = &decl_statement.expression {
+            self.report(span, SemanticErrorKind::InvalidStatement(message.clone()));
+        }
+
+        for dimension in &decl_statement.size_per_dimension {
+            if dimension.starts_with(|c: char| c.is_ascii_alphabetic()) && self.resolve(dimension).is_none() {
+                self.report(span, SemanticErrorKind::UndeclaredSymbol(dimension.clone()));
+            }
+        }
+
+        if self.function && decl_statement.decl_type != DeclType::Variable {
+            self.report(span, SemanticErrorKind::DeclarationInFunction(decl_statement.name.clone()));
+        }
+
+        match decl_statement.decl_type {
+            DeclType::Signal => {
+                if let Stmt::RegularExpr(value) = &decl_statement.expression {
+                    self.check_expr(value, span);
+                    if decl_statement.direction == SignalDirection::Input {
+                        self.report(span, SemanticErrorKind::InputSignalAssignment(decl_statement.name.clone()));
+                    }
+                }
+                self.declare(&decl_statement.name, Declared::Signal(decl_statement.direction.clone()), span);
+            },
+            DeclType::Variable => {
+                if let Stmt::RegularExpr(value) = &decl_statement.expression {
+                    self.check_expr(value, span);
+                }
+                self.declare(&decl_statement.name, Declared::Variable, span);
+            },
+            DeclType::Component => {
+                self.declare(&decl_statement.name, Declared::Component, span);
+                if let Stmt::Assign(assign) = &decl_statement.expression {
+                    self.check_instantiation(&assign.target, &assign.value, span);
+                }
+            }
+        }
+    }
+
+    fn check_statement(&mut self, statement: &Stmt, span: (usize, usize)) {
+        match statement {
+            Stmt::Constraint(constraint) => {
+                self.check_signal_target(&constraint.target, span);
+                self.check_expr(&constraint.value, span);
+            },
+            Stmt::Assign(assign) => {
+                match assign.assign_op {
+                    Operator::LeftSignalAssign | Operator::RightSignalAssign | Operator::LeftConstraint | Operator::RightConstraint => {
+                        self.check_signal_target(&assign.target, span);
+                        self.check_expr(&assign.value, span);
+                    },
+                    _ => {
+                        if let Expr::ComponentInstance(_) = &assign.value {
+                            self.check_instantiation(&assign.target, &assign.value, span);
+                        } else {
+                            self.check_variable_target(&assign.target, span);
+                            self.check_expr(&assign.value, span);
+                        }
+                    }
+                }
+            },
+            Stmt::ConditionalAssign(conditional) => {
+                self.check_expr(&conditional.condition, span);
+                self.check_expr(&conditional.true_value, span);
+                self.check_expr(&conditional.false_value, span);
+            },
+            Stmt::RegularExpr(expr) => self.check_expr(expr, span),
+            Stmt::Assert(assert) => self.check_expr(&assert.value, span),
+            Stmt::SymmetricConstraint(constraint) => {
+                self.check_expr(&constraint.left, span);
+                self.check_expr(&constraint.right, span);
+            },
+            Stmt::Invalid(message) => self.report(span, SemanticErrorKind::InvalidStatement(message.clone())),
+            Stmt::Empty => {}
+        }
+    }
+
+    ///
+    /// `target = Name(...)` is either a component instantiation or, when `Name` is a function, a plain call.
+    ///
+    fn check_call(&mut self, call: &ComponentInstance, span: (usize, usize)) {
+        if let Some(function) = self.program.functions.get(&call.name.id) {
+            if function.params.len() != call.parameter_list.len() {
+                self.report(span, SemanticErrorKind::TemplateArity {
+                    template: call.name.id.clone(),
+                    expected: function.params.len(),
+                    found: call.parameter_list.len()
+                });
+            }
+        }
+    }
+
+    fn check_instantiation(&mut self, target: &Variable, value: &Expr, span: (usize, usize)) {
+        let instance = match value {
+            Expr::ComponentInstance(instance) => instance,
+            _ => return
+        };
+        for argument in &instance.parameter_list {
+            self.check_expr(argument, span);
+        }
+        for index in &target.indexing {
+            self.check_expr(index, span);
+        }
+
+        let name = &instance.name.id;
+        if self.symbols.function(name).is_some() {
+            self.check_call(instance, span);
+            self.check_variable_target(target, span);
+            return;
+        }
+
+        match self.resolve(&target.id) {
+            None => {
+                self.report(span, SemanticErrorKind::UndeclaredSymbol(target.id.clone()));
+                return;
+            },
+            Some(Declared::Component) if target.sub_variable.is_none() => {},
+            Some(_) => {
+                self.report(span, SemanticErrorKind::NotAComponent(target.id.clone()));
+                return;
+            }
+        }
+
+        match self.program.templates.get(name) {
+            Some(instantiated) => {
+                if instantiated.params.len() != instance.parameter_list.len() {
+                    self.report(span, SemanticErrorKind::TemplateArity {
+                        template: name.clone(),
+                        expected: instantiated.params.len(),
+                        found: instance.parameter_list.len()
+                    });
+                }
+                self.component_templates.insert(target.id.clone(), name.clone());
+            },
+            None => self.report(span, SemanticErrorKind::UnknownTemplate(name.clone()))
+        }
+    }
+
+    fn check_variable_target(&mut self, target: &Variable, span: (usize, usize)) {
+        for index in &target.indexing {
+            self.check_expr(index, span);
+        }
+        match self.resolve(&target.id) {
+            None => self.report(span, SemanticErrorKind::UndeclaredSymbol(target.id.clone())),
+            Some(Declared::Signal(_)) => self.report(span, SemanticErrorKind::VariableOperatorOnSignal(target.id.clone())),
+            Some(Declared::Component) => match &target.sub_variable {
+                Some(sub_variable) => self.report(span, SemanticErrorKind::VariableOperatorOnSignal(format!("{}.{}", target.id, sub_variable.id))),
+                None => self.report(span, SemanticErrorKind::NotASignal(target.id.clone()))
+            },
+            Some(_) => {}
+        }
+    }
+
+    fn check_signal_target(&mut self, target: &Variable, span: (usize, usize)) {
+        for index in &target.indexing {
+            self.check_expr(index, span);
+        }
+        match self.resolve(&target.id) {
+            None => self.report(span, SemanticErrorKind::UndeclaredSymbol(target.id.clone())),
+            Some(Declared::Signal(direction)) => {
+                if target.sub_variable.is_some() {
+                    self.report(span, SemanticErrorKind::NotAComponent(target.id.clone()));
+                } else if direction == SignalDirection::Input {
+                    self.report(span, SemanticErrorKind::InputSignalAssignment(target.id.clone()));
+                }
+            },
+            Some(Declared::Component) => match &target.sub_variable {
+                Some(sub_variable) => {
+                    if let Some(SignalDirection::Output) = self.component_signal(target, sub_variable, span) {
+                        self.report(span, SemanticErrorKind::SubComponentOutputAssignment {
+                            component: target.id.clone(),
+                            signal: sub_variable.id.clone()
+                        });
+                    }
+                },
+                None => self.report(span, SemanticErrorKind::NotASignal(target.id.clone()))
+            },
+            Some(_) => self.report(span, SemanticErrorKind::NotASignal(target.id.clone()))
+        }
+    }
+
+    ///
+    /// Direction of `component.signal`, or `None` when the template of the component is not known statically.
+    ///
+    fn component_signal(&mut self, component: &Variable, signal: &Variable, span: (usize, usize)) -> Option<SignalDirection> {
+        for index in &signal.indexing {
+            self.check_expr(index, span);
+        }
+        let template = self.component_templates.get(&component.id)
+            .and_then(|name| self.program.templates.get(name))?;
+
+        let declared = template.private_input_signals.iter()
+            .chain(template.output_signals.iter())
+            .find(|declared| declared.name == signal.id)
+            .map(|declared| declared.direction.clone());
+
+        if declared.is_none() {
+            self.report(span, SemanticErrorKind::UnknownComponentSignal {
+                component: component.id.clone(),
+                signal: signal.id.clone()
+            });
+        }
+        declared
+    }
+
+    fn check_expr(&mut self, expr: &Expr, span: (usize, usize)) {
+        match expr {
+            Expr::Empty | Expr::Number(_) => {},
+            Expr::ComplexVariable(variable) => {
+                for index in &variable.indexing {
+                    self.check_expr(index, span);
+                }
+                match (self.resolve(&variable.id), &variable.sub_variable) {
+                    (None, _) => self.report(span, SemanticErrorKind::UndeclaredSymbol(variable.id.clone())),
+                    (Some(Declared::Component), Some(sub_variable)) => {
+                        self.component_signal(variable, sub_variable, span);
+                    },
+                    (Some(_), Some(_)) => self.report(span, SemanticErrorKind::NotAComponent(variable.id.clone())),
+                    (Some(_), None) => {}
+                }
+            },
+            Expr::BinaryOperation(operation) => {
+                self.check_expr(&operation.left, span);
+                self.check_expr(&operation.right, span);
+            },
+            Expr::LogicalOperation { left, right, .. } | Expr::BitwiseOperation { left, right, .. } => {
+                self.check_expr(left, span);
+                self.check_expr(right, span);
+            },
+            Expr::UnaryOperation { expr, .. } => self.check_expr(expr, span),
+            Expr::Conditional { condition, true_value, false_value } => {
+                self.check_expr(condition, span);
+                self.check_expr(true_value, span);
+                self.check_expr(false_value, span);
+            },
+            Expr::ComponentInstance(instance) => {
+                for argument in &instance.parameter_list {
+                    self.check_expr(argument, span);
+                }
+                self.check_call(instance, span);
+            },
+            Expr::Array(items) => {
+                for item in items {
+                    self.check_expr(item, span);
+                }
+            }
+        }
+    }
+
+}
+
+///
+/// Checks a single template: declarations before use, no redeclaration, signal directions and operators,
+/// and instantiations against the templates of the program.
+///
+pub fn check_template(program: & Program, symbols: & SymbolTable, template: & Template) -> Vec<SemanticError> {
+    let mut checker = TemplateChecker::new(program, symbols, template, false);
+    checker.check_commands(&template.instructions);
+    checker.errors
+}
+
+///
+/// Checks a single function with the rules of a template, and that it declares neither signals nor components.
+///
+pub fn check_function(program: & Program, symbols: & SymbolTable, function: & Template) -> Vec<SemanticError> {
+    let mut checker = TemplateChecker::new(program, symbols, function, true);
+    checker.check_commands(&function.instructions);
+    checker.errors
+}
+
+///
+/// Checks the main component and every template and function of the program, whether main reaches them or
+/// not: a library file is checked the same way as the circuit that includes it.
+///
+/// Errors are ordered by template or function (in symbol table order) and then by position in the source.
+///
+pub fn check_program(program: & Program, symbols: & SymbolTable) -> Vec<errors::CompileError> {
+    let mut found: Vec<SemanticError> = vec![];
+
+    if let Some(main_component) = &program.main_component {
+        if !program.templates.contains_key(&main_component.template_to_use) {
+            found.push(SemanticError {
+                file_error: symbols.files.first().cloned().unwrap_or_default(),
+                source_error: String::new(),
+                span_error: (0, 0),
+
+                template: main_component.name.clone(),
+                kind: SemanticErrorKind::UnknownTemplate(main_component.template_to_use.clone())
+            });
+        }
+    }
+
+    for (path, component) in &program.stray_components {
+        found.push(SemanticError {
+            file_error: PathBuf::from(path),
+            source_error: program.sources.get(path).map(|source| errors::common_displayer::source_line(source, &component.span)).unwrap_or_default(),
+            span_error: component.span,
+
+            template: component.name.clone(),
+            kind: SemanticErrorKind::ComponentOutsideTemplate(component.name.clone())
+        });
+    }
+
+    for symbol in &symbols.symbols {
+        let mut symbol_errors = match symbol.kind {
+            SymbolKind::Template => program.templates.get(&symbol.name).map(|template| check_template(program, symbols, template)),
+            SymbolKind::Function => program.functions.get(&symbol.name).map(|function| check_function(program, symbols, function))
+        }.unwrap_or_default();
+        symbol_errors.sort_by_key(|error| error.span_error);
+        found.append(&mut symbol_errors);
+    }
+
+    found.into_iter().map(errors::CompileError::SemanticError).collect()
+}
+
+#[cfg(test)]
+mod semantic_test {
+
+    use std::path::PathBuf;
+
+    use crate::parser::compile;
+    use crate::parser::errors::CompileError;
+    use crate::parser::errors::semantic::SemanticErrorKind;
+    use crate::parser::semantic;
+    use crate::parser::source::{DiskSource, MemorySource, SourceProvider};
+    use crate::parser::symbols;
+    use crate::parser::template;
+
+    use galvanic_assert::matchers::*;
+
+    fn check(path: &str, sources: &dyn SourceProvider) -> Vec<SemanticErrorKind> {
+        let mut ctx = compile::build_context_from_source(&PathBuf::from(path), sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = template::load_program(&ctx, &table);
+
+        semantic::check_program(&program, &table).into_iter().map(|error| match error {
+            CompileError::SemanticError(error) => error.kind,
+            other => panic!("Unexpected error {:?}", other)
+        }).collect()
+    }
+
+    fn check_main(body: &str) -> Vec<SemanticErrorKind> {
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", &format!("template Sub() {{\n    signal input a;\n    signal output b;\n    b <== a;\n}}\ntemplate Main() {{\n{}\n}}\ncomponent main = Main();\n", body));
+        check("/virtual/main.circom", &sources)
+    }
+
+    #[test]
+    fn test_sample_circuits_are_valid() {
+
+        expect_that!(&check("./src/lib/parser/sample_circuits/multiplier4.circom", &DiskSource), eq(vec![]));
+        expect_that!(&check("./src/lib/parser/sample_circuits/warships_raw.circom", &DiskSource), eq(vec![]));
+
+    }
+
+    #[test]
+    fn test_undeclared_and_redeclared() {
+
+        let found = check_main("    signal input x;\n    signal output y;\n    y <== x * z;\n    var x = 2;");
+
+        expect_that!(&found, eq(vec!(
+            SemanticErrorKind::UndeclaredSymbol("z".to_string()),
+            SemanticErrorKind::Redeclaration("x".to_string())
+        )));
+
+    }
+
+    #[test]
+    fn test_for_loop_scope_ends_with_loop() {
+
+        let found = check_main("    signal output y;\n    var acc = 0;\n    for (var i = 0; i < 3; i++) {\n        acc += i;\n    }\n    y <== i;");
+
+        expect_that!(&found, eq(vec!(SemanticErrorKind::UndeclaredSymbol("i".to_string()))));
+
+    }
+
+    #[test]
+    fn test_signal_direction_rules() {
+
+        let found = check_main("    signal input x;\n    signal output y;\n    component s = Sub();\n    x <== 1;\n    s.b <== x;\n    s.a <== x;\n    y <== s.c;");
+
+        expect_that!(&found, eq(vec!(
+            SemanticErrorKind::InputSignalAssignment("x".to_string()),
+            SemanticErrorKind::SubComponentOutputAssignment { component: "s".to_string(), signal: "b".to_string() },
+            SemanticErrorKind::UnknownComponentSignal { component: "s".to_string(), signal: "c".to_string() }
+        )));
+
+    }
+
+    #[test]
+    fn test_operator_must_match_target() {
+
+        let found = check_main("    signal output y;\n    var v;\n    v <-- 3;\n    y = 3;");
+
+        expect_that!(&found, eq(vec!(
+            SemanticErrorKind::NotASignal("v".to_string()),
+            SemanticErrorKind::VariableOperatorOnSignal("y".to_string())
+        )));
+
+    }
+
+    #[test]
+    fn test_instantiation_checks() {
+
+        let found = check_main("    component a = Sub(1);\n    component b;\n    b = Missing();");
+
+        expect_that!(&found, eq(vec!(
+            SemanticErrorKind::TemplateArity { template: "Sub".to_string(), expected: 0, found: 1 },
+            SemanticErrorKind::UnknownTemplate("Missing".to_string())
+        )));
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", "function f(x) {\n    return x;\n}\ntemplate Main() {\n    signal output y;\n    var v = f(1, 2);\n    v = f();\n    y <== f(v);\n}\ncomponent main = Main();\n");
+        expect_that!(&check("/virtual/main.circom", &sources), eq(vec!(
+            SemanticErrorKind::TemplateArity { template: "f".to_string(), expected: 1, found: 2 },
+            SemanticErrorKind::TemplateArity { template: "f".to_string(), expected: 1, found: 0 }
+        )));
+
+    }
+
+    #[test]
+    fn test_functions_and_unreachable_templates() {
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", "function f(a) {\n    var c = b + a;\n    return c;\n}\nfunction g(a) {\n    signal s;\n    component c = Unused();\n    return a;\n}\ntemplate Unused() {\n    signal input x;\n    x <== 1;\n}\ntemplate Main() {\n    signal output y;\n    y <== 1;\n}\ncomponent main = Main();\n");
+        expect_that!(&check("/virtual/main.circom", &sources), eq(vec!(
+            SemanticErrorKind::UndeclaredSymbol("b".to_string()),
+            SemanticErrorKind::DeclarationInFunction("s".to_string()),
+            SemanticErrorKind::DeclarationInFunction("c".to_string()),
+            SemanticErrorKind::InputSignalAssignment("x".to_string())
+        )));
+
+        // a library without main is checked all the same
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/lib.circom", "function f(a) {\n    var c = b + a;\n    return c;\n}\n");
+        expect_that!(&check("/virtual/lib.circom", &sources), eq(vec!(SemanticErrorKind::UndeclaredSymbol("b".to_string()))));
+
+    }
+
+    #[test]
+    fn test_components_outside_templates() {
+
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", "template Main() {\n    signal input x;\n    signal output y;\n    y <== x;\n}\ncomponent other = Main();\ncomponent main = Main();\n");
+        expect_that!(&check("/virtual/main.circom", &sources), eq(vec!(SemanticErrorKind::ComponentOutsideTemplate("other".to_string()))));
+
+    }
+
+    #[test]
+    fn test_unreadable_statements() {
+
+        let found = check_main("    signal input x;\n    signal output y;\n    var a = x.y.z;\n    var b = ++a;\n    if ((x, a)) {\n        y <== x;\n    }\n    a = [];");
+
+        expect_that!(&found, eq(vec!(
+            SemanticErrorKind::InvalidStatement("cannot read `x.y.z` at `.z`".to_string()),
+            SemanticErrorKind::InvalidStatement("cannot read `++a` at `++a`".to_string()),
+            SemanticErrorKind::InvalidStatement("cannot read `(x, a)` at `, a)`".to_string()),
+            SemanticErrorKind::InvalidStatement("cannot read `a = []` at `]`".to_string())
+        )));
+
+    }
+}
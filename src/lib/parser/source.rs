@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+///
+/// Where circuit sources are read from. `compile::build_context_from_source` only ever goes through this trait,
+/// so the same compilation can run against the disk, an in-memory map, or unsaved editor buffers.
+///
+pub trait SourceProvider {
+    fn read_source(&self, path: &Path) -> std::io::Result<String>;
+}
+
+///
+/// Reads sources straight from the filesystem. This is what `build_context` uses.
+///
+#[derive(Debug, Default, Clone)]
+pub struct DiskSource;
+
+impl SourceProvider for DiskSource {
+    fn read_source(&self, path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+}
+
+///
+/// Sources held entirely in memory, keyed by path. Used by tests and by builds without a filesystem.
+///
+#[derive(Debug, Default, Clone)]
+pub struct MemorySource {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemorySource {
+    pub fn new() -> MemorySource {
+        MemorySource { files: HashMap::new() }
+    }
+
+    pub fn insert<P: AsRef<Path>>(&mut self, path: P, content: &str) {
+        self.files.insert(normalize_path(path.as_ref()), content.to_string());
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&String> {
+        self.files.get(&normalize_path(path))
+    }
+
+    pub fn remove(&mut self, path: &Path) -> Option<String> {
+        self.files.remove(&normalize_path(path))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl SourceProvider for MemorySource {
+    fn read_source(&self, path: &Path) -> std::io::Result<String> {
+        match self.get(path) {
+            Some(content) => Ok(content.clone()),
+            None => Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("{:?} is not loaded in memory", path)))
+        }
+    }
+}
+
+///
+/// In-memory buffers layered on top of another provider. A path present in the overlay shadows the base,
+/// every other path falls through. This is how unsaved editor content gets compiled.
+///
+pub struct OverlaySource<'a> {
+    pub base: &'a dyn SourceProvider,
+    pub overlay: MemorySource,
+}
+
+impl<'a> OverlaySource<'a> {
+    pub fn new(base: &'a dyn SourceProvider, overlay: MemorySource) -> OverlaySource<'a> {
+        OverlaySource { base, overlay }
+    }
+}
+
+impl SourceProvider for OverlaySource<'_> {
+    fn read_source(&self, path: &Path) -> std::io::Result<String> {
+        match self.overlay.get(path) {
+            Some(content) => Ok(content.clone()),
+            None => self.base.read_source(path)
+        }
+    }
+}
+
+///
+/// Lexically resolves `.` and `..` components. Include paths are built by pushing the include string onto the
+/// including file's directory, so `a/b/../c.circom` and `a/c.circom` have to be the same key in memory.
+///
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => { normalized.pop(); },
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {},
+                _ => normalized.push(component)
+            },
+            _ => normalized.push(component)
+        }
+    }
+    normalized
+}
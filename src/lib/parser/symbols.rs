@@ -0,0 +1,253 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::parser::ast;
+use crate::parser::ast::tokens::{Token, NonTerminalToken};
+use crate::parser::compile::{Context, LoadAttempt};
+use crate::parser::errors;
+use crate::parser::source::normalize_path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Template,
+    Function
+}
+
+///
+/// A template or function definition, and where it was found.
+///
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: std::string::String,
+    pub kind: SymbolKind,
+    pub params: Vec<std::string::String>,
+    pub file: PathBuf,
+    pub span: (usize, usize),
+    pub name_span: (usize, usize)
+}
+
+///
+/// Every template and function of a `Context`, in a deterministic lookup order.
+///
+/// Files are visited depth-first from `main`, following includes in the order they appear in the source, so
+/// the main file always comes first and nothing depends on the iteration order of `Context::files`. The first
+/// definition of a name wins, later ones are reported as `DuplicateSymbolError`.
+///
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    pub files: Vec<PathBuf>,
+    pub symbols: Vec<Symbol>,
+    index: HashMap<std::string::String, usize>
+}
+
+impl SymbolTable {
+
+    pub fn lookup(&self, name: & str) -> Option<&Symbol> {
+        self.index.get(name).map(|idx| &self.symbols[*idx])
+    }
+
+    pub fn template(&self, name: & str) -> Option<&Symbol> {
+        self.lookup(name).filter(|symbol| symbol.kind == SymbolKind::Template)
+    }
+
+    pub fn function(&self, name: & str) -> Option<&Symbol> {
+        self.lookup(name).filter(|symbol| symbol.kind == SymbolKind::Function)
+    }
+
+    pub fn templates(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter().filter(|symbol| symbol.kind == SymbolKind::Template)
+    }
+
+    pub fn in_file<'a>(&'a self, file: &'a Path) -> impl Iterator<Item = &'a Symbol> {
+        let file = normalize_path(file);
+        self.symbols.iter().filter(move |symbol| normalize_path(&symbol.file) == file)
+    }
+
+}
+
+///
+/// Loaded files of the context, main first, then includes depth-first in source order. Paths are returned as
+/// they are keyed in `Context::files`; a file reached through two spellings of the same path is listed once.
+///
+pub fn file_order(ctx: & Context) -> Vec<PathBuf> {
+
+    let mut order: Vec<PathBuf> = Vec::new();
+    let mut visited: HashSet<PathBuf> = HashSet::new();
+    let mut stack: Vec<PathBuf> = vec!(ctx.main.clone());
+
+    while let Some(path) = stack.pop() {
+
+        if !visited.insert(normalize_path(&path)) {
+            continue;
+        }
+
+        if let Some(LoadAttempt::Loaded(file)) = ctx.files.get(&path) {
+            order.push(path.clone());
+            for include in file.includes.iter().rev() {
+                stack.push(include.clone());
+            }
+        }
+    }
+
+    order
+}
+
+fn symbol_from_block(block: & NonTerminalToken, kind: SymbolKind, file: & Path) -> Option<Symbol> {
+
+    let name_rule = match kind {
+        SymbolKind::Template => ast::Rule::TemplateName,
+        SymbolKind::Function => ast::Rule::FunctionName
+    };
+
+    let mut name: Option<(std::string::String, (usize, usize))> = None;
+    let mut params: Vec<std::string::String> = Vec::new();
+
+    for token in &block.subrules {
+        match token {
+            Token::Terminal(tt) if tt.rule == name_rule => {
+                name = Some((tt.content.clone(), tt.span));
+            },
+            Token::NonTerminal(ntt) if ntt.rule == ast::Rule::Parameters => {
+                for param in &ntt.subrules {
+                    if let Token::Terminal(tt) = param {
+                        params.push(tt.content.clone());
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+
+    name.map(|(name, name_span)| Symbol {
+        name,
+        kind,
+        params,
+        file: file.to_path_buf(),
+        span: block.span,
+        name_span
+    })
+}
+
+///
+/// Collects the templates and functions defined at the root of a parsed file, in source order.
+///
+pub fn file_symbols(file: & ast::File) -> Vec<Symbol> {
+
+    let mut symbols: Vec<Symbol> = Vec::new();
+
+    if let Some(Token::NonTerminal(circuit)) = file.root.ast.first() {
+        for token in &circuit.subrules {
+            if let Token::NonTerminal(block) = token {
+                let kind = match block.rule {
+                    ast::Rule::TemplateBlock => SymbolKind::Template,
+                    ast::Rule::FunctionBlock => SymbolKind::Function,
+                    _ => continue
+                };
+                if let Some(symbol) = symbol_from_block(block, kind, &file.path) {
+                    symbols.push(symbol);
+                }
+            }
+        }
+    }
+
+    symbols
+}
+
+///
+/// Builds the symbol table of a context. Every name defined more than once adds a `DuplicateSymbolError` to
+/// `ctx.errors`, pointing at the rejected definition and at the one that was kept.
+///
+pub fn build_symbol_table(ctx: & mut Context) -> SymbolTable {
+
+    let mut table = SymbolTable {
+        files: file_order(ctx),
+        ..Default::default()
+    };
+    let mut duplicates: Vec<errors::CompileError> = Vec::new();
+
+    for path in &table.files {
+
+        let file = match ctx.files.get(path) {
+            Some(LoadAttempt::Loaded(file)) => file,
+            _ => continue
+        };
+
+        for symbol in file_symbols(file) {
+            match table.index.get(&symbol.name) {
+                Some(first_idx) => {
+                    let first = &table.symbols[*first_idx];
+                    duplicates.push(errors::CompileError::DuplicateSymbolError(errors::symbol::DuplicateSymbolError {
+                        file_error: symbol.file.clone(),
+                        source_error: errors::common_displayer::source_line(&file.root.source, &symbol.name_span),
+                        span_error: symbol.name_span,
+
+                        symbol: symbol.name.clone(),
+                        first_file: first.file.clone(),
+                        first_span: first.name_span
+                    }));
+                },
+                None => {
+                    table.index.insert(symbol.name.clone(), table.symbols.len());
+                    table.symbols.push(symbol);
+                }
+            }
+        }
+    }
+
+    ctx.errors.append(&mut duplicates);
+
+    table
+}
+
+#[cfg(test)]
+mod symbols_test {
+
+    use crate::parser::compile;
+    use crate::parser::errors::CompileError;
+    use crate::parser::source::MemorySource;
+    use crate::parser::symbols;
+
+    use galvanic_assert::matchers::*;
+
+    fn sources() -> MemorySource {
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", "include \"a.circom\";\ninclude \"b.circom\";\ntemplate Main() {\n    signal input x;\n}\ncomponent main = Main();\n");
+        sources.insert("/virtual/a.circom", "template Hash(n) {\n    signal input in;\n}\nfunction nbits(a) {\n    return a;\n}\n");
+        sources.insert("/virtual/b.circom", "template Hash(n) {\n    signal input in;\n}\ntemplate nbits() {\n    signal input in;\n}\n");
+        sources
+    }
+
+    #[test]
+    fn test_file_order_is_main_then_includes() {
+
+        let ctx = compile::build_context_from_source(&std::path::PathBuf::from("/virtual/main.circom"), &sources());
+
+        let order: Vec<String> = symbols::file_order(&ctx).iter().map(|path| path.to_string_lossy().to_string()).collect();
+
+        expect_that!(&order, eq(vec!("/virtual/main.circom".to_string(), "/virtual/a.circom".to_string(), "/virtual/b.circom".to_string())));
+
+    }
+
+    #[test]
+    fn test_duplicates_point_at_both_definitions() {
+
+        let mut ctx = compile::build_context_from_source(&std::path::PathBuf::from("/virtual/main.circom"), &sources());
+
+        let table = symbols::build_symbol_table(&mut ctx);
+
+        expect_that!(&ctx.errors.len(), is(eq(2)));
+        expect_that!(&table.template("Hash").unwrap().file.to_string_lossy().to_string(), eq("/virtual/a.circom".to_string()));
+        expect_that!(&table.function("nbits").is_some(), is(eq(true)));
+        expect_that!(&table.template("nbits").is_none(), is(eq(true)));
+
+        match &ctx.errors[0] {
+            CompileError::DuplicateSymbolError(error) => {
+                expect_that!(&error.symbol, eq("Hash".to_string()));
+                expect_that!(&error.file_error.to_string_lossy().to_string(), eq("/virtual/b.circom".to_string()));
+                expect_that!(&error.first_file.to_string_lossy().to_string(), eq("/virtual/a.circom".to_string()));
+            },
+            other => panic!("Unexpected error {:?}", other)
+        }
+
+    }
+}
@@ -0,0 +1,857 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+use crate::parser::ast::tokens::{NonTerminalToken, Token};
+use crate::parser::ast::Rule;
+use crate::parser::compile::{Context, LoadAttempt};
+use crate::parser::symbols::SymbolTable;
+use crate::parser::expression_parser;
+use crate::parser::expression_parser::Stmt;
+use crate::r1cs::field::FieldElement;
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct Template{
+    pub name: String,
+    pub params: Vec<String>,
+    pub private_input_signals: Vec<Signal>,
+    pub output_signals: Vec<Signal>,
+    pub intermediate_signals: Vec<Signal>,
+    pub components: Vec<Component>,
+    pub constraints: Vec<Expression>,
+    pub instructions: Vec<SingleCommand>,
+    pub path: String,
+    pub span: (usize, usize),
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub enum DeclType {
+    Signal,
+    Variable,
+    Component
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub enum SignalDirection{
+    Input,
+    Output,
+    Intermediate
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct Signal {
+    pub name: String,
+    pub direction: SignalDirection,
+    pub size_per_dimension: Vec<String>,
+    pub expression: Stmt,
+    pub span: (usize, usize),
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct Component {
+    pub name: String,
+    pub size_per_dimension: Vec<String>,
+    pub template_to_use: String,
+    pub arguments: Vec<FieldElement>,
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct DeclStatement {
+    pub decl_type: DeclType,
+    pub name: String,
+    pub direction: SignalDirection,
+    pub size_per_dimension: Vec<String>,
+    pub template_to_use: String,
+    // the full size expression of every dimension, `size_per_dimension` only keeps its first token
+    pub dimensions: Vec<expression_parser::Expr>,
+    pub expression: Stmt,
+    // `<==` or `<--` for signals declared with a value, `=` for initialized vars
+    pub assign_op: Option<expression_parser::Operator>,
+    pub arguments: Vec<FieldElement>,
+    pub span: (usize, usize),
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct Expression {
+    pub content: String,
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct BoolExpression{
+    pub lhs: String,
+    pub rhs: String,
+    pub operation: String,
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct ForLoop {
+    pub index: String,
+    // `for (var i = 0; ...)` declares its index, `for (i = 0; ...)` reuses a var of the template
+    pub declares_index: bool,
+    pub start_value: i32,
+    // initial value of the index and loop condition as written, `start_value` and `condition` only cover literals
+    pub start: Stmt,
+    pub test: Stmt,
+    pub condition: BoolExpression,
+    pub step: i32,
+    pub body: Vec<SingleCommand>,
+    pub span: (usize, usize),
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct WhileLoop {
+    pub test: Stmt,
+    pub body: Vec<SingleCommand>,
+    pub span: (usize, usize),
+}
+
+// a statement that is read but not executed: `do ... while`, `break` and `continue`
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct UnsupportedStatement {
+    pub keyword: String,
+    pub span: (usize, usize),
+}
+
+// `return` of a function, a template cannot run one
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct ReturnStatement {
+    pub value: Stmt,
+    pub span: (usize, usize),
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct Branch {
+    pub condition: Stmt,
+    pub body: Vec<SingleCommand>,
+    pub span: (usize, usize),
+}
+
+// `if`, then every `else if` in order, `else_body` is empty when there is no `else`
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct IfStatement {
+    pub branches: Vec<Branch>,
+    pub else_body: Vec<SingleCommand>,
+    pub span: (usize, usize),
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub enum SingleCommand {
+    ForLoop(Box<ForLoop>),
+    Instruction(Instruction),
+    DeclarationStatement(DeclStatement),
+    IfStatement(IfStatement),
+    WhileLoop(WhileLoop),
+    Return(ReturnStatement),
+    Unsupported(UnsupportedStatement)
+}
+
+//Make this be enum
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct Instruction {
+    pub statement: expression_parser::Stmt,
+    pub span: (usize, usize),
+}
+
+fn parse_array_declaration(array_decl_root: &[Token]) -> String {
+    if let Token::NonTerminal(ntt) = &array_decl_root[1] {
+        if let Token::NonTerminal(subntt) = &ntt.subrules[0] {
+            if let Token::NonTerminal(subnntt) = &subntt.subrules[0] {
+                if let Token::Terminal(tt) = &subnntt.subrules[0] {
+                    return tt.content.clone();
+                }
+            } else if let Token::Terminal(subtt) = &subntt.subrules[0] {
+                return subtt.content.clone();
+            }
+        }
+    }
+    String::from("")
+}
+
+fn parse_dimension(array_decl_root: &[Token], path: &String, path_to_content_map: &HashMap<String, String>) -> expression_parser::Expr {
+    if let Token::NonTerminal(ntt) = &array_decl_root[1] {
+        if let Stmt::RegularExpr(expr) = extract_original_content_from_span(path_to_content_map, ntt.span, path) {
+            return expr;
+        }
+    }
+    expression_parser::Expr::Empty
+}
+
+fn parse_declaration_statement(declaration_statement_root: &[Token], span: (usize, usize), path: &String, path_to_content_map: &HashMap<String, String>) -> DeclStatement {
+    let mut name = "".to_string();
+    let mut decl_type = DeclType::Variable;
+    let mut direction = SignalDirection::Input;
+    let mut size_per_dimension = vec![];
+    let mut dimensions = vec![];
+    let mut template_to_use = "".to_string();
+    let mut expression = expression_parser::Stmt::Empty;
+    let mut assign_op = None;
+    let mut arguments = vec![];
+
+    if let Token::NonTerminal(ntt) = &declaration_statement_root[0] {
+        if ntt.rule == Rule::SignalDeclarationWithConstraint {
+            decl_type = DeclType::Signal;
+            if let Token::NonTerminal(wrapper) = &ntt.subrules[0] {
+                if let Token::Terminal(subtt) = &wrapper.subrules[2] {
+                    if subtt.content.contains("output") {
+                        direction = SignalDirection::Output;
+                    } else if subtt.content.contains("input") {
+                        direction = SignalDirection::Input;
+                    } else {
+                        direction = SignalDirection::Intermediate;
+                    }
+                }
+                if let Token::Terminal(subtt) = &ntt.subrules[1] {
+                    name = subtt.content.clone();
+                }
+            }
+
+            if ntt.subrules.len() > 2 {
+                for st_index in 2..ntt.subrules.len() {
+                    if let Token::Terminal(subsubtt) = &ntt.subrules[st_index] {
+                        if subsubtt.rule == Rule::END_OF_LINE {
+                            break;
+                        }
+                    }
+                    if let Token::Terminal(subsubtt) = &ntt.subrules[st_index] {
+                        if subsubtt.rule == Rule::E_2_SignalLeftHandOperator {
+                            assign_op = match subsubtt.content.trim() {
+                                "<--" => Some(expression_parser::Operator::LeftSignalAssign),
+                                _ => Some(expression_parser::Operator::LeftConstraint)
+                            };
+                        }
+                    }
+                    if let Token::NonTerminal(subsubtt) = &ntt.subrules[st_index] {
+                        if subsubtt.rule == Rule::ArrayDeclaration {
+                            size_per_dimension.push(parse_array_declaration(&subsubtt.subrules));
+                            dimensions.push(parse_dimension(&subsubtt.subrules, path, path_to_content_map));
+                        }
+                    } 
+                }
+                if let Token::NonTerminal(subsubtt) = &ntt.subrules[ntt.subrules.len() - 1] {
+                    if subsubtt.rule == Rule::Expression {
+                        expression = extract_original_content_from_span(path_to_content_map, subsubtt.span, path);
+                    }
+                } 
+            }
+        }
+        if ntt.rule == Rule::ComponentDeclaration {
+            decl_type = DeclType::Component;
+            if let Token::Terminal(subtt) = &ntt.subrules[1] {
+                name = subtt.content.clone();
+            }
+            if ntt.subrules.len() > 2 {
+                for st_index in 2..ntt.subrules.len() {
+                    if let Token::Terminal(subsubtt) = &ntt.subrules[st_index] {
+                        if subsubtt.rule == Rule::END_OF_LINE {
+                            break;
+                        }
+                    }
+                    if let Token::NonTerminal(subsubtt) = &ntt.subrules[st_index] {
+                        if subsubtt.rule == Rule::ArrayDeclaration {
+                            size_per_dimension.push(parse_array_declaration(&subsubtt.subrules));
+                            dimensions.push(parse_dimension(&subsubtt.subrules, path, path_to_content_map));
+                        }
+                        // = TemplateName(A, B, C)
+                        if subsubtt.rule == Rule::Expression {
+                            // keep the whole instantiation, `arguments` only holds the literal ones
+                            if let Some(content) = path_to_content_map.get(path) {
+                                let instantiation = format!("{} = {}", name, &content[subsubtt.span.0..subsubtt.span.1]);
+                                expression = expression_parser::parse_statement(&instantiation).unwrap_or_else(Stmt::Invalid);
+                            }
+                            if let Token::NonTerminal(exprntt) = &subsubtt.subrules[0] {
+                                if let Token::NonTerminal(exprnntt) = &exprntt.subrules[0] {
+                                    if let Token::Terminal(vartt) = &exprnntt.subrules[0] {
+                                        //Template name is always first
+                                        template_to_use = vartt.content.clone();
+                                    }
+                                }
+                            }
+                            if let Stmt::Assign(expression_parser::Assign{value: expression_parser::Expr::ComponentInstance(instance), ..}) = &expression {
+                                arguments = instance.parameter_list.iter().filter_map(|parameter| match parameter {
+                                    expression_parser::Expr::Number(number) => Some(number.clone()),
+                                    _ => None
+                                }).collect();
+                            }
+                        }
+                        // `{public [...]}` is read by `parse_public_signals`
+                    } 
+                }
+            }
+        }
+    }
+    if let Token::Terminal(ntt) = &declaration_statement_root[0] {
+        if ntt.rule == Rule::VariableDeclarationKW {
+            if let Token::Terminal(subtt) = &declaration_statement_root[1] {
+                name = subtt.content.clone();
+            }
+            // = is in play, or maybe array?
+            for token in declaration_statement_root.iter().skip(2) {
+                if let Token::NonTerminal(subntt) = token {
+                    if subntt.rule == Rule::ArrayDeclaration {
+                        size_per_dimension.push(parse_array_declaration(&subntt.subrules));
+                        dimensions.push(parse_dimension(&subntt.subrules, path, path_to_content_map));
+                    }
+                    if subntt.rule == Rule::Expression {
+                        expression = extract_original_content_from_span(path_to_content_map, subntt.span, path);
+                        assign_op = Some(expression_parser::Operator::Assignment);
+                    }
+                }
+            }
+
+        }
+    }
+    DeclStatement{
+        size_per_dimension,
+        name,
+        decl_type,
+        direction,
+        template_to_use,
+        dimensions,
+        expression,
+        assign_op,
+        arguments,
+        span,
+    }
+}
+
+fn parse_body_nested(elements: &[Token], path: &String, path_to_content_map: &HashMap<String, String>) -> Vec<SingleCommand> {
+    let mut lines : Vec<SingleCommand> = vec![];
+    for element in elements {
+        if let Token::NonTerminal(ntt) = element {
+            if let Some(command) = parse_control_statement(ntt, path, path_to_content_map) {
+                lines.push(command);
+            }
+            // a nested `{ ... }` block or the single statement of a one line body
+            if ntt.rule == Rule::Body || ntt.rule == Rule::OneLineBody {
+                lines.append(&mut parse_body_nested(&ntt.subrules, path, path_to_content_map));
+            }
+            if ntt.rule == Rule::DeclarationStatement {
+                lines.push(SingleCommand::DeclarationStatement(parse_declaration_statement(&ntt.subrules, ntt.span, path, path_to_content_map)));
+            }
+            if ntt.rule == Rule::Expression {
+                let statement = extract_original_content_from_span(path_to_content_map, ntt.span, path);
+                lines.push(SingleCommand::Instruction(Instruction{statement, span: ntt.span}));
+            }
+        }
+    }
+    lines
+}
+
+// loops, conditionals and the statements that leave them, none for anything else
+fn parse_control_statement(ntt: &NonTerminalToken, path: &String, path_to_content_map: &HashMap<String, String>) -> Option<SingleCommand> {
+    let keyword = match ntt.rule {
+        Rule::ForStatement => return Some(parse_for_loop(&ntt.subrules, ntt.span, path, path_to_content_map)),
+        Rule::IfStatement => return Some(parse_if_statement(&ntt.subrules, ntt.span, path, path_to_content_map)),
+        Rule::WhileStatement => return Some(parse_while_loop(&ntt.subrules, ntt.span, path, path_to_content_map)),
+        Rule::DoWhileStatement => "do ... while",
+        Rule::ReturnStatement => return Some(parse_return_statement(&ntt.subrules, ntt.span, path, path_to_content_map)),
+        Rule::BreakStatement => "break",
+        Rule::ContinueStatement => "continue",
+        _ => return None
+    };
+    Some(SingleCommand::Unsupported(UnsupportedStatement{keyword: keyword.to_string(), span: ntt.span}))
+}
+
+fn parse_return_statement(elements: &[Token], span: (usize, usize), path: &String, path_to_content_map: &HashMap<String, String>) -> SingleCommand {
+    let mut value = Stmt::Empty;
+    for element in elements {
+        if let Token::NonTerminal(ntt) = element {
+            if ntt.rule == Rule::Expression {
+                value = extract_original_content_from_span(path_to_content_map, ntt.span, path);
+            }
+        }
+    }
+    SingleCommand::Return(ReturnStatement{value, span})
+}
+
+fn parse_for_loop(elements: &[Token], span: (usize, usize), path: &String, path_to_content_map: &HashMap<String, String>) -> SingleCommand {       
+    let mut for_loop = ForLoop {
+        index: "".to_string(),
+        declares_index: false,
+        start_value: 0,
+        start: Stmt::Empty,
+        test: Stmt::Empty,
+        condition: BoolExpression{
+            lhs: "".to_string(),
+            rhs: "".to_string(),
+            operation: "".to_string(),
+        },
+        step: 0,
+        body: vec![],
+        span,
+    };
+    // for (___;  ; ) 
+    if let Token::NonTerminal(ntt) = &elements[1] {
+        if ntt.rule == Rule::DeclarationStatement {
+            let statement = parse_declaration_statement(&ntt.subrules, ntt.span, path, path_to_content_map);
+            for_loop.index = statement.name.clone();
+            for_loop.declares_index = true;
+            for_loop.start_value = 0;
+            for_loop.start = statement.expression;
+        }
+        // `for (i = 0; ...)` on a var declared before the loop
+        if ntt.rule == Rule::Expression {
+            for_loop.start = match extract_original_content_from_span(path_to_content_map, ntt.span, path) {
+                Stmt::Assign(assign) => {
+                    for_loop.index = assign.target.id;
+                    Stmt::RegularExpr(assign.value)
+                },
+                other => other
+            };
+        }
+        if let Some(start_value) = match &for_loop.start {
+            Stmt::RegularExpr(expression_parser::Expr::Number(start_value)) => start_value.to_usize().and_then(|value| i32::try_from(value).ok()),
+            _ => None
+        } {
+            for_loop.start_value = start_value;
+        }
+    } 
+    // for (;___; ) 
+    if let Token::NonTerminal(ntt) = &elements[2] {
+        let mut lhs = "".to_string();
+        let mut operation = "".to_string();
+        let mut rhs = "".to_string();
+        if ntt.rule == Rule::Expression {
+            for_loop.test = extract_original_content_from_span(path_to_content_map, ntt.span, path);
+            if let Token::NonTerminal(subntt) = &ntt.subrules[0] {
+                if subntt.rule == Rule::E_Value {
+                    if let Token::NonTerminal(subnntt) = &subntt.subrules[0] {
+                        if let Token::Terminal(subtt) = &subnntt.subrules[0] {
+                            lhs = subtt.content.clone();
+                        }
+                    }
+                }
+            }
+            if let Token::Terminal(subtt) = &ntt.subrules[1] {
+                if subtt.rule == Rule::E_12_RelationalOrderingOperator {
+                    operation = subtt.content.clone();
+                }
+            }
+            if let Token::NonTerminal(subntt) = &ntt.subrules[2] {
+                if subntt.rule == Rule::E_Value {
+                    if let Token::NonTerminal(subnntt) = &subntt.subrules[0] {
+                        if let Token::Terminal(subtt) = &subnntt.subrules[0] {
+                            rhs = subtt.content.clone();
+                        }
+                    }
+                }
+            }
+            for_loop.condition = BoolExpression{
+                lhs,
+                rhs,
+                operation
+            }
+        }
+    }
+    // for (;;___) 
+    if let Token::NonTerminal(ntt) = &elements[3] {
+        let mut step_value = 0;
+        if ntt.rule == Rule::Expression {
+            if let Token::Terminal(subntt) = &ntt.subrules[1] {
+                if subntt.rule == Rule::E_18_PostfixOperator {
+                    if subntt.content.contains("++") {
+                        step_value = 1;
+                    } else {
+                        step_value = -1;
+                    }
+                }
+            }
+            for_loop.step = step_value;
+        }
+    }
+    if let Token::NonTerminal(ntt) = &elements[4] {
+        if ntt.rule == Rule::Body || ntt.rule == Rule::OneLineBody {
+            let parsed_body = parse_body_nested(&ntt.subrules, path, path_to_content_map);
+            for_loop.body = parsed_body;
+        }
+    }
+    SingleCommand::ForLoop(Box::new(for_loop))
+}
+
+fn parse_while_loop(elements: &[Token], span: (usize, usize), path: &String, path_to_content_map: &HashMap<String, String>) -> SingleCommand {
+    let mut while_loop = WhileLoop {
+        test: Stmt::Empty,
+        body: vec![],
+        span,
+    };
+    for element in elements {
+        if let Token::NonTerminal(ntt) = element {
+            if ntt.rule == Rule::Expression {
+                while_loop.test = extract_original_content_from_span(path_to_content_map, ntt.span, path);
+            }
+            if ntt.rule == Rule::Body || ntt.rule == Rule::OneLineBody {
+                while_loop.body = parse_body_nested(&ntt.subrules, path, path_to_content_map);
+            }
+        }
+    }
+    SingleCommand::WhileLoop(while_loop)
+}
+
+fn parse_branch(elements: &[Token], span: (usize, usize), path: &String, path_to_content_map: &HashMap<String, String>) -> Branch {
+    let mut branch = Branch {
+        condition: Stmt::Empty,
+        body: vec![],
+        span,
+    };
+    for element in elements {
+        if let Token::NonTerminal(ntt) = element {
+            if ntt.rule == Rule::Expression {
+                branch.condition = extract_original_content_from_span(path_to_content_map, ntt.span, path);
+            }
+            if ntt.rule == Rule::Body || ntt.rule == Rule::OneLineBody {
+                branch.body = parse_body_nested(&ntt.subrules, path, path_to_content_map);
+            }
+        }
+    }
+    branch
+}
+
+fn parse_if_statement(elements: &[Token], span: (usize, usize), path: &String, path_to_content_map: &HashMap<String, String>) -> SingleCommand {
+    let mut if_statement = IfStatement {
+        branches: vec![parse_branch(elements, span, path, path_to_content_map)],
+        else_body: vec![],
+        span,
+    };
+    for element in elements {
+        if let Token::NonTerminal(ntt) = element {
+            if ntt.rule == Rule::ElseIfStatement {
+                if_statement.branches.push(parse_branch(&ntt.subrules, ntt.span, path, path_to_content_map));
+            }
+            if ntt.rule == Rule::ElseStatement {
+                if_statement.else_body = parse_branch(&ntt.subrules, ntt.span, path, path_to_content_map).body;
+            }
+        }
+    }
+    SingleCommand::IfStatement(if_statement)
+}
+
+pub fn parse_template_from_ast(template_root_token: &[Token], span: (usize, usize), path: &String, path_to_content_map: &HashMap<String, String>) -> Template {
+    let mut temp_name = String::from("");
+    let mut template_param_vec : Vec<String> = vec![];
+    let mut output_signals : Vec<Signal> = vec![];
+    let mut private_input_signals : Vec<Signal> = vec![];
+    let mut intermediate_signals: Vec<Signal> = vec![];
+    let mut components: Vec<Component> = vec![];
+    let constraints: Vec<Expression> = vec![];
+    let mut commands: Vec<SingleCommand> = vec![];
+
+    if let Token::Terminal(subtt) = &template_root_token[1] {
+        if subtt.rule == Rule::TemplateName || subtt.rule == Rule::FunctionName {
+            temp_name=subtt.content.clone();
+        }
+    }
+    if let Token::NonTerminal(subtt) = &template_root_token[2] {
+        if subtt.rule == Rule::Parameters {
+            for subsubtoken in &subtt.subrules {
+                if let Token::Terminal(subsubtt) = subsubtoken {
+                    template_param_vec.push(subsubtt.content.clone());
+                }
+            }
+        }
+    }
+    if let Token::NonTerminal(subtt) = &template_root_token[3] {
+        if subtt.rule == Rule::Body {
+            for subsubtoken in &subtt.subrules {
+                if let Token::NonTerminal(subsubtt) = subsubtoken {
+                    if subsubtt.rule == Rule::DeclarationStatement {
+                        let statement = parse_declaration_statement(&subsubtt.subrules, subsubtt.span, path, path_to_content_map);
+                        commands.push(SingleCommand::DeclarationStatement(statement.clone()));
+                        if statement.decl_type == DeclType::Signal {
+                            if statement.direction == SignalDirection::Output {
+                                output_signals.push(Signal{
+                                    name: statement.name.clone(),
+                                    direction: statement.direction,
+                                    size_per_dimension: statement.size_per_dimension.clone(),
+                                    expression: statement.expression.clone(),
+                                    span: statement.span,
+                                });
+                            } else if statement.direction == SignalDirection::Input {
+                                private_input_signals.push(Signal{
+                                    name: statement.name.clone(),
+                                    direction: statement.direction,
+                                    size_per_dimension: statement.size_per_dimension.clone(),
+                                    expression: statement.expression.clone(),
+                                    span: statement.span,
+                                });
+                            } else {
+                                intermediate_signals.push(Signal{
+                                    name: statement.name.clone(),
+                                    direction: statement.direction,
+                                    size_per_dimension: statement.size_per_dimension.clone(),
+                                    expression: statement.expression.clone(),
+                                    span: statement.span,
+                                });
+                            }
+                        }
+                        if statement.decl_type == DeclType::Component {
+                            components.push(Component{
+                                name: statement.name.clone(),
+                                size_per_dimension: statement.size_per_dimension.clone(),
+                                template_to_use: statement.template_to_use.clone(),
+                                arguments: statement.arguments.clone(),
+                            });
+                        }
+                    }
+                    if let Some(command) = parse_control_statement(subsubtt, path, path_to_content_map) {
+                        commands.push(command);
+                    }
+                    if subsubtt.rule == Rule::Expression {
+                        let statement = extract_original_content_from_span(path_to_content_map, subsubtt.span, path);
+                        commands.push(SingleCommand::Instruction(Instruction{
+                            statement,
+                            span: subsubtt.span,
+                        }));
+                    }
+                }
+            }
+        }
+    }
+    
+    Template{
+        name: temp_name,
+        params: template_param_vec,
+        private_input_signals,
+        output_signals,
+        intermediate_signals,
+        components,
+        constraints,
+        instructions: commands,
+        path: path.clone(),
+        span,
+        }
+}
+
+// Names listed in `component main {public [a, b]} = ...`
+fn parse_public_signals(component_declaration: &[Token], path: &String, path_to_content_map: &HashMap<String, String>) -> Vec<String> {
+    let mut public_signals = vec![];
+    if let Some(Token::NonTerminal(ntt)) = component_declaration.first() {
+        for token in &ntt.subrules {
+            if let Token::NonTerminal(block) = token {
+                if block.rule != Rule::PublicSignalBlock {
+                    continue;
+                }
+                for array in &block.subrules {
+                    if let Token::NonTerminal(array_ntt) = array {
+                        if let (Some(Token::NonTerminal(list)), Some(content)) = (array_ntt.subrules.get(1), path_to_content_map.get(path)) {
+                            public_signals.extend(content[list.span.0..list.span.1].split(',').map(|name| name.trim().to_string()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    public_signals
+}
+
+// what the root of a file declares
+pub struct FileItems {
+    pub main_component: Option<Component>,
+    // inputs of main declared public with `component main {public [...]}`
+    pub public_signals: Vec<String>,
+    pub templates: Vec<Template>,
+    pub functions: Vec<Template>,
+    // components declared outside of a template other than `main`
+    pub stray_components: Vec<DeclStatement>,
+}
+
+// AST root level function
+pub fn find_templates(subrules: &[Token], path: &String, path_to_content_map: &HashMap<String, String>) -> FileItems {
+    let mut templates : Vec<Template> = Vec::new();
+    let mut functions : Vec<Template> = Vec::new();
+    let mut main_component = None;
+    let mut public_signals = vec![];
+    let mut stray_components = vec![];
+
+    for token in subrules {
+        if let Token::NonTerminal(ntt) = token {
+            if ntt.rule == Rule::TemplateBlock {
+                let temp = parse_template_from_ast(&ntt.subrules, ntt.span, path, path_to_content_map); 
+                templates.push(temp);
+            }
+            if ntt.rule == Rule::FunctionBlock {
+                functions.push(parse_template_from_ast(&ntt.subrules, ntt.span, path, path_to_content_map));
+            }
+            // this is probably main component definition
+            if ntt.rule == Rule::DeclarationStatement {
+                let decl_statement = parse_declaration_statement(&ntt.subrules, ntt.span, path, path_to_content_map);
+                if decl_statement.decl_type == DeclType::Component{
+                    if decl_statement.name.eq(&String::from("main")) {
+                        public_signals = parse_public_signals(&ntt.subrules, path, path_to_content_map);
+                        main_component = Some(Component{
+                            name: decl_statement.name,
+                            template_to_use: decl_statement.template_to_use,
+                            size_per_dimension: decl_statement.size_per_dimension,
+                            arguments: decl_statement.arguments
+                        });
+                    }
+                    else {
+                        stray_components.push(decl_statement);
+                    }
+                }
+            }
+        }
+    }
+    FileItems { main_component, public_signals, templates, functions, stray_components }
+}
+
+fn instantiated_templates(commands: &[SingleCommand], names: &mut Vec<String>) {
+    for command in commands {
+        match command {
+            SingleCommand::ForLoop(for_loop) => instantiated_templates(&for_loop.body, names),
+            SingleCommand::WhileLoop(while_loop) => instantiated_templates(&while_loop.body, names),
+            SingleCommand::IfStatement(if_statement) => {
+                for branch in &if_statement.branches {
+                    instantiated_templates(&branch.body, names);
+                }
+                instantiated_templates(&if_statement.else_body, names);
+            },
+            SingleCommand::Instruction(instruction) => {
+                if let Stmt::Assign(assign) = &instruction.statement {
+                    if let expression_parser::Expr::ComponentInstance(instance) = &assign.value {
+                        names.push(instance.name.id.clone());
+                    }
+                }
+            },
+            SingleCommand::DeclarationStatement(_) | SingleCommand::Return(_) | SingleCommand::Unsupported(_) => {}
+        }
+    }
+}
+
+///
+/// Templates reachable from `start_node`, following both `component c = T()` declarations and `c = T()`
+/// instantiations in the template bodies. Each template is visited once, so recursive templates terminate.
+///
+pub fn get_used_templates(template_map: &HashMap<String, Template>, start_node: Component) -> HashMap<String, Template> {
+    let mut queue: VecDeque<String> = VecDeque::new();
+    let mut set = HashMap::<String, Template>::new();
+
+    queue.push_back(start_node.template_to_use);
+    while let Some(current_template) = queue.pop_front() {
+        if set.contains_key(&current_template) {
+            continue;
+        }
+        if let Some(template) = template_map.get(&current_template) {
+            set.insert(current_template.clone(), template.clone());
+
+            for component in &template.components {
+                queue.push_back(component.template_to_use.clone());
+            }
+            let mut instantiated = vec![];
+            instantiated_templates(&template.instructions, &mut instantiated);
+            queue.extend(instantiated);
+        }
+    }
+
+    set
+}
+
+pub fn extract_original_content_from_span(path_to_content_map: &HashMap<String, String>, span: (usize, usize), file_path: &String) -> expression_parser::Stmt {
+    let result = match path_to_content_map.get(file_path) {
+        Some(content) => &content[span.0..span.1],
+        None => ""
+    };
+    expression_parser::parse_statement(result).unwrap_or_else(Stmt::Invalid)
+ }
+
+///
+/// Templates of a compiled context, ready to be checked or executed.
+///
+/// `sources` maps every file path to its content, the spans stored in the templates point into it.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    pub main_component: Option<Component>,
+    // inputs of main declared public with `component main {public [...]}`
+    pub public_signals: Vec<String>,
+    pub templates: HashMap<String, Template>,
+    // functions read as templates without signals nor components, by name
+    pub functions: HashMap<String, Template>,
+    pub sources: HashMap<String, String>,
+    // components declared outside of a template other than `main`, with their file, never instantiated
+    pub stray_components: Vec<(String, DeclStatement)>,
+}
+
+///
+/// Converts every loaded file of the context into templates and functions, walking files in symbol table
+/// order. When a name is defined twice the first definition is kept, as `SymbolTable::lookup` does.
+///
+pub fn load_program(ctx: &Context, symbol_table: &SymbolTable) -> Program {
+    let mut program = Program::default();
+
+    for path in symbol_table.files.iter() {
+        if let Some(LoadAttempt::Loaded(file)) = ctx.files.get(path) {
+            let path_as_string = path.to_string_lossy().to_string();
+            program.sources.insert(path_as_string.clone(), file.root.source.clone());
+
+            if let Some(Token::NonTerminal(token)) = file.root.ast.first() {
+                let FileItems { main_component, public_signals, templates, functions, stray_components } = find_templates(&token.subrules, &path_as_string, &program.sources);
+                if program.main_component.is_none() && main_component.is_some() {
+                    program.main_component = main_component;
+                    program.public_signals = public_signals;
+                }
+                for template in templates {
+                    program.templates.entry(template.name.clone()).or_insert(template);
+                }
+                for function in functions {
+                    program.functions.entry(function.name.clone()).or_insert(function);
+                }
+                program.stray_components.extend(stray_components.into_iter().map(|component| (path_as_string.clone(), component)));
+            }
+        }
+    }
+
+    program
+}
+
+#[cfg(test)]
+mod template_test {
+
+    use std::path::PathBuf;
+
+    use crate::parser::compile;
+    use crate::parser::expression_parser::Stmt;
+    use crate::parser::source::MemorySource;
+    use crate::parser::symbols;
+    use crate::parser::template::{self, SingleCommand};
+
+    use galvanic_assert::matchers::*;
+
+    fn load(source: &str) -> template::Program {
+        let mut sources = MemorySource::new();
+        sources.insert("/virtual/main.circom", source);
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("/virtual/main.circom"), &sources);
+        let table = symbols::build_symbol_table(&mut ctx);
+        template::load_program(&ctx, &table)
+    }
+
+    #[test]
+    fn test_if_statement() {
+
+        let program = load("template Main(n) {\n    signal output y;\n    var x = 0;\n    if (n == 1) {\n        x = 1;\n        x += 1;\n    } else if (n > 1) x = 2;\n    else if (n < 0) {\n    } else {\n        if (n == 0) x = 3;\n    }\n    y <== x;\n}\ncomponent main = Main(1);\n");
+        let main = &program.templates["Main"];
+        let statement = main.instructions.iter().find_map(|command| match command {
+            SingleCommand::IfStatement(statement) => Some(statement),
+            _ => None
+        }).unwrap();
+
+        let conditions: Vec<String> = statement.branches.iter().map(|branch| match &branch.condition {
+            Stmt::RegularExpr(condition) => condition.to_string(),
+            other => format!("{:?}", other)
+        }).collect();
+        expect_that!(&conditions, eq(vec!["n == 1".to_string(), "n > 1".to_string(), "n < 0".to_string()]));
+        let bodies: Vec<usize> = statement.branches.iter().map(|branch| branch.body.len()).collect();
+        expect_that!(&bodies, eq(vec![2, 1, 0]));
+        // the `if` of the `else` is nested, not one more branch
+        expect_that!(&statement.else_body.len(), eq(1));
+        match &statement.else_body[0] {
+            SingleCommand::IfStatement(nested) => {
+                expect_that!(&(nested.branches.len(), nested.else_body.is_empty()), eq((1, true)));
+            },
+            other => panic!("expected an if statement, got {:?}", other)
+        }
+
+        let source = &program.sources["/virtual/main.circom"];
+        expect_that!(&source[statement.span.0..].starts_with("if (n == 1)"), eq(true));
+        expect_that!(&source[statement.branches[1].span.0..statement.branches[1].span.1].trim(), eq("else if (n > 1) x = 2;"));
+    }
+
+}
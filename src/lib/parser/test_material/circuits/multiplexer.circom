@@ -94,10 +94,11 @@ template Multiplexor(wIn, nIn) {
     signal input inp[nIn][wIn];
     signal input sel;
     signal output out[wIn];
-    component Decoder(nIn) dec;
-    component EscalarProduct(nIn) ep[wIn];
+    component dec = Decoder(nIn);
+    component ep[wIn];
     sel ==> dec.inp;
     for (var j=0; j<wIn; j++) {
+        ep[j] = EscalarProduct(nIn);
         for (var k=0; k<nIn; k++) {
             inp[k][j] ==> ep[j].in1[k];
             dec.out[k] ==> ep[j].in2[k];
@@ -107,6 +108,6 @@ template Multiplexor(wIn, nIn) {
     dec.success === 1;
 }
 
-component Multiplexor(8,3) main;
+component main = Multiplexor(8,3);
 
 
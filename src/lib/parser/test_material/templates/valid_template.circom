@@ -16,8 +16,8 @@ template test(arg1, arg2) {
     // Component declaration
     component comp_a = Component();
     component comp_b = Component(arg2);
-    component Component() comp_c;
-    component Component(arg2) comp_d;
+    component comp_c = Component();
+    component comp_d = Component(arg2);
 
     // Main Expression Types (
     12345;
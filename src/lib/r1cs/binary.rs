@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use num_bigint::BigUint;
+
+use crate::r1cs::field::FieldElement;
+use crate::r1cs::{Constraint, CustomGate, CustomGateApplication, LinearCombination, R1cs};
+
+///
+/// iden3 binary format, as read by snarkjs: `r1cs`, version, number of sections, then every section as
+/// its type, its size in bytes and its content. Integers and field elements are little endian.
+///
+pub const MAGIC: &[u8; 4] = b"r1cs";
+pub const VERSION: u32 = 1;
+
+pub const HEADER: u32 = 1;
+pub const CONSTRAINTS: u32 = 2;
+pub const WIRE_TO_LABEL: u32 = 3;
+pub const CUSTOM_GATES_USED: u32 = 4;
+pub const CUSTOM_GATES_APPLIED: u32 = 5;
+
+///
+/// Bytes taken by a field element, the prime rounded up to 64-bit words.
+///
+pub fn field_size(prime: & BigUint) -> usize {
+    (prime.bits() as usize).div_ceil(64) * 8
+}
+
+fn push_u32(buffer: &mut Vec<u8>, value: usize) {
+    buffer.extend_from_slice(&(value as u32).to_le_bytes());
+}
+
+fn push_u64(buffer: &mut Vec<u8>, value: usize) {
+    buffer.extend_from_slice(&(value as u64).to_le_bytes());
+}
+
+fn push_linear_combination(buffer: &mut Vec<u8>, combination: & LinearCombination, size: usize) {
+    push_u32(buffer, combination.terms.len());
+    for (wire, coefficient) in &combination.terms {
+        push_u32(buffer, *wire);
+        buffer.extend(coefficient.to_le_bytes(size));
+    }
+}
+
+fn sections(r1cs: & R1cs) -> Vec<(u32, Vec<u8>)> {
+    let size = field_size(&r1cs.prime);
+    let mut sections = vec![];
+
+    let mut header = vec![];
+    push_u32(&mut header, size);
+    let mut prime = r1cs.prime.to_bytes_le();
+    prime.resize(size, 0);
+    header.extend(prime);
+    push_u32(&mut header, r1cs.n_wires);
+    push_u32(&mut header, r1cs.n_pub_out);
+    push_u32(&mut header, r1cs.n_pub_in);
+    push_u32(&mut header, r1cs.n_prv_in);
+    push_u64(&mut header, r1cs.n_labels);
+    push_u32(&mut header, r1cs.constraints.len());
+    sections.push((HEADER, header));
+
+    let mut constraints = vec![];
+    for constraint in &r1cs.constraints {
+        push_linear_combination(&mut constraints, &constraint.a, size);
+        push_linear_combination(&mut constraints, &constraint.b, size);
+        push_linear_combination(&mut constraints, &constraint.c, size);
+    }
+    sections.push((CONSTRAINTS, constraints));
+
+    let mut wire_to_label = vec![];
+    for label in &r1cs.wire_to_label {
+        push_u64(&mut wire_to_label, *label);
+    }
+    sections.push((WIRE_TO_LABEL, wire_to_label));
+
+    // snarkjs refuses Groth16 for any file with custom gate sections, they are only written when used
+    if !r1cs.custom_gates.is_empty() || !r1cs.custom_gate_applications.is_empty() {
+        let mut used = vec![];
+        push_u32(&mut used, r1cs.custom_gates.len());
+        for gate in &r1cs.custom_gates {
+            used.extend(gate.name.as_bytes());
+            used.push(0);
+            push_u32(&mut used, gate.parameters.len());
+            for parameter in &gate.parameters {
+                used.extend(parameter.to_le_bytes(size));
+            }
+        }
+        sections.push((CUSTOM_GATES_USED, used));
+
+        let mut applied = vec![];
+        push_u32(&mut applied, r1cs.custom_gate_applications.len());
+        for application in &r1cs.custom_gate_applications {
+            push_u32(&mut applied, application.gate);
+            push_u32(&mut applied, application.signals.len());
+            for signal in &application.signals {
+                push_u64(&mut applied, *signal);
+            }
+        }
+        sections.push((CUSTOM_GATES_APPLIED, applied));
+    }
+
+    sections
+}
+
+pub fn write(r1cs: & R1cs, writer: &mut dyn Write) -> io::Result<()> {
+    let sections = sections(r1cs);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(sections.len() as u32).to_le_bytes())?;
+    for (section_type, content) in sections {
+        writer.write_all(&section_type.to_le_bytes())?;
+        writer.write_all(&(content.len() as u64).to_le_bytes())?;
+        writer.write_all(&content)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// reads a section front to back, running past its end is an error
+pub(crate) struct Cursor<'a> {
+    pub(crate) bytes: &'a [u8],
+    pub(crate) offset: usize
+}
+
+impl<'a> Cursor<'a> {
+
+    pub(crate) fn take(&mut self, size: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() - self.offset < size {
+            return Err(invalid(format!("unexpected end of data at byte {}", self.offset)));
+        }
+        self.offset += size;
+        Ok(&self.bytes[self.offset - size..self.offset])
+    }
+
+    pub(crate) fn u32(&mut self) -> io::Result<usize> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    pub(crate) fn u64(&mut self) -> io::Result<usize> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes) as usize)
+    }
+
+    pub(crate) fn field(&mut self, size: usize, prime: & BigUint) -> io::Result<FieldElement> {
+        Ok(FieldElement::from_le_bytes_over(self.take(size)?, prime))
+    }
+
+    fn linear_combination(&mut self, size: usize, prime: & BigUint) -> io::Result<LinearCombination> {
+        let mut combination = LinearCombination::default();
+        for _ in 0..self.u32()? {
+            let wire = self.u32()?;
+            combination.add_term(wire, self.field(size, prime)?);
+        }
+        Ok(combination)
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let length = match self.bytes[self.offset..].iter().position(|byte| *byte == 0) {
+            Some(length) => length,
+            None => return Err(invalid("unterminated string".to_string()))
+        };
+        let string = String::from_utf8_lossy(self.take(length)?).to_string();
+        self.take(1)?;
+        Ok(string)
+    }
+
+}
+
+///
+/// Parses a whole file, sections can come in any order (older circom versions write the constraints
+/// first). Any prime is read, see `R1cs::is_bn254` before computing with the coefficients.
+///
+pub fn read(reader: &mut dyn Read) -> io::Result<R1cs> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    let mut file = Cursor { bytes: &bytes, offset: 0 };
+    if file.take(4)? != MAGIC {
+        return Err(invalid("not an r1cs file".to_string()));
+    }
+    let version = file.u32()?;
+    if version != VERSION as usize {
+        return Err(invalid(format!("unsupported r1cs version {}", version)));
+    }
+    let mut sections = HashMap::new();
+    for _ in 0..file.u32()? {
+        let section_type = file.u32()? as u32;
+        let size = file.u64()?;
+        sections.insert(section_type, file.take(size)?);
+    }
+    let section = |section_type: u32| sections.get(&section_type).map(|bytes| Cursor { bytes, offset: 0 });
+
+    let mut header = match section(HEADER) {
+        Some(header) => header,
+        None => return Err(invalid("missing header section".to_string()))
+    };
+    let size = header.u32()?;
+    let prime = BigUint::from_bytes_le(header.take(size)?);
+    if prime.bits() < 2 {
+        return Err(invalid(format!("invalid prime {}", prime)));
+    }
+    let n_wires = header.u32()?;
+    let n_pub_out = header.u32()?;
+    let n_pub_in = header.u32()?;
+    let n_prv_in = header.u32()?;
+    let n_labels = header.u64()?;
+    let n_constraints = header.u32()?;
+
+    let mut constraints = vec![];
+    if let Some(mut section) = section(CONSTRAINTS) {
+        for _ in 0..n_constraints {
+            let a = section.linear_combination(size, &prime)?;
+            let b = section.linear_combination(size, &prime)?;
+            let c = section.linear_combination(size, &prime)?;
+            constraints.push(Constraint { a, b, c });
+        }
+    }
+
+    let wire_to_label = match section(WIRE_TO_LABEL) {
+        Some(mut section) => (0..n_wires).map(|_| section.u64()).collect::<io::Result<Vec<usize>>>()?,
+        None => (0..n_wires).collect()
+    };
+
+    let mut custom_gates = vec![];
+    if let Some(mut section) = section(CUSTOM_GATES_USED) {
+        for _ in 0..section.u32()? {
+            let name = section.string()?;
+            let parameters = (0..section.u32()?).map(|_| section.field(size, &prime)).collect::<io::Result<Vec<FieldElement>>>()?;
+            custom_gates.push(CustomGate { name, parameters });
+        }
+    }
+    let mut custom_gate_applications = vec![];
+    if let Some(mut section) = section(CUSTOM_GATES_APPLIED) {
+        for _ in 0..section.u32()? {
+            let gate = section.u32()?;
+            let signals = (0..section.u32()?).map(|_| section.u64()).collect::<io::Result<Vec<usize>>>()?;
+            custom_gate_applications.push(CustomGateApplication { gate, signals });
+        }
+    }
+
+    Ok(R1cs { prime, n_wires, n_pub_out, n_pub_in, n_prv_in, n_labels, wire_to_label, constraints, custom_gates, custom_gate_applications })
+}
+
+#[cfg(test)]
+mod binary_test {
+
+    use num_bigint::BigUint;
+
+    use crate::r1cs::binary;
+    use crate::r1cs::field::{self, FieldElement};
+    use crate::r1cs::{Constraint, CustomGate, CustomGateApplication, LinearCombination, R1cs};
+
+    use galvanic_assert::matchers::*;
+
+    fn u32_at(bytes: & [u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn u64_at(bytes: & [u8], offset: usize) -> u64 {
+        u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_layout() {
+
+        // out = a * b
+        let mut r1cs = R1cs {
+            prime: field::prime().clone(),
+            n_wires: 4,
+            n_pub_out: 1,
+            n_pub_in: 0,
+            n_prv_in: 2,
+            n_labels: 4,
+            wire_to_label: vec![0, 1, 2, 3],
+            constraints: vec![Constraint {
+                a: LinearCombination::wire(2),
+                b: LinearCombination::wire(3),
+                c: LinearCombination::wire(1)
+            }],
+            custom_gates: vec![],
+            custom_gate_applications: vec![]
+        };
+        let mut bytes = vec![];
+        binary::write(&r1cs, &mut bytes).unwrap();
+
+        expect_that!(&bytes[0..4].to_vec(), eq(b"r1cs".to_vec()));
+        expect_that!(&(u32_at(&bytes, 4), u32_at(&bytes, 8)), eq((1, 3)));
+        // header: field size, prime, 4 counts, labels, constraints
+        expect_that!(&(u32_at(&bytes, 12), u64_at(&bytes, 16)), eq((1, 4 + 32 + 16 + 8 + 4)));
+        expect_that!(&u32_at(&bytes, 24), eq(32));
+        expect_that!(&bytes[28..60].to_vec(), eq(field::prime().to_bytes_le()));
+        expect_that!(&(u32_at(&bytes, 60), u32_at(&bytes, 64), u32_at(&bytes, 68), u32_at(&bytes, 72), u64_at(&bytes, 76), u32_at(&bytes, 84)), eq((4, 1, 0, 2, 4, 1)));
+        // constraints: 3 combinations of one term each
+        expect_that!(&(u32_at(&bytes, 88), u64_at(&bytes, 92)), eq((2, 3 * (4 + 4 + 32))));
+        expect_that!(&(u32_at(&bytes, 100), u32_at(&bytes, 104), u32_at(&bytes, 108)), eq((1, 2, 1)));
+        // wire to label
+        expect_that!(&(u32_at(&bytes, 220), u64_at(&bytes, 224)), eq((3, 32)));
+        expect_that!(&bytes.len(), eq(232 + 32));
+
+        r1cs.custom_gates.push(CustomGate { name: "Gate".to_string(), parameters: vec![FieldElement::from_i64(2)] });
+        r1cs.custom_gate_applications.push(CustomGateApplication { gate: 0, signals: vec![1, 2] });
+        let mut with_gates = vec![];
+        binary::write(&r1cs, &mut with_gates).unwrap();
+        expect_that!(&u32_at(&with_gates, 8), eq(5));
+        expect_that!(&(u32_at(&with_gates, 264), u64_at(&with_gates, 268)), eq((4, 4 + 5 + 4 + 32)));
+        expect_that!(&binary::read(&mut &with_gates[..]).unwrap(), eq(r1cs));
+        expect_that!(&binary::read(&mut &with_gates[..100]).is_err(), eq(true));
+
+    }
+
+    #[test]
+    fn test_other_primes() {
+
+        // out = -a over goldilocks, on 8-byte field elements
+        let prime: BigUint = "18446744069414584321".parse().unwrap();
+        let minus_one = FieldElement::from_le_bytes_over(&(&prime - 1u32).to_bytes_le(), &prime);
+        let r1cs = R1cs {
+            prime: prime.clone(),
+            n_wires: 3,
+            n_pub_out: 1,
+            n_pub_in: 0,
+            n_prv_in: 1,
+            n_labels: 3,
+            wire_to_label: vec![0, 1, 2],
+            constraints: vec![Constraint {
+                a: LinearCombination::default(),
+                b: LinearCombination::default(),
+                c: LinearCombination { terms: [(1, FieldElement::one()), (2, minus_one)].into_iter().collect() }
+            }],
+            custom_gates: vec![],
+            custom_gate_applications: vec![]
+        };
+        let mut bytes = vec![];
+        binary::write(&r1cs, &mut bytes).unwrap();
+        expect_that!(&u32_at(&bytes, 24), eq(8));
+
+        let read = binary::read(&mut &bytes[..]).unwrap();
+        expect_that!(&read, eq(r1cs));
+        expect_that!(&(read.is_bn254(), field::prime_name(&read.prime)), eq((false, Some("goldilocks"))));
+        expect_that!(&read.constraints[0].format_over(&prime, &|wire| format!("w{}", wire)), eq("(0) * (0) - (w1 + -w2) = 0".to_string()));
+
+    }
+}
@@ -0,0 +1,273 @@
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::sync::OnceLock;
+
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{One, ToPrimitive, Zero};
+
+///
+/// Order of the BN254 scalar field, the field circom compiles to by default.
+///
+pub const BN254_PRIME: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+pub fn prime() -> &'static BigUint {
+    static PRIME: OnceLock<BigUint> = OnceLock::new();
+    PRIME.get_or_init(|| BN254_PRIME.parse().expect("the BN254 prime is a valid number"))
+}
+
+///
+/// Name snarkjs and circom give to a prime, `None` for the primes they do not know.
+///
+pub fn prime_name(prime: & BigUint) -> Option<&'static str> {
+    match prime.to_string().as_str() {
+        BN254_PRIME => Some("bn128"),
+        "52435875175126190479447740508185965837690552500527637822603658699938581184513" => Some("bls12381"),
+        "18446744069414584321" => Some("goldilocks"),
+        _ => None
+    }
+}
+
+///
+/// Element of the BN254 scalar field, always reduced. Integer operators (`\`, `%`, shifts, bitwise, ordering)
+/// follow circom and work on the representative, ordering on the signed one in `(-p/2, p/2]`.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct FieldElement(BigUint);
+
+impl FieldElement {
+
+    pub fn zero() -> FieldElement {
+        FieldElement(BigUint::zero())
+    }
+
+    pub fn one() -> FieldElement {
+        FieldElement(BigUint::one())
+    }
+
+    pub fn from_biguint(value: BigUint) -> FieldElement {
+        FieldElement(value % prime())
+    }
+
+    pub fn from_bigint(value: & BigInt) -> FieldElement {
+        let modulus = BigInt::from_biguint(Sign::Plus, prime().clone());
+        let reduced = ((value % &modulus) + &modulus) % &modulus;
+        FieldElement(reduced.to_biguint().unwrap_or_default())
+    }
+
+    pub fn from_i64(value: i64) -> FieldElement {
+        FieldElement::from_bigint(&BigInt::from(value))
+    }
+
+    ///
+    /// Decimal, optionally negative, as found in input and constraint files.
+    ///
+    pub fn parse(text: & str) -> Option<FieldElement> {
+        text.trim().parse::<BigInt>().ok().map(|value| FieldElement::from_bigint(&value))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn as_biguint(&self) -> & BigUint {
+        &self.0
+    }
+
+    ///
+    /// Representative in `(-p/2, p/2]`, how circom compares and prints values.
+    ///
+    pub fn to_signed(&self) -> BigInt {
+        self.to_signed_over(prime())
+    }
+
+    ///
+    /// Same as `to_signed` for an element read over another prime, see `from_le_bytes_over`.
+    ///
+    pub fn to_signed_over(&self, prime: & BigUint) -> BigInt {
+        let half = prime / 2u32;
+        if self.0 > half {
+            BigInt::from_biguint(Sign::Plus, self.0.clone()) - BigInt::from_biguint(Sign::Plus, prime.clone())
+        } else {
+            BigInt::from_biguint(Sign::Plus, self.0.clone())
+        }
+    }
+
+    ///
+    /// Little endian on `size` bytes, the encoding of iden3 binary files.
+    ///
+    pub fn to_le_bytes(&self, size: usize) -> Vec<u8> {
+        let mut bytes = self.0.to_bytes_le();
+        bytes.resize(size, 0);
+        bytes
+    }
+
+    pub fn from_le_bytes(bytes: & [u8]) -> FieldElement {
+        FieldElement::from_biguint(BigUint::from_bytes_le(bytes))
+    }
+
+    ///
+    /// Element of a file over any prime, reduced by that prime. It can be printed or written back, but the
+    /// operators stay those of BN254: only elements read over `prime()` can be computed with.
+    ///
+    pub fn from_le_bytes_over(bytes: & [u8], prime: & BigUint) -> FieldElement {
+        FieldElement(BigUint::from_bytes_le(bytes) % prime)
+    }
+
+    pub fn to_usize(&self) -> Option<usize> {
+        self.0.to_usize()
+    }
+
+    pub fn inverse(&self) -> Option<FieldElement> {
+        if self.is_zero() {
+            return None;
+        }
+        Some(FieldElement(self.0.modpow(&(prime() - 2u32), prime())))
+    }
+
+    pub fn pow(&self, exponent: & FieldElement) -> FieldElement {
+        FieldElement(self.0.modpow(&exponent.0, prime()))
+    }
+
+    pub fn quotient(&self, divisor: & FieldElement) -> Option<FieldElement> {
+        if divisor.is_zero() {
+            return None;
+        }
+        Some(FieldElement(&self.0 / &divisor.0))
+    }
+
+    pub fn modulo(&self, divisor: & FieldElement) -> Option<FieldElement> {
+        if divisor.is_zero() {
+            return None;
+        }
+        Some(FieldElement(&self.0 % &divisor.0))
+    }
+
+    // a shift by more than p/2 is a shift the other way by p - k
+    fn shift_amount(&self) -> (bool, usize) {
+        let half = prime() / 2u32;
+        if self.0 > half {
+            (true, (prime() - &self.0).to_usize().unwrap_or(usize::MAX))
+        } else {
+            (false, self.0.to_usize().unwrap_or(usize::MAX))
+        }
+    }
+
+    pub fn shift_right(&self, amount: & FieldElement) -> FieldElement {
+        match amount.shift_amount() {
+            (false, bits) if bits >= 254 => FieldElement::zero(),
+            (false, bits) => FieldElement(&self.0 >> bits),
+            (true, bits) => self.shl_bits(bits)
+        }
+    }
+
+    pub fn shift_left(&self, amount: & FieldElement) -> FieldElement {
+        match amount.shift_amount() {
+            (false, bits) => self.shl_bits(bits),
+            (true, bits) if bits >= 254 => FieldElement::zero(),
+            (true, bits) => FieldElement(&self.0 >> bits)
+        }
+    }
+
+    fn shl_bits(&self, bits: usize) -> FieldElement {
+        if bits >= 254 {
+            return FieldElement::zero();
+        }
+        FieldElement::from_biguint(&self.0 << bits)
+    }
+
+    pub fn bit_and(&self, other: & FieldElement) -> FieldElement {
+        FieldElement(&self.0 & &other.0)
+    }
+
+    pub fn bit_or(&self, other: & FieldElement) -> FieldElement {
+        FieldElement::from_biguint(&self.0 | &other.0)
+    }
+
+    pub fn bit_xor(&self, other: & FieldElement) -> FieldElement {
+        FieldElement::from_biguint(&self.0 ^ &other.0)
+    }
+
+    // complement on the 254 bits of the field
+    pub fn bit_not(&self) -> FieldElement {
+        let mask = (BigUint::one() << 254u32) - 1u32;
+        FieldElement::from_biguint(mask ^ &self.0)
+    }
+
+    pub fn from_bool(value: bool) -> FieldElement {
+        if value { FieldElement::one() } else { FieldElement::zero() }
+    }
+
+}
+
+impl fmt::Display for FieldElement {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.0)
+    }
+}
+
+// decimal strings in JSON, as snarkjs writes them
+impl serde::Serialize for FieldElement {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for FieldElement {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<FieldElement, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        FieldElement::parse(&text).ok_or_else(|| serde::de::Error::custom(format!("`{}` is not a number", text)))
+    }
+}
+
+impl Add for & FieldElement {
+    type Output = FieldElement;
+    fn add(self, other: & FieldElement) -> FieldElement {
+        FieldElement::from_biguint(&self.0 + &other.0)
+    }
+}
+
+impl Sub for & FieldElement {
+    type Output = FieldElement;
+    fn sub(self, other: & FieldElement) -> FieldElement {
+        FieldElement::from_biguint(&self.0 + prime() - &other.0)
+    }
+}
+
+impl Mul for & FieldElement {
+    type Output = FieldElement;
+    fn mul(self, other: & FieldElement) -> FieldElement {
+        FieldElement::from_biguint(&self.0 * &other.0)
+    }
+}
+
+impl Neg for & FieldElement {
+    type Output = FieldElement;
+    fn neg(self) -> FieldElement {
+        FieldElement::zero().sub(self)
+    }
+}
+
+#[cfg(test)]
+mod field_test {
+
+    use crate::r1cs::field::FieldElement;
+
+    use galvanic_assert::matchers::*;
+
+    #[test]
+    fn test_circom_semantics() {
+
+        let minus_one = FieldElement::from_i64(-1);
+        expect_that!(&minus_one.to_string(), eq("21888242871839275222246405745257275088548364400416034343698204186575808495616".to_string()));
+        expect_that!(&minus_one.to_signed().to_string(), eq("-1".to_string()));
+        expect_that!(&(&minus_one + &FieldElement::one()), eq(FieldElement::zero()));
+
+        let three = FieldElement::from_i64(3);
+        expect_that!(&(&three.inverse().unwrap() * &three), eq(FieldElement::one()));
+        expect_that!(&FieldElement::from_i64(7).quotient(&FieldElement::from_i64(2)), eq(Some(three.clone())));
+        expect_that!(&FieldElement::from_i64(6).shift_right(&FieldElement::one()), eq(three.clone()));
+        expect_that!(&FieldElement::one().shift_left(&FieldElement::from_i64(3)), eq(FieldElement::from_i64(8)));
+        expect_that!(&FieldElement::parse("-2"), eq(Some(FieldElement::from_i64(-2))));
+
+    }
+}
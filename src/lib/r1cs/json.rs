@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use crate::r1cs::field::FieldElement;
+use crate::r1cs::{Constraint, LinearCombination};
+
+fn linear_combination_to_json(combination: & LinearCombination) -> String {
+    let terms: Vec<String> = combination.terms.iter()
+        .map(|(wire, coefficient)| format!("\"{}\":\"{}\"", wire, coefficient))
+        .collect();
+    format!("{{{}}}", terms.join(","))
+}
+
+///
+/// circom's `--json` dump, byte for byte: one `[A,B,C]` per line, wires in increasing order and
+/// coefficients as decimal strings of the reduced field element.
+///
+pub fn to_json(constraints: & [Constraint]) -> String {
+    let lines: Vec<String> = constraints.iter()
+        .map(|constraint| format!("[{},{},{}]", linear_combination_to_json(&constraint.a), linear_combination_to_json(&constraint.b), linear_combination_to_json(&constraint.c)))
+        .collect();
+    format!("{{\n\"constraints\": [\n{}\n]\n}}", lines.join(",\n"))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+///
+/// Loads the constraints of a `--json` dump, coefficients may also be negative.
+///
+pub fn from_json(text: & str) -> io::Result<Vec<Constraint>> {
+    #[derive(serde::Deserialize)]
+    struct Dump {
+        constraints: Vec<[BTreeMap<String, String>; 3]>
+    }
+
+    let dump: Dump = serde_json::from_str(text).map_err(|error| invalid(error.to_string()))?;
+    let mut constraints = vec![];
+    for (idx, [a, b, c]) in dump.constraints.iter().enumerate() {
+        let mut combinations = vec![];
+        for terms in [a, b, c] {
+            let mut combination = LinearCombination::default();
+            for (wire, coefficient) in terms {
+                match (wire.parse::<usize>(), FieldElement::parse(coefficient)) {
+                    (Ok(wire), Some(coefficient)) => combination.add_term(wire, coefficient),
+                    _ => return Err(invalid(format!("invalid term \"{}\":\"{}\" in constraint {}", wire, coefficient, idx)))
+                }
+            }
+            combinations.push(combination);
+        }
+        let c = combinations.pop().unwrap_or_default();
+        let b = combinations.pop().unwrap_or_default();
+        let a = combinations.pop().unwrap_or_default();
+        constraints.push(Constraint { a, b, c });
+    }
+    Ok(constraints)
+}
+
+#[cfg(test)]
+mod json_test {
+
+    use std::fs;
+
+    use crate::r1cs::json;
+
+    use galvanic_assert::matchers::*;
+
+    #[test]
+    fn test_round_trip_circom_dump() {
+
+        let dump = fs::read_to_string("./src/lib/parser/sample_circuits/warships_raw_constraints.json").unwrap();
+        let constraints = json::from_json(&dump).unwrap();
+        expect_that!(&constraints.len(), eq(181));
+        expect_that!(&json::to_json(&constraints), eq(dump));
+
+        expect_that!(&json::from_json("{\"constraints\": [[{},{},{\"1\":\"-1\",\"0\":\"3\"}]]}").unwrap()[0].c.format(&|wire| format!("w{}", wire)), eq("3 + -w1".to_string()));
+        expect_that!(&json::from_json("{\"constraints\": [[{},{},{\"x\":\"1\"}]]}").is_err(), eq(true));
+
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+use num_bigint::BigUint;
+
+use crate::r1cs::field::FieldElement;
+
+pub mod binary;
+pub mod field;
+pub mod json;
+pub mod satisfaction;
+pub mod simplify;
+pub mod sym;
+pub mod wtns;
+
+///
+/// Wire 0 always carries the constant 1, constant terms of a linear combination are multiples of it.
+///
+pub const ONE: usize = 0;
+
+///
+/// Sparse sum of `coefficient * wire`, without zero coefficients.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LinearCombination {
+    pub terms: BTreeMap<usize, FieldElement>
+}
+
+impl LinearCombination {
+
+    pub fn constant(value: FieldElement) -> LinearCombination {
+        let mut combination = LinearCombination::default();
+        combination.add_term(ONE, value);
+        combination
+    }
+
+    pub fn wire(wire: usize) -> LinearCombination {
+        let mut combination = LinearCombination::default();
+        combination.add_term(wire, FieldElement::one());
+        combination
+    }
+
+    pub fn add_term(&mut self, wire: usize, coefficient: FieldElement) {
+        let sum = match self.terms.get(&wire) {
+            Some(current) => current + &coefficient,
+            None => coefficient
+        };
+        if sum.is_zero() {
+            self.terms.remove(&wire);
+        } else {
+            self.terms.insert(wire, sum);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    ///
+    /// The value of a combination that only uses wire 0.
+    ///
+    pub fn constant_value(&self) -> Option<FieldElement> {
+        match self.terms.keys().any(|wire| *wire != ONE) {
+            true => None,
+            false => Some(self.terms.get(&ONE).cloned().unwrap_or_else(FieldElement::zero))
+        }
+    }
+
+    pub fn add(&self, other: & LinearCombination) -> LinearCombination {
+        let mut sum = self.clone();
+        for (wire, coefficient) in &other.terms {
+            sum.add_term(*wire, coefficient.clone());
+        }
+        sum
+    }
+
+    pub fn scale(&self, factor: & FieldElement) -> LinearCombination {
+        let mut scaled = LinearCombination::default();
+        for (wire, coefficient) in &self.terms {
+            scaled.add_term(*wire, coefficient * factor);
+        }
+        scaled
+    }
+
+    pub fn negate(&self) -> LinearCombination {
+        self.scale(&FieldElement::from_i64(-1))
+    }
+
+    ///
+    /// Same combination over other wire indices, `renumber` maps every old wire to its new one.
+    ///
+    pub fn renumber(&self, renumber: &dyn Fn(usize) -> usize) -> LinearCombination {
+        let mut renumbered = LinearCombination::default();
+        for (wire, coefficient) in &self.terms {
+            renumbered.add_term(renumber(*wire), coefficient.clone());
+        }
+        renumbered
+    }
+
+    ///
+    /// Coefficients are printed signed, `name` gives the label of every wire but wire 0.
+    ///
+    pub fn format(&self, name: &dyn Fn(usize) -> String) -> String {
+        self.format_over(field::prime(), name)
+    }
+
+    ///
+    /// Same as `format` for a combination read over another prime.
+    ///
+    pub fn format_over(&self, prime: & BigUint, name: &dyn Fn(usize) -> String) -> String {
+        if self.is_empty() {
+            return "0".to_string();
+        }
+        let terms: Vec<String> = self.terms.iter().map(|(wire, coefficient)| {
+            let coefficient = coefficient.to_signed_over(prime).to_string();
+            match (*wire == ONE, coefficient.as_str()) {
+                (true, _) => coefficient,
+                (false, "1") => name(*wire),
+                (false, "-1") => format!("-{}", name(*wire)),
+                (false, _) => format!("{}*{}", coefficient, name(*wire))
+            }
+        }).collect();
+        terms.join(" + ")
+    }
+
+}
+
+///
+/// `A * B - C = 0`
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Constraint {
+    pub a: LinearCombination,
+    pub b: LinearCombination,
+    pub c: LinearCombination
+}
+
+impl Constraint {
+
+    ///
+    /// `(A) * (B) - (C) = 0` with `name` labelling the wires.
+    ///
+    pub fn format(&self, name: &dyn Fn(usize) -> String) -> String {
+        self.format_over(field::prime(), name)
+    }
+
+    pub fn format_over(&self, prime: & BigUint, name: &dyn Fn(usize) -> String) -> String {
+        format!("({}) * ({}) - ({}) = 0", self.a.format_over(prime, name), self.b.format_over(prime, name), self.c.format_over(prime, name))
+    }
+
+}
+
+///
+/// A custom template (`pragma custom_templates`) used by the circuit, with the parameters of its instance.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomGate {
+    pub name: String,
+    pub parameters: Vec<FieldElement>
+}
+
+///
+/// One use of `custom_gates[gate]` on the given wires.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomGateApplication {
+    pub gate: usize,
+    pub signals: Vec<usize>
+}
+
+///
+/// A rank-1 constraint system with circom's wire layout: the constant one, the outputs of main, its public
+/// inputs, its private inputs, then every other signal. Labels are the signal ids of the circuit, a wire
+/// only keeps its label when the system was simplified.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct R1cs {
+    pub prime: BigUint,
+    pub n_wires: usize,
+    pub n_pub_out: usize,
+    pub n_pub_in: usize,
+    pub n_prv_in: usize,
+    pub n_labels: usize,
+    pub wire_to_label: Vec<usize>,
+    pub constraints: Vec<Constraint>,
+    pub custom_gates: Vec<CustomGate>,
+    pub custom_gate_applications: Vec<CustomGateApplication>
+}
+
+impl R1cs {
+
+    ///
+    /// Whether the system is over BN254. Its coefficients can only be evaluated, simplified or proved with
+    /// then, a system over another prime can only be inspected.
+    ///
+    pub fn is_bn254(&self) -> bool {
+        &self.prime == field::prime()
+    }
+
+}
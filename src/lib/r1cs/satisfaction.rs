@@ -0,0 +1,80 @@
+use crate::r1cs::field::FieldElement;
+use crate::r1cs::{Constraint, LinearCombination};
+
+///
+/// A constraint the witness does not satisfy, with the values of its three linear combinations:
+/// `a * b != c`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unsatisfied {
+    pub index: usize,
+    pub a: FieldElement,
+    pub b: FieldElement,
+    pub c: FieldElement
+}
+
+impl LinearCombination {
+
+    ///
+    /// Value of the combination for the given witness, `None` when it uses a wire the witness does not have.
+    ///
+    pub fn evaluate(&self, witness: & [FieldElement]) -> Option<FieldElement> {
+        let mut sum = FieldElement::zero();
+        for (wire, coefficient) in &self.terms {
+            sum = &sum + &(coefficient * witness.get(*wire)?);
+        }
+        Some(sum)
+    }
+
+}
+
+///
+/// Evaluates every constraint with the witness, indexed by wire. Fails with the index of the first
+/// constraint that uses a wire past the end of the witness.
+///
+pub fn check(constraints: & [Constraint], witness: & [FieldElement]) -> Result<Vec<Unsatisfied>, usize> {
+    let mut unsatisfied = vec![];
+    for (index, constraint) in constraints.iter().enumerate() {
+        let values = (constraint.a.evaluate(witness), constraint.b.evaluate(witness), constraint.c.evaluate(witness));
+        match values {
+            (Some(a), Some(b), Some(c)) => {
+                if &a * &b != c {
+                    unsatisfied.push(Unsatisfied { index, a, b, c });
+                }
+            },
+            _ => return Err(index)
+        }
+    }
+    Ok(unsatisfied)
+}
+
+#[cfg(test)]
+mod satisfaction_test {
+
+    use std::fs::File;
+
+    use crate::r1cs::field::FieldElement;
+    use crate::r1cs::{binary, satisfaction, wtns};
+
+    use galvanic_assert::matchers::*;
+
+    #[test]
+    fn test_check_circom_witness() {
+
+        let r1cs = binary::read(&mut File::open("./src/lib/parser/sample_circuits/warships_raw.r1cs").unwrap()).unwrap();
+        let mut witness = wtns::read(&mut File::open("./src/lib/parser/sample_circuits/witness.wtns").unwrap()).unwrap().values;
+        expect_that!(&satisfaction::check(&r1cs.constraints, &witness).unwrap().is_empty(), eq(true));
+
+        witness[1] = &witness[1] + &FieldElement::one();
+        let unsatisfied = satisfaction::check(&r1cs.constraints, &witness).unwrap();
+        expect_that!(&unsatisfied.is_empty(), eq(false));
+        for failure in &unsatisfied {
+            let constraint = &r1cs.constraints[failure.index];
+            expect_that!(&[&constraint.a, &constraint.b, &constraint.c].iter().any(|combination| combination.terms.contains_key(&1)), eq(true));
+            expect_that!(&(&failure.a * &failure.b == failure.c), eq(false));
+        }
+
+        expect_that!(&satisfaction::check(&r1cs.constraints, &witness[..10]).is_err(), eq(true));
+
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use crate::r1cs::field::FieldElement;
+use crate::r1cs::sym::Symbol;
+use crate::r1cs::{Constraint, CustomGateApplication, LinearCombination, R1cs, ONE};
+
+///
+/// Simplifications circom applies with `--O1` (constants and equalities) and `--O2` (every linear
+/// constraint). Each one removes a linear constraint and a wire, substituted everywhere else by the
+/// combination the constraint gives for it. The constant one, the inputs and outputs of main and the wires
+/// of custom gates stay.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    /// `k * x + c = 0`: `x` is a constant.
+    ConstantFolding,
+    /// `x - y = 0`: `x` is another name for `y`.
+    SignalEquality,
+    /// any linear constraint: one of its wires is a combination of the others.
+    LinearSubstitution
+}
+
+impl fmt::Display for Pass {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pass::ConstantFolding => write!(formatter, "constant folding"),
+            Pass::SignalEquality => write!(formatter, "signal equalities"),
+            Pass::LinearSubstitution => write!(formatter, "linear substitution")
+        }
+    }
+}
+
+///
+/// The passes of circom's optimisation levels: none for `--O0`, constants and equalities for `--O1`, all
+/// of them for `--O2`.
+///
+pub fn passes(level: usize) -> Vec<Pass> {
+    match level {
+        0 => vec![],
+        1 => vec![Pass::ConstantFolding, Pass::SignalEquality],
+        _ => vec![Pass::ConstantFolding, Pass::SignalEquality, Pass::LinearSubstitution]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassReport {
+    pub pass: Pass,
+    pub removed_constraints: usize,
+    pub removed_wires: usize
+}
+
+///
+/// A simplified system with what was done to get it. `renaming` gives the new wire of every wire of the
+/// original system, `None` when it was removed, and `substitutions` the combination of original wires that
+/// replaced each removed one.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Simplified {
+    pub r1cs: R1cs,
+    pub reports: Vec<PassReport>,
+    pub renaming: Vec<Option<usize>>,
+    pub substitutions: BTreeMap<usize, LinearCombination>
+}
+
+impl Simplified {
+
+    ///
+    /// The witness of the simplified system, from the witness of the original one.
+    ///
+    pub fn witness(&self, witness: & [FieldElement]) -> Vec<FieldElement> {
+        witness.iter().zip(&self.renaming)
+            .filter(|(_, renamed)| renamed.is_some())
+            .map(|(value, _)| value.clone())
+            .collect()
+    }
+
+    ///
+    /// Symbols over the simplified wires, removed signals get no wire as in circom's `.sym` files.
+    ///
+    pub fn symbols(&self, symbols: & [Symbol]) -> Vec<Symbol> {
+        symbols.iter().map(|symbol| Symbol {
+            wire: symbol.wire.and_then(|wire| self.renaming.get(wire).cloned().flatten()),
+            ..symbol.clone()
+        }).collect()
+    }
+
+}
+
+// `L = 0` when the constraint is linear: `A * B - C` with a constant or empty side
+fn linear_form(constraint: & Constraint) -> Option<LinearCombination> {
+    let product = match (constraint.a.constant_value(), constraint.b.constant_value()) {
+        (Some(a), _) => constraint.b.scale(&a),
+        (_, Some(b)) => constraint.a.scale(&b),
+        _ => return None
+    };
+    Some(product.add(&constraint.c.negate()))
+}
+
+fn substitute(combination: & LinearCombination, wire: usize, replacement: & LinearCombination) -> LinearCombination {
+    match combination.terms.get(&wire) {
+        Some(coefficient) => {
+            let mut substituted = combination.clone();
+            substituted.terms.remove(&wire);
+            substituted.add(&replacement.scale(coefficient))
+        },
+        None => combination.clone()
+    }
+}
+
+// the wire the pass removes with the constraint `linear = 0`, the last one it may remove
+fn eliminated_wire(pass: Pass, linear: & LinearCombination, protected: & HashSet<usize>) -> Option<usize> {
+    let wires: Vec<usize> = linear.terms.keys().filter(|wire| **wire != ONE).cloned().collect();
+    let candidate = wires.iter().rev().find(|wire| !protected.contains(wire)).cloned();
+    match pass {
+        Pass::ConstantFolding if wires.len() == 1 => candidate,
+        Pass::SignalEquality if wires.len() == 2 && !linear.terms.contains_key(&ONE) => {
+            let (first, second) = (&linear.terms[&wires[0]], &linear.terms[&wires[1]]);
+            match (first + second).is_zero() {
+                true => candidate,
+                false => None
+            }
+        },
+        Pass::LinearSubstitution => candidate,
+        _ => None
+    }
+}
+
+// removes the constraint `linear = 0` and `wire` with it, substituting it everywhere
+fn eliminate(constraints: &mut [Option<Constraint>], substitutions: &mut BTreeMap<usize, LinearCombination>, report: &mut PassReport, idx: usize, linear: LinearCombination, wire: usize) {
+    // k * wire + rest = 0, so wire = -rest / k
+    let mut rest = linear;
+    let coefficient = rest.terms.remove(&wire).expect("the wire is a term of the constraint");
+    let inverse = coefficient.inverse().expect("terms are never zero");
+    let replacement = rest.scale(&-&inverse);
+
+    constraints[idx] = None;
+    report.removed_constraints += 1;
+    report.removed_wires += 1;
+    for existing in substitutions.values_mut() {
+        *existing = substitute(existing, wire, &replacement);
+    }
+    for slot in constraints.iter_mut() {
+        let substituted = match slot {
+            Some(constraint) => Constraint {
+                a: substitute(&constraint.a, wire, &replacement),
+                b: substitute(&constraint.b, wire, &replacement),
+                c: substitute(&constraint.c, wire, &replacement)
+            },
+            None => continue
+        };
+        *slot = match linear_form(&substituted) {
+            Some(linear) if linear.is_empty() => {
+                report.removed_constraints += 1;
+                None
+            },
+            Some(linear) => Some(Constraint { a: LinearCombination::default(), b: LinearCombination::default(), c: linear.negate() }),
+            None => Some(substituted)
+        };
+    }
+    substitutions.insert(wire, replacement);
+}
+
+///
+/// Runs the passes in order, each one until it finds nothing to remove. A constraint left with nothing
+/// but zeros is removed too, as it always holds.
+///
+pub fn simplify(r1cs: & R1cs, passes: & [Pass]) -> Simplified {
+    let mut protected: HashSet<usize> = (0..=r1cs.n_pub_out + r1cs.n_pub_in + r1cs.n_prv_in).collect();
+    for application in &r1cs.custom_gate_applications {
+        protected.extend(&application.signals);
+    }
+    let mut constraints: Vec<Option<Constraint>> = r1cs.constraints.iter().cloned().map(Some).collect();
+    let mut substitutions: BTreeMap<usize, LinearCombination> = BTreeMap::new();
+    let mut reports = vec![];
+
+    for pass in passes {
+        let mut report = PassReport { pass: *pass, removed_constraints: 0, removed_wires: 0 };
+        // a substitution may turn constraints already looked at into ones this pass removes
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for idx in 0..constraints.len() {
+                let found = constraints[idx].as_ref()
+                    .and_then(linear_form)
+                    .and_then(|linear| eliminated_wire(*pass, &linear, &protected).map(|wire| (linear, wire)));
+                if let Some((linear, wire)) = found {
+                    eliminate(&mut constraints, &mut substitutions, &mut report, idx, linear, wire);
+                    changed = true;
+                }
+            }
+        }
+        reports.push(report);
+    }
+
+    let mut renaming = vec![None; r1cs.n_wires];
+    let mut wire_to_label = vec![];
+    for (wire, renamed) in renaming.iter_mut().enumerate() {
+        if !substitutions.contains_key(&wire) {
+            *renamed = Some(wire_to_label.len());
+            wire_to_label.push(r1cs.wire_to_label.get(wire).cloned().unwrap_or(wire));
+        }
+    }
+    let renumber = |wire: usize| renaming[wire].expect("removed wires were substituted everywhere");
+    let constraints = constraints.into_iter().flatten().map(|constraint| Constraint {
+        a: constraint.a.renumber(&renumber),
+        b: constraint.b.renumber(&renumber),
+        c: constraint.c.renumber(&renumber)
+    }).collect();
+
+    let custom_gate_applications = r1cs.custom_gate_applications.iter().map(|application| CustomGateApplication {
+        gate: application.gate,
+        signals: application.signals.iter().map(|wire| renumber(*wire)).collect()
+    }).collect();
+
+    Simplified {
+        r1cs: R1cs {
+            n_wires: wire_to_label.len(),
+            wire_to_label,
+            constraints,
+            custom_gate_applications,
+            ..r1cs.clone()
+        },
+        reports,
+        renaming,
+        substitutions
+    }
+}
+
+#[cfg(test)]
+mod simplify_test {
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::execution::{self, inputs};
+    use crate::parser::compile;
+    use crate::parser::source::DiskSource;
+    use crate::parser::symbols;
+    use crate::parser::template;
+    use crate::r1cs::field::{self, FieldElement};
+    use crate::r1cs::simplify::{self, Pass, PassReport};
+    use crate::r1cs::sym::Symbol;
+    use crate::r1cs::{binary, satisfaction, Constraint, LinearCombination, R1cs};
+
+    use galvanic_assert::matchers::*;
+
+    fn linear(terms: & [(usize, i64)]) -> Constraint {
+        let mut c = LinearCombination::default();
+        for (wire, coefficient) in terms {
+            c.add_term(*wire, FieldElement::from_i64(*coefficient));
+        }
+        Constraint { a: LinearCombination::default(), b: LinearCombination::default(), c }
+    }
+
+    #[test]
+    fn test_simplify_passes() {
+
+        // out = 1, in = 2; 3 = 5, 4 = in, 5 = 3 * in + 4 and out = 4 * 5
+        let r1cs = R1cs {
+            prime: field::prime().clone(),
+            n_wires: 6,
+            n_pub_out: 1,
+            n_pub_in: 0,
+            n_prv_in: 1,
+            n_labels: 6,
+            wire_to_label: (0..6).collect(),
+            constraints: vec![
+                linear(&[(3, 1), (0, -5)]),
+                linear(&[(4, 1), (2, -1)]),
+                linear(&[(5, 1), (3, -1), (4, -1)]),
+                Constraint { a: LinearCombination::wire(4), b: LinearCombination::wire(5), c: LinearCombination::wire(1) }
+            ],
+            custom_gates: vec![],
+            custom_gate_applications: vec![]
+        };
+
+        let folded = simplify::simplify(&r1cs, &simplify::passes(1));
+        expect_that!(&folded.reports, eq(vec![
+            PassReport { pass: Pass::ConstantFolding, removed_constraints: 1, removed_wires: 1 },
+            PassReport { pass: Pass::SignalEquality, removed_constraints: 1, removed_wires: 1 }
+        ]));
+        expect_that!(&folded.renaming, eq(vec![Some(0), Some(1), Some(2), None, None, Some(3)]));
+        expect_that!(&folded.substitutions[&3], eq(LinearCombination::constant(FieldElement::from_i64(5))));
+        expect_that!(&folded.substitutions[&4], eq(LinearCombination::wire(2)));
+        expect_that!(&folded.r1cs.constraints.len(), eq(2));
+        expect_that!(&folded.r1cs.wire_to_label, eq(vec![0, 1, 2, 5]));
+
+        let substituted = simplify::simplify(&r1cs, &simplify::passes(2));
+        expect_that!(&substituted.reports[2], eq(PassReport { pass: Pass::LinearSubstitution, removed_constraints: 1, removed_wires: 1 }));
+        expect_that!(&substituted.r1cs.constraints[0].format(&|wire| format!("w{}", wire)), eq("(w2) * (5 + w2) - (w1) = 0".to_string()));
+
+        let witness: Vec<FieldElement> = [1, 24, 3, 5, 3, 8].iter().map(|value| FieldElement::from_i64(*value)).collect();
+        expect_that!(&satisfaction::check(&substituted.r1cs.constraints, &substituted.witness(&witness)), eq(Ok(vec![])));
+        let symbols = vec![Symbol { label: 5, wire: Some(5), component: 0, name: "main.sum".to_string() }];
+        expect_that!(&substituted.symbols(&symbols)[0].wire, eq(None));
+        expect_that!(&folded.symbols(&symbols)[0].wire, eq(Some(3)));
+
+        // the inputs and outputs of main are never removed
+        expect_that!(&simplify::simplify(&R1cs { constraints: vec![linear(&[(1, 1), (2, -1)])], ..r1cs.clone() }, &simplify::passes(2)).r1cs.constraints.len(), eq(1));
+
+    }
+
+    #[test]
+    fn test_simplify_like_circom() {
+
+        let mut ctx = compile::build_context_from_source(&PathBuf::from("./src/lib/parser/sample_circuits/warships_raw.circom"), &DiskSource);
+        let table = symbols::build_symbol_table(&mut ctx);
+        let program = template::load_program(&ctx, &table);
+        let inputs = inputs::from_json(&fs::read_to_string("./src/lib/parser/sample_circuits/input.json").unwrap()).unwrap();
+        let circuit = execution::execute_with_inputs(&program, &inputs).unwrap();
+
+        let simplified = simplify::simplify(&circuit.r1cs(), &simplify::passes(2));
+        let removed: Vec<(usize, usize)> = simplified.reports.iter().map(|report| (report.removed_constraints, report.removed_wires)).collect();
+        expect_that!(&removed, eq(vec![(36, 36), (72, 72), (19, 19)]));
+        expect_that!(&satisfaction::check(&simplified.r1cs.constraints, &simplified.witness(&circuit.witness().unwrap())), eq(Ok(vec![])));
+
+        // same constraints and wires as circom's --O2
+        let circom = binary::read(&mut fs::File::open("./src/lib/parser/sample_circuits/warships_raw.r1cs").unwrap()).unwrap();
+        expect_that!(&simplified.r1cs.wire_to_label, eq(circom.wire_to_label.clone()));
+        expect_that!(&simplified.r1cs.constraints, eq(circom.constraints.clone()));
+
+    }
+}
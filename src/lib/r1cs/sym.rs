@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::io;
+
+///
+/// Line of a circom `.sym` file: `label,wire,component,name`. The wire is `-1` in the file when the
+/// signal was simplified away.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub label: usize,
+    pub wire: Option<usize>,
+    pub component: usize,
+    pub name: String
+}
+
+fn field<T: std::str::FromStr>(value: Option<& str>, line: usize) -> io::Result<T> {
+    match value.and_then(|value| value.trim().parse().ok()) {
+        Some(value) => Ok(value),
+        None => Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid symbol on line {}", line + 1)))
+    }
+}
+
+pub fn parse(text: & str) -> io::Result<Vec<Symbol>> {
+    let mut symbols = vec![];
+    for (line, content) in text.lines().enumerate() {
+        if content.trim().is_empty() {
+            continue;
+        }
+        let mut fields = content.splitn(4, ',');
+        let label = field(fields.next(), line)?;
+        let wire: i64 = field(fields.next(), line)?;
+        let component = field(fields.next(), line)?;
+        let name: String = field(fields.next(), line)?;
+        symbols.push(Symbol { label, wire: usize::try_from(wire).ok(), component, name });
+    }
+    Ok(symbols)
+}
+
+///
+/// One symbol per line, in the order given.
+///
+pub fn to_sym(symbols: & [Symbol]) -> String {
+    let mut text = String::new();
+    for symbol in symbols {
+        let wire = symbol.wire.map(|wire| wire.to_string()).unwrap_or_else(|| "-1".to_string());
+        text.push_str(&format!("{},{},{},{}\n", symbol.label, wire, symbol.component, symbol.name));
+    }
+    text
+}
+
+///
+/// Name of every wire that survived simplification.
+///
+pub fn wire_names(symbols: & [Symbol]) -> HashMap<usize, String> {
+    let mut names = HashMap::new();
+    for symbol in symbols {
+        if let Some(wire) = symbol.wire {
+            names.entry(wire).or_insert_with(|| symbol.name.clone());
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod sym_test {
+
+    use std::fs::File;
+
+    use crate::r1cs::binary;
+    use crate::r1cs::sym::{self, Symbol};
+
+    use galvanic_assert::matchers::*;
+
+    #[test]
+    fn test_render_circom_output() {
+
+        let r1cs = binary::read(&mut File::open("./src/lib/parser/sample_circuits/warships_raw.r1cs").unwrap()).unwrap();
+        expect_that!(&(r1cs.n_wires, r1cs.n_pub_out, r1cs.n_pub_in, r1cs.n_prv_in, r1cs.n_labels, r1cs.constraints.len()), eq((66, 1, 0, 11, 193, 54)));
+        expect_that!(&r1cs.wire_to_label[..15].to_vec(), eq(vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 16]));
+
+        let symbols = sym::parse("1,1,0,main.answer\n2,2,0,main.board[0][0]\n14,-1,1,main.checkField[0][0].c[0]\n").unwrap();
+        expect_that!(&symbols[2], eq(Symbol { label: 14, wire: None, component: 1, name: "main.checkField[0][0].c[0]".to_string() }));
+        expect_that!(&sym::parse("1,x,0,main.answer").is_err(), eq(true));
+        expect_that!(&sym::parse(&sym::to_sym(&symbols)).unwrap(), eq(symbols.clone()));
+
+        let names = sym::wire_names(&symbols);
+        let constraint = r1cs.constraints.iter().find(|constraint| constraint.c.terms.contains_key(&1)).unwrap();
+        let rendered = constraint.format(&|wire| names.get(&wire).cloned().unwrap_or_else(|| format!("w{}", wire)));
+        expect_that!(&rendered.contains("main.answer"), eq(true));
+
+    }
+}
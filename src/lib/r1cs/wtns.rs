@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use num_bigint::BigUint;
+
+use crate::r1cs::binary::{self, invalid, Cursor};
+use crate::r1cs::field::{self, FieldElement};
+use crate::r1cs::sym::Symbol;
+
+///
+/// iden3 witness format, version 2 as written by circom's witness generators: a header section with the
+/// field size, the prime and the number of values, then a section with every value, little endian.
+///
+pub const MAGIC: &[u8; 4] = b"wtns";
+pub const VERSION: u32 = 2;
+
+pub const HEADER: u32 = 1;
+pub const VALUES: u32 = 2;
+
+///
+/// Values of a witness file and the prime of the field they belong to.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Witness {
+    pub prime: BigUint,
+    pub values: Vec<FieldElement>
+}
+
+impl Witness {
+
+    ///
+    /// Whether the values are over BN254, the only field they can be computed with: checked against
+    /// constraints or proved. Values over another prime can only be shown.
+    ///
+    pub fn is_bn254(&self) -> bool {
+        &self.prime == field::prime()
+    }
+
+}
+
+pub fn write(witness: & [FieldElement], writer: &mut dyn Write) -> io::Result<()> {
+    let size = binary::field_size(field::prime());
+
+    let mut header = vec![];
+    header.extend_from_slice(&(size as u32).to_le_bytes());
+    let mut prime = field::prime().to_bytes_le();
+    prime.resize(size, 0);
+    header.extend(prime);
+    header.extend_from_slice(&(witness.len() as u32).to_le_bytes());
+
+    let mut values = vec![];
+    for value in witness {
+        values.extend(value.to_le_bytes(size));
+    }
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&2u32.to_le_bytes())?;
+    for (section_type, content) in [(HEADER, header), (VALUES, values)] {
+        writer.write_all(&section_type.to_le_bytes())?;
+        writer.write_all(&(content.len() as u64).to_le_bytes())?;
+        writer.write_all(&content)?;
+    }
+    Ok(())
+}
+
+///
+/// Every value of a witness file, whatever the order of its sections, over any prime.
+///
+pub fn read(reader: &mut dyn Read) -> io::Result<Witness> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    let mut file = Cursor { bytes: &bytes, offset: 0 };
+    if file.take(4)? != MAGIC {
+        return Err(invalid("not a wtns file".to_string()));
+    }
+    let version = file.u32()?;
+    if version != VERSION as usize {
+        return Err(invalid(format!("unsupported wtns version {}", version)));
+    }
+    let mut sections = HashMap::new();
+    for _ in 0..file.u32()? {
+        let section_type = file.u32()? as u32;
+        let size = file.u64()?;
+        sections.insert(section_type, file.take(size)?);
+    }
+
+    let mut header = match sections.get(&HEADER) {
+        Some(bytes) => Cursor { bytes, offset: 0 },
+        None => return Err(invalid("missing header section".to_string()))
+    };
+    let size = header.u32()?;
+    let prime = BigUint::from_bytes_le(header.take(size)?);
+    if prime.bits() < 2 {
+        return Err(invalid(format!("invalid prime {}", prime)));
+    }
+    let count = header.u32()?;
+    let mut values = match sections.get(&VALUES) {
+        Some(bytes) => Cursor { bytes, offset: 0 },
+        None => return Err(invalid("missing values section".to_string()))
+    };
+    let values = (0..count).map(|_| values.field(size, &prime)).collect::<io::Result<Vec<FieldElement>>>()?;
+    Ok(Witness { prime, values })
+}
+
+///
+/// Value of every named signal whose wire is in the witness, names and wires coming from the `.sym` file
+/// of the same compilation.
+///
+pub fn values_by_name(witness: & [FieldElement], symbols: & [Symbol]) -> HashMap<String, FieldElement> {
+    symbols.iter()
+        .filter_map(|symbol| symbol.wire.and_then(|wire| witness.get(wire)).map(|value| (symbol.name.clone(), value.clone())))
+        .collect()
+}
+
+#[cfg(test)]
+mod wtns_test {
+
+    use std::fs::File;
+
+    use num_bigint::{BigInt, BigUint};
+
+    use crate::r1cs::field::FieldElement;
+    use crate::r1cs::sym::Symbol;
+    use crate::r1cs::wtns;
+
+    use galvanic_assert::matchers::*;
+
+    #[test]
+    fn test_read_circom_witness() {
+
+        let read = wtns::read(&mut File::open("./src/lib/parser/sample_circuits/witness.wtns").unwrap()).unwrap();
+        expect_that!(&read.is_bn254(), eq(true));
+        let witness = read.values;
+        expect_that!(&witness.len(), eq(66));
+        expect_that!(&witness[0], eq(FieldElement::one()));
+
+        let mut written = vec![];
+        wtns::write(&witness, &mut written).unwrap();
+        expect_that!(&wtns::read(&mut &written[..]).unwrap().values, eq(witness.clone()));
+
+        let symbols = vec![
+            Symbol { label: 1, wire: Some(1), component: 0, name: "main.answer".to_string() },
+            Symbol { label: 14, wire: None, component: 1, name: "main.checkField[0][0].c[0]".to_string() }
+        ];
+        let values = wtns::values_by_name(&witness, &symbols);
+        expect_that!(&values.get("main.answer"), eq(Some(&witness[1])));
+        expect_that!(&values.contains_key("main.checkField[0][0].c[0]"), eq(false));
+
+    }
+
+    #[test]
+    fn test_other_primes() {
+
+        // `1, p - 1` over goldilocks, on 8-byte field elements
+        let prime: BigUint = "18446744069414584321".parse().unwrap();
+        let mut header = 8u32.to_le_bytes().to_vec();
+        header.extend(prime.to_bytes_le());
+        header.extend(2u32.to_le_bytes());
+        let mut values = 1u64.to_le_bytes().to_vec();
+        values.extend((&prime - 1u32).to_bytes_le());
+        let mut bytes = b"wtns".to_vec();
+        for word in [2u32, 2, 1] {
+            bytes.extend(word.to_le_bytes());
+        }
+        bytes.extend((header.len() as u64).to_le_bytes());
+        bytes.extend(header);
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend((values.len() as u64).to_le_bytes());
+        bytes.extend(values);
+
+        let witness = wtns::read(&mut &bytes[..]).unwrap();
+        expect_that!(&(witness.is_bn254(), witness.prime == prime), eq((false, true)));
+        expect_that!(&witness.values[1].to_signed_over(&prime), eq(BigInt::from(-1)));
+
+    }
+}
@@ -96,11 +96,13 @@ const initialEdges = [
   { id: 'e4b1-4b2', source: '4b1', target: '4b2' },
 ];
 
-const fetchGraphData = async (component, parent) => {
+const fetchGraphData = async (instance) => {
   try {
-    const encodedComponent = encodeURIComponent(component);
-    const parentComponent =  encodeURIComponent(parent);
-    const url = `http://0.0.0.0:3030/graph-data/${encodedComponent}/${parent}`;
+    const encodedInstance = encodeURIComponent(instance);
+    // `?input=<input.json>` on the page gives the values of a run with these inputs,
+    // `?provenance=<signal>` highlights how the signal got its value,
+    // `?left=<a.json>&right=<b.json>` shows the values of both runs and where they diverge
+    const url = `http://0.0.0.0:3030/graph-data/${encodedInstance}${window.location.search}`;
     const response = await fetch(url);
     if (!response.ok) {
       throw new Error(`HTTP error! status: ${response.status}`);
@@ -116,26 +118,21 @@ const NestedFlow = () => {
   const [nodes, setNodes, onNodesChange] = useNodesState(initialNodes);
   const [edges, setEdges, onEdgesChange] = useEdgesState(initialEdges);
 
-  const update_nodes = async (component, parent) => {
-    const graphData = await fetchGraphData(component, parent);
-    for (let nodeId in graphData.initialNodes) {
-      let node = graphData.initialNodes[nodeId];
-      if (node.type === 'customNode') {
-        if (!node.data) {
-          node.data = {};
+  const update_nodes = async (instance) => {
+    const graphData = await fetchGraphData(instance);
+    if (graphData) {
+      for (let node of graphData.initialNodes) {
+        if (node.type === 'customNode') {
+          node.data.onChildAction = update_nodes;
         }
-        node.data.onChildAction = update_nodes;
-        node.data.parent = graphData.initialNodes[0].id;
       }
-    }
-    if (graphData) {
       setNodes(graphData.initialNodes);
       setEdges(graphData.initialEdges);
     }
   };
 
   useEffect(() => {
-    const loadGraphData = async() => {await update_nodes('main', 'main')};
+    const loadGraphData = async() => {await update_nodes('main')};
 
     loadGraphData();
   }, []);
@@ -3,20 +3,20 @@ import { Handle, Position } from 'reactflow';
 const CustomNode = ({ data, id}) => {
 
   return (
-    <div onDoubleClick={() => data.onChildAction(data.label, data.parent)} style={{ borderStyle: 'solid', height: '100%',	display: 'flex',	alignItems: 'center'}} >
-    {Array.from({ length: data.inputHandles }).map((_, index) => (
+    <div onDoubleClick={() => data.onChildAction(id)} style={{ borderStyle: 'solid', height: '100%',	display: 'flex',	alignItems: 'center'}} >
+    {(data.inputs || []).map((input, index) => (
         <Handle
           type="target"
           position={Position.Top}
-          id={`${data.label}.in[${index}]`}
-          key={`${data.label}.in[${index}]`}
-          style={{ left: `calc(33% * ${index + 1})` }}
+          id={input}
+          key={input}
+          style={{ left: `calc(100% * ${index + 1} / ${data.inputs.length + 1})` }}
         />
       ))}
       {/* <Handle type="target" position={Position.Top} /> */}
       <label htmlFor="text" style={{overflow: 'hidden', width: '100%'}}>{data.label}</label>
-      <Handle type="source" position={Position.Bottom}  id={`${data.label}.out`}
-          key={`${data.label}.out`}/>
+      <Handle type="source" position={Position.Bottom}  id={`${id}.out`}
+          key={`${id}.out`}/>
     </div>
   );
 };